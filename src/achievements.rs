@@ -0,0 +1,85 @@
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::expr::MachineView;
+use crate::i18n::trf;
+use crate::watch::Watchpoint;
+
+// the layout of a per-rom achievements file, e.g.
+//
+// [[trigger]]
+// when = "memory[0x3E0] >= 10"
+// message = "Scored 10 points!"
+#[derive(Deserialize)]
+struct AchievementsFile {
+    #[serde(default, rename = "trigger")]
+    triggers: Vec<TriggerDef>,
+}
+
+#[derive(Deserialize)]
+struct TriggerDef {
+    when: String,
+    message: String,
+
+    // fire every time the condition becomes true instead of only once
+    #[serde(default)]
+    repeat: bool,
+}
+
+pub struct Trigger {
+    watchpoint: Watchpoint,
+    message: String,
+    repeat: bool,
+    unlocked: bool,
+}
+
+impl Trigger {
+    // check the trigger condition and return the message if it fired
+    pub fn check(&mut self, machine: &dyn MachineView) -> Option<&str> {
+        if self.unlocked && !self.repeat {
+            return None;
+        }
+
+        if self.watchpoint.check(machine) {
+            self.unlocked = true;
+            println!(
+                "{}",
+                trf(
+                    "achievement_unlocked",
+                    &[&self.message, &self.watchpoint.expr()]
+                )
+            );
+            Some(&self.message)
+        } else {
+            None
+        }
+    }
+}
+
+// the achievements file that belongs to a romfile, e.g. roms/PONG is paired
+// with roms/PONG.achievements.toml
+pub fn default_path(romfile: &Path) -> PathBuf {
+    romfile.with_extension("achievements.toml")
+}
+
+pub fn load(path: &Path) -> Result<Vec<Trigger>, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| trf("file_read_error", &[&path.display(), &e]))?;
+    let file: AchievementsFile =
+        toml::from_str(&text).map_err(|e| trf("file_invalid", &[&path.display(), &e]))?;
+
+    let mut triggers = Vec::new();
+    for def in file.triggers {
+        let watchpoint = Watchpoint::parse(&def.when)
+            .map_err(|e| trf("trigger_invalid", &[&path.display(), &e]))?;
+        triggers.push(Trigger {
+            watchpoint,
+            message: def.message,
+            repeat: def.repeat,
+            unlocked: false,
+        });
+    }
+    Ok(triggers)
+}
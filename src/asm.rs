@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use crate::banking;
+use crate::banking::WINDOW_START;
+use crate::expr::parse_number;
+use crate::i18n::trf;
+use crate::interpreter::MEM_SIZE;
+use crate::interpreter::PROGRAM_START;
+use crate::opcode::Instruction;
+
+// a line of source with the label and comment taken off
+struct SourceLine<'a> {
+    number: usize,
+    label: Option<&'a str>,
+    text: &'a str,
+}
+
+fn is_label(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// split "loop: ADD V0, 1 ; count" into the label and the instruction
+fn split_line(number: usize, line: &str) -> SourceLine<'_> {
+    let line = match line.find(';') {
+        Some(pos) => &line[..pos],
+        None => line,
+    };
+    let line = line.trim();
+    match line.split_once(':') {
+        Some((label, text)) if is_label(label.trim()) => SourceLine {
+            number,
+            label: Some(label.trim()),
+            text: text.trim(),
+        },
+        _ => SourceLine {
+            number,
+            label: None,
+            text: line,
+        },
+    }
+}
+
+// the directive name and its comma separated operands, e.g. "DB 1, 2"
+fn directive(text: &str) -> Option<(String, Vec<&str>)> {
+    let (name, rest) = text.split_once(char::is_whitespace)?;
+    let name = name.to_ascii_uppercase();
+    if name != "DB" && name != "DW" {
+        return None;
+    }
+    Some((
+        name,
+        rest.split(',').map(|operand| operand.trim()).collect(),
+    ))
+}
+
+// the bank a "BANK 1" line starts, the lines after it are assembled for the
+// window and go in that bank of the rom
+fn bank_directive(text: &str) -> Option<Result<usize, String>> {
+    let (name, rest) = text.split_once(char::is_whitespace)?;
+    if !name.eq_ignore_ascii_case("BANK") {
+        return None;
+    }
+    Some(match parse_number(rest.trim()) {
+        Ok(bank) if (0..banking::MAX_BANKS as i64).contains(&bank) => Ok(bank as usize),
+        _ => Err(trf("asm_out_of_range", &[&rest.trim()])),
+    })
+}
+
+// the number of bytes a line assembles to
+fn size(text: &str) -> usize {
+    match directive(text) {
+        Some((name, operands)) if name == "DB" => operands.len(),
+        Some((_, operands)) => 2 * operands.len(),
+        None if text.is_empty() || bank_directive(text).is_some() => 0,
+        None => 2,
+    }
+}
+
+// assemble mnemonics into a rom that is loaded at the program start. Each
+// line can have a label ("loop:"), an instruction in the same form as the
+// disassembler prints them, DB with bytes or DW with words, and a comment
+// after ";". Labels can be used anywhere a number can. For the banking
+// extension "BANK 1" starts bank 1, banks come in order after the fixed part
+pub fn assemble(source: &str) -> Result<Vec<u8>, String> {
+    let lines: Vec<SourceLine> = source
+        .lines()
+        .enumerate()
+        .map(|(index, line)| split_line(index + 1, line))
+        .collect();
+    let error = |line: &SourceLine, e: &dyn std::fmt::Display| trf("asm_error", &[&line.number, e]);
+
+    // the first pass finds the address of every label
+    let mut labels: HashMap<&str, i64> = HashMap::new();
+    let mut addr = PROGRAM_START;
+    let mut bank: Option<usize> = None;
+    for line in &lines {
+        if let Some(next) = bank_directive(line.text) {
+            let next = next.map_err(|e| error(line, &e))?;
+            // the fixed part is loaded below the window
+            if bank.is_none() && addr > WINDOW_START {
+                return Err(trf(
+                    "asm_fixed_too_big",
+                    &[&(addr - PROGRAM_START), &banking::FIXED_SIZE],
+                ));
+            }
+            check_size(addr, bank)?;
+            if bank.is_some_and(|bank| next <= bank) {
+                return Err(error(line, &trf("asm_bank_order", &[&next])));
+            }
+            bank = Some(next);
+            addr = WINDOW_START;
+        }
+        if let Some(label) = line.label {
+            if labels.insert(label, addr as i64).is_some() {
+                return Err(error(line, &trf("asm_duplicate_label", &[&label])));
+            }
+        }
+        addr += size(line.text);
+    }
+    check_size(addr, bank)?;
+
+    // the second pass encodes the instructions with the labels known
+    let symbols = |name: &str| labels.get(name).copied();
+    let value = |text: &str| {
+        parse_number(text)
+            .ok()
+            .or_else(|| symbols(text))
+            .ok_or_else(|| trf("asm_unknown_value", &[&text]))
+    };
+    let mut rom = Vec::new();
+    for line in lines.iter().filter(|line| !line.text.is_empty()) {
+        if let Some(Ok(bank)) = bank_directive(line.text) {
+            rom.resize(banking::FIXED_SIZE + bank * banking::BANK_SIZE, 0);
+            continue;
+        }
+        match directive(line.text) {
+            Some((name, operands)) => {
+                for operand in operands {
+                    let val = value(operand).map_err(|e| error(line, &e))?;
+                    let (bits, bytes) = if name == "DB" { (8, 1) } else { (16, 2) };
+                    // bytes can be negative, e.g. DB -1 is 0xFF
+                    if val < -(1 << (bits - 1)) || val >= (1 << bits) {
+                        return Err(error(line, &trf("asm_out_of_range", &[&operand])));
+                    }
+                    let val = val as u16;
+                    if bytes == 2 {
+                        rom.push((val >> 8) as u8);
+                    }
+                    rom.push(val as u8);
+                }
+            }
+            None => {
+                let instruction =
+                    Instruction::parse(line.text, &symbols).map_err(|e| error(line, &e))?;
+                let opcode = instruction.encode();
+                rom.push((opcode >> 8) as u8);
+                rom.push(opcode as u8);
+            }
+        }
+    }
+    Ok(rom)
+}
+
+// check the part of the rom that ends at addr fits, the fixed part has to
+// end before the window once there are banks
+fn check_size(addr: usize, bank: Option<usize>) -> Result<(), String> {
+    match bank {
+        Some(bank) if addr > MEM_SIZE => Err(trf(
+            "asm_bank_too_big",
+            &[&bank, &(addr - WINDOW_START), &banking::BANK_SIZE],
+        )),
+        None if addr > MEM_SIZE => Err(trf("asm_too_big", &[&(addr - PROGRAM_START)])),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn assembles_instructions_labels_and_data() {
+        let source = "
+            start: LD V0, 0x0A ; count down
+            loop:  ADD V0, 0xFF
+                   SE V0, 0
+                   JP loop
+                   LD I, sprite
+                   DRW V0, V1, 2
+            end:   JP end
+            sprite: DB 0xF0, 0b10010000
+                    DW start, -2
+        ";
+        assert_eq!(
+            assemble(source),
+            Ok(vec![
+                0x60, 0x0A, 0x70, 0xFF, 0x30, 0x00, 0x12, 0x02, 0xA2, 0x0E, 0xD0, 0x12, 0x12, 0x0C,
+                0xF0, 0x90, 0x02, 0x00, 0xFF, 0xFE,
+            ])
+        );
+    }
+
+    #[test]
+    fn banks_go_after_the_fixed_part() {
+        let rom = assemble("JP far\nBANK 1\nfar: JP far").unwrap();
+        assert_eq!(rom.len(), banking::FIXED_SIZE + banking::BANK_SIZE + 2);
+        assert_eq!(rom[..2], [0x18, 0x00]);
+        assert_eq!(rom[rom.len() - 2..], [0x18, 0x00]);
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        assert_eq!(
+            assemble("CLS\nDB 1, nowhere"),
+            Err(trf(
+                "asm_error",
+                &[&2, &trf("asm_unknown_value", &[&"nowhere"])]
+            ))
+        );
+        assert_eq!(
+            assemble("a: CLS\na: CLS"),
+            Err(trf(
+                "asm_error",
+                &[&2, &trf("asm_duplicate_label", &[&"a"])]
+            ))
+        );
+        assert_eq!(
+            assemble("DB 256"),
+            Err(trf("asm_error", &[&1, &trf("asm_out_of_range", &[&"256"])]))
+        );
+    }
+}
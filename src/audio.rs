@@ -0,0 +1,109 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+// the pitch of the buzzer for roms that don't set the xo-chip pitch register,
+// unless --tone-hz picks another
+pub const BUZZER_HZ: f32 = 440.0;
+
+// the buzzer plays a square wave of 4 bits on and 4 off from the xo-chip
+// playback rate, so the default pitch of 64 sounds at 500hz
+const PATTERN_BITS: f32 = 8.0;
+
+// the volume of the left and right channels for a pan from -1 for all the
+// way left to 1 for all the way right. The pan keeps the same loudness across
+// the middle rather than getting quieter there
+pub fn pan_gains(pan: f32) -> (f32, f32) {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * std::f32::consts::FRAC_PI_4;
+    (angle.cos(), angle.sin())
+}
+
+// the xo-chip playback rate in bits a second for a pitch register value
+pub fn playback_rate(pitch: u8) -> f32 {
+    4000.0 * 2f32.powf((pitch as f32 - 64.0) / 48.0)
+}
+
+// the frequency of the buzzer from the pitch register, or the tone for roms
+// that haven't set it, moved by a number of semitones for speakers that make
+// the register mapping sound too shrill
+pub fn buzzer_hz(pitch: Option<u8>, tone: f32, offset: f32) -> f32 {
+    let hz = match pitch {
+        Some(pitch) => playback_rate(pitch) / PATTERN_BITS,
+        None => tone,
+    };
+    hz * 2f32.powf(offset / 12.0)
+}
+
+// the highest pitch of the buzzer while fast forwarding with the limit
+// setting, fast forwarding doesn't change the pitch but beepy sections can
+// still be shrill
+pub const TURBO_PITCH_LIMIT: f32 = 440.0;
+
+// the names of the turbo audio settings for the command line
+pub const TURBO_AUDIO_NAMES: &[&str] = &["mute", "limit"];
+
+// what the buzzer does while fast forwarding
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TurboAudio {
+    // no beeps at all
+    #[default]
+    Mute,
+    // beeps, but no higher than TURBO_PITCH_LIMIT
+    Limit,
+}
+
+impl TurboAudio {
+    pub fn from_name(name: &str) -> Option<TurboAudio> {
+        match name {
+            "mute" => Some(TurboAudio::Mute),
+            "limit" => Some(TurboAudio::Limit),
+            _ => None,
+        }
+    }
+}
+
+// the most recent samples played, shared between the audio thread that adds
+// them and anything drawing or recording them. The samples are mono, before
+// the pan, from -1 to 1
+#[derive(Clone, Debug)]
+pub struct SampleTap {
+    samples: Arc<Mutex<VecDeque<f32>>>,
+    capacity: usize,
+
+    // the samples a second
+    rate: u32,
+}
+
+impl SampleTap {
+    // keep the last second of samples
+    pub fn new(rate: u32) -> SampleTap {
+        let capacity = rate as usize;
+        SampleTap {
+            samples: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+            rate,
+        }
+    }
+
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+
+    // add samples as they are played, the oldest are dropped once full
+    pub fn push(&self, samples: impl Iterator<Item = f32>) {
+        let mut buffer = self.samples.lock().unwrap();
+        for sample in samples {
+            if buffer.len() == self.capacity {
+                buffer.pop_front();
+            }
+            buffer.push_back(sample);
+        }
+    }
+
+    // the last count samples played, oldest first
+    pub fn recent(&self, count: usize) -> Vec<f32> {
+        let buffer = self.samples.lock().unwrap();
+        let skip = buffer.len().saturating_sub(count);
+        buffer.iter().skip(skip).copied().collect()
+    }
+}
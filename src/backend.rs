@@ -0,0 +1,148 @@
+use crate::audio::SampleTap;
+use crate::colors::Theme;
+use crate::display::Display;
+use crate::osd::Osd;
+use crate::palette::PaletteKey;
+use crate::screenshot::Pixelmap;
+
+// actions for the emulator that come from the frontend rather than the chip8
+// keypad
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Quit,
+    ToggleHelp,
+    ToggleRomInfo,
+    // stop or restart the machine, the window keeps drawing while paused
+    TogglePause,
+    // run one opcode or one frame while paused
+    StepOpcode,
+    StepFrame,
+    // start the rom again from the beginning
+    Reset,
+    // write the display to a png
+    Screenshot,
+    // start or stop recording the display to a gif
+    ToggleRecording,
+    // show or hide the oscilloscope
+    ToggleScope,
+    // switch to the next built in theme
+    CycleTheme,
+    // turn the crt effect on or off
+    ToggleCrt,
+    // switch between fullscreen and a window
+    ToggleFullscreen,
+    // show the save state slots to pick one to save to or load from
+    SaveStateMenu,
+    LoadStateMenu,
+    // go back to the machine as it was before the last load or reset
+    Undo,
+    // run more or fewer opcodes a second
+    Faster,
+    Slower,
+    // make the buzzer louder or quieter
+    VolumeUp,
+    VolumeDown,
+    // run this many opcodes a second
+    SetClockspeed(u32),
+    // rewinding happens while the rewind key is held
+    Rewind(bool),
+    // the machine runs faster while the turbo key is held
+    Turbo(bool),
+    // open or close the command palette
+    TogglePalette,
+    // a key typed while the command palette is open
+    Palette(PaletteKey),
+    // open or close the menu of recent roms
+    RecentMenu,
+    // show a message, the lang key of the text
+    Notify(&'static str),
+}
+
+// draws the chip8 display and the osd
+pub trait VideoSink {
+    fn render(&mut self, display: &mut Display, osd: &mut Osd);
+
+    // the display as it is drawn in the window, without the osd, None if
+    // nothing is drawn
+    fn pixelmap(&self, _display: &Display) -> Option<Pixelmap> {
+        None
+    }
+
+    // draw in other colours from the next render
+    fn set_theme(&mut self, _theme: Theme) {}
+
+    // draw the display like an old tube television, or plainly
+    fn set_crt(&mut self, _on: bool) {}
+
+    // fill the screen with the display scaled to fit, or go back to a window
+    fn set_fullscreen(&mut self, _on: bool) {}
+
+    // fade pixels out over a few frames to hide the flicker of sprites
+    // erased and drawn again, or turn them off at once
+    fn set_phosphor(&mut self, _on: bool) {}
+}
+
+// plays the buzzer
+pub trait AudioSink {
+    fn set_beep(&mut self, on: bool);
+
+    // the frequency of the beep in hz
+    fn set_pitch(&mut self, _hz: f32) {}
+
+    // how loud the beep is, from 0 for silent to 100
+    fn set_volume(&mut self, _volume: u8) {}
+
+    // the samples played, None if nothing is played
+    fn sample_tap(&self) -> Option<SampleTap> {
+        None
+    }
+}
+
+// provides the state of the hex keypad and any emulator commands
+pub trait InputSource {
+    // the state of every key on the keypad
+    fn read_keys(&mut self) -> [bool; 0x10];
+
+    // any commands that happened since the last poll
+    fn poll_commands(&mut self) -> Vec<Command>;
+
+    // describes the keypad mapping for the help overlay
+    fn help_lines(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    // describes the keys for the emulator commands for the help overlay
+    fn hotkey_lines(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+// a backend that discards the display
+pub struct NullVideo;
+
+impl VideoSink for NullVideo {
+    fn render(&mut self, display: &mut Display, osd: &mut Osd) {
+        display.take_dirty();
+        osd.take_dirty();
+    }
+}
+
+// a backend that never beeps
+pub struct NullAudio;
+
+impl AudioSink for NullAudio {
+    fn set_beep(&mut self, _on: bool) {}
+}
+
+// a backend with no keys pressed
+pub struct NullInput;
+
+impl InputSource for NullInput {
+    fn read_keys(&mut self) -> [bool; 0x10] {
+        [false; 0x10]
+    }
+
+    fn poll_commands(&mut self) -> Vec<Command> {
+        Vec::new()
+    }
+}
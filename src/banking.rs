@@ -0,0 +1,59 @@
+use crate::interpreter::MEM_SIZE;
+use crate::interpreter::PROGRAM_START;
+
+// the layout of a rom with the banking extension. The first 1.5K of the rom
+// is loaded from the program start up to the window and stays there, the
+// rest is split into 2K banks and FXB0 picks which one fills the window at
+// the top of memory. Bank 0 is there at the start, so a rom that fits in
+// memory loads the same as without banking
+//
+// rom offset  0      600        E00        1600
+//             | fixed | bank 0   | bank 1   | ...
+// memory      200     800        800        800
+
+// the start of the memory the banks are mapped into
+pub const WINDOW_START: usize = 0x800;
+
+pub const BANK_SIZE: usize = MEM_SIZE - WINDOW_START;
+
+// the bank is picked by a register so there can be up to 256
+pub const MAX_BANKS: usize = 0x100;
+
+// the bytes at the start of the rom that are always loaded
+pub const FIXED_SIZE: usize = WINDOW_START - PROGRAM_START;
+
+// the biggest rom that can be loaded with banking
+pub const MAX_ROM_SIZE: usize = FIXED_SIZE + MAX_BANKS * BANK_SIZE;
+
+// the number of banks in a rom, a rom that fits in memory has one
+pub fn bank_count(rom_size: usize) -> usize {
+    std::cmp::max(1, rom_size.saturating_sub(FIXED_SIZE).div_ceil(BANK_SIZE))
+}
+
+// the offset in the rom of the byte at a memory address with the bank in the
+// window, None for addresses before the program start
+pub fn rom_offset(addr: usize, bank: usize) -> Option<usize> {
+    if addr < WINDOW_START {
+        addr.checked_sub(PROGRAM_START)
+    } else {
+        Some(FIXED_SIZE + bank * BANK_SIZE + addr - WINDOW_START)
+    }
+}
+
+// the memory address of an offset in the rom, and the bank that has to be in
+// the window for it to be there if it's in a bank
+pub fn address(offset: usize) -> (usize, Option<usize>) {
+    match offset.checked_sub(FIXED_SIZE) {
+        None => (PROGRAM_START + offset, None),
+        Some(banked) => (WINDOW_START + banked % BANK_SIZE, Some(banked / BANK_SIZE)),
+    }
+}
+
+// an address as it's shown with banking, e.g. "2F0" in the fixed part or
+// "1:9A4" in bank 1
+pub fn format_address(addr: usize, bank: Option<usize>) -> String {
+    match bank {
+        Some(bank) => format!("{:X}:{:03X}", bank, addr),
+        None => format!("{:03X}", addr),
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
+use crate::i18n::trf;
+
+// a timer read at the same address as the one before within this many opcodes
+// is a loop waiting for the timer, the rom is done with its frame
+const WAIT_LOOP: u32 = 8;
+
+// the most address ranges an alert shows
+const MAX_RANGES: usize = 3;
+
+// a stretch of code that ran and how many of its opcodes did
+pub struct Range {
+    pub start: usize,
+    pub end: usize,
+    pub opcodes: u32,
+}
+
+// work that didn't fit in a frame and where it was done
+pub struct Alert {
+    pub opcodes: u32,
+    pub budget: u32,
+    pub ranges: Vec<Range>,
+}
+
+impl Alert {
+    // the alert and a line for each range, e.g. "2A0-2C6: 812 opcodes"
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![trf("budget_alert", &[&self.opcodes, &self.budget])];
+        for range in &self.ranges {
+            lines.push(trf(
+                "budget_range",
+                &[
+                    &format!("{:03X}", range.start),
+                    &format!("{:03X}", range.end),
+                    &range.opcodes,
+                ],
+            ));
+        }
+        lines
+    }
+}
+
+// warns rom developers when the work a rom does between waits for the delay
+// timer or a key is more than the opcodes a frame has on the hardware being
+// targeted, as it would slow down there. Roms that don't wait in a loop on
+// FX07 or on FX0A are never warned about
+pub struct CycleBudget {
+    // the opcodes a frame has
+    budget: u32,
+
+    // the opcodes run since the last wait and how many at each address
+    work: u32,
+    addresses: BTreeMap<usize, u32>,
+
+    // the address of the last timer read or key wait and the opcodes since
+    last_wait: Option<(usize, u32)>,
+
+    alert: Option<Alert>,
+    // the busiest range of each alert given, each is only given once
+    reported: HashSet<usize>,
+}
+
+impl CycleBudget {
+    pub fn new(budget: u32) -> CycleBudget {
+        CycleBudget {
+            budget: budget.max(1),
+            work: 0,
+            addresses: BTreeMap::new(),
+            last_wait: None,
+            alert: None,
+            reported: HashSet::new(),
+        }
+    }
+
+    // note the opcode that is about to run at pc
+    pub fn observe(&mut self, pc: usize, opcode: u16) {
+        let waits = matches!(opcode & 0xF0FF, 0xF007 | 0xF00A);
+        let since = self.last_wait.map(|(addr, since)| (addr, since + 1));
+        self.last_wait = since.filter(|(_, since)| *since <= WAIT_LOOP);
+        if !waits {
+            self.work += 1;
+            *self.addresses.entry(pc).or_default() += 1;
+            return;
+        }
+
+        // the rom is waiting, the work since the last wait is its frame
+        if self.last_wait.is_some_and(|(addr, _)| addr == pc) {
+            if self.work > self.budget {
+                self.over_budget();
+            }
+            self.work = 0;
+            self.addresses.clear();
+        } else {
+            self.work += 1;
+            *self.addresses.entry(pc).or_default() += 1;
+        }
+        self.last_wait = Some((pc, 0));
+    }
+
+    fn over_budget(&mut self) {
+        let mut ranges: Vec<Range> = Vec::new();
+        for (addr, opcodes) in &self.addresses {
+            match ranges.last_mut() {
+                Some(range) if *addr <= range.end + 2 => {
+                    range.end = *addr;
+                    range.opcodes += opcodes;
+                }
+                _ => ranges.push(Range {
+                    start: *addr,
+                    end: *addr,
+                    opcodes: *opcodes,
+                }),
+            }
+        }
+        ranges.sort_by_key(|range| std::cmp::Reverse(range.opcodes));
+        ranges.truncate(MAX_RANGES);
+        if ranges
+            .first()
+            .is_some_and(|range| self.reported.insert(range.start))
+        {
+            self.alert = Some(Alert {
+                opcodes: self.work,
+                budget: self.budget,
+                ranges,
+            });
+        }
+    }
+
+    // the newest work over budget not given yet
+    pub fn alert(&mut self) -> Option<Alert> {
+        self.alert.take()
+    }
+}
@@ -0,0 +1,149 @@
+use crate::colors;
+use crate::extensions;
+use crate::fonts;
+use crate::json::object;
+use crate::json::Json;
+use crate::keymap;
+use crate::library;
+use crate::quirks;
+use crate::quirks::Quirks;
+
+// the platforms opcodes come from, in the order they built on each other
+pub const PLATFORMS: &[&str] = &["chip8", "schip", "xochip"];
+
+// every opcode that runs, as the pattern, the mnemonic and the platform or
+// extension it needs
+pub const OPCODES: &[(&str, &str, &str)] = &[
+    ("00E0", "CLS", "chip8"),
+    ("00EE", "RET", "chip8"),
+    ("1NNN", "JP NNN", "chip8"),
+    ("2NNN", "CALL NNN", "chip8"),
+    ("3XNN", "SE VX, NN", "chip8"),
+    ("4XNN", "SNE VX, NN", "chip8"),
+    ("5XY0", "SE VX, VY", "chip8"),
+    ("6XNN", "LD VX, NN", "chip8"),
+    ("7XNN", "ADD VX, NN", "chip8"),
+    ("8XY0", "LD VX, VY", "chip8"),
+    ("8XY1", "OR VX, VY", "chip8"),
+    ("8XY2", "AND VX, VY", "chip8"),
+    ("8XY3", "XOR VX, VY", "chip8"),
+    ("8XY4", "ADD VX, VY", "chip8"),
+    ("8XY5", "SUB VX, VY", "chip8"),
+    ("8XY6", "SHR VX", "chip8"),
+    ("8XY7", "SUBN VX, VY", "chip8"),
+    ("8XYE", "SHL VX", "chip8"),
+    ("9XY0", "SNE VX, VY", "chip8"),
+    ("ANNN", "LD I, NNN", "chip8"),
+    ("BNNN", "JP V0, NNN", "chip8"),
+    ("CXNN", "RND VX, NN", "chip8"),
+    ("DXYN", "DRW VX, VY, N", "chip8"),
+    ("EX9E", "SKP VX", "chip8"),
+    ("EXA1", "SKNP VX", "chip8"),
+    ("FX07", "LD VX, DT", "chip8"),
+    ("FX0A", "LD VX, K", "chip8"),
+    ("FX15", "LD DT, VX", "chip8"),
+    ("FX18", "LD ST, VX", "chip8"),
+    ("FX1E", "ADD I, VX", "chip8"),
+    ("FX29", "LD F, VX", "chip8"),
+    ("FX33", "LD B, VX", "chip8"),
+    ("FX55", "LD [I], VX", "chip8"),
+    ("FX65", "LD VX, [I]", "chip8"),
+    ("00CN", "SCD N", "schip"),
+    ("00FB", "SCR", "schip"),
+    ("00FC", "SCL", "schip"),
+    ("00FD", "EXIT", "schip"),
+    ("00FE", "LOW", "schip"),
+    ("00FF", "HIGH", "schip"),
+    ("DXY0", "DRW VX, VY, 0", "schip"),
+    ("FX30", "LD HF, VX", "schip"),
+    ("FX3A", "LD PITCH, VX", "xochip"),
+    ("FXB0", "LD BANK, VX", "banking"),
+];
+
+// the ways of running and controlling the emulator this build has
+pub fn frontends() -> Vec<&'static str> {
+    let mut frontends = Vec::new();
+    if cfg!(feature = "sdl") {
+        frontends.extend(["window", "library", "repl", "gamepad"]);
+    }
+    frontends.extend(["headless", "gdb", "osc", "input-pipe"]);
+    if cfg!(feature = "midi") {
+        frontends.push("midi");
+    }
+    if cfg!(feature = "tty") {
+        frontends.push("tty");
+    }
+    frontends
+}
+
+// the opcodes of a platform or extension
+pub fn opcodes(platform: &str) -> Vec<(&'static str, &'static str)> {
+    OPCODES
+        .iter()
+        .filter(|(_, _, from)| *from == platform)
+        .map(|(pattern, mnemonic, _)| (*pattern, *mnemonic))
+        .collect()
+}
+
+fn opcode_list(platform: &str) -> Json {
+    Json::List(
+        opcodes(platform)
+            .into_iter()
+            .map(|(pattern, mnemonic)| {
+                object(vec![
+                    ("opcode", Json::Str(pattern.to_string())),
+                    ("mnemonic", Json::Str(mnemonic.to_string())),
+                ])
+            })
+            .collect(),
+    )
+}
+
+// what this build supports for launchers to check, as json
+pub fn json() -> String {
+    let platforms = PLATFORMS
+        .iter()
+        .map(|name| {
+            object(vec![
+                ("name", Json::Str(name.to_string())),
+                ("opcodes", opcode_list(name)),
+            ])
+        })
+        .collect();
+    let presets = quirks::PRESET_NAMES
+        .iter()
+        .filter_map(|name| Quirks::preset(name).map(|preset| (name, preset)))
+        .map(|(name, preset)| {
+            let values = preset
+                .values()
+                .into_iter()
+                .map(|(quirk, on)| (quirk, Json::Bool(on)))
+                .collect();
+            (name.to_string(), object(values))
+        })
+        .collect();
+    let extensions = extensions::EXTENSION_NAMES
+        .iter()
+        .map(|name| {
+            object(vec![
+                ("name", Json::Str(name.to_string())),
+                ("opcodes", opcode_list(name)),
+            ])
+        })
+        .collect();
+    let font_names: Vec<&str> = fonts::FONTS.iter().map(|(name, _)| *name).collect();
+
+    let report = object(vec![
+        ("version", Json::Str(env!("CARGO_PKG_VERSION").to_string())),
+        ("platforms", Json::List(platforms)),
+        ("quirks", Json::strings(quirks::QUIRK_NAMES)),
+        ("quirk_presets", Json::Object(presets)),
+        ("extensions", Json::List(extensions)),
+        ("frontends", Json::strings(&frontends())),
+        ("rom_extensions", Json::strings(library::ROM_EXTENSIONS)),
+        ("themes", Json::strings(colors::THEME_NAMES)),
+        ("keymaps", Json::strings(keymap::PRESET_NAMES)),
+        ("fonts", Json::strings(&font_names)),
+    ]);
+    report.pretty()
+}
@@ -1,15 +0,0 @@
-use sdl2::pixels::Color;
-
-pub const BLACK: Color = Color {
-    r: 0x00,
-    g: 0x00,
-    b: 0x00,
-    a: 0xFF,
-};
-
-pub const WHITE: Color = Color {
-    r: 0xFF,
-    g: 0xFF,
-    b: 0xFF,
-    a: 0xFF,
-};
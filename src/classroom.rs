@@ -0,0 +1,84 @@
+use crate::expr::MachineView;
+use crate::opcode::Instruction;
+
+// the cpu state before and after an instruction, used to show what an
+// instruction changed
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct CpuState {
+    pc: usize,
+    i: usize,
+    sp: usize,
+    delay_timer: u8,
+    sound_timer: u8,
+    registers: [u8; 0x10],
+}
+
+impl CpuState {
+    pub fn capture(machine: &dyn MachineView) -> CpuState {
+        let mut registers = [0; 0x10];
+        for (index, register) in registers.iter_mut().enumerate() {
+            *register = machine.register(index);
+        }
+
+        CpuState {
+            pc: machine.pc(),
+            i: machine.i(),
+            sp: machine.sp(),
+            delay_timer: machine.delay_timer(),
+            sound_timer: machine.sound_timer(),
+            registers,
+        }
+    }
+}
+
+// describe an instruction that was just processed: the address, bytes and
+// mnemonic, what it does and what it changed
+pub fn annotate(before: &CpuState, after: &CpuState, opcode: u16) -> Vec<String> {
+    let instruction = Instruction::decode(opcode);
+    let mut changes = Vec::new();
+
+    for (index, (old, new)) in before
+        .registers
+        .iter()
+        .zip(after.registers.iter())
+        .enumerate()
+    {
+        if old != new {
+            changes.push(format!("V{:X} {:02X}->{:02X}", index, old, new));
+        }
+    }
+    if before.i != after.i {
+        changes.push(format!("I {:03X}->{:03X}", before.i, after.i));
+    }
+    if before.sp != after.sp {
+        changes.push(format!("SP {}->{}", before.sp, after.sp));
+    }
+    if before.delay_timer != after.delay_timer {
+        changes.push(format!("DT {}->{}", before.delay_timer, after.delay_timer));
+    }
+    if before.sound_timer != after.sound_timer {
+        changes.push(format!("ST {}->{}", before.sound_timer, after.sound_timer));
+    }
+    // the program counter normally just moves on to the next instruction
+    if after.pc != before.pc + 2 {
+        changes.push(format!("PC {:03X}->{:03X}", before.pc, after.pc));
+    }
+
+    let changed = if changes.is_empty() {
+        "No registers changed".to_string()
+    } else {
+        changes.join(", ")
+    };
+
+    vec![
+        format!(
+            "{:03X}: {:02X} {:02X}  {}",
+            before.pc,
+            opcode >> 8,
+            opcode & 0xFF,
+            instruction
+        ),
+        instruction.explain(),
+        changed,
+    ]
+}
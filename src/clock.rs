@@ -0,0 +1,62 @@
+// the clock for the emulator's deadlines and the osd. std::time::Instant
+// panics in the browser, so on wasm32 the time comes from the page's
+// performance.now() instead, see web/chip8.js
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::Instant;
+
+#[cfg(target_arch = "wasm32")]
+pub use browser::Instant;
+
+// the time an emulator keeps to, from the start of the run, and how it
+// waits for its next deadline. The start of the run is the real clock, tests
+// give the emulator one they move on themselves
+pub trait Clock {
+    // the time since the run started
+    fn elapsed(&self) -> Duration;
+
+    // wait till the time has passed
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+impl Clock for Instant {
+    fn elapsed(&self) -> Duration {
+        Instant::elapsed(self)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod browser {
+    use std::ops::Add;
+    use std::time::Duration;
+
+    extern "C" {
+        // the milliseconds since the page loaded
+        fn chip8_now() -> f64;
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct Instant(Duration);
+
+    impl Instant {
+        pub fn now() -> Instant {
+            let millis = unsafe { chip8_now() };
+            Instant(Duration::from_secs_f64(millis.max(0.0) / 1000.0))
+        }
+
+        pub fn elapsed(&self) -> Duration {
+            Instant::now().0.saturating_sub(self.0)
+        }
+    }
+
+    impl Add<Duration> for Instant {
+        type Output = Instant;
+
+        fn add(self, duration: Duration) -> Instant {
+            Instant(self.0 + duration)
+        }
+    }
+}
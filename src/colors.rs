@@ -0,0 +1,138 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+pub const BLACK: Color = Color {
+    r: 0x00,
+    g: 0x00,
+    b: 0x00,
+};
+
+pub const WHITE: Color = Color {
+    r: 0xFF,
+    g: 0xFF,
+    b: 0xFF,
+};
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    }
+}
+
+impl Color {
+    pub const fn new(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+
+    // parse a colour written as "#RRGGBB"
+    pub fn parse(text: &str) -> Result<Color, String> {
+        let hex = text.strip_prefix('#').unwrap_or(text);
+        let channel = |index: usize| {
+            hex.get(index..index + 2)
+                .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+        };
+        match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(Color { r, g, b }),
+            _ => Err(format!("Invalid colour \"{}\", expected #RRGGBB", text)),
+        }
+    }
+
+    // this colour with an amount of the other, from 0 for none to 1 for all
+    pub fn mix(self, other: Color, amount: f32) -> Color {
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * amount).round() as u8;
+        Color {
+            r: channel(self.r, other.r),
+            g: channel(self.g, other.g),
+            b: channel(self.b, other.b),
+        }
+    }
+}
+
+// the colours the display is drawn in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub foreground: Color,
+    pub background: Color,
+}
+
+impl Theme {
+    // the colour of a lit or an unlit pixel
+    pub fn pixel(&self, lit: bool) -> Color {
+        if lit {
+            self.foreground
+        } else {
+            self.background
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            foreground: WHITE,
+            background: BLACK,
+        }
+    }
+}
+
+// the built in themes in the order the theme hotkey steps through them,
+// classic is the default
+pub const THEMES: &[(&str, Theme)] = &[
+    ("classic", Theme::new(WHITE, BLACK)),
+    // a green phosphor monitor
+    ("green", Theme::new(Color::new(0x33, 0xFF, 0x66), BLACK)),
+    // an amber terminal
+    ("amber", Theme::new(Color::new(0xFF, 0xB0, 0x00), BLACK)),
+    // the green on green of a handheld's lcd
+    (
+        "lcd",
+        Theme::new(Color::new(0x0F, 0x38, 0x0F), Color::new(0x9B, 0xBC, 0x0F)),
+    ),
+    // dark ink on paper for projectors and bright rooms
+    (
+        "paper",
+        Theme::new(Color::new(0x1A, 0x1A, 0x1A), Color::new(0xF4, 0xF1, 0xE8)),
+    ),
+    // yellow on dark blue, told apart by brightness and by every kind of
+    // colour blindness
+    (
+        "contrast",
+        Theme::new(Color::new(0xFF, 0xE6, 0x00), Color::new(0x00, 0x1F, 0x5C)),
+    ),
+];
+
+// the names of the built in themes
+pub const THEME_NAMES: &[&str] = &["classic", "green", "amber", "lcd", "paper", "contrast"];
+
+impl Theme {
+    pub const fn new(foreground: Color, background: Color) -> Theme {
+        Theme {
+            foreground,
+            background,
+        }
+    }
+
+    // a built in theme by name
+    pub fn named(name: &str) -> Option<Theme> {
+        THEMES
+            .iter()
+            .find(|(theme_name, _)| *theme_name == name)
+            .map(|(_, theme)| *theme)
+    }
+
+    // the theme after the named one in THEMES, wrapping around, for the
+    // theme hotkey. A name that isn't built in starts from the first
+    pub fn next(name: Option<&str>) -> (&'static str, Theme) {
+        let index = THEMES
+            .iter()
+            .position(|(theme_name, _)| Some(*theme_name) == name)
+            .map_or(0, |index| (index + 1) % THEMES.len());
+        THEMES[index]
+    }
+}
@@ -0,0 +1,210 @@
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::i18n::trf;
+
+// the config file loaded at start up if it exists
+pub const DEFAULT_PATH: &str = "chip8.toml";
+
+// the presets that are built in, the config file can change them or add more
+pub const PRESET_NAMES: &[&str] = &["handheld", "projector", "dev"];
+
+// the settings kept in the config file, e.g.
+//
+// [hotkeys]
+// help = "F1"
+// rewind = "Backspace"
+//
+// rom_dir = "roms"
+//
+// [defaults]
+// keymap = "azerty"
+// theme = "amber"
+//
+// [keymap]
+// 0 = "X"
+// A = "Z"
+//
+// [gamepad]
+// a = "5"
+// leftx- = "4"
+//
+// [midi]
+// 36 = "5"
+// C3 = "0"
+//
+// [presets.projector]
+// pixelsize = 16
+// foreground = "#FFB000"
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    // where to look for romfiles that aren't in the current directory
+    pub rom_dir: Option<String>,
+
+    // the key names typed to unlock the hotkeys in kiosk mode, e.g.
+    // "F12 1 2 3 4"
+    pub kiosk_passcode: Option<String>,
+
+    // the settings used when neither the command line nor the preset has
+    // them
+    #[serde(default)]
+    pub defaults: Preset,
+
+    // action names to key names, see hotkeys::ACTION_NAMES
+    #[serde(default)]
+    pub hotkeys: BTreeMap<String, String>,
+
+    // keypad keys "0" to "F" to key names, on top of the --keymap layout
+    #[serde(default)]
+    pub keymap: BTreeMap<String, String>,
+
+    // gamepad inputs to keypad keys, on top of the default bindings
+    #[serde(default)]
+    pub gamepad: BTreeMap<String, String>,
+
+    // midi notes to keypad keys, on top of the default bindings
+    #[serde(default)]
+    pub midi: BTreeMap<String, String>,
+
+    // named bundles of settings picked with --preset
+    #[serde(default)]
+    pub presets: BTreeMap<String, Preset>,
+}
+
+// a bundle of settings for one way of playing, anything left out keeps its
+// default. Settings given on the command line win over the preset
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Preset {
+    pub pixelsize: Option<usize>,
+    pub clockspeed: Option<u32>,
+
+    // a keymap layout, see keymap::PRESET_NAMES
+    pub keymap: Option<String>,
+
+    // a theme name, see colors::THEME_NAMES
+    pub theme: Option<String>,
+
+    // the colours of lit and unlit pixels, e.g. "#FFFFFF", on top of the
+    // theme
+    pub foreground: Option<String>,
+    pub background: Option<String>,
+
+    // a quirks preset name, see quirks::PRESET_NAMES
+    pub quirks: Option<String>,
+
+    // run in classroom mode at this many opcodes per second
+    pub classroom: Option<u32>,
+
+    // show the rom info panel when the rom starts
+    pub rom_info: Option<bool>,
+
+    // warn when a frame's work is more than this many opcodes
+    pub cycle_budget: Option<u32>,
+
+    // fault policies separated by commas, see fault::FaultPolicies
+    pub on_fault: Option<String>,
+
+    // the pitch of the buzzer in hz for roms that don't set it
+    pub tone_hz: Option<u32>,
+
+    // how loud the buzzer is, from 0 to 100
+    pub volume: Option<u8>,
+
+    // draw the display like an old tube television
+    pub crt: Option<bool>,
+}
+
+impl Preset {
+    // the settings of this preset with any that other sets on top
+    pub fn merge(self, other: Preset) -> Preset {
+        Preset {
+            pixelsize: other.pixelsize.or(self.pixelsize),
+            clockspeed: other.clockspeed.or(self.clockspeed),
+            keymap: other.keymap.or(self.keymap),
+            theme: other.theme.or(self.theme),
+            foreground: other.foreground.or(self.foreground),
+            background: other.background.or(self.background),
+            quirks: other.quirks.or(self.quirks),
+            classroom: other.classroom.or(self.classroom),
+            rom_info: other.rom_info.or(self.rom_info),
+            cycle_budget: other.cycle_budget.or(self.cycle_budget),
+            on_fault: other.on_fault.or(self.on_fault),
+            tone_hz: other.tone_hz.or(self.tone_hz),
+            volume: other.volume.or(self.volume),
+            crt: other.crt.or(self.crt),
+        }
+    }
+}
+
+// small and fast for a handheld, big and bright for a projector, and the rom
+// info up front and cycle budget alerts for developing
+fn builtin_preset(name: &str) -> Option<Preset> {
+    match name {
+        "handheld" => Some(Preset {
+            pixelsize: Some(4),
+            ..Preset::default()
+        }),
+        "projector" => Some(Preset {
+            pixelsize: Some(16),
+            foreground: Some("#FFB000".to_string()),
+            background: Some("#000000".to_string()),
+            ..Preset::default()
+        }),
+        "dev" => Some(Preset {
+            clockspeed: Some(1000),
+            rom_info: Some(true),
+            cycle_budget: Some(15),
+            ..Preset::default()
+        }),
+        _ => None,
+    }
+}
+
+impl Config {
+    // the defaults with the profile of the rom and then the named preset on
+    // top
+    pub fn settings(
+        &self,
+        preset: Option<&str>,
+        profile: Option<&Preset>,
+    ) -> Result<Preset, String> {
+        let settings = self
+            .defaults
+            .clone()
+            .merge(profile.cloned().unwrap_or_default());
+        match preset {
+            Some(name) => Ok(settings.merge(self.preset(name)?)),
+            None => Ok(settings),
+        }
+    }
+
+    // a preset from the config file on top of the built in one of the same
+    // name
+    pub fn preset(&self, name: &str) -> Result<Preset, String> {
+        let builtin = builtin_preset(name);
+        let configured = self.presets.get(name).cloned();
+        if builtin.is_none() && configured.is_none() {
+            let mut names: Vec<&str> = PRESET_NAMES.to_vec();
+            names.extend(
+                self.presets
+                    .keys()
+                    .map(|name| name.as_str())
+                    .filter(|name| !PRESET_NAMES.contains(name)),
+            );
+            return Err(trf("preset_unknown", &[&name, &names.join(", ")]));
+        }
+        Ok(builtin
+            .unwrap_or_default()
+            .merge(configured.unwrap_or_default()))
+    }
+}
+
+pub fn load(path: &Path) -> Result<Config, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| trf("file_read_error", &[&path.display(), &e]))?;
+    toml::from_str(&text).map_err(|e| trf("file_invalid", &[&path.display(), &e]))
+}
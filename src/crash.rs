@@ -0,0 +1,96 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::debugger;
+use crate::i18n::trf;
+use crate::interpreter::Interpreter;
+use crate::interpreter::MEM_SIZE;
+use crate::opcode::Instruction;
+
+// the number of opcodes kept for the trace in a crash bundle
+pub const TRACE_LENGTH: usize = 256;
+
+// everything known about the emulator when it crashed
+pub struct CrashReport<'a> {
+    // the panic message
+    pub message: &'a str,
+    pub machine: &'a Interpreter,
+
+    // the address and opcode of the last opcodes run, oldest first
+    pub trace: Vec<(usize, u16)>,
+    pub rom_sha1: &'a str,
+
+    // the text of the config file if there is one
+    pub config: Option<String>,
+}
+
+// write the report as a directory of text files inside dir and return the
+// path of the directory
+pub fn write_bundle(dir: &Path, report: &CrashReport) -> Result<PathBuf, String> {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let bundle = dir.join(format!("crash-{}", seconds));
+    fs::create_dir_all(&bundle).map_err(|e| trf("file_create_error", &[&bundle.display(), &e]))?;
+
+    let summary = [
+        format!("chip8 {}", env!("CARGO_PKG_VERSION")),
+        format!("error: {}", report.message),
+        format!("rom sha1: {}", report.rom_sha1),
+        format!("quirks: {:?}", report.machine.quirks()),
+    ];
+    write(&bundle.join("report.txt"), &summary)?;
+
+    let machine = report.machine;
+    let mut state = vec![debugger::location(machine)];
+    state.extend(debugger::registers(machine));
+    let stack: Vec<String> = machine
+        .stack()
+        .iter()
+        .map(|addr| format!("{:03X}", addr))
+        .collect();
+    state.push(format!("stack: {}", stack.join(" ")));
+    state.push(String::new());
+    state.extend(debugger::hexdump(machine, 0, MEM_SIZE));
+    write(&bundle.join("state.txt"), &state)?;
+
+    let trace: Vec<String> = report
+        .trace
+        .iter()
+        .map(|(addr, opcode)| {
+            format!(
+                "{:03X}: {:04X}  {}",
+                addr,
+                opcode,
+                Instruction::decode(*opcode)
+            )
+        })
+        .collect();
+    write(&bundle.join("trace.txt"), &trace)?;
+
+    if let Some(ref config) = report.config {
+        let path = bundle.join("config.toml");
+        fs::write(&path, config).map_err(|e| trf("file_write_error", &[&path.display(), &e]))?;
+    }
+    Ok(bundle)
+}
+
+fn write(path: &Path, lines: &[String]) -> Result<(), String> {
+    let mut text = lines.join("\n");
+    text.push('\n');
+    fs::write(path, text).map_err(|e| trf("file_write_error", &[&path.display(), &e]))
+}
+
+// the message of a panic payload
+pub fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("unknown error")
+    }
+}
@@ -0,0 +1,99 @@
+use crate::screenshot::Pixelmap;
+
+// how much of the blurred picture is added back on top as glow
+const GLOW: f32 = 0.45;
+
+// how far the picture bulges, as the fraction the corners are pulled in by
+const CURVATURE: f32 = 0.04;
+
+// the brightness of the scanline along the bottom of each chip8 pixel
+const SCANLINE: f32 = 0.55;
+
+// how much darker the corners are than the middle
+const VIGNETTE: f32 = 0.35;
+
+// the display as it would look on an old tube: the lit pixels glow into
+// their neighbours, each row of chip8 pixels has a dark scanline along its
+// bottom, the picture bulges slightly like the glass and the corners are
+// darker. The frame is drawn at pixelsize pixels per chip8 pixel
+pub fn apply(frame: &Pixelmap, pixelsize: usize) -> Pixelmap {
+    let (width, height) = (frame.width, frame.height);
+    let source: Vec<f32> = frame.data.iter().map(|value| *value as f32).collect();
+    let glow = blur(&source, width, height, (pixelsize / 2).max(1));
+
+    let mut data = Vec::with_capacity(frame.data.len());
+    for y in 0..height {
+        for x in 0..width {
+            // from -1 to 1 across the picture, pushed outwards from the
+            // middle so the middle is magnified like a curved screen
+            let u = 2.0 * (x as f32 + 0.5) / width as f32 - 1.0;
+            let v = 2.0 * (y as f32 + 0.5) / height as f32 - 1.0;
+            let bulge = 1.0 - CURVATURE * (2.0 - u * u - v * v);
+            let (su, sv) = (u * bulge, v * bulge);
+            let sx = ((su + 1.0) / 2.0 * width as f32) as isize;
+            let sy = ((sv + 1.0) / 2.0 * height as f32) as isize;
+            if sx < 0 || sy < 0 || sx >= width as isize || sy >= height as isize {
+                data.extend_from_slice(&[0, 0, 0]);
+                continue;
+            }
+            let (sx, sy) = (sx as usize, sy as usize);
+
+            let scanline = if pixelsize >= 3 && sy % pixelsize >= pixelsize - pixelsize / 3 {
+                SCANLINE
+            } else {
+                1.0
+            };
+            let vignette = 1.0 - VIGNETTE * (u * u * v * v).sqrt();
+            let index = (sy * width + sx) * 3;
+            for channel in 0..3 {
+                let lit = source[index + channel] + GLOW * glow[index + channel];
+                let value = lit * scanline * vignette;
+                data.push(value.clamp(0.0, 255.0) as u8);
+            }
+        }
+    }
+    Pixelmap {
+        width,
+        height,
+        data,
+    }
+}
+
+// a box blur of the rgb values radius pixels each way, across and then down
+fn blur(data: &[f32], width: usize, height: usize, radius: usize) -> Vec<f32> {
+    let across = blur_pass(data, width, height, radius, 3, width * 3);
+    blur_pass(&across, height, width, radius, width * 3, 3)
+}
+
+// blur each line of count values step apart, the lines starting line_step
+// apart, with a running sum so any radius costs the same
+fn blur_pass(
+    data: &[f32],
+    count: usize,
+    lines: usize,
+    radius: usize,
+    step: usize,
+    line_step: usize,
+) -> Vec<f32> {
+    let mut output = vec![0.0; data.len()];
+    let span = (2 * radius + 1) as f32;
+    for line in 0..lines {
+        for channel in 0..3 {
+            let start = line * line_step + channel;
+            let at = |index: isize| -> f32 {
+                if index < 0 || index >= count as isize {
+                    0.0
+                } else {
+                    data[start + index as usize * step]
+                }
+            };
+            let mut sum: f32 = (-(radius as isize)..=radius as isize).map(at).sum();
+            for index in 0..count {
+                output[start + index * step] = sum / span;
+                sum +=
+                    at(index as isize + radius as isize + 1) - at(index as isize - radius as isize);
+            }
+        }
+    }
+    output
+}
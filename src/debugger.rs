@@ -0,0 +1,471 @@
+use std::collections::BTreeSet;
+use std::collections::VecDeque;
+use std::fs;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+
+use crate::banking;
+use crate::banking::WINDOW_START;
+use crate::error::Chip8Error;
+use crate::expr::parse_number;
+use crate::expr::MachineView;
+use crate::i18n::tr;
+use crate::i18n::trf;
+use crate::interpreter::Interpreter;
+use crate::interpreter::MEM_SIZE;
+use crate::opcode::Instruction;
+use crate::tracepoint::Tracepoint;
+
+// the number of bytes shown by the memory command when no count is given
+const MEM_DEFAULT_COUNT: usize = 16;
+
+// the number of instructions shown by the list command when no count is given
+const LIST_DEFAULT_COUNT: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Running,
+    Paused,
+    // run this many more opcodes then pause
+    Stepping(usize),
+}
+
+// pauses the emulator at breakpoints and takes commands typed on the console
+// to step through opcodes and inspect the machine
+pub struct Debugger {
+    // lines typed on the console
+    commands: Receiver<String>,
+
+    // the address and with banking the bank a breakpoint is in, None for a
+    // breakpoint in every bank
+    breakpoints: BTreeSet<(usize, Option<usize>)>,
+
+    // log a message when hit without pausing
+    tracepoints: Vec<Tracepoint>,
+
+    state: State,
+
+    // a breakpoint at this address is not hit, so continuing from a
+    // breakpoint doesn't stop straight away on it again
+    resume_pc: Option<usize>,
+
+    // set once quit was typed
+    quit: bool,
+
+    // the lines of a script not run yet, each runs once the machine is
+    // paused so a script plays out like the session it was recorded from
+    script: VecDeque<String>,
+
+    // the file the commands of this session are written to
+    recording: Option<(File, PathBuf)>,
+}
+
+// something that can stop the machine between opcodes and look at it, the
+// emulator calls these around every opcode and once an update
+pub trait DebugHook {
+    // true while the machine is stopped, the timers don't run
+    fn is_paused(&self) -> bool;
+
+    // called before every opcode, returns false if the opcode should not run
+    fn before_opcode(&mut self, interp: &Interpreter) -> bool;
+
+    // called after every opcode that ran
+    fn after_opcode(&mut self, interp: &Interpreter);
+
+    // handle any commands that came in since the last poll
+    fn poll(&mut self, interp: &mut Interpreter);
+
+    // true once the debugger asked for the emulator to quit
+    fn wants_quit(&self) -> bool {
+        false
+    }
+
+    // called when an opcode faulted and the fault policy is to debug it,
+    // true if the debugger stopped the machine at the opcode
+    fn fault(&mut self, _interp: &Interpreter, _fault: &Chip8Error) -> bool {
+        false
+    }
+}
+
+impl Debugger {
+    // the debugger starts paused before the first opcode
+    pub fn new(commands: Receiver<String>) -> Debugger {
+        Debugger {
+            commands,
+            breakpoints: BTreeSet::new(),
+            tracepoints: Vec::new(),
+            state: State::Paused,
+            resume_pc: None,
+            quit: false,
+            script: VecDeque::new(),
+            recording: None,
+        }
+    }
+
+    // run the commands of a script file before any typed, blank lines and
+    // lines starting with # are skipped
+    pub fn run_script(&mut self, path: &Path) -> Result<(), String> {
+        let text =
+            fs::read_to_string(path).map_err(|e| trf("file_read_error", &[&path.display(), &e]))?;
+        self.script.extend(
+            text.lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(String::from),
+        );
+        Ok(())
+    }
+
+    // write every command run from here on to a script file for run_script,
+    // except quit so the script leaves the machine where the session was
+    pub fn record_script(&mut self, path: &Path) -> Result<(), String> {
+        let file =
+            File::create(path).map_err(|e| trf("file_create_error", &[&path.display(), &e]))?;
+        self.recording = Some((file, path.to_path_buf()));
+        Ok(())
+    }
+
+    // add a command to the script being recorded, a write error stops the
+    // recording
+    fn record(&mut self, line: &str) {
+        let Some((file, path)) = self.recording.as_mut() else {
+            return;
+        };
+        if matches!(line, "" | "q" | "quit") {
+            return;
+        }
+        if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+            println!("{}", trf("file_write_error", &[&path.display(), &e]));
+            self.recording = None;
+        }
+    }
+
+    fn command(&mut self, line: &str, interp: &Interpreter) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if let [name, _, ..] = words.as_slice() {
+            if *name == "t" || *name == "trace" {
+                return self.add_tracepoint(line);
+            }
+        }
+        match words.as_slice() {
+            [] => {}
+            ["c"] | ["continue"] => self.resume(State::Running, interp),
+            ["s"] | ["step"] => self.resume(State::Stepping(1), interp),
+            ["s", count] | ["step", count] => match parse_count(count) {
+                Ok(count) => self.resume(State::Stepping(count), interp),
+                Err(e) => println!("{}", e),
+            },
+            ["p"] | ["pause"] => {
+                self.state = State::Paused;
+                println!("{}", location(interp));
+            }
+            ["b"] | ["break"] => {
+                if self.breakpoints.is_empty() {
+                    println!("{}", tr("debug_no_breakpoints"));
+                }
+                for (addr, bank) in &self.breakpoints {
+                    println!("{}", banking::format_address(*addr, *bank));
+                }
+            }
+            ["b", addr] | ["break", addr] => match parse_breakpoint(addr) {
+                Ok((addr, bank)) => {
+                    self.breakpoints.insert((addr, bank));
+                    println!(
+                        "{}",
+                        trf(
+                            "debug_breakpoint_set",
+                            &[&banking::format_address(addr, bank)]
+                        )
+                    );
+                }
+                Err(e) => println!("{}", e),
+            },
+            ["d", addr] | ["delete", addr] => match parse_breakpoint(addr) {
+                Ok((addr, bank)) => {
+                    self.breakpoints.remove(&(addr, bank));
+                    println!(
+                        "{}",
+                        trf(
+                            "debug_breakpoint_deleted",
+                            &[&banking::format_address(addr, bank)]
+                        )
+                    );
+                }
+                Err(e) => println!("{}", e),
+            },
+            ["t"] | ["trace"] => {
+                if self.tracepoints.is_empty() {
+                    println!("{}", tr("debug_no_tracepoints"));
+                }
+                for (index, tracepoint) in self.tracepoints.iter().enumerate() {
+                    println!("{}: {}", index + 1, tracepoint);
+                }
+            }
+            ["ut", number] | ["untrace", number] => match parse_count(number) {
+                Ok(number) if number <= self.tracepoints.len() => {
+                    let tracepoint = self.tracepoints.remove(number - 1);
+                    println!("{}", trf("debug_tracepoint_deleted", &[&tracepoint]));
+                }
+                Ok(number) => println!("{}", trf("debug_no_tracepoint", &[&number])),
+                Err(e) => println!("{}", e),
+            },
+            ["r"] | ["regs"] => {
+                for line in registers(interp) {
+                    println!("{}", line);
+                }
+                let stack: Vec<String> = interp
+                    .stack()
+                    .iter()
+                    .map(|a| format!("{:03X}", a))
+                    .collect();
+                println!("{}", trf("debug_stack", &[&stack.join(" ")]));
+            }
+            ["x", addr] | ["mem", addr] => print_memory(interp, addr, None),
+            ["x", addr, count] | ["mem", addr, count] => print_memory(interp, addr, Some(count)),
+            ["l"] | ["list"] => print_listing(interp, interp.pc(), LIST_DEFAULT_COUNT),
+            ["l", addr] | ["list", addr] => match parse_addr(addr) {
+                Ok(addr) => print_listing(interp, addr, LIST_DEFAULT_COUNT),
+                Err(e) => println!("{}", e),
+            },
+            ["h"] | ["help"] => println!("{}", tr("debug_help")),
+            ["q"] | ["quit"] => self.quit = true,
+            _ => println!("{}", trf("debug_unknown_command", &[&line])),
+        }
+    }
+
+    // t ADDR "MESSAGE" or t "CONDITION" "MESSAGE", a trigger that is an
+    // address is one, anything else is a condition
+    fn add_tracepoint(&mut self, line: &str) {
+        let args = split_quoted(line);
+        let tracepoint = match args.as_slice() {
+            [_, trigger, message] => match parse_breakpoint(trigger) {
+                Ok((addr, bank)) => Tracepoint::at(addr, bank, message),
+                Err(_) => Tracepoint::when(trigger, message),
+            },
+            _ => Err(tr("debug_trace_usage")),
+        };
+        match tracepoint {
+            Ok(tracepoint) => {
+                println!("{}", trf("debug_tracepoint_set", &[&tracepoint]));
+                self.tracepoints.push(tracepoint);
+            }
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    // log the message of every tracepoint the opcode about to run hits
+    fn trace(&mut self, interp: &Interpreter) {
+        for tracepoint in &mut self.tracepoints {
+            if let Some(message) = tracepoint.check(interp) {
+                println!("{}: {}", address(interp, interp.pc()), message);
+            }
+        }
+    }
+
+    fn resume(&mut self, state: State, interp: &Interpreter) {
+        self.resume_pc = Some(interp.pc());
+        self.state = state;
+    }
+}
+
+impl DebugHook for Debugger {
+    fn is_paused(&self) -> bool {
+        self.state == State::Paused
+    }
+
+    fn before_opcode(&mut self, interp: &Interpreter) -> bool {
+        let runs = match self.state {
+            State::Paused => false,
+            State::Stepping(_) => true,
+            State::Running => {
+                let pc = interp.pc();
+                let hit = self.breakpoints.contains(&(pc, None))
+                    || interp.bank().is_some() && self.breakpoints.contains(&(pc, interp.bank()));
+                if hit && self.resume_pc != Some(pc) {
+                    self.state = State::Paused;
+                    println!("{}", trf("debug_breakpoint_hit", &[&address(interp, pc)]));
+                    println!("{}", location(interp));
+                    false
+                } else {
+                    true
+                }
+            }
+        };
+        // a tracepoint at a breakpoint logs once the opcode does run
+        if runs {
+            self.trace(interp);
+        }
+        runs
+    }
+
+    fn after_opcode(&mut self, interp: &Interpreter) {
+        self.resume_pc = None;
+        if let State::Stepping(steps) = self.state {
+            if steps <= 1 {
+                self.state = State::Paused;
+                println!("{}", location(interp));
+            } else {
+                self.state = State::Stepping(steps - 1);
+            }
+        }
+    }
+
+    // run the script while the machine is paused and any commands that were
+    // typed since the last poll
+    fn poll(&mut self, interp: &mut Interpreter) {
+        while self.state == State::Paused && !self.quit {
+            let Some(line) = self.script.pop_front() else {
+                break;
+            };
+            println!("> {}", line);
+            self.record(&line);
+            self.command(&line, interp);
+        }
+        while let Ok(line) = self.commands.try_recv() {
+            self.record(line.trim());
+            self.command(line.trim(), interp);
+        }
+    }
+
+    fn wants_quit(&self) -> bool {
+        self.quit
+    }
+
+    fn fault(&mut self, interp: &Interpreter, fault: &Chip8Error) -> bool {
+        self.state = State::Paused;
+        println!("{}", trf("debug_fault", &[fault]));
+        println!("{}", location(interp));
+        true
+    }
+}
+
+fn print_memory(machine: &dyn MachineView, addr: &str, count: Option<&str>) {
+    let addr = match parse_addr(addr) {
+        Ok(addr) => addr,
+        Err(e) => return println!("{}", e),
+    };
+    let count = match count.map(parse_count) {
+        None => MEM_DEFAULT_COUNT,
+        Some(Ok(count)) => count,
+        Some(Err(e)) => return println!("{}", e),
+    };
+    for line in hexdump(machine, addr, count) {
+        println!("{}", line);
+    }
+}
+
+fn parse_addr(text: &str) -> Result<usize, String> {
+    match parse_number(text)? {
+        addr if (0..MEM_SIZE as i64).contains(&addr) => Ok(addr as usize),
+        addr => Err(trf("debug_invalid_address", &[&addr])),
+    }
+}
+
+// an address, or with banking "1:9A4" for an address only in bank 1
+fn parse_breakpoint(text: &str) -> Result<(usize, Option<usize>), String> {
+    match text.split_once(':') {
+        Some((bank, addr)) => match parse_number(bank)? {
+            bank if (0..banking::MAX_BANKS as i64).contains(&bank) => {
+                Ok((parse_addr(addr)?, Some(bank as usize)))
+            }
+            bank => Err(trf("debug_invalid_bank", &[&bank])),
+        },
+        None => Ok((parse_addr(text)?, None)),
+    }
+}
+
+// split a line into words, with text in double quotes one word
+fn split_quoted(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut rest = line.trim_start();
+    while !rest.is_empty() {
+        let (word, after) = match rest.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => rest.split_once(char::is_whitespace).unwrap_or((rest, "")),
+        };
+        words.push(word.to_string());
+        rest = after.trim_start();
+    }
+    words
+}
+
+fn parse_count(text: &str) -> Result<usize, String> {
+    match parse_number(text)? {
+        count if count > 0 => Ok(count as usize),
+        count => Err(trf("debug_invalid_count", &[&count])),
+    }
+}
+
+fn opcode_at(machine: &dyn MachineView, addr: usize) -> u16 {
+    ((machine.read_memory(addr) as u16) << 8) | machine.read_memory(addr + 1) as u16
+}
+
+// the instruction at the program counter, e.g. "200: 6A 02  LD VA, 0x02"
+pub fn location(machine: &dyn MachineView) -> String {
+    listing_line(machine, machine.pc())
+}
+
+// an address with the bank in front if it's in the window of a banked rom,
+// e.g. "2F0" or "1:9A4"
+fn address(machine: &dyn MachineView, addr: usize) -> String {
+    let bank = machine.bank().filter(|_| addr >= WINDOW_START);
+    banking::format_address(addr, bank)
+}
+
+fn listing_line(machine: &dyn MachineView, addr: usize) -> String {
+    let opcode = opcode_at(machine, addr);
+    format!(
+        "{}: {:02X} {:02X}  {}",
+        address(machine, addr),
+        opcode >> 8,
+        opcode & 0xFF,
+        Instruction::decode(opcode)
+    )
+}
+
+fn print_listing(machine: &dyn MachineView, addr: usize, count: usize) {
+    for index in 0..count {
+        let line_addr = addr + 2 * index;
+        if line_addr + 1 >= MEM_SIZE {
+            break;
+        }
+        let marker = if line_addr == machine.pc() { ">" } else { " " };
+        println!("{} {}", marker, listing_line(machine, line_addr));
+    }
+}
+
+// the registers, I, the program counter, stack pointer and timers
+pub fn registers(machine: &dyn MachineView) -> Vec<String> {
+    let registers: Vec<String> = (0..0x10)
+        .map(|index| format!("V{:X} {:02X}", index, machine.register(index)))
+        .collect();
+    let mut state = format!(
+        "PC {:03X}  I {:03X}  SP {}  DT {}  ST {}",
+        machine.pc(),
+        machine.i(),
+        machine.sp(),
+        machine.delay_timer(),
+        machine.sound_timer()
+    );
+    if let Some(bank) = machine.bank() {
+        state.push_str(&format!("  BANK {:X}", bank));
+    }
+    vec![registers[..8].join("  "), registers[8..].join("  "), state]
+}
+
+// a hex dump of memory, 16 bytes to a line
+pub fn hexdump(machine: &dyn MachineView, addr: usize, count: usize) -> Vec<String> {
+    let end = std::cmp::min(addr + count, MEM_SIZE);
+    (addr..end)
+        .step_by(16)
+        .map(|line_start| {
+            let bytes: Vec<String> = (line_start..std::cmp::min(line_start + 16, end))
+                .map(|addr| format!("{:02X}", machine.read_memory(addr)))
+                .collect();
+            format!("{:03X}: {}", line_start, bytes.join(" "))
+        })
+        .collect()
+}
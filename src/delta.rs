@@ -0,0 +1,266 @@
+use crate::i18n::tr;
+use crate::i18n::trf;
+
+// the frames between keyframes when the encoder isn't told, one every two
+// seconds at 60hz so a viewer that joins or drops a message catches up soon
+pub const DEFAULT_KEYFRAME_INTERVAL: u32 = 120;
+
+// the first byte of an encoded frame
+const KEYFRAME: u8 = b'K';
+const DELTA: u8 = b'D';
+
+// encodes framebuffers for streaming to many viewers. Each frame is packed to
+// a bit per pixel, a delta frame is xored with the frame before so only the
+// pixels that changed are set, and then runs of zero bytes are shortened,
+// which leaves a few bytes for most frames. A frame is
+//
+// K or D, the width, the height, the packed pixels
+//
+// with the width and height a byte each. In the packed pixels a 0 byte is
+// followed by the number of zero bytes it stands for, 1 to 255, and any other
+// byte is itself
+pub struct DeltaEncoder {
+    keyframe_interval: u32,
+    since_keyframe: u32,
+    // the packed frame sent last and its size
+    previous: Option<(usize, usize, Vec<u8>)>,
+}
+
+impl Default for DeltaEncoder {
+    fn default() -> Self {
+        DeltaEncoder::new(DEFAULT_KEYFRAME_INTERVAL)
+    }
+}
+
+impl DeltaEncoder {
+    // an encoder that sends a keyframe every interval frames, at least 1
+    pub fn new(keyframe_interval: u32) -> DeltaEncoder {
+        DeltaEncoder {
+            keyframe_interval: keyframe_interval.max(1),
+            since_keyframe: 0,
+            previous: None,
+        }
+    }
+
+    // make the next frame a keyframe, for when a viewer joins
+    pub fn request_keyframe(&mut self) {
+        self.previous = None;
+    }
+
+    // encode a framebuffer of a byte per pixel row by row, like
+    // Interpreter::framebuffer(). The first frame, a frame of another size and
+    // every interval frames are keyframes
+    pub fn encode(&mut self, pixels: &[u8], width: usize, height: usize) -> Vec<u8> {
+        let packed = pack(pixels);
+        let delta = match self.previous {
+            Some((w, h, ref previous))
+                if w == width && h == height && self.since_keyframe < self.keyframe_interval =>
+            {
+                Some(xor(&packed, previous))
+            }
+            _ => None,
+        };
+
+        let mut out = vec![0, width as u8, height as u8];
+        match delta {
+            Some(delta) => {
+                out[0] = DELTA;
+                compress(&delta, &mut out);
+                self.since_keyframe += 1;
+            }
+            None => {
+                out[0] = KEYFRAME;
+                compress(&packed, &mut out);
+                self.since_keyframe = 1;
+            }
+        }
+        self.previous = Some((width, height, packed));
+        out
+    }
+}
+
+// turns encoded frames back into framebuffers on the viewer's side
+#[derive(Default)]
+pub struct DeltaDecoder {
+    previous: Option<(usize, usize, Vec<u8>)>,
+}
+
+impl DeltaDecoder {
+    pub fn new() -> DeltaDecoder {
+        DeltaDecoder::default()
+    }
+
+    // decode a frame to its width, height and a byte per pixel. A delta frame
+    // before the first keyframe is an error, wait for the next keyframe
+    pub fn decode(&mut self, data: &[u8]) -> Result<(usize, usize, Vec<u8>), String> {
+        let (kind, width, height) = match data {
+            [kind, width, height, ..] => (*kind, *width as usize, *height as usize),
+            _ => return Err(tr("delta_invalid")),
+        };
+        let size = (width * height).div_ceil(8);
+        let unpacked = decompress(&data[3..], size)?;
+
+        let packed = match kind {
+            KEYFRAME => unpacked,
+            DELTA => match self.previous {
+                Some((w, h, ref previous)) if w == width && h == height => xor(&unpacked, previous),
+                _ => return Err(tr("delta_no_keyframe")),
+            },
+            _ => return Err(tr("delta_invalid")),
+        };
+        let pixels = unpack(&packed, width * height);
+        self.previous = Some((width, height, packed));
+        Ok((width, height, pixels))
+    }
+}
+
+// a bit per pixel, the first pixel in the top bit
+fn pack(pixels: &[u8]) -> Vec<u8> {
+    pixels
+        .chunks(8)
+        .map(|chunk| {
+            chunk.iter().enumerate().fold(0, |byte, (i, pixel)| {
+                byte | ((*pixel != 0) as u8) << (7 - i)
+            })
+        })
+        .collect()
+}
+
+fn unpack(packed: &[u8], count: usize) -> Vec<u8> {
+    (0..count)
+        .map(|i| (packed[i / 8] >> (7 - i % 8)) & 1)
+        .collect()
+}
+
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(a, b)| a ^ b).collect()
+}
+
+// shorten the runs of zero bytes
+fn compress(data: &[u8], out: &mut Vec<u8>) {
+    let mut zeros = 0;
+    for byte in data {
+        if *byte == 0 {
+            zeros += 1;
+            if zeros == u8::MAX {
+                out.extend([0, zeros]);
+                zeros = 0;
+            }
+        } else {
+            if zeros > 0 {
+                out.extend([0, zeros]);
+                zeros = 0;
+            }
+            out.push(*byte);
+        }
+    }
+    if zeros > 0 {
+        out.extend([0, zeros]);
+    }
+}
+
+fn decompress(data: &[u8], size: usize) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(size);
+    let mut bytes = data.iter();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            0 => match bytes.next() {
+                Some(count) if *count > 0 => out.extend(std::iter::repeat_n(0, *count as usize)),
+                _ => return Err(tr("delta_invalid")),
+            },
+            byte => out.push(*byte),
+        }
+    }
+    if out.len() != size {
+        return Err(trf("delta_wrong_size", &[&out.len(), &size]));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a 64x32 frame with the given pixels set
+    fn frame(set: &[usize]) -> Vec<u8> {
+        let mut pixels = vec![0; 64 * 32];
+        for pixel in set {
+            pixels[*pixel] = 1;
+        }
+        pixels
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let mut encoder = DeltaEncoder::new(3);
+        let mut decoder = DeltaDecoder::new();
+        let frames = [
+            frame(&[0, 7, 8, 2047]),
+            frame(&[0, 7, 8, 2047]),
+            frame(&[1, 100, 1000]),
+            frame(&[]),
+            frame(&[64, 65, 66]),
+        ];
+        for pixels in &frames {
+            let encoded = encoder.encode(pixels, 64, 32);
+            assert_eq!(decoder.decode(&encoded), Ok((64, 32, pixels.clone())));
+        }
+    }
+
+    #[test]
+    fn keyframes_come_every_interval_and_on_request() {
+        let mut encoder = DeltaEncoder::new(2);
+        let pixels = frame(&[5]);
+        let kinds: Vec<u8> = (0..5).map(|_| encoder.encode(&pixels, 64, 32)[0]).collect();
+        assert_eq!(kinds, [KEYFRAME, DELTA, KEYFRAME, DELTA, KEYFRAME]);
+        encoder.request_keyframe();
+        assert_eq!(encoder.encode(&pixels, 64, 32)[0], KEYFRAME);
+        // a frame of another size is a keyframe too
+        assert_eq!(encoder.encode(&vec![0; 128 * 64], 128, 64)[0], KEYFRAME);
+    }
+
+    #[test]
+    fn an_unchanged_frame_is_a_few_bytes() {
+        let mut encoder = DeltaEncoder::default();
+        let pixels = frame(&[3, 300, 2000]);
+        encoder.encode(&pixels, 64, 32);
+        // 256 zero bytes, a run of 255 and a run of 1
+        assert_eq!(
+            encoder.encode(&pixels, 64, 32),
+            [DELTA, 64, 32, 0, 255, 0, 1]
+        );
+    }
+
+    #[test]
+    fn a_delta_before_a_keyframe_is_an_error() {
+        let mut encoder = DeltaEncoder::default();
+        let pixels = frame(&[]);
+        encoder.encode(&pixels, 64, 32);
+        let delta = encoder.encode(&pixels, 64, 32);
+        assert_eq!(
+            DeltaDecoder::new().decode(&delta),
+            Err(tr("delta_no_keyframe"))
+        );
+    }
+
+    #[test]
+    fn a_frame_of_the_wrong_size_is_an_error() {
+        // 64x32 needs 256 bytes, this has 10
+        assert_eq!(
+            DeltaDecoder::new().decode(&[KEYFRAME, 64, 32, 0, 10]),
+            Err(trf("delta_wrong_size", &[&10, &256]))
+        );
+    }
+
+    #[test]
+    fn garbage_is_an_error() {
+        let mut decoder = DeltaDecoder::new();
+        assert_eq!(decoder.decode(&[KEYFRAME, 8]), Err(tr("delta_invalid")));
+        assert_eq!(decoder.decode(&[b'X', 8, 1, 5]), Err(tr("delta_invalid")));
+        // a zero with no count after it
+        assert_eq!(
+            decoder.decode(&[KEYFRAME, 8, 1, 0]),
+            Err(tr("delta_invalid"))
+        );
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::banking;
+use crate::banking::WINDOW_START;
+use crate::i18n::trf;
+use crate::interpreter::PROGRAM_START;
+use crate::opcode::Instruction;
+
+// a line of a disassembly, either an instruction or a byte of data
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Line {
+    pub addr: usize,
+    // the bank the line is in for the banked part of a banked rom
+    pub bank: Option<usize>,
+    pub bytes: Vec<u8>,
+    pub text: String,
+}
+
+// e.g. "200: 6A 02  LD VA, 0x02", "2F0: FF     DB 0xFF" or in bank 1 of a
+// banked rom "1:800: 00 E0  CLS"
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bytes: Vec<String> = self.bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        write!(
+            f,
+            "{}: {:<5}  {}",
+            banking::format_address(self.addr, self.bank),
+            bytes.join(" "),
+            self.text
+        )
+    }
+}
+
+// find which offsets in the rom start an instruction by following every path
+// the program can take from the start. Anything that is never reached is
+// treated as data. Jumps through V0 can't be followed so the code they reach
+// is only found if another path gets there
+pub fn trace_code(rom: &[u8]) -> Vec<bool> {
+    trace(rom, false)
+}
+
+// trace_code for a rom loaded with or without banking. Code in a bank can
+// only go to the window with the same bank in it, but the fixed part can't
+// know which bank is in the window so its jumps there are followed in every
+// bank
+fn trace(rom: &[u8], banked: bool) -> Vec<bool> {
+    let banks = if banked {
+        banking::bank_count(rom.len())
+    } else {
+        1
+    };
+    let mut code = vec![false; rom.len()];
+    let mut todo = vec![(PROGRAM_START, 0)];
+
+    while let Some((addr, bank)) = todo.pop() {
+        let offset = match banking::rom_offset(addr, bank) {
+            Some(offset) if offset + 1 < rom.len() => offset,
+            _ => continue,
+        };
+        if code[offset] {
+            continue;
+        }
+        code[offset] = true;
+
+        let opcode = ((rom[offset] as u16) << 8) | rom[offset + 1] as u16;
+        let instruction = Instruction::decode(opcode);
+        if let Instruction::Invalid(_) = instruction {
+            code[offset] = false;
+            continue;
+        }
+        for next in successors(addr, instruction) {
+            if next < WINDOW_START || addr >= WINDOW_START {
+                todo.push((next, bank));
+            } else {
+                todo.extend((0..banks).map(|bank| (next, bank)));
+            }
+        }
+    }
+    code
+}
+
+// the address of an offset in the rom and the bank it's in if it's in one
+fn location(offset: usize, banked: bool) -> (usize, Option<usize>) {
+    if banked {
+        banking::address(offset)
+    } else {
+        (PROGRAM_START + offset, None)
+    }
+}
+
+// the addresses the program can go to after the instruction at addr, jumps
+// through V0 can't be known without running the program
+pub fn successors(addr: usize, instruction: Instruction) -> Vec<usize> {
+    match instruction {
+        Instruction::Jp(nnn) => vec![nnn],
+        Instruction::Call(nnn) => vec![nnn, addr + 2],
+        Instruction::Ret | Instruction::Exit | Instruction::JpV0(_) | Instruction::Invalid(_) => {
+            Vec::new()
+        }
+        Instruction::SeVxNn(..)
+        | Instruction::SneVxNn(..)
+        | Instruction::SeVxVy(..)
+        | Instruction::SneVxVy(..)
+        | Instruction::Skp(_)
+        | Instruction::Sknp(_) => vec![addr + 2, addr + 4],
+        _ => vec![addr + 2],
+    }
+}
+
+// disassemble a rom loaded at the program start, traced code is shown as
+// instructions and everything else as data bytes. A banked rom is loaded as
+// with the banking extension and its lines past the fixed part are in banks
+pub fn disassemble(rom: &[u8], banked: bool) -> Vec<Line> {
+    let code = trace(rom, banked);
+    let mut lines = Vec::new();
+
+    let mut offset = 0;
+    while offset < rom.len() {
+        let (addr, bank) = location(offset, banked);
+        if code[offset] {
+            let opcode = ((rom[offset] as u16) << 8) | rom[offset + 1] as u16;
+            lines.push(Line {
+                addr,
+                bank,
+                bytes: rom[offset..offset + 2].to_vec(),
+                text: Instruction::decode(opcode).to_string(),
+            });
+            offset += 2;
+        } else {
+            lines.push(Line {
+                addr,
+                bank,
+                bytes: vec![rom[offset]],
+                text: format!("DB {:#04X}", rom[offset]),
+            });
+            offset += 1;
+        }
+    }
+    lines
+}
+
+// the number of data bytes on one line of a listing
+const DATA_BYTES_PER_LINE: usize = 8;
+
+// how an address is used by the instructions that refer to it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Reference {
+    Call,
+    Jump,
+    Data,
+}
+
+// an address and the bank it's in, for the banked part of a banked rom
+type Location = (usize, Option<usize>);
+
+// the addresses that instructions jump to, call or point I at, along with
+// where they are referred to from. With banking an address in the window is
+// in the same bank as a line in a bank refers to it from, or in any bank if
+// it's referred to from the fixed part
+fn references(lines: &[Line], banks: usize) -> BTreeMap<Location, Vec<(Reference, Location)>> {
+    let mut references: BTreeMap<Location, Vec<(Reference, Location)>> = BTreeMap::new();
+    for line in lines.iter().filter(|line| line.bytes.len() == 2) {
+        let opcode = ((line.bytes[0] as u16) << 8) | line.bytes[1] as u16;
+        let (kind, nnn) = match Instruction::decode(opcode) {
+            Instruction::Call(nnn) => (Reference::Call, nnn),
+            Instruction::Jp(nnn) | Instruction::JpV0(nnn) => (Reference::Jump, nnn),
+            Instruction::LdI(nnn) => (Reference::Data, nnn),
+            _ => continue,
+        };
+        let targets: Vec<Location> = match line.bank {
+            _ if nnn < WINDOW_START || banks == 0 => vec![(nnn, None)],
+            Some(bank) => vec![(nnn, Some(bank))],
+            None => (0..banks).map(|bank| (nnn, Some(bank))).collect(),
+        };
+        for target in targets {
+            references
+                .entry(target)
+                .or_default()
+                .push((kind, (line.addr, line.bank)));
+        }
+    }
+    references
+}
+
+// the label line for an address that is referred to, e.g.
+// "L2F0:  ; called from 204, 21A" or in bank 1 "L1_9A4:  ; called from 1:802"
+fn label((addr, bank): Location, refs: &[(Reference, Location)]) -> String {
+    let kind = refs.iter().map(|(kind, _)| *kind).min();
+    let from: Vec<String> = refs
+        .iter()
+        .map(|(_, (from, bank))| banking::format_address(*from, *bank))
+        .collect();
+    let key = match kind {
+        Some(Reference::Call) => "disasm_called_from",
+        Some(Reference::Jump) => "disasm_jumped_from",
+        _ => "disasm_used_by",
+    };
+    let name = match bank {
+        Some(bank) => format!("L{:X}_{:03X}", bank, addr),
+        None => format!("L{:03X}", addr),
+    };
+    format!("{}:  ; {}", name, trf(key, &[&from.join(", ")]))
+}
+
+// an annotated disassembly of a rom: instructions, data bytes grouped into
+// lines and a label before every address that is jumped to, called or
+// loaded into I
+pub fn listing(rom: &[u8], banked: bool) -> Vec<String> {
+    let lines = disassemble(rom, banked);
+    let banks = if banked {
+        banking::bank_count(rom.len())
+    } else {
+        0
+    };
+    let references = references(&lines, banks);
+
+    let mut output = Vec::new();
+    let mut data: Vec<&Line> = Vec::new();
+    for line in &lines {
+        let refs = references.get(&(line.addr, line.bank));
+        let is_data = line.bytes.len() == 1;
+        let new_bank = line.bank.is_some() && line.addr == WINDOW_START;
+        if !data.is_empty()
+            && (!is_data || refs.is_some() || new_bank || data.len() == DATA_BYTES_PER_LINE)
+        {
+            output.push(data_line(&data));
+            data.clear();
+        }
+        if let (true, Some(bank)) = (new_bank, line.bank) {
+            output.push(String::new());
+            output.push(format!("; {}", trf("disasm_bank", &[&bank])));
+        }
+        if let Some(refs) = refs {
+            output.push(String::new());
+            output.push(label((line.addr, line.bank), refs));
+        }
+        if is_data {
+            data.push(line);
+        } else {
+            output.push(line.to_string());
+        }
+    }
+    if !data.is_empty() {
+        output.push(data_line(&data));
+    }
+    output
+}
+
+// e.g. "2F0: DB 0xFF, 0x81, 0x81, 0xFF"
+fn data_line(data: &[&Line]) -> String {
+    let bytes: Vec<String> = data
+        .iter()
+        .map(|line| format!("{:#04X}", line.bytes[0]))
+        .collect();
+    format!(
+        "{}: DB {}",
+        banking::format_address(data[0].addr, data[0].bank),
+        bytes.join(", ")
+    )
+}
@@ -0,0 +1,173 @@
+use std::ops::Range;
+
+// 256 bytes for the display
+pub const DISPLAY_WIDTH: usize = 64;
+pub const DISPLAY_HEIGHT: usize = 32;
+
+pub const DISPLAY_SIZE: usize = DISPLAY_WIDTH * DISPLAY_HEIGHT;
+
+// the super chip high resolution mode doubles the display in each direction
+pub const HIRES_DISPLAY_WIDTH: usize = 128;
+pub const HIRES_DISPLAY_HEIGHT: usize = 64;
+
+pub const HIRES_DISPLAY_SIZE: usize = HIRES_DISPLAY_WIDTH * HIRES_DISPLAY_HEIGHT;
+
+// the chip8 framebuffer, one byte per pixel that is either 0 or 1. The buffer
+// is big enough for the high resolution mode, in low resolution only the
+// start of it is used
+pub struct Display {
+    pixelmap: [u8; HIRES_DISPLAY_SIZE],
+
+    // true in the super chip 128x64 mode
+    hires: bool,
+
+    // the rows with pixels that changed since the display was last drawn,
+    // so only those have to be drawn again
+    dirty_rows: Option<Range<usize>>,
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Display {
+            pixelmap: [0; HIRES_DISPLAY_SIZE],
+            hires: false,
+            dirty_rows: Some(0..DISPLAY_HEIGHT),
+        }
+    }
+}
+
+impl Display {
+    pub fn clear(&mut self) {
+        // set all pixels to 0
+        self.pixelmap.fill(0);
+        self.mark_all();
+    }
+
+    pub fn get_pixel_byte_addr(&self, x: u8, y: u8) -> usize {
+        (x as usize) + ((y as usize) * self.get_width())
+    }
+
+    pub fn get_pixel(&self, x: u8, y: u8) -> u8 {
+        let pixel_byte_addr = self.get_pixel_byte_addr(x, y);
+        self.pixelmap[pixel_byte_addr]
+    }
+
+    pub fn set_pixel(&mut self, x: u8, y: u8, mut val: u8) {
+        val &= 0x1;
+
+        let pixel_byte_addr = self.get_pixel_byte_addr(x, y);
+        self.pixelmap[pixel_byte_addr] ^= val;
+
+        self.mark_row(y as usize);
+    }
+
+    pub fn get_width(&self) -> usize {
+        if self.hires {
+            HIRES_DISPLAY_WIDTH
+        } else {
+            DISPLAY_WIDTH
+        }
+    }
+
+    pub fn get_height(&self) -> usize {
+        if self.hires {
+            HIRES_DISPLAY_HEIGHT
+        } else {
+            DISPLAY_HEIGHT
+        }
+    }
+
+    pub fn is_hires(&self) -> bool {
+        self.hires
+    }
+
+    // switch between the 64x32 and 128x64 modes, this clears the display
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.clear();
+    }
+
+    // move every row down by n pixels, rows scrolled in at the top are blank
+    pub fn scroll_down(&mut self, n: usize) {
+        let width = self.get_width();
+        let size = width * self.get_height();
+        let n = std::cmp::min(n, self.get_height());
+
+        self.pixelmap.copy_within(0..size - n * width, n * width);
+        self.pixelmap[..n * width].fill(0);
+        self.mark_all();
+    }
+
+    // move every row right by n pixels, columns scrolled in are blank
+    pub fn scroll_right(&mut self, n: usize) {
+        let width = self.get_width();
+        let size = width * self.get_height();
+        let n = std::cmp::min(n, width);
+
+        for row in self.pixelmap[..size].chunks_mut(width) {
+            row.copy_within(0..width - n, n);
+            row[..n].fill(0);
+        }
+        self.mark_all();
+    }
+
+    // move every row left by n pixels, columns scrolled in are blank
+    pub fn scroll_left(&mut self, n: usize) {
+        let width = self.get_width();
+        let size = width * self.get_height();
+        let n = std::cmp::min(n, width);
+
+        for row in self.pixelmap[..size].chunks_mut(width) {
+            row.copy_within(n.., 0);
+            row[width - n..].fill(0);
+        }
+        self.mark_all();
+    }
+
+    // the pixels row by row
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixelmap[..self.get_width() * self.get_height()]
+    }
+
+    // the pixels packed 8 to a byte, for compact snapshots
+    pub fn pack(&self) -> Vec<u8> {
+        self.pixels()
+            .chunks(8)
+            .map(|chunk| chunk.iter().fold(0, |byte, pixel| (byte << 1) | pixel))
+            .collect()
+    }
+
+    // restore the mode and pixels saved with pack()
+    pub fn unpack(&mut self, hires: bool, packed: &[u8]) {
+        self.hires = hires;
+        for (i, byte) in packed.iter().enumerate() {
+            for bit in 0..8 {
+                self.pixelmap[i * 8 + bit] = (byte >> (7 - bit)) & 0x1;
+            }
+        }
+        self.mark_all();
+    }
+
+    fn mark_row(&mut self, y: usize) {
+        self.dirty_rows = Some(match self.dirty_rows.take() {
+            Some(rows) => rows.start.min(y)..rows.end.max(y + 1),
+            None => y..y + 1,
+        });
+    }
+
+    fn mark_all(&mut self) {
+        self.dirty_rows = Some(0..self.get_height());
+    }
+
+    // returns the rows that changed since the last call, from the first to
+    // the last changed one, and resets them
+    pub fn take_dirty_rows(&mut self) -> Option<Range<usize>> {
+        self.dirty_rows.take()
+    }
+
+    // returns true if the display changed since the last call and resets the
+    // changed rows
+    pub fn take_dirty(&mut self) -> bool {
+        self.take_dirty_rows().is_some()
+    }
+}
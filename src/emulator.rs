@@ -0,0 +1,1917 @@
+use std::collections::VecDeque;
+use std::num::Wrapping;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::achievements::Trigger;
+use crate::audio;
+use crate::audio::TurboAudio;
+use crate::backend::AudioSink;
+use crate::backend::Command;
+use crate::backend::InputSource;
+use crate::backend::VideoSink;
+use crate::budget::CycleBudget;
+use crate::classroom;
+use crate::classroom::CpuState;
+use crate::clock::Clock;
+use crate::clock::Instant;
+use crate::colors;
+use crate::colors::Theme;
+use crate::debugger::DebugHook;
+use crate::error::Chip8Error;
+use crate::expr::MachineView;
+use crate::fault::FaultPolicies;
+use crate::fault::FaultPolicy;
+use crate::ffmpeg::VideoRecorder;
+use crate::gif::GifRecorder;
+use crate::hotkeys::Hotkeys;
+use crate::i18n::tr;
+use crate::i18n::trf;
+use crate::interpreter::Interpreter;
+use crate::interpreter::Snapshot;
+use crate::library::Thumbnail;
+use crate::netplay::Netplay;
+use crate::osd::Osd;
+use crate::osd::Scope;
+use crate::osd::StateMenu;
+use crate::palette::Palette;
+use crate::palette::PaletteKey;
+use crate::quirkhints::QuirkHints;
+use crate::recent::RecentMenu;
+use crate::replay::Event;
+use crate::replay::Replay;
+use crate::replay::ReplayRecorder;
+use crate::rewind::RewindBuffer;
+use crate::romdb::RomInfo;
+use crate::savestate;
+use crate::savestate::SaveState;
+use crate::savestate::StateInfo;
+use crate::screenshot;
+use crate::screenshot::ScreenshotTriggers;
+use crate::spectate::SpectatorServer;
+use crate::stats;
+#[cfg(feature = "strict")]
+use crate::strict::Strict;
+use crate::stuck::StuckDetector;
+
+// the rate the timers count down at, and the default number of updates a
+// second
+const TIMER_RATE: u32 = 60;
+
+// the clock speeds stepped through by the faster and slower hotkeys
+const CLOCKSPEED_STEPS: &[u32] = &[
+    100, 200, 300, 400, 500, 600, 700, 800, 1000, 1200, 1500, 2000, 3000, 5000, 10000,
+];
+
+// how much the volume hotkeys change the volume by
+const VOLUME_STEP: u8 = 10;
+
+// the number of updates of sound timer history the oscilloscope shows
+const SCOPE_HISTORY: usize = 64;
+
+// the frames a spectator can be behind the host before playing faster, and
+// the most played an update to catch up
+const CATCH_UP_AFTER: usize = 10;
+const MAX_CATCH_UP: usize = 30;
+
+// the furthest catch_up runs behind before it gives up on the time missed,
+// e.g. while the browser tab was hidden, in microseconds
+const MAX_LAG: u128 = 250_000;
+
+// runs the interpreter in real time with backends for the display, sound and
+// keypad
+pub struct Emulator<'a> {
+    // the chip8 machine
+    interp: Interpreter,
+
+    // the machine as it was with the rom just loaded, resetting goes back to
+    // it
+    power_on: Snapshot,
+
+    // the machine as it was before the last state load or reset, undoing
+    // swaps it with the machine as it is so undoing again redoes
+    backup: Option<Snapshot>,
+
+    // the video device used for drawing to screen
+    video_device: Box<dyn VideoSink + 'a>,
+
+    // the audio device used for the beeps
+    audio_device: Box<dyn AudioSink + 'a>,
+
+    // the input devices used for the keypad and commands
+    input_device: Box<dyn InputSource + 'a>,
+
+    // text drawn on top of the display
+    osd: Osd,
+
+    // conditions on the machine state that show a message when they are met
+    triggers: Vec<Trigger>,
+
+    // conditions on the machine state that take a screenshot when first met
+    screenshot_triggers: Option<ScreenshotTriggers>,
+
+    // screenshots taken with the hotkey are written to
+    // <prefix>-screenshot-<n>.png and recordings to <prefix>-recording-<n>.gif
+    capture_prefix: PathBuf,
+
+    // the gif being recorded
+    recorder: Option<GifRecorder>,
+    video_recorder: Option<VideoRecorder>,
+
+    // how the opcodes of this update changed the buzzer while recording
+    // video, each the number of opcodes into the update, whether the sound
+    // timer is running and the pitch register. The first is the buzzer at
+    // the start of the update
+    buzzer_changes: Vec<(u32, bool, Option<u8>)>,
+    update_opcodes: u32,
+
+    // the sound timer at each of the last updates while the oscilloscope is
+    // shown, None if it is hidden
+    scope_timers: Option<VecDeque<u8>>,
+
+    // the sha1 of the rom, save states remember which rom they are of
+    rom_sha1: String,
+
+    // the seconds the rom was played before this run and when it started,
+    // save states note the total
+    playtime: u64,
+    playtime_start: Instant,
+
+    // the save state slots while picking one, the machine waits meanwhile
+    state_menu: Option<StateMenu>,
+
+    // the built in theme being drawn in, None for colours of its own
+    theme: Option<&'static str>,
+    crt: bool,
+    fullscreen: bool,
+
+    // the opcodes run since the rom was loaded, replays time keys by it
+    cycles: u64,
+    replay: Option<Replay>,
+    replay_keys: [bool; 0x10],
+    replay_recorder: Option<ReplayRecorder>,
+    // the replay streamed to spectators watching live
+    spectator_feed: Option<ReplayRecorder>,
+    // the game played with another player over the network, a frame at a
+    // time in step with their machine
+    netplay: Option<Netplay>,
+
+    // the database entry for the loaded rom if it was recognised
+    rom_info: Option<RomInfo>,
+
+    // show every instruction and what it changed
+    classroom: bool,
+
+    // a snapshot from every update, used to run the machine backwards
+    rewind: RewindBuffer,
+    rewind_seconds: u32,
+
+    // true while the rewind key is held
+    rewinding: bool,
+
+    // the pitch of the buzzer for roms that don't set the pitch register,
+    // and the semitones the buzzer is moved up or down by
+    tone_hz: f32,
+    pitch_offset: f32,
+
+    // how loud the buzzer is, from 0 to 100
+    volume: u8,
+
+    // true while the turbo key is held, the machine then runs this many
+    // times faster
+    turbo: bool,
+    turbo_multiplier: u32,
+    turbo_audio: TurboAudio,
+
+    // pauses at breakpoints and steps through opcodes
+    debugger: Option<Box<dyn DebugHook + 'a>>,
+
+    // the key bindings and the command palette while it is open
+    hotkeys: Hotkeys,
+    palette: Option<Palette>,
+
+    // the other roms played lately, the menu of them while it is open, when
+    // the machine waits, and the one picked from it to run next
+    recent_roms: Vec<PathBuf>,
+    recent_menu: Option<RecentMenu>,
+    next_rom: Option<PathBuf>,
+
+    // the address and opcode of the last opcodes run, kept for crash
+    // reports. Nothing is kept when the length is 0
+    trace: VecDeque<(usize, u16)>,
+    trace_length: usize,
+
+    // watches the opcodes run for signs the rom wants other quirks
+    quirk_hints: QuirkHints,
+    // warns when a frame's work is more than the opcodes a frame has
+    cycle_budget: Option<CycleBudget>,
+    // pauses the machine when the rom looks hung, and set once it has
+    stuck_detector: Option<StuckDetector>,
+    stuck: bool,
+    // faults on anything conservative interpreters run differently
+    #[cfg(feature = "strict")]
+    strict: Option<Strict>,
+
+    // set while the user has paused the machine
+    paused: bool,
+
+    // set when the user asked to quit, the frontend stops calling update
+    quit: bool,
+
+    // what stopped the machine if an opcode faulted, it quits too
+    fault: Option<Chip8Error>,
+    // what to do about each kind of fault, and the number the machine carried
+    // on after
+    fault_policies: FaultPolicies,
+    faults_survived: u64,
+
+    // the number of opcodes a second and the ticks between them
+    clockspeed: u32,
+    opcode_ticks: u128,
+
+    // the number of updates a second and the ticks between them
+    refresh: u32,
+    update_ticks: u128,
+
+    // the timers count down at 60hz when updating faster, this adds up 60
+    // each update and the timers count down once for every refresh in it
+    timer_credit: u32,
+
+    // Time of the next opcode
+    next_opcode_time: Wrapping<u128>,
+
+    // the update time controls when the render and the timer decrement happens
+    // this happens at a rate of 60hz
+    next_update_time: Wrapping<u128>,
+}
+
+impl<'a> Emulator<'a> {
+    pub fn new(
+        interp: Interpreter,
+        video_device: Box<dyn VideoSink + 'a>,
+        audio_device: Box<dyn AudioSink + 'a>,
+        input_device: Box<dyn InputSource + 'a>,
+        clockspeed: u32,
+        clock: &impl Clock,
+    ) -> Emulator<'a> {
+        Emulator {
+            power_on: interp.snapshot(),
+            backup: None,
+            interp,
+            video_device,
+            audio_device,
+            input_device,
+            osd: Osd::default(),
+            triggers: Vec::new(),
+            screenshot_triggers: None,
+            capture_prefix: PathBuf::from("chip8"),
+            recorder: None,
+            video_recorder: None,
+            buzzer_changes: Vec::new(),
+            update_opcodes: 0,
+            scope_timers: None,
+            rom_sha1: String::new(),
+            playtime: 0,
+            playtime_start: Instant::now(),
+            state_menu: None,
+            theme: None,
+            crt: false,
+            fullscreen: false,
+            cycles: 0,
+            replay: None,
+            replay_keys: [false; 0x10],
+            replay_recorder: None,
+            spectator_feed: None,
+            netplay: None,
+            rom_info: None,
+            classroom: false,
+            rewind: RewindBuffer::new(0),
+            rewind_seconds: 0,
+            rewinding: false,
+            tone_hz: audio::BUZZER_HZ,
+            volume: 100,
+            pitch_offset: 0.0,
+            turbo: false,
+            turbo_multiplier: 4,
+            turbo_audio: TurboAudio::default(),
+            debugger: None,
+            hotkeys: Hotkeys::default(),
+            palette: None,
+            recent_roms: Vec::new(),
+            recent_menu: None,
+            next_rom: None,
+            trace: VecDeque::new(),
+            trace_length: 0,
+            quirk_hints: QuirkHints::new(),
+            cycle_budget: None,
+            stuck_detector: None,
+            stuck: false,
+            #[cfg(feature = "strict")]
+            strict: None,
+            paused: false,
+            quit: false,
+            fault: None,
+            fault_policies: FaultPolicies::default(),
+            faults_survived: 0,
+            clockspeed,
+            opcode_ticks: ticks_between(clockspeed),
+            refresh: TIMER_RATE,
+            update_ticks: ticks_between(TIMER_RATE),
+            timer_credit: 0,
+            next_opcode_time: Wrapping(clock.elapsed().as_micros()),
+            next_update_time: Wrapping(clock.elapsed().as_micros()),
+        }
+    }
+
+    // set the achievement triggers that are checked after every opcode
+    pub fn set_triggers(&mut self, triggers: Vec<Trigger>) {
+        self.triggers = triggers;
+    }
+
+    // set the conditions that take a screenshot when first met
+    pub fn set_screenshot_triggers(&mut self, screenshot_triggers: ScreenshotTriggers) {
+        self.screenshot_triggers = Some(screenshot_triggers);
+    }
+
+    // where the screenshots and recordings are written, usually the romfile
+    // without its extension
+    pub fn set_capture_prefix(&mut self, prefix: PathBuf) {
+        self.capture_prefix = prefix;
+    }
+
+    // write the display to a png as it is drawn in the window, returns the
+    // path of the file
+    pub fn take_screenshot(&mut self) -> Result<Option<PathBuf>, String> {
+        let Some(pixelmap) = self.video_device.pixelmap(self.interp.display()) else {
+            return Ok(None);
+        };
+        let path = screenshot::next_free_path(&self.capture_prefix, "screenshot", "png");
+        pixelmap.save_png(&path)?;
+        Ok(Some(path))
+    }
+
+    // take a screenshot for the hotkey and say where it went
+    fn screenshot(&mut self) {
+        let message = match self.take_screenshot() {
+            Ok(Some(path)) => trf("screenshot_saved", &[&path.display()]),
+            Ok(None) => return,
+            Err(e) => e,
+        };
+        println!("{}", message);
+        self.osd.toast(&message);
+    }
+
+    // start recording a gif of the display, or stop and write it
+    fn toggle_recording(&mut self) {
+        if self.recorder.is_some() {
+            self.stop_recording();
+            return;
+        }
+        let Some(pixelmap) = self.video_device.pixelmap(self.interp.display()) else {
+            return;
+        };
+        let path = screenshot::next_free_path(&self.capture_prefix, "recording", "gif");
+        let message = match GifRecorder::create(&path, pixelmap.width, pixelmap.height) {
+            Ok(recorder) => {
+                self.recorder = Some(recorder);
+                trf("recording_started", &[&path.display()])
+            }
+            Err(e) => e,
+        };
+        println!("{}", message);
+        self.osd.toast(&message);
+    }
+
+    // finish the gif being recorded if there is one
+    pub fn stop_recording(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+        let message = match recorder.finish() {
+            Ok(path) => trf("recording_saved", &[&path.display()]),
+            Err(e) => e,
+        };
+        println!("{}", message);
+        self.osd.toast(&message);
+    }
+
+    // add the display to the gif being recorded, a write error stops the
+    // recording
+    fn record_frame(&mut self) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+        let Some(pixelmap) = self.video_device.pixelmap(self.interp.display()) else {
+            return;
+        };
+        if let Err(e) = recorder.add_frame(pixelmap, 100.0 / self.refresh as f32) {
+            println!("{}", e);
+            self.osd.toast(&e);
+            self.recorder = None;
+        }
+    }
+
+    // start recording the display and the buzzer to a video with ffmpeg,
+    // at one frame for each update
+    pub fn start_video(&mut self, path: &Path) -> Result<(), String> {
+        let Some(pixelmap) = self.video_device.pixelmap(self.interp.display()) else {
+            return Ok(());
+        };
+        let recorder = VideoRecorder::create(path, pixelmap.width, pixelmap.height, self.refresh)?;
+        self.video_recorder = Some(recorder);
+        self.start_buzzer_changes();
+        println!("{}", trf("recording_started", &[&path.display()]));
+        Ok(())
+    }
+
+    // finish the video being recorded if there is one
+    pub fn stop_video(&mut self) {
+        let Some(recorder) = self.video_recorder.take() else {
+            return;
+        };
+        match recorder.finish() {
+            Ok(path) => println!("{}", trf("recording_saved", &[&path.display()])),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    // start following the buzzer through an update from where it is now
+    fn start_buzzer_changes(&mut self) {
+        self.buzzer_changes.clear();
+        self.buzzer_changes
+            .push((0, self.interp.is_beeping(), self.interp.pitch()));
+        self.update_opcodes = 0;
+    }
+
+    // note a change to the buzzer by the opcode just run while recording
+    // video, so the sound changes at the same point of the frame
+    fn track_buzzer(&mut self) {
+        if self.video_recorder.is_none() {
+            return;
+        }
+        self.update_opcodes += 1;
+        let change = (
+            self.update_opcodes,
+            self.interp.is_beeping(),
+            self.interp.pitch(),
+        );
+        let last = self
+            .buzzer_changes
+            .last()
+            .map(|(_, on, pitch)| (*on, *pitch));
+        if last != Some((change.1, change.2)) {
+            self.buzzer_changes.push(change);
+        }
+    }
+
+    // add the display and the buzzer of this update to the video being
+    // recorded, an error stops the recording
+    fn record_video(&mut self, audible: bool) {
+        if self.video_recorder.is_none() {
+            return;
+        }
+        let Some(pixelmap) = self.video_device.pixelmap(self.interp.display()) else {
+            return;
+        };
+        let opcodes = self.update_opcodes.max(1) as f32;
+        let buzzer: Vec<(f32, Option<f32>)> = self
+            .buzzer_changes
+            .iter()
+            .map(|(opcode, on, pitch)| {
+                let beep = Some(self.buzzer_pitch(*pitch)).filter(|_| *on && audible);
+                (*opcode as f32 / opcodes, beep)
+            })
+            .collect();
+        self.start_buzzer_changes();
+
+        let Some(recorder) = self.video_recorder.as_mut() else {
+            return;
+        };
+        if let Err(e) = recorder.add_frame(&pixelmap, &buzzer) {
+            println!("{}", e);
+            self.osd.toast(&e);
+            if let Some(recorder) = self.video_recorder.take() {
+                recorder.cancel();
+            }
+        }
+    }
+
+    // start writing a replay of the keys from here, the seed is written to
+    // it so the random numbers play out the same
+    pub fn record_replay(&mut self, path: &Path, sha1: &str, seed: u64) -> Result<(), String> {
+        let recorder = ReplayRecorder::create(path, sha1, seed, self.refresh)?;
+        self.replay_recorder = Some(recorder);
+        println!("{}", trf("replay_recording", &[&path.display()]));
+        Ok(())
+    }
+
+    // finish the replay being recorded if there is one
+    pub fn stop_replay_recording(&mut self) {
+        let Some(recorder) = self.replay_recorder.take() else {
+            return;
+        };
+        match recorder.finish() {
+            Ok(path) => println!("{}", trf("replay_saved", &[&path])),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    // stream the run to spectators on a port as it is played, the seed is
+    // sent to them like a replay's
+    pub fn host_spectators(&mut self, port: u16, sha1: &str, seed: u64) -> Result<(), String> {
+        let server = SpectatorServer::bind(port)?;
+        let name = trf("spectators_name", &[&port]);
+        let feed = ReplayRecorder::stream(Box::new(server), &name, sha1, seed, self.refresh)?;
+        self.spectator_feed = Some(feed);
+        println!("{}", trf("spectators_listening", &[&port]));
+        Ok(())
+    }
+
+    // a replay is being written, to a file or to spectators, so the machine
+    // can't jump around
+    fn is_recording_replay(&self) -> bool {
+        self.replay_recorder.is_some() || self.spectator_feed.is_some()
+    }
+
+    // play a replay in place of the keypad, the machine must be fresh from
+    // loading the rom with the seed of the replay
+    pub fn set_replay(&mut self, replay: Replay) {
+        self.replay = Some(replay);
+    }
+
+    // write an event to the replays being recorded and streamed, an error
+    // stops that one. A checksum is only written to those it is due in
+    fn record_replay_event(&mut self, event: Event) {
+        for slot in [&mut self.replay_recorder, &mut self.spectator_feed] {
+            let Some(recorder) = slot.as_mut() else {
+                continue;
+            };
+            let result = match event {
+                Event::Keys(cycle, keys) => recorder.keys(cycle, keys),
+                Event::Frame(cycle, count) => recorder.frame(cycle, count),
+                Event::Reset(cycle) => recorder.reset(cycle),
+                Event::Checksum(_, checksum) => match recorder.checksum_due() {
+                    Some(frame) => recorder.checksum(frame, checksum),
+                    None => Ok(()),
+                },
+            };
+            if let Err(e) = result {
+                println!("{}", e);
+                self.osd.toast(&e);
+                *slot = None;
+            }
+        }
+    }
+
+    // run the next update of the replay, each key change and reset happens
+    // at the opcode it happened at when recorded. False when the replay is
+    // over, the keypad takes over again
+    fn play_replay_frame(&mut self) -> bool {
+        let Some(events) = self.replay.as_mut().and_then(|replay| replay.next_frame()) else {
+            if self.replay.take().is_some() {
+                println!("{}", tr("replay_finished"));
+                self.osd.toast(&tr("replay_finished"));
+            }
+            return false;
+        };
+        for event in events {
+            // the keypad is set again every update, as FX0A lets go of the
+            // keys it waited for
+            match event {
+                Event::Keys(cycle, keys) => {
+                    self.run_to_cycle(cycle);
+                    self.replay_keys = keys;
+                    self.interp.set_keys(keys);
+                }
+                Event::Frame(cycle, count) => {
+                    self.run_to_cycle(cycle);
+                    self.interp.set_keys(self.replay_keys);
+                    self.run_timers(count);
+                }
+                Event::Reset(cycle) => {
+                    self.run_to_cycle(cycle);
+                    self.reset();
+                }
+                Event::Checksum(frame, checksum) if checksum != self.interp.checksum() => {
+                    // carrying on would only play keys to a different game
+                    let message = trf("replay_desync", &[&frame]);
+                    println!("{}", message);
+                    self.osd.toast(&message);
+                    self.replay = None;
+                    return false;
+                }
+                Event::Checksum(..) => {}
+            }
+        }
+        true
+    }
+
+    // play with another player over the network, the machine must be fresh
+    // from loading the rom with the settings of the game
+    pub fn set_netplay(&mut self, netplay: Netplay) {
+        self.netplay = Some(netplay);
+    }
+
+    // run the next frame of a network game once the other player's keys for
+    // it have come, with both players' keys held. The machine waits
+    // meanwhile, and carries on with the keypad alone if the game ends
+    fn play_netplay_frame(&mut self, keys: [bool; 0x10]) {
+        let Some(netplay) = self.netplay.as_mut() else {
+            return;
+        };
+        let keys = match netplay.next_keys(keys) {
+            Ok(Some(keys)) => keys,
+            Ok(None) => {
+                if netplay.is_stalled() {
+                    self.osd.toast(&tr("netplay_stalled"));
+                }
+                return;
+            }
+            Err(e) => return self.end_netplay(&e),
+        };
+        self.interp.set_keys(keys);
+        self.record_replay_event(Event::Keys(self.cycles, keys));
+        self.run_frame();
+        let checksum = self.interp.checksum();
+        if let Some(Err(e)) = self
+            .netplay
+            .as_mut()
+            .map(|netplay| netplay.frame_done(checksum))
+        {
+            self.end_netplay(&e);
+        }
+    }
+
+    fn end_netplay(&mut self, message: &str) {
+        println!("{}", message);
+        self.osd.toast(message);
+        self.netplay = None;
+    }
+
+    // run opcodes until the count since loading reaches cycle, stopping
+    // early if the debugger stops the machine
+    fn run_to_cycle(&mut self, cycle: u64) {
+        while self.cycles < cycle {
+            if !self.run_opcode() {
+                break;
+            }
+        }
+    }
+
+    // show the oscilloscope, or hide it if it is shown
+    fn toggle_scope(&mut self) {
+        if self.scope_timers.take().is_some() {
+            self.osd.set_scope(None);
+        } else {
+            self.scope_timers = Some(VecDeque::with_capacity(SCOPE_HISTORY));
+            self.update_scope();
+        }
+    }
+
+    // give the oscilloscope the sound of the last update and the sound timer
+    fn update_scope(&mut self) {
+        let Some(timers) = self.scope_timers.as_mut() else {
+            return;
+        };
+        if timers.len() == SCOPE_HISTORY {
+            timers.pop_front();
+        }
+        timers.push_back(self.interp.sound_timer());
+        let samples = match self.audio_device.sample_tap() {
+            Some(tap) => tap.recent((tap.rate() / self.refresh) as usize),
+            None => Vec::new(),
+        };
+        self.osd.set_scope(Some(Scope {
+            samples,
+            timers: timers.iter().copied().collect(),
+        }));
+    }
+
+    // set the database entry shown in the rom info panel
+    pub fn set_rom_info(&mut self, rom_info: RomInfo) {
+        self.rom_info = Some(rom_info);
+    }
+
+    // annotate each instruction on screen and on the console
+    pub fn set_classroom(&mut self, classroom: bool) {
+        self.classroom = classroom;
+    }
+
+    // keep enough snapshots to rewind the given number of seconds, 0 turns
+    // rewinding off
+    pub fn set_rewind_seconds(&mut self, seconds: u32) {
+        self.rewind_seconds = seconds;
+        // more snapshots than a u32 holds is no use to anyone, rewinding is
+        // turned off rather than taking all the memory
+        let snapshots = seconds.checked_mul(self.refresh).unwrap_or(0);
+        self.rewind = RewindBuffer::new(snapshots as usize);
+    }
+
+    // the number of times a second the screen is drawn and the keys read.
+    // Below 60 the timers count down once an update too, so roms timed by
+    // the delay timer run slower like on a 50hz PAL machine. Above 60 the
+    // timers still count down at 60hz and only the drawing gets smoother
+    pub fn set_refresh(&mut self, refresh: u32) {
+        self.refresh = refresh;
+        self.update_ticks = ticks_between(refresh);
+        self.set_rewind_seconds(self.rewind_seconds);
+    }
+
+    // run under a debugger, the console one or a gdb connection
+    pub fn set_debugger(&mut self, debugger: Box<dyn DebugHook + 'a>) {
+        self.debugger = Some(debugger);
+    }
+
+    // true while the debugger has stopped the machine
+    fn is_paused(&self) -> bool {
+        self.paused
+            || self.state_menu.is_some()
+            || self.recent_menu.is_some()
+            || self
+                .debugger
+                .as_ref()
+                .is_some_and(|debugger| debugger.is_paused())
+    }
+
+    // stop running opcodes and timers, or start them again
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.show_state();
+    }
+
+    // the pitch of the buzzer in hz for roms that don't set the pitch
+    // register
+    pub fn set_tone_hz(&mut self, hz: f32) {
+        self.tone_hz = hz;
+    }
+
+    // how loud the buzzer is, from 0 for silent to 100
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume.min(100);
+        self.audio_device.set_volume(self.volume);
+    }
+
+    // step the volume up or down and show it for a moment
+    fn change_volume(&mut self, louder: bool) {
+        let volume = if louder {
+            self.volume.saturating_add(VOLUME_STEP)
+        } else {
+            self.volume.saturating_sub(VOLUME_STEP)
+        };
+        self.set_volume(volume);
+        self.osd.toast(&trf("volume_changed", &[&self.volume]));
+    }
+
+    // move the buzzer up or down by a number of semitones
+    pub fn set_pitch_offset(&mut self, offset: f32) {
+        self.pitch_offset = offset;
+    }
+
+    // how many times faster the machine runs while the turbo key is held
+    pub fn set_turbo_multiplier(&mut self, multiplier: u32) {
+        self.turbo_multiplier = multiplier;
+    }
+
+    // mute the buzzer or limit its pitch while the turbo key is held
+    pub fn set_turbo_audio(&mut self, turbo_audio: TurboAudio) {
+        self.turbo_audio = turbo_audio;
+    }
+
+    // the number of opcodes and timer counts run for each normal one
+    fn speed_multiplier(&self) -> u32 {
+        if self.turbo {
+            self.turbo_multiplier
+        } else {
+            1
+        }
+    }
+
+    // show on screen that the machine is rewinding, in turbo or paused
+    fn show_state(&mut self) {
+        if self.rewinding {
+            self.osd.set_status(vec![tr("rewinding")]);
+        } else if self.turbo {
+            self.osd.set_status(vec![tr("turbo")]);
+        } else if self.paused {
+            self.osd.set_status(vec![tr("paused")]);
+        } else {
+            self.osd.set_status(Vec::new());
+        }
+    }
+
+    // keep the last length opcodes run
+    pub fn set_trace_length(&mut self, length: usize) {
+        self.trace_length = length;
+        self.trace.truncate(length);
+    }
+
+    // the last opcodes run and their addresses, oldest first
+    pub fn trace(&self) -> Vec<(usize, u16)> {
+        self.trace.iter().copied().collect()
+    }
+
+    pub fn interpreter(&self) -> &Interpreter {
+        &self.interp
+    }
+
+    // the name of the built in theme the display is drawn in, the theme
+    // hotkey changes it
+    pub fn theme(&self) -> Option<&'static str> {
+        self.theme
+    }
+
+    // the built in theme the display starts in, the theme hotkey steps on
+    // from it
+    pub fn set_theme(&mut self, name: &str) {
+        self.theme = colors::THEME_NAMES
+            .iter()
+            .find(|theme_name| **theme_name == name)
+            .copied();
+    }
+
+    // whether the display is drawn like an old tube television
+    pub fn crt(&self) -> bool {
+        self.crt
+    }
+
+    // draw the display like an old tube television, or plainly
+    pub fn set_crt(&mut self, on: bool) {
+        self.crt = on;
+        self.video_device.set_crt(on);
+    }
+
+    pub fn set_fullscreen(&mut self, on: bool) {
+        self.fullscreen = on;
+        self.video_device.set_fullscreen(on);
+    }
+
+    // draw in the next built in theme and say which it is
+    fn cycle_theme(&mut self) {
+        let (name, theme) = Theme::next(self.theme);
+        self.theme = Some(name);
+        self.video_device.set_theme(theme);
+        self.osd.toast(&trf("theme_changed", &[&name]));
+    }
+
+    // the number of opcodes run a second
+    pub fn clockspeed(&self) -> u32 {
+        self.clockspeed
+    }
+
+    // the opcodes run since the rom started
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    // change the number of opcodes run a second while running
+    pub fn set_clockspeed(&mut self, clockspeed: u32) {
+        self.clockspeed = clockspeed;
+        self.opcode_ticks = ticks_between(clockspeed);
+    }
+
+    // step to the next faster or slower clock speed and show it for a moment
+    fn change_clockspeed(&mut self, faster: bool) {
+        let step = if faster {
+            CLOCKSPEED_STEPS
+                .iter()
+                .find(|step| **step > self.clockspeed)
+        } else {
+            CLOCKSPEED_STEPS
+                .iter()
+                .rev()
+                .find(|step| **step < self.clockspeed)
+        };
+        if let Some(step) = step {
+            self.set_clockspeed(*step);
+        }
+        self.osd
+            .toast(&trf("clockspeed_changed", &[&self.clockspeed]));
+    }
+
+    // true once the window was closed or the quit hotkey pressed
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    // the fault that stopped the machine, if one did
+    pub fn fault(&self) -> Option<&Chip8Error> {
+        self.fault.as_ref()
+    }
+
+    // run what is due by the clock and sleep till the next deadline, the
+    // start of the run is the clock for real time
+    pub fn update(&mut self, clock: &impl Clock) {
+        let ticks = Wrapping(clock.elapsed().as_micros());
+        if self.run_due(ticks) {
+            self.do_sleep(clock, ticks);
+        }
+    }
+
+    // run everything due by the time since the start for a frontend that is
+    // called back once a frame instead of sleeping, such as the browser with
+    // requestAnimationFrame. Each batch of opcodes and each update runs at
+    // its deadline as if the emulator had slept till it
+    pub fn catch_up(&mut self, elapsed: Duration) {
+        let ticks = elapsed.as_micros();
+        let deadline =
+            |emulator: &Emulator| emulator.next_opcode_time.0.min(emulator.next_update_time.0);
+        if ticks.saturating_sub(deadline(self)) > MAX_LAG {
+            self.next_opcode_time = Wrapping(ticks);
+            self.next_update_time = Wrapping(ticks);
+        }
+        while !self.quit && deadline(self) <= ticks {
+            self.run_due(Wrapping(deadline(self)));
+        }
+    }
+
+    // run the opcodes and the update if their time has come, true if either
+    // did
+    fn run_due(&mut self, ticks: Wrapping<u128>) -> bool {
+        let mut action_happened = false;
+
+        // handle opcode timer
+        if ticks >= self.next_opcode_time {
+            self.handle_opcode(ticks);
+            action_happened = true;
+        }
+
+        // handle update timer
+        if ticks >= self.next_update_time {
+            self.handle_update(ticks);
+            action_happened = true;
+        }
+        action_happened
+    }
+
+    fn handle_opcode(&mut self, ticks: Wrapping<u128>) {
+        // the machine only goes backwards while rewinding
+        self.next_opcode_time = ticks + Wrapping(self.opcode_ticks);
+        if self.rewinding
+            || self.paused
+            || self.replay.is_some()
+            || self.netplay.is_some()
+            || self.state_menu.is_some()
+            || self.recent_menu.is_some()
+        {
+            return;
+        }
+        self.run_opcodes(self.speed_multiplier());
+    }
+
+    // run a number of opcodes straight away, whatever the time. This stops
+    // early if the debugger stops the machine
+    pub fn run_opcodes(&mut self, count: u32) {
+        for _ in 0..count {
+            if !self.run_opcode() {
+                break;
+            }
+        }
+    }
+
+    // end a frame of emulation, saving a snapshot for rewinding and counting
+    // the timers down the given number of 60ths of a second
+    fn run_timers(&mut self, count: u32) {
+        self.record_replay_event(Event::Frame(self.cycles, count));
+        self.rewind.push(self.interp.snapshot());
+        for _ in 0..count {
+            self.count_down_timers();
+        }
+        let due = [&self.replay_recorder, &self.spectator_feed]
+            .into_iter()
+            .flatten()
+            .filter_map(|recorder| recorder.checksum_due())
+            .next();
+        if let Some(frame) = due {
+            self.record_replay_event(Event::Checksum(frame, self.interp.checksum()));
+        }
+        self.suggest_quirks();
+        self.report_budget();
+        self.check_stuck(count);
+    }
+
+    // pause when the rom has gone round the same few opcodes for this many
+    // seconds without drawing, reading the keys or using the timers
+    pub fn set_pause_when_stuck(&mut self, seconds: u32) {
+        self.stuck_detector = Some(StuckDetector::new(seconds));
+    }
+
+    // true if the machine was paused because the rom looked hung
+    pub fn is_stuck(&self) -> bool {
+        self.stuck
+    }
+
+    // pause with where the rom is stuck if it has hung
+    fn check_stuck(&mut self, count: u32) {
+        let refresh = self.refresh;
+        let Some(stuck) = self
+            .stuck_detector
+            .as_mut()
+            .and_then(|detector| detector.update(&self.interp, count, refresh))
+        else {
+            return;
+        };
+        let lines = stuck.lines(&self.interp);
+        for line in &lines {
+            println!("{}", line);
+        }
+        self.stuck = true;
+        self.paused = true;
+        self.show_state();
+        self.osd.toast(&lines[0]);
+    }
+
+    // fault on opcodes beyond chip8, memory out of bounds, deep calls and
+    // anything the quirks change, before the opcode runs
+    #[cfg(feature = "strict")]
+    pub fn set_strict(&mut self) {
+        self.strict = Some(Strict::default());
+    }
+
+    // the fault strict mode finds in the opcode about to run
+    fn check_strict(&mut self) -> Result<(), Chip8Error> {
+        #[cfg(feature = "strict")]
+        if let Some(strict) = self.strict.as_mut() {
+            return strict.check(&self.interp);
+        }
+        Ok(())
+    }
+
+    // warn about the rom's frames that need more opcodes than the budget, the
+    // work the rom does between waiting for the timer
+    pub fn set_cycle_budget(&mut self, opcodes: u32) {
+        self.cycle_budget = Some(CycleBudget::new(opcodes));
+    }
+
+    // tell the user about work that went over the cycle budget, once for
+    // each part of the rom it was done in
+    fn report_budget(&mut self) {
+        let Some(alert) = self.cycle_budget.as_mut().and_then(|budget| budget.alert()) else {
+            return;
+        };
+        let lines = alert.lines();
+        for line in &lines {
+            println!("{}", line);
+        }
+        self.osd.toast(&lines[0]);
+    }
+
+    // tell the user once if the rom has run opcodes that look like it was
+    // written for other quirks
+    fn suggest_quirks(&mut self) {
+        if let Some(suggestion) = self.quirk_hints.suggest(self.interp.quirks()) {
+            let message = trf("quirks_hint", &[&suggestion.fix]);
+            println!("{}", message);
+            for reason in &suggestion.reasons {
+                println!("  {}", reason);
+            }
+            self.osd.toast(&message);
+        }
+    }
+
+    // run the opcodes of one update and count the timers down once, the same
+    // as an update at the current clock speed without waiting for it
+    pub fn run_frame(&mut self) {
+        self.run_opcodes((self.clockspeed / self.refresh).max(1));
+        self.run_timers(1);
+    }
+
+    // run up to count opcodes as fast as possible for running without a
+    // window, with the timers counting down after each update's worth of
+    // opcodes. This stops early if the rom exits with 00FD or is paused for
+    // being stuck, returns the number of opcodes run
+    pub fn run_headless(&mut self, count: u64) -> u64 {
+        let per_update = (self.clockspeed / self.refresh).max(1) as u64;
+        let mut run = 0;
+        while run < count {
+            let mut in_update = 0;
+            while in_update < per_update && run < count {
+                if !self.run_opcode() {
+                    return run;
+                }
+                run += 1;
+                in_update += 1;
+                if self.interp.current_opcode() == 0x00FD {
+                    return run;
+                }
+            }
+            if in_update == per_update {
+                self.run_timers(1);
+                if self.paused {
+                    return run;
+                }
+            }
+        }
+        run
+    }
+
+    // run one opcode or one frame, stepping only works while paused
+    fn step(&mut self, frame: bool) {
+        if self.replay.is_some() {
+            self.osd.toast(&tr("replay_playing"));
+        } else if !self.paused {
+            self.osd.toast(&tr("step_not_paused"));
+        } else if frame {
+            self.run_frame();
+        } else {
+            self.run_opcodes(1);
+        }
+    }
+
+    // run the next opcode, false if the debugger stopped it or it faulted
+    fn run_opcode(&mut self) -> bool {
+        if let Some(debugger) = self.debugger.as_mut() {
+            if !debugger.before_opcode(&self.interp) {
+                return false;
+            }
+        }
+
+        if self.trace_length > 0 {
+            if self.trace.len() == self.trace_length {
+                self.trace.pop_front();
+            }
+            self.trace
+                .push_back((self.interp.pc(), self.interp.current_opcode()));
+        }
+        self.quirk_hints.observe(self.interp.current_opcode());
+        if let Some(budget) = self.cycle_budget.as_mut() {
+            budget.observe(self.interp.pc(), self.interp.current_opcode());
+        }
+        if let Some(detector) = self.stuck_detector.as_mut() {
+            detector.observe(self.interp.pc(), self.interp.current_opcode());
+        }
+
+        let result = self.check_strict().and_then(|_| {
+            if self.classroom {
+                let before = CpuState::capture(&self.interp);
+                let opcode = self.interp.current_opcode();
+                let result = self.interp.cycle();
+                let after = CpuState::capture(&self.interp);
+
+                if result.is_ok() {
+                    let lines = classroom::annotate(&before, &after, opcode);
+                    println!("{}", lines.join("  "));
+                    self.osd.set_status(lines);
+                }
+                result
+            } else {
+                self.interp.cycle()
+            }
+        });
+        if let Err(fault) = result {
+            if !self.handle_fault(fault) {
+                return false;
+            }
+        }
+        self.cycles += 1;
+        self.track_buzzer();
+        if let Some(debugger) = self.debugger.as_mut() {
+            debugger.after_opcode(&self.interp);
+        }
+        self.check_triggers();
+        true
+    }
+
+    // deal with a fault the way its policy says, true if the machine carries
+    // on, with the next opcode or from power on
+    fn handle_fault(&mut self, fault: Chip8Error) -> bool {
+        match self.fault_policies.policy(&fault) {
+            FaultPolicy::Skip => {
+                let message = trf("fault_skipped", &[&fault]);
+                println!("{}", message);
+                self.osd.toast(&message);
+                self.interp.set_pc(self.interp.pc() + 2);
+                self.faults_survived += 1;
+                return true;
+            }
+            FaultPolicy::Reset => {
+                println!("{}", fault);
+                self.reset();
+                self.osd.toast(&fault.to_string());
+                self.faults_survived += 1;
+                return true;
+            }
+            FaultPolicy::Debug => {
+                let stopped = self
+                    .debugger
+                    .as_mut()
+                    .is_some_and(|debugger| debugger.fault(&self.interp, &fault));
+                if stopped {
+                    return false;
+                }
+            }
+            FaultPolicy::Halt => {}
+        }
+        self.fault = Some(fault);
+        self.quit = true;
+        false
+    }
+
+    // the faults skipped or reset after by their policies
+    pub fn faults_survived(&self) -> u64 {
+        self.faults_survived
+    }
+
+    // what to do when an opcode faults, halting unless a policy says
+    // otherwise
+    pub fn set_fault_policies(&mut self, policies: FaultPolicies) {
+        self.fault_policies = policies;
+    }
+
+    fn handle_update(&mut self, ticks: Wrapping<u128>) {
+        // check events, the keypad state only changes with them. A replay
+        // being played or a network game sets the keys itself
+        self.handle_events();
+        let keys = self.input_device.read_keys();
+        if self.replay.is_none() && self.netplay.is_none() {
+            self.interp.set_keys(keys);
+            self.record_replay_event(Event::Keys(self.cycles, keys));
+        }
+
+        if let Some(debugger) = self.debugger.as_mut() {
+            debugger.poll(&mut self.interp);
+            if debugger.wants_quit() {
+                self.quit = true;
+            }
+        }
+
+        // step back one snapshot each update while rewinding, otherwise save
+        // one. Nothing changes while the debugger is paused
+        let paused = self.is_paused();
+        if self.rewinding && !paused {
+            if let Some(snapshot) = self.rewind.pop() {
+                self.interp.restore(&snapshot);
+            }
+        } else if !paused && self.replay.is_some() {
+            // a spectator that has fallen behind the host plays faster
+            let behind = self
+                .replay
+                .as_ref()
+                .map_or(0, |replay| replay.frames_behind());
+            let frames = match behind {
+                behind if behind > CATCH_UP_AFTER => behind.min(MAX_CATCH_UP) as u32,
+                _ => self.speed_multiplier(),
+            };
+            for _ in 0..frames {
+                if !self.play_replay_frame() {
+                    break;
+                }
+            }
+        } else if !paused && self.netplay.is_some() {
+            self.play_netplay_frame(keys);
+        } else if !paused {
+            self.run_timers(self.speed_multiplier());
+        }
+
+        // draw to screen
+        self.osd.update();
+        self.update_scope();
+        self.video_device
+            .render(self.interp.display_mut(), &mut self.osd);
+        self.record_frame();
+
+        // set the beep, while fast forwarding it is muted or its pitch limited
+        let muted = self.turbo && self.turbo_audio == TurboAudio::Mute;
+        let audible = !(self.rewinding || paused || muted);
+        self.audio_device
+            .set_pitch(self.buzzer_pitch(self.interp.pitch()));
+        self.audio_device
+            .set_beep(self.interp.is_beeping() && audible);
+        self.record_video(audible);
+
+        self.next_update_time = ticks + Wrapping(self.update_ticks);
+    }
+
+    // the frequency of the buzzer for the pitch register, limited while
+    // fast forwarding if the turbo audio says so
+    fn buzzer_pitch(&self, pitch: Option<u8>) -> f32 {
+        let hz = audio::buzzer_hz(pitch, self.tone_hz, self.pitch_offset);
+        if self.turbo && self.turbo_audio == TurboAudio::Limit {
+            hz.min(audio::TURBO_PITCH_LIMIT)
+        } else {
+            hz
+        }
+    }
+
+    // count the timers down once for a 60th of a second
+    fn count_down_timers(&mut self) {
+        if self.refresh <= TIMER_RATE {
+            self.interp.update_timers();
+        } else {
+            self.timer_credit += TIMER_RATE;
+            while self.timer_credit >= self.refresh {
+                self.timer_credit -= self.refresh;
+                self.interp.update_timers();
+            }
+        }
+    }
+
+    // sleep until the next action, be it opcode processing or update
+    fn do_sleep(&self, clock: &impl Clock, ticks: Wrapping<u128>) {
+        if let Some(sleep_time) = sleep_time(ticks, self.next_opcode_time, self.next_update_time) {
+            clock.sleep(sleep_time);
+        }
+    }
+
+    fn check_triggers(&mut self) {
+        for trigger in self.triggers.iter_mut() {
+            if let Some(message) = trigger.check(&self.interp) {
+                self.osd.toast(message);
+            }
+        }
+
+        if let Some(screenshot_triggers) = self.screenshot_triggers.as_mut() {
+            screenshot_triggers.check(&self.interp, self.interp.display());
+        }
+    }
+
+    fn handle_events(&mut self) {
+        for command in self.input_device.poll_commands() {
+            self.handle_command(command);
+        }
+    }
+
+    fn handle_command(&mut self, command: Command) {
+        match command {
+            // the other machine wouldn't follow anything that changes the
+            // machine or its speed
+            Command::Reset
+            | Command::Undo
+            | Command::StepOpcode
+            | Command::StepFrame
+            | Command::Faster
+            | Command::Slower
+            | Command::SetClockspeed(_)
+            | Command::Rewind(true)
+            | Command::Turbo(true)
+                if self.netplay.is_some() =>
+            {
+                self.osd.toast(&tr("netplay_locked"));
+            }
+            Command::Quit => {
+                self.quit = true;
+            }
+            Command::ToggleRomInfo => {
+                self.toggle_rom_info();
+            }
+            Command::ToggleHelp => {
+                self.toggle_help();
+            }
+            Command::TogglePause => {
+                self.toggle_pause();
+            }
+            Command::StepOpcode => {
+                self.step(false);
+            }
+            Command::StepFrame => {
+                self.step(true);
+            }
+            Command::Reset if self.replay.is_some() => {
+                self.osd.toast(&tr("replay_playing"));
+            }
+            Command::Reset => {
+                self.reset();
+            }
+            Command::Screenshot => {
+                self.screenshot();
+            }
+            Command::ToggleRecording => {
+                self.toggle_recording();
+            }
+            Command::ToggleScope => {
+                self.toggle_scope();
+            }
+            Command::CycleTheme => {
+                self.cycle_theme();
+            }
+            Command::ToggleCrt => {
+                self.set_crt(!self.crt);
+                let key = if self.crt { "crt_on" } else { "crt_off" };
+                self.osd.toast(&tr(key));
+            }
+            Command::ToggleFullscreen => {
+                self.set_fullscreen(!self.fullscreen);
+            }
+            Command::SaveStateMenu => {
+                self.open_state_menu(true);
+            }
+            Command::LoadStateMenu => {
+                self.open_state_menu(false);
+            }
+            Command::Undo => {
+                self.undo();
+            }
+            Command::Faster => {
+                self.change_clockspeed(true);
+            }
+            Command::Slower => {
+                self.change_clockspeed(false);
+            }
+            Command::VolumeUp => {
+                self.change_volume(true);
+            }
+            Command::VolumeDown => {
+                self.change_volume(false);
+            }
+            Command::SetClockspeed(clockspeed) => {
+                let max = CLOCKSPEED_STEPS[CLOCKSPEED_STEPS.len() - 1];
+                self.set_clockspeed(clockspeed.clamp(1, max));
+                self.osd
+                    .toast(&trf("clockspeed_changed", &[&self.clockspeed]));
+            }
+            Command::Rewind(rewinding) => {
+                self.set_rewinding(rewinding);
+            }
+            Command::Turbo(turbo) => {
+                self.turbo = turbo;
+                self.show_state();
+            }
+            Command::TogglePalette => {
+                self.toggle_palette();
+            }
+            Command::Palette(key) => {
+                self.palette_key(key);
+            }
+            Command::RecentMenu => {
+                self.toggle_recent_menu();
+            }
+            Command::Notify(key) => {
+                self.osd.toast(&tr(key));
+            }
+        }
+    }
+
+    // open the command palette, or close it if it is open
+    fn toggle_palette(&mut self) {
+        if self.palette.take().is_some() {
+            self.osd.hide_panel("palette");
+        } else {
+            let palette = Palette::default();
+            self.osd.show_panel("palette", palette.lines(&self.hotkeys));
+            self.palette = Some(palette);
+        }
+    }
+
+    // type into the command palette, enter runs the selected action
+    fn palette_key(&mut self, key: PaletteKey) {
+        if self.state_menu.is_some() {
+            self.state_menu_key(key);
+            return;
+        }
+        if self.recent_menu.is_some() {
+            self.recent_menu_key(key);
+            return;
+        }
+        let Some(palette) = self.palette.as_mut() else {
+            return;
+        };
+        match key {
+            PaletteKey::Enter => {
+                let selected = palette.selected();
+                self.toggle_palette();
+                if let Some(command) = selected.and_then(|action| action.command(true)) {
+                    self.handle_command(command);
+                }
+            }
+            PaletteKey::Escape => self.toggle_palette(),
+            key => {
+                palette.key(key);
+                let lines = palette.lines(&self.hotkeys);
+                self.osd.show_panel("palette", lines);
+            }
+        }
+    }
+
+    // the other roms played lately, newest first, for the recent roms menu
+    pub fn set_recent_roms(&mut self, roms: Vec<PathBuf>) {
+        self.recent_roms = roms;
+    }
+
+    // the rom picked from the recent roms menu, the emulator quits to run it
+    pub fn next_rom(&self) -> Option<&Path> {
+        self.next_rom.as_deref()
+    }
+
+    // open the menu of recent roms, or close it if it is open
+    fn toggle_recent_menu(&mut self) {
+        if self.recent_menu.take().is_some() {
+            self.osd.hide_panel("recent");
+        } else {
+            let menu = RecentMenu::new(self.recent_roms.clone());
+            self.osd.show_panel("recent", menu.lines());
+            self.recent_menu = Some(menu);
+        }
+    }
+
+    // move through the recent roms, enter quits to run the selected one
+    fn recent_menu_key(&mut self, key: PaletteKey) {
+        let Some(menu) = self.recent_menu.as_mut() else {
+            return;
+        };
+        match key {
+            PaletteKey::Enter => {
+                self.next_rom = menu.selected().map(Path::to_path_buf);
+                self.quit = self.next_rom.is_some();
+                self.toggle_recent_menu();
+            }
+            PaletteKey::Escape => self.toggle_recent_menu(),
+            key => {
+                menu.key(key);
+                let lines = menu.lines();
+                self.osd.show_panel("recent", lines);
+            }
+        }
+    }
+
+    // the sha1 of the rom, written to the save states
+    pub fn set_rom_sha1(&mut self, sha1: &str) {
+        self.rom_sha1 = sha1.to_string();
+    }
+
+    // the seconds the rom was played before this run
+    pub fn set_playtime(&mut self, seconds: u64) {
+        self.playtime = seconds;
+        self.playtime_start = Instant::now();
+    }
+
+    // show the save state slots with a thumbnail of each saved one, to pick
+    // one with the arrow keys and enter
+    fn open_state_menu(&mut self, saving: bool) {
+        if self.state_menu.is_some() {
+            return;
+        }
+        let slots = (0..savestate::SLOTS)
+            .map(|slot| SaveState::load(&savestate::slot_path(&self.capture_prefix, slot)).ok())
+            .collect();
+        let mut menu = StateMenu {
+            saving,
+            slots,
+            ..StateMenu::default()
+        };
+        menu.name = slot_name(&menu);
+        self.state_menu = Some(menu);
+        self.osd.set_state_menu(self.state_menu.clone());
+    }
+
+    // move through the save state menu, enter saves or loads the selected
+    // slot and escape closes it. When saving the slot can be given a name,
+    // it starts as the name of the state already in the slot
+    fn state_menu_key(&mut self, key: PaletteKey) {
+        let Some(menu) = self.state_menu.as_mut() else {
+            return;
+        };
+        let row = savestate::SLOTS / 2;
+        let selected = menu.selected;
+        match key {
+            PaletteKey::Left => menu.selected = menu.selected.saturating_sub(1),
+            PaletteKey::Right => menu.selected = (menu.selected + 1).min(savestate::SLOTS - 1),
+            PaletteKey::Up => menu.selected %= row,
+            PaletteKey::Down => menu.selected = menu.selected % row + row,
+            PaletteKey::Enter => {
+                let (saving, slot, name) = (menu.saving, menu.selected, menu.name.clone());
+                self.close_state_menu();
+                if saving {
+                    self.save_state(slot, &name);
+                } else {
+                    self.load_state(slot);
+                }
+                return;
+            }
+            PaletteKey::Escape => {
+                self.close_state_menu();
+                return;
+            }
+            PaletteKey::Char(c) if menu.saving => {
+                if menu.name.chars().count() < savestate::MAX_NAME && !c.is_control() {
+                    menu.name.push(c);
+                }
+            }
+            PaletteKey::Backspace if menu.saving => {
+                menu.name.pop();
+            }
+            PaletteKey::Char(_) | PaletteKey::Backspace => {}
+        }
+        if menu.selected != selected {
+            menu.name = slot_name(menu);
+        }
+        self.osd.set_state_menu(self.state_menu.clone());
+    }
+
+    fn close_state_menu(&mut self) {
+        self.state_menu = None;
+        self.osd.set_state_menu(None);
+    }
+
+    // save the machine to a slot under a name, which can be empty. The file
+    // is named after the rom like the screenshots
+    pub fn save_state(&mut self, slot: usize, name: &str) {
+        let title = match &self.rom_info {
+            Some(rom_info) => rom_info.title.clone(),
+            None => self.capture_prefix.display().to_string(),
+        };
+        let info = StateInfo {
+            rom: self.rom_sha1.clone(),
+            title,
+            name: name.to_string(),
+            saved: stats::unix_time(),
+            playtime: self.playtime + self.playtime_start.elapsed().as_secs(),
+        };
+        let state = SaveState::capture(&self.interp, info);
+        let path = savestate::slot_path(&self.capture_prefix, slot);
+        let message = match state.save(&path) {
+            Ok(()) => trf("state_saved", &[&slot]),
+            Err(e) => e,
+        };
+        println!("{}", message);
+        self.osd.toast(&message);
+    }
+
+    // load the machine from a slot saved with the same rom
+    pub fn load_state(&mut self, slot: usize) {
+        // a replay only has the keys, the state would be missing from it
+        if self.replay.is_some() || self.is_recording_replay() {
+            self.osd.toast(&tr("replay_no_state"));
+            return;
+        }
+        if self.netplay.is_some() {
+            self.osd.toast(&tr("netplay_locked"));
+            return;
+        }
+        let path = savestate::slot_path(&self.capture_prefix, slot);
+        let state = match SaveState::load(&path) {
+            Ok(state) => state,
+            Err(e) => {
+                self.osd.toast(&e);
+                return;
+            }
+        };
+        if state.info.rom != self.rom_sha1 {
+            self.osd.toast(&trf("state_other_rom", &[&slot]));
+            return;
+        }
+        let before = Thumbnail::from_display(self.interp.display());
+        let backup = self.interp.snapshot();
+        if state.restore(&mut self.interp).is_none() {
+            self.osd.toast(&trf("state_unusable", &[&slot]));
+            return;
+        }
+        self.backup = Some(backup);
+        self.osd.set_ghost(before);
+        self.trace.clear();
+        self.osd.toast(&trf("state_loaded", &[&slot]));
+    }
+
+    // go back to the machine as it was before the last state load or reset,
+    // in case the wrong key was pressed
+    fn undo(&mut self) {
+        // like loading a state, the replay would be missing the jump
+        if self.replay.is_some() || self.is_recording_replay() {
+            self.osd.toast(&tr("replay_no_undo"));
+            return;
+        }
+        let Some(backup) = self.backup.take() else {
+            self.osd.toast(&tr("undo_nothing"));
+            return;
+        };
+        let before = Thumbnail::from_display(self.interp.display());
+        self.backup = Some(self.interp.snapshot());
+        self.interp.restore(&backup);
+        self.osd.set_ghost(before);
+        self.trace.clear();
+        self.osd.toast(&tr("undo_done"));
+    }
+
+    // start the rom again, the memory goes back to the rom as it was loaded
+    // and the registers, stack, timers and display are cleared
+    fn reset(&mut self) {
+        self.record_replay_event(Event::Reset(self.cycles));
+        self.backup = Some(self.interp.snapshot());
+        self.interp.restore(&self.power_on);
+        self.trace.clear();
+        self.osd.toast(&tr("reset_done"));
+    }
+
+    // the key bindings, used to show the keys in the command palette
+    pub fn set_hotkeys(&mut self, hotkeys: Hotkeys) {
+        self.hotkeys = hotkeys;
+    }
+
+    // start or stop rewinding, this is shown on screen while it happens
+    fn set_rewinding(&mut self, rewinding: bool) {
+        // going back would leave the replay with keys from two timelines
+        if rewinding && (self.replay.is_some() || self.is_recording_replay()) {
+            self.osd.toast(&tr("replay_no_rewind"));
+            return;
+        }
+        self.rewinding = rewinding;
+        self.show_state();
+    }
+
+    // show or hide the controls help, this shows the keypad mapping and the
+    // controls of the rom if it is in the database
+    fn toggle_help(&mut self) {
+        let mut lines = self.input_device.help_lines();
+        if let Some(controls) = self
+            .rom_info
+            .as_ref()
+            .and_then(|info| info.controls.clone())
+        {
+            lines.push(String::new());
+            lines.push(controls);
+        }
+        let hotkey_lines = self.input_device.hotkey_lines();
+        if !hotkey_lines.is_empty() {
+            lines.push(String::new());
+            lines.extend(hotkey_lines);
+        }
+        self.osd.toggle_panel("help", lines);
+    }
+
+    // show the panel describing the loaded rom, or say it isn't in the
+    // database
+    pub fn show_rom_info(&mut self) {
+        self.toggle_rom_info();
+    }
+
+    // show or hide the panel describing the loaded rom
+    fn toggle_rom_info(&mut self) {
+        match &self.rom_info {
+            Some(rom_info) => self.osd.toggle_panel("rom info", rom_info.describe()),
+            None => self.osd.toast(&tr("rom_not_in_database")),
+        }
+    }
+}
+
+// the name of the state in the selected slot of the menu, empty if the slot
+// is empty or the state wasn't named
+fn slot_name(menu: &StateMenu) -> String {
+    match &menu.slots[menu.selected] {
+        Some(state) => state.info.name.clone(),
+        None => String::new(),
+    }
+}
+
+// the ticks between opcodes or updates at a rate a second, at least one so
+// the deadlines move on
+fn ticks_between(rate: u32) -> u128 {
+    ((1000000.0 / (rate as f64)) as u128).max(1)
+}
+
+// the time till the earlier of the two deadlines with 10% taken off to wake
+// up in time. None when the deadline has already passed, the emulator is
+// behind and carries on straight away to catch up
+fn sleep_time(
+    ticks: Wrapping<u128>,
+    next_opcode_time: Wrapping<u128>,
+    next_update_time: Wrapping<u128>,
+) -> Option<Duration> {
+    let deadline = next_opcode_time.0.min(next_update_time.0);
+    let remaining = deadline.saturating_sub(ticks.0);
+    if remaining == 0 {
+        return None;
+    }
+    let sleep_time = remaining - remaining / 10;
+    Some(Duration::from_micros(
+        u64::try_from(sleep_time).unwrap_or(u64::MAX),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::backend::NullAudio;
+    use crate::backend::NullInput;
+    use crate::backend::NullVideo;
+
+    // a clock that only moves when the test moves it, sleeping passes no
+    // time but the last sleep is kept
+    #[derive(Default)]
+    struct FakeClock {
+        now: Cell<Duration>,
+        slept: Cell<Option<Duration>>,
+    }
+
+    impl FakeClock {
+        fn advance(&self, duration: Duration) {
+            self.now.set(self.now.get() + duration);
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn elapsed(&self) -> Duration {
+            self.now.get()
+        }
+
+        fn sleep(&self, duration: Duration) {
+            self.slept.set(Some(duration));
+        }
+    }
+
+    // an emulator running a rom that sets the delay timer to 60 with its
+    // second opcode and then loops forever, at 500 opcodes a second
+    fn emulator(clock: &FakeClock) -> Emulator<'static> {
+        let mut interp = Interpreter::new();
+        interp
+            .load_rom(&[0x60, 0x3C, 0xF0, 0x15, 0x12, 0x04])
+            .unwrap();
+        Emulator::new(
+            interp,
+            Box::new(NullVideo),
+            Box::new(NullAudio),
+            Box::new(NullInput),
+            500,
+            clock,
+        )
+    }
+
+    // the clock is given in microseconds since the start
+    fn sleep_at(now: u128, next_opcode: u128, next_update: u128) -> Option<Duration> {
+        sleep_time(Wrapping(now), Wrapping(next_opcode), Wrapping(next_update))
+    }
+
+    #[test]
+    fn sleeps_until_the_next_opcode() {
+        assert_eq!(
+            sleep_at(1000, 3000, 16667),
+            Some(Duration::from_micros(1800))
+        );
+    }
+
+    #[test]
+    fn sleeps_until_the_next_update() {
+        assert_eq!(
+            sleep_at(16000, 20000, 16667),
+            Some(Duration::from_micros(601))
+        );
+    }
+
+    #[test]
+    fn does_not_sleep_on_the_deadline() {
+        assert_eq!(sleep_at(3000, 3000, 16667), None);
+    }
+
+    #[test]
+    fn does_not_sleep_when_behind() {
+        assert_eq!(sleep_at(5000, 3000, 16667), None);
+        assert_eq!(sleep_at(20000, 3000, 16667), None);
+    }
+
+    #[test]
+    fn does_not_sleep_when_one_deadline_has_passed() {
+        assert_eq!(sleep_at(5000, 60000, 4000), None);
+    }
+
+    #[test]
+    fn sleeps_at_least_a_microsecond_when_just_ahead() {
+        assert_eq!(sleep_at(2999, 3000, 16667), Some(Duration::from_micros(1)));
+    }
+
+    #[test]
+    fn far_off_deadlines_do_not_overflow() {
+        assert_eq!(
+            sleep_at(0, u128::MAX, u128::MAX),
+            Some(Duration::from_micros(u64::MAX))
+        );
+    }
+
+    #[test]
+    fn runs_opcodes_and_timers_on_time_by_the_clock() {
+        let clock = FakeClock::default();
+        let mut emulator = emulator(&clock);
+        // half a second, an opcode is due every 2ms and an update every
+        // 16666us, the first of each at the start
+        while clock.elapsed() < Duration::from_millis(500) {
+            emulator.update(&clock);
+            clock.advance(Duration::from_micros(1));
+        }
+        assert_eq!(emulator.cycles(), 250);
+        // set after the update at the start, then counted down by the 30
+        // updates after it
+        assert_eq!(emulator.interpreter().delay_timer(), 30);
+    }
+
+    #[test]
+    fn sleeps_till_the_next_opcode_even_after_running_late() {
+        let clock = FakeClock::default();
+        let mut emulator = emulator(&clock);
+        emulator.update(&clock);
+        assert_eq!(clock.slept.take(), Some(Duration::from_micros(1800)));
+        // a second late only one opcode runs, the next is due an opcode's
+        // time from now rather than straight away
+        clock.advance(Duration::from_secs(1));
+        emulator.update(&clock);
+        assert_eq!(emulator.cycles(), 2);
+        assert_eq!(clock.slept.take(), Some(Duration::from_micros(1800)));
+        // nothing is due yet, so nothing runs and there is no sleep
+        clock.advance(Duration::from_micros(1000));
+        emulator.update(&clock);
+        assert_eq!(emulator.cycles(), 2);
+        assert_eq!(clock.slept.take(), None);
+    }
+}
@@ -0,0 +1,88 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::i18n::tr;
+use crate::i18n::trf;
+
+// what can go wrong running a rom, from loading it and opening the devices to
+// the faults that stop the machine. The faults are at the address of the
+// opcode that caused them, the program counter is left there
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Chip8Error {
+    // an opcode that isn't in the instruction set or its extensions
+    InvalidOpcode { pc: usize, opcode: u16 },
+    // 0NNN, a call to machine code of the computers chip8 first ran on
+    MachineCode { pc: usize, addr: usize },
+    StackOverflow { pc: usize },
+    StackUnderflow { pc: usize },
+    // FXB0 with a bank past the end of the rom
+    BankOutOfRange { pc: usize, bank: usize },
+    // an opcode strict mode doesn't allow and why
+    Strict { pc: usize, message: String },
+    // a rom that doesn't fit in memory from 0x200
+    RomTooBig { size: usize, max: usize },
+    // a romfile that couldn't be read or assembled
+    Rom(String),
+    // a window, sound or input device that couldn't be opened
+    Device(String),
+}
+
+impl Chip8Error {
+    // a device that couldn't be opened and why
+    pub fn device(device: &str, error: impl fmt::Display) -> Chip8Error {
+        Chip8Error::Device(trf("device_error", &[&tr(device), &error]))
+    }
+
+    // the name fault policies give the fault, see fault::FAULT_NAMES. None
+    // for the errors that aren't faults of a running machine
+    pub fn fault_name(&self) -> Option<&'static str> {
+        match self {
+            Chip8Error::InvalidOpcode { .. } => Some("invalid-opcode"),
+            Chip8Error::MachineCode { .. } => Some("machine-code"),
+            Chip8Error::StackOverflow { .. } => Some("stack-overflow"),
+            Chip8Error::StackUnderflow { .. } => Some("stack-underflow"),
+            Chip8Error::BankOutOfRange { .. } => Some("bank-out-of-range"),
+            Chip8Error::Strict { .. } => Some("strict"),
+            Chip8Error::RomTooBig { .. } | Chip8Error::Rom(_) | Chip8Error::Device(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            Chip8Error::InvalidOpcode { pc, opcode } => trf(
+                "fault_invalid_opcode",
+                &[&format!("{:04X}", opcode), &format!("{:03X}", pc)],
+            ),
+            Chip8Error::MachineCode { pc, addr } => trf(
+                "fault_machine_code",
+                &[&format!("{:03X}", addr), &format!("{:03X}", pc)],
+            ),
+            Chip8Error::StackOverflow { pc } => {
+                trf("fault_stack_overflow", &[&format!("{:03X}", pc)])
+            }
+            Chip8Error::StackUnderflow { pc } => {
+                trf("fault_stack_underflow", &[&format!("{:03X}", pc)])
+            }
+            Chip8Error::BankOutOfRange { pc, bank } => {
+                trf("fault_bank_out_of_range", &[&format!("{:03X}", pc), bank])
+            }
+            Chip8Error::Strict { pc, message } => {
+                trf("fault_strict", &[&format!("{:03X}", pc), message])
+            }
+            Chip8Error::RomTooBig { size, max } => trf("id_too_big", &[size, max]),
+            Chip8Error::Rom(message) | Chip8Error::Device(message) => message.clone(),
+        };
+        write!(f, "{}", message)
+    }
+}
+
+impl Error for Chip8Error {}
+
+// most of the frontend reports errors as text
+impl From<Chip8Error> for String {
+    fn from(error: Chip8Error) -> String {
+        error.to_string()
+    }
+}
@@ -0,0 +1,354 @@
+use std::fmt;
+
+// read-only view of the machine that expressions are evaluated against
+pub trait MachineView {
+    fn pc(&self) -> usize;
+    fn i(&self) -> usize;
+    fn sp(&self) -> usize;
+    fn register(&self, index: usize) -> u8;
+    fn delay_timer(&self) -> u8;
+    fn sound_timer(&self) -> u8;
+    fn read_memory(&self, addr: usize) -> u8;
+
+    // the bank in the window with the banking extension, None without it
+    fn bank(&self) -> Option<usize> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Var {
+    Pc,
+    I,
+    Sp,
+    Dt,
+    St,
+    V(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum UnOp {
+    Not,
+    Neg,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinOp {
+    Or,
+    And,
+    Eq,
+    Neq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Num(i64),
+    Var(Var),
+    Mem(Box<Node>),
+    Unary(UnOp, Box<Node>),
+    Binary(BinOp, Box<Node>, Box<Node>),
+}
+
+// a parsed machine condition such as "memory[0x3E0] >= 10" or "PC == 0x2F0"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    source: String,
+    root: Node,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(i64),
+    Ident(String),
+    Op(&'static str),
+    LBracket,
+    RBracket,
+    LParen,
+    RParen,
+}
+
+// operators ordered so that the longer ones are matched first
+const OPERATORS: &[&str] = &[
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "|", "^", "&", "+", "-", "*", "/", "%", "!",
+];
+
+impl Expr {
+    pub fn parse(source: &str) -> Result<Expr, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let root = parser.parse_binary(0)?;
+        if parser.pos != parser.tokens.len() {
+            return Err(format!("Unexpected trailing input in \"{}\"", source));
+        }
+
+        Ok(Expr {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    pub fn eval(&self, machine: &dyn MachineView) -> i64 {
+        eval_node(&self.root, machine)
+    }
+
+    // true if the expression evaluates to anything other than 0
+    pub fn is_true(&self, machine: &dyn MachineView) -> bool {
+        self.eval(machine) != 0
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    'outer: while pos < chars.len() {
+        let c = chars[pos];
+        if c.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        match c {
+            '[' => tokens.push(Token::LBracket),
+            ']' => tokens.push(Token::RBracket),
+            '(' => tokens.push(Token::LParen),
+            ')' => tokens.push(Token::RParen),
+            _ if c.is_ascii_digit() => {
+                let start = pos;
+                while pos < chars.len() && chars[pos].is_ascii_alphanumeric() {
+                    pos += 1;
+                }
+                let text: String = chars[start..pos].iter().collect();
+                tokens.push(Token::Num(parse_number(&text)?));
+                continue;
+            }
+            _ if c.is_ascii_alphabetic() || c == '_' => {
+                let start = pos;
+                while pos < chars.len() && (chars[pos].is_ascii_alphanumeric() || chars[pos] == '_')
+                {
+                    pos += 1;
+                }
+                tokens.push(Token::Ident(chars[start..pos].iter().collect()));
+                continue;
+            }
+            _ => {
+                for op in OPERATORS {
+                    let len = op.len();
+                    if pos + len <= chars.len()
+                        && chars[pos..pos + len].iter().collect::<String>() == *op
+                    {
+                        tokens.push(Token::Op(op));
+                        pos += len;
+                        continue 'outer;
+                    }
+                }
+                return Err(format!("Unexpected character '{}' in \"{}\"", c, source));
+            }
+        }
+        pos += 1;
+    }
+
+    Ok(tokens)
+}
+
+pub fn parse_number(text: &str) -> Result<i64, String> {
+    let lower = text.to_ascii_lowercase();
+    let result = if let Some(hex) = lower.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16)
+    } else if let Some(bin) = lower.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2)
+    } else {
+        lower.parse::<i64>()
+    };
+    result.map_err(|_| format!("Invalid number \"{}\"", text))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+// binary operators grouped by precedence, lowest first
+const PRECEDENCE: &[&[(&str, BinOp)]] = &[
+    &[("||", BinOp::Or)],
+    &[("&&", BinOp::And)],
+    &[
+        ("==", BinOp::Eq),
+        ("!=", BinOp::Neq),
+        ("<", BinOp::Lt),
+        ("<=", BinOp::Le),
+        (">", BinOp::Gt),
+        (">=", BinOp::Ge),
+    ],
+    &[("|", BinOp::BitOr)],
+    &[("^", BinOp::BitXor)],
+    &[("&", BinOp::BitAnd)],
+    &[("+", BinOp::Add), ("-", BinOp::Sub)],
+    &[("*", BinOp::Mul), ("/", BinOp::Div), ("%", BinOp::Rem)],
+];
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.next() {
+            Some(ref token) if *token == expected => Ok(()),
+            other => Err(format!("Expected {:?} but found {:?}", expected, other)),
+        }
+    }
+
+    fn parse_binary(&mut self, level: usize) -> Result<Node, String> {
+        if level == PRECEDENCE.len() {
+            return self.parse_unary();
+        }
+
+        let mut lhs = self.parse_binary(level + 1)?;
+        'outer: loop {
+            if let Some(Token::Op(text)) = self.peek() {
+                for (op_text, op) in PRECEDENCE[level] {
+                    if text == op_text {
+                        self.pos += 1;
+                        let rhs = self.parse_binary(level + 1)?;
+                        lhs = Node::Binary(*op, Box::new(lhs), Box::new(rhs));
+                        continue 'outer;
+                    }
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, String> {
+        match self.peek() {
+            Some(Token::Op("!")) => {
+                self.pos += 1;
+                Ok(Node::Unary(UnOp::Not, Box::new(self.parse_unary()?)))
+            }
+            Some(Token::Op("-")) => {
+                self.pos += 1;
+                Ok(Node::Unary(UnOp::Neg, Box::new(self.parse_unary()?)))
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some(Token::Num(val)) => Ok(Node::Num(val)),
+            Some(Token::LParen) => {
+                let node = self.parse_binary(0)?;
+                self.expect(Token::RParen)?;
+                Ok(node)
+            }
+            Some(Token::Ident(name)) => {
+                let upper = name.to_ascii_uppercase();
+                match upper.as_str() {
+                    "MEMORY" | "MEM" => {
+                        self.expect(Token::LBracket)?;
+                        let addr = self.parse_binary(0)?;
+                        self.expect(Token::RBracket)?;
+                        Ok(Node::Mem(Box::new(addr)))
+                    }
+                    "PC" => Ok(Node::Var(Var::Pc)),
+                    "I" => Ok(Node::Var(Var::I)),
+                    "SP" => Ok(Node::Var(Var::Sp)),
+                    "DT" => Ok(Node::Var(Var::Dt)),
+                    "ST" => Ok(Node::Var(Var::St)),
+                    _ => parse_register(&upper)
+                        .map(|index| Node::Var(Var::V(index)))
+                        .ok_or(format!("Unknown name \"{}\"", name)),
+                }
+            }
+            other => Err(format!("Unexpected token {:?}", other)),
+        }
+    }
+}
+
+// parse "V0" through "VF" into a register index
+pub fn parse_register(name: &str) -> Option<usize> {
+    let upper = name.to_ascii_uppercase();
+    let digit = upper.strip_prefix('V')?;
+    if digit.len() != 1 {
+        return None;
+    }
+    usize::from_str_radix(digit, 16).ok()
+}
+
+fn eval_node(node: &Node, machine: &dyn MachineView) -> i64 {
+    match node {
+        Node::Num(val) => *val,
+        Node::Var(var) => match var {
+            Var::Pc => machine.pc() as i64,
+            Var::I => machine.i() as i64,
+            Var::Sp => machine.sp() as i64,
+            Var::Dt => machine.delay_timer() as i64,
+            Var::St => machine.sound_timer() as i64,
+            Var::V(index) => machine.register(*index) as i64,
+        },
+        Node::Mem(addr) => {
+            let addr = eval_node(addr, machine);
+            machine.read_memory(addr as usize) as i64
+        }
+        Node::Unary(op, val) => {
+            let val = eval_node(val, machine);
+            match op {
+                UnOp::Not => (val == 0) as i64,
+                UnOp::Neg => val.wrapping_neg(),
+            }
+        }
+        Node::Binary(op, lhs, rhs) => {
+            let lhs = eval_node(lhs, machine);
+            // short circuit the logic operators
+            match op {
+                BinOp::Or if lhs != 0 => return 1,
+                BinOp::And if lhs == 0 => return 0,
+                _ => {}
+            }
+            let rhs = eval_node(rhs, machine);
+            match op {
+                BinOp::Or | BinOp::And => (rhs != 0) as i64,
+                BinOp::Eq => (lhs == rhs) as i64,
+                BinOp::Neq => (lhs != rhs) as i64,
+                BinOp::Lt => (lhs < rhs) as i64,
+                BinOp::Le => (lhs <= rhs) as i64,
+                BinOp::Gt => (lhs > rhs) as i64,
+                BinOp::Ge => (lhs >= rhs) as i64,
+                BinOp::BitOr => lhs | rhs,
+                BinOp::BitXor => lhs ^ rhs,
+                BinOp::BitAnd => lhs & rhs,
+                BinOp::Add => lhs.wrapping_add(rhs),
+                BinOp::Sub => lhs.wrapping_sub(rhs),
+                BinOp::Mul => lhs.wrapping_mul(rhs),
+                BinOp::Div => lhs.checked_div(rhs).unwrap_or(0),
+                BinOp::Rem => lhs.checked_rem(rhs).unwrap_or(0),
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+// additions to the instruction set that no original interpreter had, for
+// homebrew that needs them. They are off unless turned on with --ext so roms
+// that use the opcodes by mistake still fail the same way
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Extensions {
+    // FXB0 maps a bank of a rom bigger than memory into the upper 2K
+    pub banking: bool,
+}
+
+// the extension names accepted by enable()
+pub const EXTENSION_NAMES: &[&str] = &["banking"];
+
+impl Extensions {
+    pub fn enable(&mut self, name: &str) -> Result<(), String> {
+        match name {
+            "banking" => self.banking = true,
+            _ => {
+                return Err(format!(
+                    "Unknown extension \"{}\", expected one of {}",
+                    name,
+                    EXTENSION_NAMES.join(", ")
+                ))
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeMap;
+
+use crate::error::Chip8Error;
+use crate::i18n::trf;
+
+// what the emulator can do when an opcode faults
+pub const POLICY_NAMES: &[&str] = &["halt", "reset", "skip", "debug"];
+
+// the faults that can be given a policy of their own
+pub const FAULT_NAMES: &[&str] = &[
+    "invalid-opcode",
+    "machine-code",
+    "stack-overflow",
+    "stack-underflow",
+    "bank-out-of-range",
+    "strict",
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FaultPolicy {
+    // stop the machine and quit, with a crash report if one was asked for
+    #[default]
+    Halt,
+    // go back to power on, the same as the reset key
+    Reset,
+    // log the fault and carry on with the opcode after it
+    Skip,
+    // pause under the debugger at the opcode, halting without one
+    Debug,
+}
+
+impl FaultPolicy {
+    pub fn from_name(name: &str) -> Option<FaultPolicy> {
+        match name {
+            "halt" => Some(FaultPolicy::Halt),
+            "reset" => Some(FaultPolicy::Reset),
+            "skip" => Some(FaultPolicy::Skip),
+            "debug" => Some(FaultPolicy::Debug),
+            _ => None,
+        }
+    }
+}
+
+// the policy for each fault, those without one of their own use the policy
+// for every fault
+#[derive(Clone, Debug, Default)]
+pub struct FaultPolicies {
+    all: FaultPolicy,
+    faults: BTreeMap<&'static str, FaultPolicy>,
+}
+
+impl FaultPolicies {
+    // apply a policy for every fault such as "skip", or for one fault such as
+    // "stack-overflow=halt"
+    pub fn apply(&mut self, setting: &str) -> Result<(), String> {
+        let (fault, policy) = match setting.split_once('=') {
+            Some((fault, policy)) => (Some(fault.trim()), policy.trim()),
+            None => (None, setting.trim()),
+        };
+        let policy = FaultPolicy::from_name(policy)
+            .ok_or_else(|| trf("fault_policy_unknown", &[&policy, &POLICY_NAMES.join(", ")]))?;
+        match fault {
+            Some(fault) => {
+                let fault = FAULT_NAMES
+                    .iter()
+                    .find(|name| **name == fault)
+                    .ok_or_else(|| trf("fault_name_unknown", &[&fault, &FAULT_NAMES.join(", ")]))?;
+                self.faults.insert(fault, policy);
+            }
+            None => {
+                self.all = policy;
+                self.faults.clear();
+            }
+        }
+        Ok(())
+    }
+
+    // apply settings separated by commas, e.g. "skip, stack-overflow=halt"
+    pub fn apply_list(&mut self, list: &str) -> Result<(), String> {
+        list.split(',')
+            .filter(|setting| !setting.trim().is_empty())
+            .try_for_each(|setting| self.apply(setting))
+    }
+
+    // what to do about a fault
+    pub fn policy(&self, fault: &Chip8Error) -> FaultPolicy {
+        fault
+            .fault_name()
+            .and_then(|name| self.faults.get(name))
+            .copied()
+            .unwrap_or(self.all)
+    }
+}
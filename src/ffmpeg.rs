@@ -0,0 +1,207 @@
+use std::fs;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Child;
+use std::process::ChildStdin;
+use std::process::Command;
+use std::process::Stdio;
+
+use crate::i18n::trf;
+use crate::screenshot::Pixelmap;
+
+// the samples a second of the recorded sound
+const AUDIO_RATE: u32 = 44100;
+
+// the volume of the square wave, the same as the speakers
+const VOLUME: f32 = 0.25;
+
+// records the display and the buzzer to a video with an ffmpeg process. The
+// frames are piped to ffmpeg as raw rgb while the buzzer is written to a file
+// next to the video, then a second ffmpeg run puts the two together once
+// recording stops. The sound is made from the emulated buzzer rather than
+// taken from the sound device, so it lines up with the frames to the sample
+// however the device keeps time
+pub struct VideoRecorder {
+    ffmpeg: Child,
+    frames: BufWriter<ChildStdin>,
+    audio: BufWriter<File>,
+
+    path: PathBuf,
+    video_path: PathBuf,
+    audio_path: PathBuf,
+    width: usize,
+    height: usize,
+
+    // the samples for each frame, the fraction is carried over
+    samples_per_frame: f32,
+    sample_credit: f32,
+
+    // where the square wave is between 0 and 1
+    phase: f32,
+}
+
+impl VideoRecorder {
+    // start ffmpeg recording frames of the given size at fps frames a
+    // second, the format of the video comes from the extension of the path
+    pub fn create(
+        path: &Path,
+        width: usize,
+        height: usize,
+        fps: u32,
+    ) -> Result<VideoRecorder, String> {
+        let video_path = part_path(path, "video", true);
+        let audio_path = part_path(path, "audio", false);
+        let audio = File::create(&audio_path)
+            .map_err(|e| trf("file_create_error", &[&audio_path.display(), &e]))?;
+
+        let mut ffmpeg = Command::new("ffmpeg")
+            .args(["-y", "-loglevel", "error"])
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24"])
+            .args(["-s", &format!("{}x{}", width, height)])
+            .args(["-r", &fps.to_string(), "-i", "-"])
+            // most players only play 4:2:0 video
+            .args(["-pix_fmt", "yuv420p"])
+            .arg(&video_path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                let _ = fs::remove_file(&audio_path);
+                trf("ffmpeg_start_error", &[&path.display(), &e])
+            })?;
+        let frames = BufWriter::new(ffmpeg.stdin.take().unwrap());
+
+        Ok(VideoRecorder {
+            ffmpeg,
+            frames,
+            audio: BufWriter::new(audio),
+            path: path.to_path_buf(),
+            video_path,
+            audio_path,
+            width,
+            height,
+            samples_per_frame: AUDIO_RATE as f32 / fps as f32,
+            sample_credit: 0.0,
+            phase: 0.0,
+        })
+    }
+
+    // add a frame and the buzzer played with it. The buzzer is given as the
+    // changes through the frame, each the fraction of the way into the frame
+    // it happens at and the frequency of the beep in hz or None while quiet
+    pub fn add_frame(
+        &mut self,
+        frame: &Pixelmap,
+        buzzer: &[(f32, Option<f32>)],
+    ) -> Result<(), String> {
+        if frame.width != self.width || frame.height != self.height {
+            return Ok(());
+        }
+        self.frames
+            .write_all(&frame.data)
+            .map_err(|e| trf("file_write_error", &[&self.path.display(), &e]))?;
+
+        self.sample_credit += self.samples_per_frame;
+        let count = self.sample_credit as usize;
+        self.sample_credit -= count as f32;
+        let mut samples = Vec::with_capacity(count * 2);
+        let mut change = 0;
+        for index in 0..count {
+            let time = index as f32 / count as f32;
+            while change + 1 < buzzer.len() && buzzer[change + 1].0 <= time {
+                change += 1;
+            }
+            let beep = buzzer.get(change).and_then(|(_, beep)| *beep);
+            let sample = match beep {
+                Some(hz) => {
+                    let sample = if self.phase <= 0.5 { VOLUME } else { -VOLUME };
+                    self.phase = (self.phase + hz / AUDIO_RATE as f32) % 1.0;
+                    sample
+                }
+                None => 0.0,
+            };
+            samples.extend_from_slice(&((sample * i16::MAX as f32) as i16).to_le_bytes());
+        }
+        self.audio
+            .write_all(&samples)
+            .map_err(|e| trf("file_write_error", &[&self.audio_path.display(), &e]))
+    }
+
+    // close the pipe, wait for ffmpeg to finish the video and add the sound
+    // to it, returns the path of the video
+    pub fn finish(self) -> Result<PathBuf, String> {
+        let VideoRecorder {
+            mut ffmpeg,
+            frames,
+            audio,
+            path,
+            video_path,
+            audio_path,
+            ..
+        } = self;
+        let result = finish_parts(&mut ffmpeg, frames, audio, &path, &video_path, &audio_path);
+        let _ = fs::remove_file(&video_path);
+        let _ = fs::remove_file(&audio_path);
+        result.map(|_| path)
+    }
+
+    // stop ffmpeg and throw the recording away after an error
+    pub fn cancel(mut self) {
+        let _ = self.ffmpeg.kill();
+        let _ = self.ffmpeg.wait();
+        let _ = fs::remove_file(&self.video_path);
+        let _ = fs::remove_file(&self.audio_path);
+    }
+}
+
+fn finish_parts(
+    ffmpeg: &mut Child,
+    mut frames: BufWriter<ChildStdin>,
+    mut audio: BufWriter<File>,
+    path: &Path,
+    video_path: &Path,
+    audio_path: &Path,
+) -> Result<(), String> {
+    // dropping the pipe tells ffmpeg there are no more frames
+    let flushed = frames.flush();
+    drop(frames);
+    wait(ffmpeg.wait(), path)?;
+    flushed.map_err(|e| trf("file_write_error", &[&path.display(), &e]))?;
+    audio
+        .flush()
+        .map_err(|e| trf("file_write_error", &[&audio_path.display(), &e]))?;
+
+    let status = Command::new("ffmpeg")
+        .args(["-y", "-nostdin", "-loglevel", "error"])
+        .arg("-i")
+        .arg(video_path)
+        .args(["-f", "s16le", "-ar", &AUDIO_RATE.to_string(), "-ac", "1"])
+        .arg("-i")
+        .arg(audio_path)
+        .args(["-c:v", "copy", "-shortest"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .status();
+    wait(status, path)
+}
+
+// turn how ffmpeg exited into an error if it failed
+fn wait(status: std::io::Result<std::process::ExitStatus>, path: &Path) -> Result<(), String> {
+    match status {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(trf("ffmpeg_error", &[&path.display(), &status])),
+        Err(e) => Err(trf("ffmpeg_error", &[&path.display(), &e])),
+    }
+}
+
+// the path of one part of the video while it is recorded, e.g.
+// "out.video.mp4" or "out.audio.raw"
+fn part_path(path: &Path, part: &str, keep_extension: bool) -> PathBuf {
+    let extension = match path.extension() {
+        Some(extension) if keep_extension => extension.to_string_lossy().into_owned(),
+        _ => "raw".to_string(),
+    };
+    path.with_extension(format!("{}.{}", part, extension))
+}
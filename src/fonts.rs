@@ -0,0 +1,69 @@
+use std::fs;
+use std::path::Path;
+
+use crate::i18n::trf;
+use crate::interpreter::FONT_DATA;
+use crate::interpreter::FONT_SIZE;
+
+// the font of the DREAM 6800, 3 pixels wide
+const DREAM6800: &[u8; FONT_SIZE] = &[
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // "0"
+    0x40, 0x40, 0x40, 0x40, 0x40, // "1"
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // "2"
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // "3"
+    0x80, 0xA0, 0xA0, 0xE0, 0x20, // "4"
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // "5"
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // "6"
+    0xE0, 0x20, 0x20, 0x20, 0x20, // "7"
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // "8"
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // "9"
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // "A"
+    0xC0, 0xA0, 0xE0, 0xA0, 0xC0, // "B"
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // "C"
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // "D"
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // "E"
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // "F"
+];
+
+// the font of the ETI-660, 3 pixels wide with a lower case b and d
+const ETI660: &[u8; FONT_SIZE] = &[
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // "0"
+    0x20, 0x20, 0x20, 0x20, 0x20, // "1"
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // "2"
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // "3"
+    0xA0, 0xA0, 0xE0, 0x20, 0x20, // "4"
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // "5"
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // "6"
+    0xE0, 0x20, 0x20, 0x20, 0x20, // "7"
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // "8"
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // "9"
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // "A"
+    0x80, 0x80, 0xE0, 0xA0, 0xE0, // "b"
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // "C"
+    0x20, 0x20, 0xE0, 0xA0, 0xE0, // "d"
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // "E"
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // "F"
+];
+
+// the built in fonts by name, chip8 is the one loaded unless another is given
+pub const FONTS: &[(&str, &[u8; FONT_SIZE])] = &[
+    ("chip8", FONT_DATA),
+    ("dream6800", DREAM6800),
+    ("eti660", ETI660),
+];
+
+// a built in font or a font file, 5 bytes for each of the 16 hex digits 0 to
+// F with the pixels in the top 4 bits, 80 bytes in all
+pub fn load(name_or_path: &str) -> Result<[u8; FONT_SIZE], String> {
+    if let Some((_, font)) = FONTS.iter().find(|(name, _)| *name == name_or_path) {
+        return Ok(**font);
+    }
+    let path = Path::new(name_or_path);
+    let data = fs::read(path).map_err(|e| trf("file_read_error", &[&path.display(), &e]))?;
+    data.try_into().map_err(|data: Vec<u8>| {
+        trf(
+            "font_wrong_size",
+            &[&path.display(), &data.len(), &FONT_SIZE],
+        )
+    })
+}
@@ -0,0 +1,135 @@
+use sdl2::audio::AudioCallback;
+use sdl2::audio::AudioSpecDesired;
+use sdl2::Sdl;
+
+use chip8::audio;
+use chip8::audio::SampleTap;
+use chip8::audio::BUZZER_HZ;
+use chip8::backend::AudioSink;
+use chip8::error::Chip8Error;
+
+// the height of the square wave at a volume of 100
+const FULL_VOLUME: f32 = 0.25;
+
+struct SquareWave {
+    // the samples a second of the device
+    freq: f32,
+    phase_inc: f32,
+    phase: f32,
+    volume: f32,
+
+    // the device keeps playing silence while the buzzer is off so the tap
+    // sees exactly what is played
+    on: bool,
+    tap: SampleTap,
+
+    // the number of channels of the device and the volume of the left and
+    // right ones when there are two
+    channels: usize,
+    gains: (f32, f32),
+}
+
+impl AudioCallback for SquareWave {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        // Generate a square wave, the samples of the channels are
+        // interleaved
+        let mut samples = Vec::with_capacity(out.len() / self.channels);
+        for frame in out.chunks_mut(self.channels) {
+            let sample = if !self.on {
+                0.0
+            } else if self.phase >= 0.0 && self.phase <= 0.5 {
+                self.volume
+            } else {
+                -self.volume
+            };
+            samples.push(sample);
+            match frame {
+                [left, right, ..] => {
+                    *left = sample * self.gains.0;
+                    *right = sample * self.gains.1;
+                }
+                frame => frame.fill(sample),
+            }
+            self.phase = (self.phase + self.phase_inc) % 1.0;
+        }
+        self.tap.push(samples.into_iter());
+    }
+}
+
+pub struct AudioDevice {
+    device: sdl2::audio::AudioDevice<SquareWave>,
+
+    // the frequency of the beep and whether it is on, the device is only
+    // locked to change them
+    pitch: f32,
+    on: bool,
+
+    tap: SampleTap,
+}
+
+impl AudioDevice {
+    pub fn new(sdl_context: &Sdl) -> Result<AudioDevice, Chip8Error> {
+        let audio_subsystem = sdl_context
+            .audio()
+            .map_err(|e| Chip8Error::device("device_audio", e))?;
+        let desired_spec = AudioSpecDesired {
+            freq: Some(44100),
+            channels: Some(2),
+            samples: None,
+        };
+        let mut device = audio_subsystem
+            .open_playback(None, &desired_spec, |spec| SquareWave {
+                freq: spec.freq as f32,
+                phase_inc: BUZZER_HZ / spec.freq as f32,
+                phase: 0.0,
+                volume: FULL_VOLUME,
+                channels: spec.channels.max(1) as usize,
+                gains: audio::pan_gains(0.0),
+                on: false,
+                tap: SampleTap::new(spec.freq as u32),
+            })
+            .map_err(|e| Chip8Error::device("device_audio", e))?;
+        let tap = device.lock().tap.clone();
+        device.resume();
+
+        Ok(AudioDevice {
+            device,
+            pitch: BUZZER_HZ,
+            on: false,
+            tap,
+        })
+    }
+
+    // move the buzzer between the left and right speakers, from -1 for all
+    // the way left to 1 for all the way right
+    pub fn set_pan(&mut self, pan: f32) {
+        self.device.lock().gains = audio::pan_gains(pan);
+    }
+}
+
+impl AudioSink for AudioDevice {
+    fn set_beep(&mut self, on: bool) {
+        if on != self.on {
+            self.on = on;
+            self.device.lock().on = on;
+        }
+    }
+
+    fn set_pitch(&mut self, hz: f32) {
+        if hz != self.pitch {
+            self.pitch = hz;
+            let mut wave = self.device.lock();
+            wave.phase_inc = hz / wave.freq;
+        }
+    }
+
+    fn set_volume(&mut self, volume: u8) {
+        self.device.lock().volume = FULL_VOLUME * volume.min(100) as f32 / 100.0;
+    }
+
+    fn sample_tap(&self) -> Option<SampleTap> {
+        Some(self.tap.clone())
+    }
+}
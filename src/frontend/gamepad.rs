@@ -0,0 +1,131 @@
+use sdl2::controller::Axis;
+use sdl2::controller::Button;
+use sdl2::controller::GameController;
+use sdl2::GameControllerSubsystem;
+use sdl2::Sdl;
+
+use chip8::backend::Command;
+use chip8::backend::InputSource;
+use chip8::gamepad::GamepadMap;
+use chip8::i18n::trf;
+
+// how far a stick has to be pushed to press a key
+const AXIS_THRESHOLD: i16 = i16::MAX / 2;
+
+// a gamepad button or stick direction
+#[derive(Clone, Copy, Debug)]
+enum PadInput {
+    Button(Button),
+    // true for the positive direction of the axis
+    Axis(Axis, bool),
+}
+
+impl PadInput {
+    // an SDL game controller name, with + or - after an axis
+    fn parse(name: &str) -> Option<PadInput> {
+        if let Some(axis) = name.strip_suffix('+') {
+            Axis::from_string(axis).map(|axis| PadInput::Axis(axis, true))
+        } else if let Some(axis) = name.strip_suffix('-') {
+            Axis::from_string(axis).map(|axis| PadInput::Axis(axis, false))
+        } else {
+            Button::from_string(name).map(PadInput::Button)
+        }
+    }
+
+    fn is_pressed(&self, controller: &GameController) -> bool {
+        match *self {
+            PadInput::Button(button) => controller.button(button),
+            PadInput::Axis(axis, true) => controller.axis(axis) > AXIS_THRESHOLD,
+            PadInput::Axis(axis, false) => controller.axis(axis) < -AXIS_THRESHOLD,
+        }
+    }
+}
+
+// every connected game controller pressing keypad keys. The keyboard owns the
+// event pump, so the controllers are found and read by polling their state
+// which stays current as the keyboard pumps the events
+pub struct GamepadDevice {
+    subsystem: GameControllerSubsystem,
+    controllers: Vec<GameController>,
+
+    // the number of joysticks when the controllers were last opened, a
+    // change means one was plugged in or pulled out
+    joysticks: u32,
+
+    bindings: Vec<(PadInput, u8)>,
+
+    // describes the bindings for the help overlay
+    bindings_help: String,
+}
+
+impl GamepadDevice {
+    pub fn new(sdl_context: &Sdl, map: &GamepadMap) -> Result<Self, String> {
+        let mut bindings = Vec::new();
+        let mut help = Vec::new();
+        for (name, key) in map.bindings() {
+            let input =
+                PadInput::parse(name).ok_or_else(|| trf("gamepad_unknown_input", &[&name]))?;
+            bindings.push((input, key));
+            help.push(format!("{} {:X}", name, key));
+        }
+
+        let mut device = GamepadDevice {
+            subsystem: sdl_context.game_controller()?,
+            controllers: Vec::new(),
+            joysticks: 0,
+            bindings,
+            bindings_help: help.join(", "),
+        };
+        device.check_hotplug();
+        Ok(device)
+    }
+
+    // open the controllers again when one is plugged in or pulled out
+    fn check_hotplug(&mut self) {
+        let joysticks = self.subsystem.num_joysticks().unwrap_or(0);
+        let detached = self.controllers.iter().any(|pad| !pad.attached());
+        if joysticks == self.joysticks && !detached {
+            return;
+        }
+        self.joysticks = joysticks;
+
+        let before: Vec<String> = self.controllers.iter().map(|pad| pad.name()).collect();
+        self.controllers = (0..joysticks)
+            .filter(|index| self.subsystem.is_game_controller(*index))
+            .filter_map(|index| self.subsystem.open(index).ok())
+            .collect();
+        let after: Vec<String> = self.controllers.iter().map(|pad| pad.name()).collect();
+
+        for name in after.iter().filter(|name| !before.contains(name)) {
+            println!("{}", trf("gamepad_connected", &[name]));
+        }
+        for name in before.iter().filter(|name| !after.contains(name)) {
+            println!("{}", trf("gamepad_disconnected", &[name]));
+        }
+    }
+}
+
+impl InputSource for GamepadDevice {
+    fn read_keys(&mut self) -> [bool; 0x10] {
+        let mut keys = [false; 0x10];
+        for controller in &self.controllers {
+            for (input, key) in &self.bindings {
+                keys[*key as usize] |= input.is_pressed(controller);
+            }
+        }
+        keys
+    }
+
+    fn poll_commands(&mut self) -> Vec<Command> {
+        self.check_hotplug();
+        Vec::new()
+    }
+
+    // the bindings, only while a gamepad is connected
+    fn help_lines(&self) -> Vec<String> {
+        if self.controllers.is_empty() {
+            return Vec::new();
+        }
+        vec![trf("gamepad_help", &[&self.bindings_help])]
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+
+use sdl2::event::Event;
+use sdl2::event::WindowEvent;
+use sdl2::keyboard::Keycode;
+use sdl2::keyboard::Mod;
+use sdl2::keyboard::TextInputUtil;
+use sdl2::EventPump;
+use sdl2::Sdl;
+
+use chip8::backend::Command;
+use chip8::backend::InputSource;
+use chip8::error::Chip8Error;
+use chip8::hotkeys::Action;
+use chip8::hotkeys::Hotkeys;
+use chip8::i18n::tr;
+use chip8::i18n::trf;
+use chip8::keymap::Keymap;
+use chip8::keymap::KEYPAD_LAYOUT;
+use chip8::kiosk::Kiosk;
+use chip8::palette::PaletteKey;
+
+// the modifier keys held with a hotkey
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+struct Modifiers {
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+}
+
+impl Modifiers {
+    fn from_mod(keymod: Mod) -> Modifiers {
+        Modifiers {
+            ctrl: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+            shift: keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+            alt: keymod.intersects(Mod::LALTMOD | Mod::RALTMOD),
+        }
+    }
+}
+
+// a key name such as "Ctrl+P" split into the modifiers and the key
+fn parse_key(name: &str) -> Option<(Keycode, Modifiers)> {
+    let mut modifiers = Modifiers::default();
+    let mut rest = name;
+    loop {
+        let lower = rest.to_lowercase();
+        if lower.starts_with("ctrl+") && rest.len() > 5 {
+            modifiers.ctrl = true;
+            rest = &rest[5..];
+        } else if lower.starts_with("shift+") && rest.len() > 6 {
+            modifiers.shift = true;
+            rest = &rest[6..];
+        } else if lower.starts_with("alt+") && rest.len() > 4 {
+            modifiers.alt = true;
+            rest = &rest[4..];
+        } else {
+            break;
+        }
+    }
+    Keycode::from_name(rest).map(|keycode| (keycode, modifiers))
+}
+
+pub struct KeyboardDevice {
+    event_pump: EventPump,
+
+    // turns key presses into typed text for the command palette
+    text_input: TextInputUtil,
+
+    // the emulator action for each bound key
+    hotkeys: HashMap<(Keycode, Modifiers), Action>,
+
+    // describes the bound hotkeys for the help overlay
+    hotkeys_help: String,
+
+    // the keypad key for each mapped keyboard key
+    keypad: HashMap<Keycode, u8>,
+
+    // the key names of the keymap, indexed by the keypad key
+    keypad_names: Vec<String>,
+
+    // the keypad state, kept up to date from the key events so reading it
+    // doesn't need to scan the keyboard
+    keys: [bool; 0x10],
+
+    // keys go to the command palette or the save state menu instead of the
+    // keypad while either is open
+    palette_open: bool,
+
+    // while locked the hotkeys and closing the window do nothing
+    kiosk: Option<Kiosk>,
+}
+
+impl KeyboardDevice {
+    pub fn new(sdl_context: &Sdl, hotkeys: &Hotkeys, keymap: &Keymap) -> Result<Self, String> {
+        hotkeys.check_conflicts(&keymap.names())?;
+
+        let mut keypad = HashMap::new();
+        for key in 0..0x10 {
+            let name = keymap.key(key);
+            let keycode = Keycode::from_name(name)
+                .ok_or_else(|| trf("keymap_unknown_key", &[&name, &format!("{:X}", key)]))?;
+            keypad.insert(keycode, key);
+        }
+
+        let mut keycodes = HashMap::new();
+        for (action, key) in hotkeys.bindings() {
+            let keycode =
+                parse_key(key).ok_or_else(|| trf("hotkey_unknown_key", &[&key, &action.name()]))?;
+            keycodes.insert(keycode, action);
+        }
+
+        let text_input = sdl_context
+            .video()
+            .map_err(|e| Chip8Error::device("device_keyboard", e))?
+            .text_input();
+        text_input.stop();
+
+        Ok(KeyboardDevice {
+            event_pump: sdl_context
+                .event_pump()
+                .map_err(|e| Chip8Error::device("device_keyboard", e))?,
+            text_input,
+            hotkeys: keycodes,
+            hotkeys_help: hotkeys.describe(),
+            keypad,
+            keypad_names: keymap.names().iter().map(|name| name.to_string()).collect(),
+            keys: [false; 0x10],
+            palette_open: false,
+            kiosk: None,
+        })
+    }
+
+    // lock the hotkeys until the passcode is typed
+    pub fn set_kiosk(&mut self, kiosk: Kiosk) -> Result<(), String> {
+        for key in kiosk.passcode() {
+            if Keycode::from_name(key).is_none() {
+                return Err(trf("kiosk_unknown_key", &[&key]));
+            }
+        }
+        self.kiosk = Some(kiosk);
+        Ok(())
+    }
+
+    // follow the kiosk passcode, returns true if the event is swallowed
+    // because the hotkeys are locked
+    fn kiosk_event(&mut self, event: &Event, commands: &mut Vec<Command>) -> bool {
+        let Some(kiosk) = self.kiosk.as_mut() else {
+            return false;
+        };
+        if let Event::KeyDown {
+            keycode: Some(keycode),
+            repeat: false,
+            ..
+        } = event
+        {
+            if kiosk.key_pressed(&keycode.name()) {
+                commands.push(Command::Notify(if kiosk.is_locked() {
+                    "kiosk_locked"
+                } else {
+                    "kiosk_unlocked"
+                }));
+            }
+        }
+        if !kiosk.is_locked() {
+            return false;
+        }
+        matches!(event, Event::Quit { .. }) || self.hotkey_event(event).is_some()
+    }
+
+    fn set_key(&mut self, keycode: Keycode, pressed: bool) {
+        if let Some(key) = self.keypad.get(&keycode) {
+            self.keys[*key as usize] = pressed;
+        }
+    }
+
+    // the command for a hotkey being pressed or let go of. The modifiers
+    // only matter for the press, so letting go of ctrl first still releases
+    fn hotkey_command(&self, keycode: Keycode, keymod: Mod, pressed: bool) -> Option<Command> {
+        let action = if pressed {
+            self.hotkeys.get(&(keycode, Modifiers::from_mod(keymod)))
+        } else {
+            self.hotkeys
+                .iter()
+                .find(|((code, _), _)| *code == keycode)
+                .map(|(_, action)| action)
+        };
+        action.and_then(|action| action.command(pressed))
+    }
+
+    // the command for a key event if the key is a hotkey
+    fn hotkey_event(&self, event: &Event) -> Option<Command> {
+        match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                keymod,
+                repeat: false,
+                ..
+            } => self.hotkey_command(*keycode, *keymod, true),
+            Event::KeyUp {
+                keycode: Some(keycode),
+                keymod,
+                ..
+            } => self.hotkey_command(*keycode, *keymod, false),
+            _ => None,
+        }
+    }
+
+    fn set_palette_open(&mut self, open: bool) {
+        self.palette_open = open;
+        self.keys = [false; 0x10];
+        if open {
+            self.text_input.start();
+        } else {
+            self.text_input.stop();
+        }
+    }
+
+    // the keys used to search and pick from the command palette
+    fn palette_command(&mut self, event: &Event) -> Option<Command> {
+        let key = match event {
+            Event::TextInput { text, .. } => {
+                return text
+                    .chars()
+                    .next()
+                    .map(|c| Command::Palette(PaletteKey::Char(c)));
+            }
+            Event::KeyDown {
+                keycode: Some(keycode),
+                keymod,
+                ..
+            } => match keycode {
+                Keycode::Backspace => PaletteKey::Backspace,
+                Keycode::Up => PaletteKey::Up,
+                Keycode::Down => PaletteKey::Down,
+                Keycode::Left => PaletteKey::Left,
+                Keycode::Right => PaletteKey::Right,
+                Keycode::Return | Keycode::KpEnter => PaletteKey::Enter,
+                Keycode::Escape => PaletteKey::Escape,
+                keycode => {
+                    return match self.hotkey_command(*keycode, *keymod, true) {
+                        Some(Command::TogglePalette) => {
+                            self.set_palette_open(false);
+                            Some(Command::TogglePalette)
+                        }
+                        _ => None,
+                    };
+                }
+            },
+            _ => return None,
+        };
+        if matches!(key, PaletteKey::Enter | PaletteKey::Escape) {
+            self.set_palette_open(false);
+        }
+        Some(Command::Palette(key))
+    }
+}
+
+impl InputSource for KeyboardDevice {
+    // the keys as of the last poll
+    fn read_keys(&mut self) -> [bool; 0x10] {
+        self.keys
+    }
+
+    fn poll_commands(&mut self) -> Vec<Command> {
+        let mut commands = Vec::new();
+        let events: Vec<Event> = self.event_pump.poll_iter().collect();
+        for event in events {
+            if self.kiosk_event(&event, &mut commands) {
+                continue;
+            }
+            if self.palette_open {
+                commands.extend(self.palette_command(&event));
+                continue;
+            }
+            if let Some(command) = self.hotkey_event(&event) {
+                // the save state and recent roms menus are picked from with
+                // the same keys
+                if matches!(
+                    command,
+                    Command::TogglePalette
+                        | Command::SaveStateMenu
+                        | Command::LoadStateMenu
+                        | Command::RecentMenu
+                ) {
+                    self.set_palette_open(true);
+                }
+                commands.push(command);
+                continue;
+            }
+            match event {
+                Event::Quit { .. } => {
+                    commands.push(Command::Quit);
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    self.set_key(keycode, true);
+                }
+                Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                } => {
+                    self.set_key(keycode, false);
+                }
+                // keys let go of in another window never send a key up
+                Event::Window {
+                    win_event: WindowEvent::FocusLost,
+                    ..
+                } => {
+                    self.keys = [false; 0x10];
+                }
+                _ => {
+                    //println!("Another Event!");
+                }
+            }
+        }
+        commands
+    }
+
+    // the keypad layout next to the keyboard keys each keypad key is mapped
+    // to, for showing in the help overlay
+    fn help_lines(&self) -> Vec<String> {
+        let mut lines = vec![tr("help_header")];
+        for row in KEYPAD_LAYOUT.iter() {
+            let keypad: Vec<String> = row.iter().map(|key| format!("{:X}", key)).collect();
+            let keyboard: Vec<&str> = row
+                .iter()
+                .map(|key| self.keypad_names[*key as usize].as_str())
+                .collect();
+            lines.push(format!("{}   {}", keypad.join(" "), keyboard.join(" ")));
+        }
+        lines
+    }
+
+    fn hotkey_lines(&self) -> Vec<String> {
+        vec![self.hotkeys_help.clone()]
+    }
+}
@@ -0,0 +1,110 @@
+use std::path::Path;
+use std::path::PathBuf;
+
+use sdl2::event::Event;
+use sdl2::event::WindowEvent;
+use sdl2::keyboard::Keycode;
+
+use chip8::backend::VideoSink;
+use chip8::colors::Theme;
+use chip8::error::Chip8Error;
+use chip8::i18n::tr;
+use chip8::i18n::trf;
+use chip8::library;
+use chip8::playtime;
+use chip8::playtime::Playtime;
+
+use super::video::VideoDevice;
+
+// show the roms in a directory as a grid of thumbnails and return the one
+// picked, or None if the window was closed
+pub fn pick(
+    dir: &Path,
+    pixelsize: usize,
+    theme: Theme,
+    playtime: &Playtime,
+) -> Result<Option<PathBuf>, String> {
+    let entries = library::load(dir)?;
+    if entries.is_empty() {
+        return Err(trf("library_empty", &[&dir.display()]));
+    }
+    println!("{}", tr("library_help"));
+
+    let sdl_context = super::init_sdl()?;
+    let mut video = VideoDevice::new(&sdl_context, pixelsize)?;
+    video.set_theme(theme);
+    let mut event_pump = sdl_context
+        .event_pump()
+        .map_err(|e| Chip8Error::device("device_keyboard", e))?;
+
+    let last = entries.len() - 1;
+    let mut selected = 0;
+    let mut first = 0;
+    let mut dirty = true;
+    loop {
+        // the grid changes with the size of the window
+        let (columns, rows) = video.library_grid();
+        let page = columns * rows;
+        if dirty {
+            // scroll a row at a time to keep the selected rom on the page
+            if selected < first {
+                first = selected - selected % columns;
+            } else if selected >= first + page {
+                first = (selected / columns + 1 - rows) * columns;
+            }
+            let entry = &entries[selected];
+            let played = entry.sha1.as_ref().map_or(0, |sha1| playtime.get(sha1));
+            let title = if played > 0 {
+                trf(
+                    "library_title_played",
+                    &[
+                        &entry.title,
+                        &(selected + 1),
+                        &entries.len(),
+                        &playtime::format(played),
+                    ],
+                )
+            } else {
+                trf(
+                    "library_title",
+                    &[&entry.title, &(selected + 1), &entries.len()],
+                )
+            };
+            video.set_title(&title);
+            video.render_library(&entries, first, selected);
+            dirty = false;
+        }
+
+        let before = selected;
+        match event_pump.wait_event() {
+            Event::Quit { .. }
+            | Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } => return Ok(None),
+            Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            } => match keycode {
+                Keycode::Left => selected = selected.saturating_sub(1),
+                Keycode::Right => selected = std::cmp::min(selected + 1, last),
+                Keycode::Up if selected >= columns => selected -= columns,
+                Keycode::Down if selected + columns <= last => selected += columns,
+                Keycode::PageUp => selected = selected.saturating_sub(page),
+                Keycode::PageDown => selected = std::cmp::min(selected + page, last),
+                Keycode::Home => selected = 0,
+                Keycode::End => selected = last,
+                Keycode::Return | Keycode::KpEnter => {
+                    return Ok(Some(entries[selected].path.clone()));
+                }
+                _ => {}
+            },
+            Event::Window {
+                win_event: WindowEvent::Exposed | WindowEvent::SizeChanged(..),
+                ..
+            } => dirty = true,
+            _ => {}
+        }
+        dirty |= selected != before;
+    }
+}
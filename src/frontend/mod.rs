@@ -0,0 +1,1613 @@
+extern crate sdl2;
+
+mod audio;
+mod gamepad;
+mod keyboard;
+mod library;
+mod repl;
+mod setup;
+mod themes;
+mod tools;
+#[cfg(feature = "tty")]
+mod tty;
+mod video;
+
+use clap::Parser;
+use clap::Subcommand;
+use sdl2::Sdl;
+use std::fs;
+use std::io;
+use std::io::BufRead;
+use std::io::IsTerminal;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use chip8::achievements;
+use chip8::audio::TurboAudio;
+use chip8::audio::TURBO_AUDIO_NAMES;
+use chip8::backend::AudioSink;
+use chip8::backend::Command;
+use chip8::backend::InputSource;
+use chip8::backend::NullAudio;
+use chip8::backend::NullInput;
+use chip8::backend::NullVideo;
+use chip8::backend::VideoSink;
+use chip8::banking;
+use chip8::colors;
+use chip8::colors::Color;
+use chip8::colors::Theme;
+use chip8::config;
+use chip8::config::Config;
+use chip8::config::Preset;
+use chip8::crash;
+use chip8::crash::CrashReport;
+use chip8::debugger;
+use chip8::debugger::Debugger;
+use chip8::display::Display;
+use chip8::error::Chip8Error;
+use chip8::expr::MachineView;
+use chip8::extensions;
+use chip8::extensions::Extensions;
+use chip8::fault::FaultPolicies;
+use chip8::fonts;
+use chip8::gamepad::GamepadMap;
+use chip8::gdb::GdbStub;
+use chip8::hotkeys::Hotkeys;
+use chip8::i18n;
+use chip8::i18n::tr;
+use chip8::i18n::trf;
+use chip8::input::InputMux;
+use chip8::input::Priority;
+use chip8::interpreter::LOAD_ADDRESS_PRESETS;
+use chip8::interpreter::MEM_SIZE;
+use chip8::interpreter::PROGRAM_START;
+use chip8::keymap;
+use chip8::keymap::Keymap;
+use chip8::kiosk::Kiosk;
+#[cfg(feature = "midi")]
+use chip8::midi::MidiInput;
+#[cfg(feature = "midi")]
+use chip8::midi::MidiMap;
+use chip8::netplay;
+use chip8::netplay::Netplay;
+use chip8::octo;
+use chip8::osc::OscInput;
+use chip8::osd::Osd;
+use chip8::pipe::PipeInput;
+use chip8::playtime;
+use chip8::playtime::Playtime;
+use chip8::profiles;
+use chip8::quirks;
+use chip8::quirks::Quirks;
+use chip8::recent;
+use chip8::replay::Replay;
+use chip8::rewind;
+use chip8::romdb;
+use chip8::romid;
+use chip8::screenshot;
+use chip8::screenshot::Pixelmap;
+use chip8::screenshot::ScreenshotTriggers;
+use chip8::stats;
+use chip8::stats::Session;
+use chip8::testpattern::TestPattern;
+use chip8::testpattern::PATTERN_NAMES;
+use chip8::watch::Watchpoint;
+use chip8::Emulator;
+use chip8::Interpreter;
+
+use audio::AudioDevice;
+use gamepad::GamepadDevice;
+use keyboard::KeyboardDevice;
+#[cfg(feature = "tty")]
+use tty::TtyBell;
+#[cfg(feature = "tty")]
+use tty::TtyInput;
+#[cfg(feature = "tty")]
+use tty::TtyVideo;
+use video::VideoDevice;
+
+/// Chip8 Interpreter
+#[derive(Parser, Debug)]
+#[clap(
+    author,
+    version,
+    about,
+    long_about = None,
+    subcommand_negates_reqs = true
+)]
+struct Args {
+    #[clap(subcommand)]
+    command: Option<Commands>,
+
+    /// The rom or Octo program to run, without one a rom is picked from the
+    /// library of the rom_dir of the config file or the current directory
+    romfile: Option<String>,
+
+    /// The number of pixels that each "chip8" pixel is represented by on the
+    /// window canvas [default: 8]
+    #[clap(short, long)]
+    pixelsize: Option<usize>,
+
+    /// The clock speed on the "cpu" in MHz, this is the number of chip8 opcodes
+    /// that will be processed per second [default: 400]
+    #[clap(short, long)]
+    clockspeed: Option<u32>,
+
+    /// Warn when the work a rom does between waits for the delay timer is
+    /// more than this many opcodes, so it would slow down on hardware with
+    /// that many opcodes a frame, and show where in the rom it was done
+    #[clap(long, value_name = "OPCODES")]
+    cycle_budget: Option<u32>,
+
+    /// A bundle of settings from the config file or one of the built in
+    /// handheld, projector or dev presets. Options given on the command line
+    /// override the preset
+    #[clap(long, value_name = "NAME")]
+    preset: Option<String>,
+
+    /// Slow down to a few opcodes per second and explain each one on screen,
+    /// optionally give the number of opcodes per second [default: 2]
+    #[clap(
+        long,
+        value_name = "SPEED",
+        min_values = 0,
+        default_missing_value = "2"
+    )]
+    classroom: Option<u32>,
+
+    /// Draw the display like an old tube television with scanlines, glow
+    /// and a curved screen, F3 turns it on and off while running
+    #[clap(long)]
+    crt: bool,
+
+    /// Fill the screen with the display scaled to fit rather than opening a
+    /// window, F11 switches between the two while running
+    #[clap(long)]
+    fullscreen: bool,
+
+    /// Stretch the display to fill the window or screen, instead of drawing
+    /// it as big as whole pixels fit with black bars around it
+    #[clap(long)]
+    stretch: bool,
+
+    /// Fade pixels out over a few frames like the phosphor of an old screen,
+    /// which hides the flicker of sprites being erased and drawn again
+    #[clap(long)]
+    phosphor: bool,
+
+    /// A built in colour palette, the same as the theme of the config file.
+    /// The theme hotkey, F4, steps through them while running
+    #[clap(long, value_name = "NAME", possible_values = colors::THEME_NAMES)]
+    palette: Option<String>,
+
+    /// The colour the pixels are drawn in as hex, e.g. 00FF66, this
+    /// overrides the colours of the theme
+    #[clap(long, value_name = "RRGGBB")]
+    fg: Option<String>,
+
+    /// The colour behind the pixels as hex, e.g. 101010
+    #[clap(long, value_name = "RRGGBB")]
+    bg: Option<String>,
+
+    /// The keyboard keys for the hex keypad, as the 4x4 block under the 1 to 4
+    /// keys of a keyboard layout or on the numeric keypad. The keymap table of
+    /// the config file changes single keys [default: qwerty]
+    #[clap(long, value_name = "LAYOUT", possible_values = keymap::PRESET_NAMES)]
+    keymap: Option<String>,
+
+    /// Run with the quirks of another interpreter, for roms that glitch
+    /// with the default behaviour
+    #[clap(long, value_name = "PRESET", possible_values = quirks::PRESET_NAMES)]
+    quirks: Option<String>,
+
+    /// Turn a single quirk on or off after the preset, one of shift,
+    /// load-store, jump, vf-reset or wrap, e.g. "wrap" or "shift=off". Can be
+    /// given multiple times
+    #[clap(long, value_name = "NAME[=on|off]", multiple_occurrences = true)]
+    quirk: Vec<String>,
+
+    /// What to do when an opcode faults, one of halt, reset, skip or debug,
+    /// for every fault or for one such as "stack-overflow=skip". Can be given
+    /// multiple times [default: halt]
+    #[clap(long, value_name = "[FAULT=]POLICY", multiple_occurrences = true)]
+    on_fault: Vec<String>,
+
+    /// Fault on anything the most conservative interpreters would run
+    /// differently or not at all: opcodes beyond chip8, memory out of
+    /// bounds, more than 16 nested calls and anything the quirks change
+    #[cfg(feature = "strict")]
+    #[clap(long)]
+    strict: bool,
+
+    /// Turn on an addition to the instruction set for homebrew that needs
+    /// it. "banking" loads roms bigger than memory in 2K banks switched with
+    /// FXB0. Can be given multiple times
+    #[clap(
+        long,
+        value_name = "NAME",
+        possible_values = extensions::EXTENSION_NAMES,
+        multiple_occurrences = true,
+        global = true
+    )]
+    ext: Vec<String>,
+
+    /// Load the rom and start running it at another address, as hex or
+    /// eti660 for roms written for the ETI-660 that start at 0x600
+    /// [default: 0x200]
+    #[clap(long, value_name = "ADDRESS")]
+    load_address: Option<String>,
+
+    /// The font of the hex digits the FX29 opcode draws, one of chip8,
+    /// dream6800 and eti660 or a file of 5 bytes for each digit from 0 to F
+    /// [default: chip8]
+    #[clap(long, value_name = "NAME|FILE")]
+    font: Option<String>,
+
+    /// The number of seconds that can be rewound by holding backspace, up to
+    /// 600. 0 turns rewinding off
+    #[clap(long, value_name = "SECONDS", default_value_t = 30)]
+    rewind: u32,
+
+    /// Seed the random numbers of the CXNN opcode so runs with the same
+    /// seed and keys play out the same
+    #[clap(long, value_name = "SEED")]
+    seed: Option<u64>,
+
+    /// Where to play the rom, in a window or with "tty" in the terminal, e.g.
+    /// over ssh
+    #[clap(long, value_name = "FRONTEND", default_value = "window", possible_values = FRONTEND_NAMES)]
+    frontend: String,
+
+    /// Show a test pattern in the frontend instead of running a rom, to try
+    /// out the frontend, the colours and the window options
+    #[clap(long, value_name = "PATTERN", possible_values = PATTERN_NAMES)]
+    test_pattern: Option<String>,
+
+    /// Run the rom without a window or sound for --max-cycles opcodes and
+    /// exit, e.g. in ci or on a server
+    #[clap(
+        long,
+        requires = "max-cycles",
+        conflicts_with_all = &["debug", "gdb", "kiosk"]
+    )]
+    headless: bool,
+
+    /// The number of opcodes a --headless run stops after, it stops sooner
+    /// if the rom exits with 00FD
+    #[clap(long, value_name = "N", requires = "headless")]
+    max_cycles: Option<u64>,
+
+    /// Pause with where the rom is stuck if it goes round the same few
+    /// opcodes for this many seconds without drawing, reading the keys or
+    /// using the timers. A --headless run stops and fails
+    #[clap(long, value_name = "SECONDS")]
+    pause_when_stuck: Option<u32>,
+
+    /// The number of times a second the screen is drawn and the keys read.
+    /// The timers count down at this rate up to 60, so 50 runs roms timed by
+    /// the delay timer at PAL speed. Above 60 the timers stay at 60 and only
+    /// the drawing gets smoother
+    #[clap(long, value_name = "HZ", default_value_t = 60)]
+    refresh: u32,
+
+    /// How many times faster the machine runs while the turbo key, tab, is
+    /// held
+    #[clap(long, value_name = "N", default_value_t = 4)]
+    turbo_multiplier: u32,
+
+    /// What the buzzer does while the turbo key is held, mute it or limit
+    /// its pitch so fast forwarding through beeps isn't shrill
+    #[clap(long, value_name = "MODE", default_value = "mute", possible_values = TURBO_AUDIO_NAMES)]
+    turbo_audio: String,
+
+    /// Move the buzzer up or down by this many semitones, e.g. -12 for an
+    /// octave lower if the xo-chip pitch sounds too shrill
+    #[clap(
+        long,
+        value_name = "SEMITONES",
+        default_value_t = 0.0,
+        allow_hyphen_values = true
+    )]
+    pitch_offset: f32,
+
+    /// The pitch of the buzzer in hz for roms that don't set it, e.g. 220
+    /// for a softer beep than the classic 440
+    #[clap(long, value_name = "HZ")]
+    tone_hz: Option<u32>,
+
+    /// How loud the buzzer is, from 0 for silent to 100. The volume hotkeys,
+    /// [ and ], change it while running
+    #[clap(long, value_name = "VOLUME")]
+    volume: Option<u8>,
+
+    /// Where the buzzer is between the left and right speakers, from -1 for
+    /// all the way left to 1 for all the way right
+    #[clap(
+        long,
+        value_name = "PAN",
+        default_value_t = 0.0,
+        allow_hyphen_values = true
+    )]
+    audio_pan: f32,
+
+    /// Start paused under the debugger, commands are typed on the console.
+    /// Type help for the list of commands
+    #[clap(long)]
+    debug: bool,
+
+    /// Run the debugger commands of a script file first, e.g. one written
+    /// with --record-script, to set up a debugging session in one step
+    #[clap(long, value_name = "FILE", requires = "debug")]
+    run_script: Option<PathBuf>,
+
+    /// Write the debugger commands run this session to a script file to run
+    /// again later with --run-script
+    #[clap(long, value_name = "FILE", requires = "debug")]
+    record_script: Option<PathBuf>,
+
+    /// Start paused and wait for gdb or another debugger to connect with the
+    /// remote serial protocol on this port
+    #[clap(long, value_name = "PORT", conflicts_with = "debug")]
+    gdb: Option<u16>,
+
+    /// Lock the hotkeys and the window close button for a public kiosk, only
+    /// the keypad works until the kiosk_passcode of the config file is typed
+    #[clap(long, conflicts_with_all = &["debug", "gdb"])]
+    kiosk: bool,
+
+    /// Press and let go of keys with lines like "KEYDOWN 5" and "KEYUP 5"
+    /// read from a named pipe or file, or from stdin if this is "-"
+    #[clap(long, value_name = "FILE")]
+    input_pipe: Option<String>,
+
+    /// Listen for open sound control messages such as /chip8/key/5 on this
+    /// udp port, or an address such as 0.0.0.0:9000 to take them from other
+    /// machines
+    #[clap(long, value_name = "ADDRESS")]
+    osc: Option<String>,
+
+    /// Press keys with the notes played on a midi device, e.g.
+    /// /dev/snd/midiC1D0. The midi table of the config file maps notes to
+    /// keys
+    #[cfg(feature = "midi")]
+    #[clap(long, value_name = "DEVICE")]
+    midi: Option<PathBuf>,
+
+    /// Write a bundle of the machine state, the last opcodes run, the config
+    /// and the rom hash to a new directory inside this one if the emulator
+    /// crashes, to attach to a bug report
+    #[clap(long, value_name = "DIR")]
+    crash_dir: Option<String>,
+
+    /// Add a line of json to this file for each rom played, with how long it
+    /// ran and its faults. The file stays on this computer, nothing is sent
+    /// anywhere
+    #[clap(long, value_name = "FILE")]
+    stats_file: Option<String>,
+
+    /// A toml file of achievement triggers for the rom, defaults to the
+    /// romfile with the extension ".achievements.toml" if that exists
+    #[clap(short, long)]
+    achievements: Option<String>,
+
+    /// A toml file of settings such as hotkey bindings, defaults to chip8.toml
+    /// in the current directory if that exists
+    #[clap(long, value_name = "FILE")]
+    config: Option<String>,
+
+    /// A toml file of translated user interface strings, see lang/en.toml
+    #[clap(long, global = true)]
+    lang: Option<String>,
+
+    /// Take a screenshot when a condition on the machine state first becomes
+    /// true, e.g. "PC==0x2F0". Can be given multiple times
+    #[clap(long, multiple_occurrences = true)]
+    screenshot_when: Vec<String>,
+
+    /// Write a png of the display when the emulator quits, named after the
+    /// rom like the screenshots taken with F12
+    #[clap(long)]
+    screenshot_on_exit: bool,
+
+    /// Record the display and the buzzer to a video with ffmpeg, which must
+    /// be installed. The format comes from the extension, e.g. out.mp4
+    #[clap(long, value_name = "FILE")]
+    record: Option<PathBuf>,
+
+    /// Write the keys pressed to a replay file, timed to the opcode they
+    /// changed at, to play the run back later with --replay
+    #[clap(long, value_name = "FILE", conflicts_with_all = &["replay", "headless"])]
+    record_replay: Option<PathBuf>,
+
+    /// Play a replay recorded with --record-replay in place of the keypad,
+    /// the keypad takes over when it ends
+    #[clap(long, value_name = "FILE", conflicts_with = "headless")]
+    replay: Option<PathBuf>,
+
+    /// Let spectators watch the run live with --spectate, streaming the keys
+    /// to them on this port as it is played
+    #[clap(long, value_name = "PORT", conflicts_with_all = &["replay", "headless"])]
+    spectators: Option<u16>,
+
+    /// Watch a run live from a host started with --spectators, e.g.
+    /// 192.168.1.5:7000. The rom and quirks have to be the host's
+    #[clap(
+        long,
+        value_name = "ADDRESS",
+        conflicts_with_all = &["replay", "record-replay", "spectators", "headless"]
+    )]
+    spectate: Option<String>,
+
+    /// Host a game for two on this port, a second player joins with
+    /// --netplay-join and the keys of both play the one game
+    #[clap(
+        long,
+        value_name = "PORT",
+        conflicts_with_all = &["replay", "spectate", "headless"]
+    )]
+    netplay_host: Option<u16>,
+
+    /// Join a game hosted with --netplay-host, e.g. 192.168.1.5:7001. The
+    /// rom and quirks have to be the host's
+    #[clap(
+        long,
+        value_name = "ADDRESS",
+        conflicts_with_all = &["replay", "spectate", "headless", "netplay-host"]
+    )]
+    netplay_join: Option<String>,
+
+    /// The frames each player's keys are sent ahead of being used in a
+    /// network game, so they arrive in time over a connection with some
+    /// ping. The larger of the two players' is used
+    #[clap(long, value_name = "FRAMES", default_value_t = 3)]
+    input_delay: u32,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Type opcodes, mnemonics or Octo statements and run each one straight
+    /// away on a machine shown in the window
+    Repl {
+        /// The number of pixels that each "chip8" pixel is represented by on
+        /// the window canvas
+        #[clap(short, long, default_value_t = 8)]
+        pixelsize: usize,
+    },
+
+    /// Compare the disassembly of two roms and show the instructions and
+    /// data that changed
+    Romdiff { a: String, b: String },
+
+    /// Run a rom or an Octo program, the same as giving the romfile without a
+    /// subcommand
+    Run { romfile: String },
+
+    /// Assemble a file of mnemonics with labels and DB/DW data into a rom
+    Asm {
+        source: String,
+
+        /// The rom to write, defaults to the source file with the extension
+        /// ".ch8"
+        #[clap(short, long)]
+        output: Option<String>,
+    },
+
+    /// Disassemble a rom into mnemonics with labels for jump targets and
+    /// data sections
+    Disasm { romfile: String },
+
+    /// Print the size, checksums, database entry and likely platform of a
+    /// rom and check that it looks like a good dump
+    Id { romfile: String },
+
+    /// List the save states of a rom with the name, title and time each was
+    /// saved and the playtime then
+    States { romfile: String },
+
+    /// Show the roms in a directory as a grid of screenshots to pick one to
+    /// run, defaults to the rom directory of the config file. The screenshots
+    /// are taken by running each rom for a second and kept in a
+    /// .chip8-thumbnails directory
+    Library { dir: Option<String> },
+
+    /// Answer a few questions about the keyboard layout, colours, rom
+    /// directory and pixel size to write the config file. This runs by itself
+    /// the first time a rom is run without a config file
+    Setup,
+
+    /// List the built in themes and the colours of the presets in the config
+    /// file
+    Themes {
+        /// Open a window showing a test pattern in each theme in turn, to
+        /// pick colours without running a rom
+        #[clap(long)]
+        preview: bool,
+    },
+
+    /// Print the platforms, opcodes, quirks, extensions and frontends this
+    /// build supports, for launchers to check what they can use
+    Capabilities {
+        /// Print everything as json, with every opcode
+        #[clap(long)]
+        json: bool,
+    },
+
+    /// Run a small program for every implemented opcode and check the
+    /// registers, memory and screen afterwards, to check a build on a new
+    /// platform
+    Selftest {
+        /// A directory of Timendus' chip8 test roms to run as well, each
+        /// screen is compared with the one saved by --bless
+        roms: Option<String>,
+
+        /// Save the screens of the test roms as the expected ones, after
+        /// checking that they show every test passing
+        #[clap(long, requires = "roms")]
+        bless: bool,
+    },
+}
+
+// run what the arguments ask for and return the exit status. Everything is
+// dropped before returning, closing the window and the sound device cleanly
+pub fn start() -> ExitCode {
+    // parse the arguments
+    let args = Args::parse();
+
+    // load the translated strings before anything is shown to the user
+    if let Some(ref lang) = args.lang {
+        if let Err(e) = i18n::load(Path::new(lang)) {
+            println!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    }
+
+    run_command(&args).unwrap_or_else(|e| {
+        println!("{}", e);
+        ExitCode::FAILURE
+    })
+}
+
+// run the subcommand, or the rom or library when there is none. An error is
+// returned to be printed once the window or terminal it happened in is gone
+fn run_command(args: &Args) -> Result<ExitCode, String> {
+    match args.command {
+        Some(Commands::Repl { pixelsize }) => repl::run(pixelsize)?,
+        Some(Commands::Romdiff { ref a, ref b }) => return tools::romdiff(a, b),
+        Some(Commands::Asm {
+            ref source,
+            ref output,
+        }) => tools::asm(source, output.as_deref())?,
+        Some(Commands::Disasm { ref romfile }) => {
+            tools::disasm(romfile, parse_extensions(args)?.banking)?
+        }
+        Some(Commands::Id { ref romfile }) => tools::id(romfile)?,
+        Some(Commands::States { ref romfile }) => tools::states(romfile),
+        Some(Commands::Library { ref dir }) => {
+            first_run_setup(args);
+            return library(args, dir.as_deref());
+        }
+        Some(Commands::Themes { preview }) => themes(args, preview)?,
+        Some(Commands::Capabilities { json }) => tools::capabilities(json),
+        Some(Commands::Setup) => setup::run(&config_path(args)),
+        Some(Commands::Selftest { ref roms, bless }) => {
+            return Ok(tools::selftest(roms.as_deref(), bless))
+        }
+        Some(Commands::Run { ref romfile }) => {
+            first_run_setup(args);
+            return Ok(run_rom(args, romfile));
+        }
+        None if args.test_pattern.is_some() => return test_pattern(args),
+        None => {
+            first_run_setup(args);
+            return match args.romfile {
+                Some(ref romfile) => Ok(run_rom(args, romfile)),
+                None if args.headless => Err(tr("headless_no_romfile")),
+                None => library(args, None),
+            };
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+// ask the setup questions when there is no config file yet, unless the input
+// isn't someone at a terminal
+fn first_run_setup(args: &Args) {
+    let path = config_path(args);
+    if args.config.is_none()
+        && !path.exists()
+        && io::stdin().is_terminal()
+        && io::stdout().is_terminal()
+    {
+        setup::run(&path);
+    }
+}
+
+// pick a rom from the library and run it
+fn library(args: &Args, dir: Option<&str>) -> Result<ExitCode, String> {
+    let config = load_config(args)?;
+    let dir = dir.or(config.rom_dir.as_deref()).unwrap_or(".");
+    let preset = config.settings(args.preset.as_deref(), None)?;
+    let pixelsize = args.pixelsize.or(preset.pixelsize).unwrap_or(8);
+
+    let playtime = playtime::load(&playtime_path(args)).unwrap_or_else(|e| {
+        println!("{}", e);
+        Playtime::default()
+    });
+    let picked = library::pick(
+        Path::new(dir),
+        pixelsize,
+        parse_theme(args, &preset)?,
+        &playtime,
+    )?;
+    Ok(match picked {
+        Some(path) => run_rom(args, &path.to_string_lossy()),
+        None => ExitCode::SUCCESS,
+    })
+}
+
+// list the themes or preview them in the window
+fn themes(args: &Args, preview: bool) -> Result<(), String> {
+    let config = load_config(args)?;
+    if !preview {
+        return themes::list(&config);
+    }
+    let preset = config.settings(args.preset.as_deref(), None)?;
+    themes::preview(&config, args.pixelsize.or(preset.pixelsize).unwrap_or(8))
+}
+
+// draw a test pattern with the frontend until the quit hotkey, the pause
+// hotkey stops a moving one and the theme hotkey steps through the themes
+fn test_pattern(args: &Args) -> Result<ExitCode, String> {
+    let config = load_config(args)?;
+    let preset = config.settings(args.preset.as_deref(), None)?;
+    let pixelsize = args.pixelsize.or(preset.pixelsize).unwrap_or(8);
+    let theme = parse_theme(args, &preset)?;
+    let pattern = args
+        .test_pattern
+        .as_deref()
+        .and_then(TestPattern::from_name)
+        .unwrap_or(TestPattern::Checkerboard);
+    if !(1..=1000).contains(&args.refresh) {
+        return Err(trf("refresh_out_of_range", &[&args.refresh]));
+    }
+    let hotkeys = Hotkeys::from_config(&config.hotkeys)?;
+
+    let frontend = open_frontend(args)?;
+    let input = match frontend {
+        Frontend::Window(ref sdl_context) => {
+            KeyboardDevice::new(sdl_context, &hotkeys, &Keymap::default())
+                .map(|keyboard| Box::new(keyboard) as Box<dyn InputSource>)
+        }
+        #[cfg(feature = "tty")]
+        Frontend::Terminal => TtyInput::new(&hotkeys, &Keymap::default())
+            .map(|keyboard| Box::new(keyboard) as Box<dyn InputSource>),
+    };
+    let mut input = input?;
+    let mut video: Box<dyn VideoSink> = match frontend {
+        Frontend::Window(ref sdl_context) => {
+            let mut video = VideoDevice::new(sdl_context, pixelsize)?;
+            video.set_stretch(args.stretch);
+            Box::new(video)
+        }
+        #[cfg(feature = "tty")]
+        Frontend::Terminal => Box::new(TtyVideo::default()),
+    };
+    video.set_theme(theme);
+    video.set_crt(args.crt);
+    video.set_fullscreen(args.fullscreen);
+    video.set_phosphor(args.phosphor);
+
+    let mut display = Display::default();
+    let mut osd = Osd::default();
+    let mut theme_name = colors::THEMES
+        .iter()
+        .find(|(_, named)| *named == theme)
+        .map(|(name, _)| *name);
+    let mut frame = 0;
+    let mut paused = false;
+    pattern.draw(&mut display, frame);
+    loop {
+        for command in input.poll_commands() {
+            match command {
+                Command::Quit => return Ok(ExitCode::SUCCESS),
+                Command::TogglePause => paused = !paused,
+                Command::CycleTheme => {
+                    let (name, theme) = Theme::next(theme_name);
+                    theme_name = Some(name);
+                    video.set_theme(theme);
+                    osd.toast(&trf("theme_changed", &[&name]));
+                }
+                Command::ToggleHelp => osd.toggle_panel("help", input.help_lines()),
+                _ => {}
+            }
+        }
+        if pattern.is_moving() && !paused {
+            frame += 1;
+            pattern.draw(&mut display, frame);
+        }
+        osd.update();
+        video.render(&mut display, &mut osd);
+        thread::sleep(Duration::from_secs(1) / args.refresh);
+    }
+}
+
+// run a romfile in the window and then any rom picked from the recent roms
+// menu while it ran, failing if a rom faulted
+fn run_rom(args: &Args, romfile: &str) -> ExitCode {
+    let mut next_rom = None;
+    let mut result = play_rom(args, romfile, &mut next_rom);
+    while let Some(romfile) = next_rom.take() {
+        result = play_rom(args, &romfile.to_string_lossy(), &mut next_rom);
+    }
+    result.unwrap_or_else(|e| {
+        println!("{}", e);
+        ExitCode::FAILURE
+    })
+}
+
+// run a romfile in the window, failing if the rom faulted. The rom picked
+// from the recent roms menu to run next is put in next_rom. Errors are
+// returned rather than exiting so the frontend is dropped and the terminal
+// put back before they are printed
+fn play_rom(
+    args: &Args,
+    romfile: &str,
+    next_rom: &mut Option<PathBuf>,
+) -> Result<ExitCode, String> {
+    // check if the romfile exists, here or in the rom directory, and if it
+    // does then load it
+    let config = load_config(args)?;
+    let mut path = PathBuf::from(romfile);
+    if !path.exists() && path.is_relative() {
+        if let Some(ref rom_dir) = config.rom_dir {
+            path = Path::new(rom_dir).join(romfile);
+        }
+    }
+    if !path.exists() {
+        return Err(tr("rom_not_found"));
+    }
+    let path = path.as_path();
+
+    // the start time
+    let start_time = Instant::now();
+
+    // setup the chip8 interpretter
+    let data = read_rom(path)?;
+
+    // catch files that are not roms or are cut short before running garbage
+    let extensions = parse_extensions(args)?;
+    let load_address = parse_load_address(args, &extensions)?;
+    let max_size = if extensions.banking {
+        banking::MAX_ROM_SIZE
+    } else {
+        MEM_SIZE - load_address
+    };
+    let problems = romid::check(&data, load_address, max_size);
+    for problem in &problems {
+        let key = if problem.fatal {
+            "id_error"
+        } else {
+            "id_warning"
+        };
+        println!("{}", trf(key, &[&problem.message]));
+    }
+    if problems.iter().any(|problem| problem.fatal) {
+        return Ok(ExitCode::FAILURE);
+    }
+    println!(
+        "{}",
+        trf(
+            "rom_loaded",
+            &[
+                &path
+                    .file_name()
+                    .unwrap_or(path.as_os_str())
+                    .to_string_lossy(),
+                &data.len(),
+                &romdb::crc32_hex(&data),
+            ]
+        )
+    );
+
+    // the preset, the profile saved for the rom and then the defaults of the
+    // config file fill in the settings that aren't on the command line
+    let sha1 = romdb::sha1_hex(&data);
+    let mut profiles = profiles::load(&profiles_path(args))?;
+    let profile = profiles.get(&sha1);
+    if profile.is_some() {
+        println!("{}", tr("profile_applied"));
+    }
+    let preset = config.settings(args.preset.as_deref(), profile)?;
+    let pixelsize = args.pixelsize.or(preset.pixelsize).unwrap_or(8);
+    let clockspeed = args.clockspeed.or(preset.clockspeed).unwrap_or(400);
+    let classroom = args.classroom.or(preset.classroom);
+    let theme = parse_theme(args, &preset)?;
+
+    let mut interp = Interpreter::new();
+    interp.set_extensions(extensions);
+    interp.set_load_address(load_address);
+    if let Some(ref font) = args.font {
+        match fonts::load(font) {
+            Ok(font) => interp.set_font(&font),
+            Err(e) => return Err(e),
+        }
+    }
+    interp.load_rom(&data)?;
+    interp.set_quirks(parse_quirks(args, &preset)?);
+    check_ranges(args, &preset)?;
+    // a replay plays out the same from the seed it was recorded with, a
+    // recording picks one if none is given
+    let replay = match (&args.replay, &args.spectate) {
+        (Some(path), _) => Some(Replay::load(path)),
+        (None, Some(address)) => Some(Replay::connect(address)),
+        (None, None) => None,
+    };
+    let replay = replay.transpose()?;
+    // a network game runs with the seed, refresh and clock speed of the host
+    let netplay = match (args.netplay_host, &args.netplay_join) {
+        (Some(port), _) => {
+            let settings = netplay::Settings {
+                seed: args.seed.unwrap_or_else(rand::random),
+                refresh: args.refresh,
+                clockspeed: classroom.unwrap_or(clockspeed),
+                delay: args.input_delay,
+            };
+            Some(Netplay::host(port, &sha1, settings)?)
+        }
+        (None, Some(address)) => Some(Netplay::join(address, &sha1, args.input_delay)?),
+        (None, None) => None,
+    };
+    let recording = args.record_replay.is_some() || args.spectators.is_some();
+    let seed = match (&replay, &netplay) {
+        (Some(replay), _) => Some(replay.seed),
+        (None, Some(netplay)) => Some(netplay.settings().seed),
+        (None, None) if recording => Some(args.seed.unwrap_or_else(rand::random)),
+        (None, None) => args.seed,
+    };
+    if let Some(seed) = seed {
+        interp.set_seed(seed);
+    }
+
+    if let Some(max_cycles) = args.max_cycles {
+        return run_headless(
+            args,
+            interp,
+            &preset,
+            clockspeed,
+            max_cycles,
+            path,
+            (pixelsize, theme),
+        );
+    }
+
+    let frontend = open_frontend(args)?;
+
+    // every input source for the run goes through the mux
+    let mut input = InputMux::default();
+    let hotkeys = Hotkeys::from_config(&config.hotkeys)?;
+    let keymap = match args.keymap.as_ref().or(preset.keymap.as_ref()) {
+        Some(name) => Keymap::preset(name).ok_or_else(|| {
+            trf(
+                "keymap_unknown_layout",
+                &[&name, &keymap::PRESET_NAMES.join(", ")],
+            )
+        }),
+        None => Ok(Keymap::default()),
+    };
+    let keymap = keymap.and_then(|keymap| keymap.with_config(&config.keymap))?;
+    let keyboard = match frontend {
+        Frontend::Window(ref sdl_context) => KeyboardDevice::new(sdl_context, &hotkeys, &keymap)
+            .and_then(|mut keyboard| {
+                if args.kiosk {
+                    let passcode = config.kiosk_passcode.as_deref().unwrap_or("");
+                    if passcode.trim().is_empty() {
+                        println!("{}", tr("kiosk_no_passcode"));
+                    }
+                    keyboard.set_kiosk(Kiosk::new(passcode))?;
+                }
+                Ok(Box::new(keyboard) as Box<dyn InputSource>)
+            }),
+        // only the window can be locked
+        #[cfg(feature = "tty")]
+        Frontend::Terminal if args.kiosk => Err(tr("tty_kiosk")),
+        #[cfg(feature = "tty")]
+        Frontend::Terminal => TtyInput::new(&hotkeys, &keymap)
+            .map(|keyboard| Box::new(keyboard) as Box<dyn InputSource>),
+    };
+    match keyboard {
+        Ok(keyboard) => input.add(keyboard, Priority::Merge),
+        Err(e) => return Err(e),
+    }
+
+    match frontend {
+        Frontend::Window(ref sdl_context) => {
+            let gamepad = GamepadMap::from_config(&config.gamepad)
+                .and_then(|map| GamepadDevice::new(sdl_context, &map));
+            match gamepad {
+                Ok(gamepad) => input.add(Box::new(gamepad), Priority::Merge),
+                Err(e) => return Err(e),
+            }
+        }
+        #[cfg(feature = "tty")]
+        Frontend::Terminal => {}
+    }
+
+    // stdin can't be read by both the debugger and the pipe
+    match args.input_pipe.as_deref() {
+        Some("-") if args.debug => {
+            return Err(tr("input_pipe_debug_conflict"));
+        }
+        Some("-") => input.add(Box::new(PipeInput::new(stdin_lines())), Priority::Merge),
+        Some(pipe) => {
+            let lines = pipe_lines(Path::new(pipe))?;
+            input.add(Box::new(PipeInput::new(lines)), Priority::Merge);
+        }
+        None => {}
+    }
+
+    if let Some(ref address) = args.osc {
+        // a port on its own only listens to this machine
+        let address = match address.parse::<u16>() {
+            Ok(port) => format!("127.0.0.1:{}", port),
+            Err(_) => address.clone(),
+        };
+        match OscInput::bind(&address) {
+            Ok(osc) => {
+                println!("{}", trf("osc_listening", &[&address]));
+                input.add(Box::new(osc), Priority::Merge);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    #[cfg(feature = "midi")]
+    if let Some(ref device) = args.midi {
+        let midi = MidiMap::from_config(&config.midi)
+            .and_then(|map| Ok(MidiInput::new(midi_bytes(device)?, map)));
+        match midi {
+            Ok(midi) => input.add(Box::new(midi), Priority::Merge),
+            Err(e) => return Err(e),
+        }
+    }
+
+    if args.debug {
+        println!("{}", tr("debug_help"));
+        println!("{}", debugger::location(&interp));
+    }
+
+    let (video, audio): (Box<dyn VideoSink>, Box<dyn AudioSink>) = match frontend {
+        Frontend::Window(ref sdl_context) => {
+            let mut video = VideoDevice::new(sdl_context, pixelsize)?;
+            video.set_theme(theme);
+            video.set_phosphor(args.phosphor);
+            video.set_stretch(args.stretch);
+
+            let mut audio = AudioDevice::new(sdl_context)?;
+            audio.set_pan(args.audio_pan);
+            (Box::new(video), Box::new(audio))
+        }
+        #[cfg(feature = "tty")]
+        Frontend::Terminal => {
+            let mut video = TtyVideo::default();
+            video.set_theme(theme);
+            (Box::new(video), Box::new(TtyBell::default()))
+        }
+    };
+
+    let mut emulator = Emulator::new(
+        interp,
+        video,
+        audio,
+        Box::new(input),
+        classroom.unwrap_or(clockspeed),
+        &start_time,
+    );
+    emulator.set_classroom(classroom.is_some());
+    let crt = args.crt || preset.crt.unwrap_or(false);
+    emulator.set_crt(crt);
+    emulator.set_fullscreen(args.fullscreen);
+    // the theme hotkey steps on from the theme if the colours are still its
+    if let Some((name, _)) = colors::THEMES.iter().find(|(_, named)| *named == theme) {
+        emulator.set_theme(name);
+    }
+    let start_theme = emulator.theme();
+    match replay {
+        Some(ref replay) if replay.refresh != args.refresh => {
+            println!("{}", trf("replay_refresh_ignored", &[&replay.refresh]));
+            emulator.set_refresh(replay.refresh);
+        }
+        _ => emulator.set_refresh(args.refresh),
+    }
+    if let Some(netplay) = netplay {
+        let settings = netplay.settings();
+        emulator.set_refresh(settings.refresh);
+        emulator.set_clockspeed(settings.clockspeed);
+        emulator.set_netplay(netplay);
+    }
+    emulator.set_turbo_multiplier(args.turbo_multiplier);
+    emulator.set_turbo_audio(TurboAudio::from_name(&args.turbo_audio).unwrap_or_default());
+    emulator.set_pitch_offset(args.pitch_offset);
+    let volume = args.volume.or(preset.volume).unwrap_or(100);
+    emulator.set_volume(volume);
+    if let Some(hz) = args.tone_hz.or(preset.tone_hz) {
+        emulator.set_tone_hz(hz as f32);
+    }
+    emulator.set_rewind_seconds(args.rewind);
+    if let Some(budget) = args.cycle_budget.or(preset.cycle_budget) {
+        emulator.set_cycle_budget(budget);
+    }
+    if let Some(seconds) = args.pause_when_stuck {
+        emulator.set_pause_when_stuck(seconds);
+    }
+    emulator.set_fault_policies(parse_fault_policies(args, &preset)?);
+    #[cfg(feature = "strict")]
+    if args.strict {
+        emulator.set_strict();
+    }
+    emulator.set_hotkeys(hotkeys);
+    if args.debug {
+        let mut debugger = Debugger::new(stdin_lines());
+        if let Some(ref path) = args.run_script {
+            debugger.run_script(path)?;
+        }
+        if let Some(ref path) = args.record_script {
+            debugger.record_script(path)?;
+        }
+        emulator.set_debugger(Box::new(debugger));
+    }
+    if let Some(port) = args.gdb {
+        match GdbStub::new(port) {
+            Ok(stub) => emulator.set_debugger(Box::new(stub)),
+            Err(e) => return Err(e),
+        }
+        println!("{}", trf("gdb_waiting", &[&port, &port]));
+    }
+
+    // look the rom up in the database, the info is shown with F2
+    if let Some(rom_info) = romdb::lookup(&data) {
+        println!("{}", trf("rom_recognised", &[&rom_info.title]));
+        emulator.set_rom_info(rom_info);
+    }
+    if preset.rom_info == Some(true) {
+        emulator.show_rom_info();
+    }
+
+    // load the achievement triggers for the rom if there are any
+    let achievements_path = match args.achievements {
+        Some(ref file) => Some(PathBuf::from(file)),
+        None => Some(achievements::default_path(path)).filter(|p| p.exists()),
+    };
+    if let Some(achievements_path) = achievements_path {
+        match achievements::load(&achievements_path) {
+            Ok(triggers) => emulator.set_triggers(triggers),
+            Err(e) => return Err(e),
+        }
+    }
+
+    // set up the screenshots and save states, these are named after the romfile
+    let prefix = PathBuf::from(path.file_stem().unwrap_or(path.as_os_str()));
+    emulator.set_capture_prefix(prefix.clone());
+    emulator.set_rom_sha1(&sha1);
+    match playtime::load(&playtime_path(args)) {
+        Ok(playtime) => emulator.set_playtime(playtime.get(&sha1)),
+        Err(e) => println!("{}", e),
+    }
+    emulator.set_recent_roms(record_recent_rom(args, path));
+    if !args.screenshot_when.is_empty() {
+        let mut watchpoints = Vec::new();
+        for condition in &args.screenshot_when {
+            match Watchpoint::parse(condition) {
+                Ok(watchpoint) => watchpoints.push(watchpoint),
+                Err(e) => {
+                    return Err(trf("invalid_screenshot_condition", &[&e]));
+                }
+            }
+        }
+        emulator.set_screenshot_triggers(ScreenshotTriggers::new(watchpoints, prefix, pixelsize));
+    }
+
+    if let Some(ref record) = args.record {
+        emulator.start_video(record)?;
+    }
+
+    if let Some(replay) = replay {
+        if replay.sha1 != sha1 {
+            println!("{}", tr("replay_rom_mismatch"));
+        }
+        emulator.set_replay(replay);
+    }
+    if let Some(ref record_replay) = args.record_replay {
+        emulator.record_replay(record_replay, &sha1, seed.unwrap_or_default())?;
+    }
+    if let Some(port) = args.spectators {
+        emulator.host_spectators(port, &sha1, seed.unwrap_or_default())?;
+    }
+
+    if args.crash_dir.is_some() {
+        emulator.set_trace_length(crash::TRACE_LENGTH);
+    }
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        while !emulator.has_quit() {
+            emulator.update(&start_time);
+        }
+    }));
+    if let Err(payload) = result {
+        match args.crash_dir {
+            Some(ref dir) => {
+                let message = crash::panic_message(&*payload);
+                write_crash_bundle(args, Path::new(dir), &emulator, &message, &sha1);
+                return Ok(ExitCode::FAILURE);
+            }
+            None => panic::resume_unwind(payload),
+        }
+    }
+    emulator.stop_recording();
+    emulator.stop_video();
+    emulator.stop_replay_recording();
+    if args.screenshot_on_exit {
+        match emulator.take_screenshot() {
+            Ok(Some(path)) => println!("{}", trf("screenshot_saved", &[&path.display()])),
+            Ok(None) => {}
+            Err(e) => println!("{}", e),
+        }
+    }
+    record_playtime(args, &data, start_time.elapsed().as_secs());
+    if let Some(ref stats_file) = args.stats_file {
+        record_session(Path::new(stats_file), path, &sha1, &emulator, &start_time);
+    }
+    *next_rom = emulator.next_rom().map(Path::to_path_buf);
+    if let Some(fault) = emulator.fault() {
+        println!("{}", fault);
+        if let Some(ref dir) = args.crash_dir {
+            write_crash_bundle(args, Path::new(dir), &emulator, &fault.to_string(), &sha1);
+        }
+        return Ok(ExitCode::FAILURE);
+    }
+
+    // keep the settings changed while playing for the next time
+    let mut changes = Preset::default();
+    let netplayed = args.netplay_host.is_some() || args.netplay_join.is_some();
+    if classroom.is_none() && !netplayed && emulator.clockspeed() != clockspeed {
+        changes.clockspeed = Some(emulator.clockspeed());
+    }
+    if emulator.theme() != start_theme {
+        changes.theme = emulator.theme().map(str::to_string);
+    }
+    if emulator.volume() != volume {
+        changes.volume = Some(emulator.volume());
+    }
+    if emulator.crt() != crt {
+        changes.crt = Some(emulator.crt());
+    }
+    if changes != Preset::default() {
+        profiles.update(&sha1, changes);
+        match profiles.save(&profiles_path(args)) {
+            Ok(()) => println!("{}", tr("profile_saved")),
+            Err(e) => println!("{}", e),
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+// write the crash bundle for a panic or fault and print where it is
+fn write_crash_bundle(args: &Args, dir: &Path, emulator: &Emulator, message: &str, rom_sha1: &str) {
+    let report = CrashReport {
+        message,
+        machine: emulator.interpreter(),
+        trace: emulator.trace(),
+        rom_sha1,
+        config: fs::read_to_string(config_path(args)).ok(),
+    };
+    match crash::write_bundle(dir, &report) {
+        Ok(bundle) => println!("{}", trf("crash_bundle_written", &[&bundle.display()])),
+        Err(e) => println!("{}", e),
+    }
+}
+
+// the per rom profiles file next to the config file
+fn profiles_path(args: &Args) -> PathBuf {
+    let config = config_path(args);
+    config
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(profiles::FILE_NAME)
+}
+
+// the playtime file next to the config file
+fn playtime_path(args: &Args) -> PathBuf {
+    let config = config_path(args);
+    config
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(playtime::FILE_NAME)
+}
+
+// the recent roms file next to the config file
+fn recent_path(args: &Args) -> PathBuf {
+    let config = config_path(args);
+    config
+        .parent()
+        .unwrap_or(Path::new(""))
+        .join(recent::FILE_NAME)
+}
+
+// put the romfile first in the recent roms and give the others, newest first
+fn record_recent_rom(args: &Args, path: &Path) -> Vec<PathBuf> {
+    let file = recent_path(args);
+    let mut recent = match recent::load(&file) {
+        Ok(recent) => recent,
+        Err(e) => {
+            println!("{}", e);
+            return Vec::new();
+        }
+    };
+    let path = path.canonicalize().unwrap_or(path.to_path_buf());
+    recent.add(&path);
+    if let Err(e) = recent.save(&file) {
+        println!("{}", e);
+    }
+    recent.roms()[1..].to_vec()
+}
+
+// add the time the rom was played to its total
+fn record_playtime(args: &Args, rom: &[u8], seconds: u64) {
+    let path = playtime_path(args);
+    let mut playtime = match playtime::load(&path) {
+        Ok(playtime) => playtime,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let sha1 = romdb::sha1_hex(rom);
+    playtime.add(&sha1, seconds);
+    println!(
+        "{}",
+        trf(
+            "rom_played",
+            &[
+                &playtime::format(seconds),
+                &playtime::format(playtime.get(&sha1))
+            ]
+        )
+    );
+    if let Err(e) = playtime.save(&path) {
+        println!("{}", e);
+    }
+}
+
+// add the rom played to the stats file
+fn record_session(file: &Path, path: &Path, sha1: &str, emulator: &Emulator, start_time: &Instant) {
+    let seconds = start_time.elapsed().as_secs();
+    let session = Session {
+        rom: &path.file_name().unwrap_or_default().to_string_lossy(),
+        sha1,
+        started: stats::unix_time().saturating_sub(seconds),
+        seconds,
+        opcodes: emulator.cycles(),
+        faults: emulator.faults_survived(),
+        fault: emulator.fault().and_then(Chip8Error::fault_name),
+    };
+    if let Err(e) = stats::append(file, &session) {
+        println!("{}", e);
+    }
+}
+
+// read a romfile, Octo programs are assembled first
+fn read_rom(path: &Path) -> Result<Vec<u8>, Chip8Error> {
+    let read_error = |e| Chip8Error::Rom(trf("file_read_error", &[&path.display(), &e]));
+    if path.extension().is_some_and(|ext| ext == "8o") {
+        let source = fs::read_to_string(path).map_err(read_error)?;
+        octo::assemble(&source).map_err(Chip8Error::Rom)
+    } else {
+        fs::read(path).map_err(read_error)
+    }
+}
+
+// the frontends a rom can be played in
+#[cfg(feature = "tty")]
+const FRONTEND_NAMES: &[&str] = &["window", "tty"];
+#[cfg(not(feature = "tty"))]
+const FRONTEND_NAMES: &[&str] = &["window"];
+
+// where a rom is played, sdl is kept open for as long as the window is
+enum Frontend {
+    Window(Sdl),
+    #[cfg(feature = "tty")]
+    Terminal,
+}
+
+fn open_frontend(args: &Args) -> Result<Frontend, String> {
+    match args.frontend.as_str() {
+        #[cfg(feature = "tty")]
+        "tty" => Ok(Frontend::Terminal),
+        _ => Ok(Frontend::Window(init_sdl()?)),
+    }
+}
+
+// open sdl, there is no window, sound or keyboard without it
+fn init_sdl() -> Result<Sdl, String> {
+    Ok(sdl2::init().map_err(|e| Chip8Error::device("device_sdl", e))?)
+}
+
+// the config file given with --config or the default one
+fn config_path(args: &Args) -> PathBuf {
+    match args.config {
+        Some(ref file) => PathBuf::from(file),
+        None => PathBuf::from(config::DEFAULT_PATH),
+    }
+}
+
+// the config file given with --config, or the default one if it exists
+fn load_config(args: &Args) -> Result<Config, String> {
+    let path = config_path(args);
+    if args.config.is_none() && !path.exists() {
+        return Ok(Config::default());
+    }
+    config::load(&path)
+}
+
+// the extensions turned on with --ext
+fn parse_extensions(args: &Args) -> Result<Extensions, String> {
+    let mut extensions = Extensions::default();
+    for name in &args.ext {
+        extensions.enable(name)?;
+    }
+    Ok(extensions)
+}
+
+// the address to load the rom at, a name or hex with or without 0x. Banking
+// keeps its rom at 0x200 as its window is fixed
+fn parse_load_address(args: &Args, extensions: &Extensions) -> Result<usize, String> {
+    let Some(text) = args.load_address.as_deref() else {
+        return Ok(PROGRAM_START);
+    };
+    let preset = LOAD_ADDRESS_PRESETS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(text))
+        .map(|(_, address)| *address);
+    let hex = text.trim_start_matches("0x").trim_start_matches("0X");
+    let address = preset
+        .or_else(|| usize::from_str_radix(hex, 16).ok())
+        .filter(|address| (PROGRAM_START..MEM_SIZE).contains(address));
+    let Some(address) = address else {
+        return Err(trf("load_address_invalid", &[&text]));
+    };
+    if extensions.banking && address != PROGRAM_START {
+        return Err(tr("load_address_banking"));
+    }
+    Ok(address)
+}
+
+// check the numbers of the command line and preset are in range before
+// anything is connected to or the frontend is opened
+fn check_ranges(args: &Args, preset: &Preset) -> Result<(), String> {
+    if !(1..=1000).contains(&args.refresh) {
+        return Err(trf("refresh_out_of_range", &[&args.refresh]));
+    }
+    if !(1..=64).contains(&args.turbo_multiplier) {
+        return Err(trf("turbo_out_of_range", &[&args.turbo_multiplier]));
+    }
+    if !(-48.0..=48.0).contains(&args.pitch_offset) {
+        return Err(trf("pitch_offset_out_of_range", &[&args.pitch_offset]));
+    }
+    if let Some(volume) = args.volume.or(preset.volume) {
+        if volume > 100 {
+            return Err(trf("volume_out_of_range", &[&volume]));
+        }
+    }
+    if args.rewind > rewind::MAX_REWIND_SECONDS {
+        return Err(trf(
+            "rewind_out_of_range",
+            &[&args.rewind, &rewind::MAX_REWIND_SECONDS],
+        ));
+    }
+    if args.input_delay > netplay::MAX_INPUT_DELAY {
+        return Err(trf(
+            "input_delay_out_of_range",
+            &[&args.input_delay, &netplay::MAX_INPUT_DELAY],
+        ));
+    }
+    if let Some(hz) = args.tone_hz.or(preset.tone_hz) {
+        if !(20..=20000).contains(&hz) {
+            return Err(trf("tone_hz_out_of_range", &[&hz]));
+        }
+    }
+    if !(-1.0..=1.0).contains(&args.audio_pan) {
+        return Err(trf("audio_pan_out_of_range", &[&args.audio_pan]));
+    }
+    Ok(())
+}
+
+// the quirks preset with any single quirks turned on or off
+fn parse_quirks(args: &Args, preset: &Preset) -> Result<Quirks, String> {
+    let mut quirks = match args.quirks.as_ref().or(preset.quirks.as_ref()) {
+        Some(name) => Quirks::preset(name)
+            .ok_or_else(|| trf("quirks_unknown", &[&name, &quirks::PRESET_NAMES.join(", ")]))?,
+        None => Quirks::default(),
+    };
+    for quirk in &args.quirk {
+        quirks.apply_override(quirk)?;
+    }
+    Ok(quirks)
+}
+
+// the fault policies of the preset with those of the command line on top
+fn parse_fault_policies(args: &Args, preset: &Preset) -> Result<FaultPolicies, String> {
+    let mut policies = FaultPolicies::default();
+    if let Some(ref list) = preset.on_fault {
+        policies.apply_list(list)?;
+    }
+    for policy in &args.on_fault {
+        policies.apply(policy)?;
+    }
+    Ok(policies)
+}
+
+// the display colours of the preset, its theme with any colours it or the
+// command line change. A palette on the command line replaces the colours of
+// the preset
+fn parse_theme(args: &Args, preset: &Preset) -> Result<Theme, String> {
+    let (name, foreground, background) = match args.palette {
+        Some(ref palette) => (Some(palette), None, None),
+        None => (
+            preset.theme.as_ref(),
+            preset.foreground.as_ref(),
+            preset.background.as_ref(),
+        ),
+    };
+    theme_colors(
+        name,
+        args.fg.as_ref().or(foreground),
+        args.bg.as_ref().or(background),
+    )
+}
+
+// a built in theme with either of its colours changed
+fn theme_colors(
+    name: Option<&String>,
+    foreground: Option<&String>,
+    background: Option<&String>,
+) -> Result<Theme, String> {
+    let parse = |color: Option<&String>, default: Color| match color {
+        Some(color) => Color::parse(color),
+        None => Ok(default),
+    };
+    let default = match name {
+        Some(name) => Theme::named(name)
+            .ok_or_else(|| trf("theme_unknown", &[&name, &colors::THEME_NAMES.join(", ")]))?,
+        None => Theme::default(),
+    };
+    Ok(Theme {
+        foreground: parse(foreground, default.foreground)?,
+        background: parse(background, default.background)?,
+    })
+}
+
+// lines written to a named pipe or file, read on another thread like stdin.
+// A named pipe is opened again each time the writer closes it so each echo
+// of a script gets through
+fn pipe_lines(path: &Path) -> Result<Receiver<String>, String> {
+    let metadata =
+        fs::metadata(path).map_err(|e| trf("file_read_error", &[&path.display(), &e]))?;
+    let reopen = !metadata.is_file();
+    let path = path.to_path_buf();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || loop {
+        let Ok(file) = fs::File::open(&path) else {
+            break;
+        };
+        for line in io::BufReader::new(file).lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                return;
+            }
+        }
+        if !reopen {
+            break;
+        }
+    });
+    Ok(receiver)
+}
+
+// run the rom with the null backends and no waiting, then write a screenshot
+// if asked for one. Fails if the rom faulted
+fn run_headless(
+    args: &Args,
+    interp: Interpreter,
+    preset: &Preset,
+    clockspeed: u32,
+    max_cycles: u64,
+    path: &Path,
+    (pixelsize, theme): (usize, Theme),
+) -> Result<ExitCode, String> {
+    let start_time = Instant::now();
+    let mut emulator = Emulator::new(
+        interp,
+        Box::new(NullVideo),
+        Box::new(NullAudio),
+        Box::new(NullInput),
+        clockspeed,
+        &start_time,
+    );
+    emulator.set_refresh(args.refresh);
+    emulator.set_rewind_seconds(0);
+    if let Some(budget) = args.cycle_budget.or(preset.cycle_budget) {
+        emulator.set_cycle_budget(budget);
+    }
+    if let Some(seconds) = args.pause_when_stuck {
+        emulator.set_pause_when_stuck(seconds);
+    }
+    emulator.set_fault_policies(parse_fault_policies(args, preset)?);
+    #[cfg(feature = "strict")]
+    if args.strict {
+        emulator.set_strict();
+    }
+
+    let cycles = emulator.run_headless(max_cycles);
+    println!(
+        "{}",
+        trf(
+            "headless_done",
+            &[&cycles, &format!("{:03X}", emulator.interpreter().pc())]
+        )
+    );
+
+    if args.screenshot_on_exit {
+        let prefix = path.file_stem().unwrap_or(path.as_os_str());
+        let path = screenshot::next_free_path(Path::new(prefix), "screenshot", "png");
+        let pixelmap = Pixelmap::render(emulator.interpreter().display(), pixelsize, theme);
+        match pixelmap.save_png(&path) {
+            Ok(()) => println!("{}", trf("screenshot_saved", &[&path.display()])),
+            Err(e) => println!("{}", e),
+        }
+    }
+    Ok(match emulator.fault() {
+        Some(fault) => {
+            println!("{}", fault);
+            ExitCode::FAILURE
+        }
+        None if emulator.is_stuck() => ExitCode::FAILURE,
+        None => ExitCode::SUCCESS,
+    })
+}
+
+// the bytes sent by a raw midi device, read on another thread as they come
+#[cfg(feature = "midi")]
+fn midi_bytes(path: &Path) -> Result<Receiver<Vec<u8>>, String> {
+    use std::io::Read;
+
+    let mut device =
+        fs::File::open(path).map_err(|e| trf("file_read_error", &[&path.display(), &e]))?;
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = [0; 256];
+        while let Ok(count) = device.read(&mut buffer) {
+            if count == 0 || sender.send(buffer[..count].to_vec()).is_err() {
+                break;
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+// lines typed on the console, these are read on another thread so the window
+// keeps drawing while waiting for input
+fn stdin_lines() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
@@ -0,0 +1,253 @@
+use std::io;
+use std::io::Write;
+use std::sync::mpsc::TryRecvError;
+use std::thread;
+use std::time::Duration;
+
+use chip8::backend::AudioSink;
+use chip8::backend::Command;
+use chip8::backend::InputSource;
+use chip8::backend::VideoSink;
+use chip8::classroom;
+use chip8::classroom::CpuState;
+use chip8::debugger;
+use chip8::expr::parse_number;
+use chip8::expr::MachineView;
+use chip8::hotkeys::Hotkeys;
+use chip8::i18n::tr;
+use chip8::i18n::trf;
+use chip8::interpreter::MEM_SIZE;
+use chip8::keymap::Keymap;
+use chip8::octo;
+use chip8::opcode::Instruction;
+use chip8::osd::Osd;
+use chip8::Interpreter;
+
+use super::audio::AudioDevice;
+use super::keyboard::KeyboardDevice;
+use super::video::VideoDevice;
+
+// the repl runs the window at 60hz
+const FRAME_TIME: Duration = Duration::from_micros(16667);
+
+// the number of bytes shown by mem when no count is given
+const MEM_DEFAULT_COUNT: usize = 16;
+
+// an instruction that is waiting for a key press, with the state from before
+// it started so it can be described once it finishes
+struct Pending {
+    pc: usize,
+    before: CpuState,
+    opcode: u16,
+}
+
+// read instructions from stdin and run each one as soon as it is entered
+// against a machine shown in the window
+pub fn run(pixelsize: usize) -> Result<(), String> {
+    let sdl_context = super::init_sdl()?;
+    let mut video = VideoDevice::new(&sdl_context, pixelsize)?;
+    let mut audio = AudioDevice::new(&sdl_context)?;
+    let mut keyboard = KeyboardDevice::new(&sdl_context, &Hotkeys::default(), &Keymap::default())?;
+
+    let mut interp = Interpreter::new();
+    let mut osd = Osd::default();
+    let mut pending: Option<Pending> = None;
+
+    let receiver = super::stdin_lines();
+
+    println!("{}", tr("repl_welcome"));
+    prompt();
+
+    loop {
+        for command in keyboard.poll_commands() {
+            match command {
+                Command::Quit => return Ok(()),
+                Command::ToggleHelp => osd.toggle_panel("help", keyboard.help_lines()),
+                Command::Notify(key) => osd.toast(&tr(key)),
+                Command::ToggleRomInfo
+                | Command::TogglePause
+                | Command::StepOpcode
+                | Command::StepFrame
+                | Command::Reset
+                | Command::Screenshot
+                | Command::ToggleRecording
+                | Command::ToggleScope
+                | Command::CycleTheme
+                | Command::ToggleCrt
+                | Command::ToggleFullscreen
+                | Command::SaveStateMenu
+                | Command::LoadStateMenu
+                | Command::Undo
+                | Command::Faster
+                | Command::Slower
+                | Command::VolumeUp
+                | Command::VolumeDown
+                | Command::SetClockspeed(_)
+                | Command::Rewind(_)
+                | Command::Turbo(_)
+                | Command::TogglePalette
+                | Command::Palette(_)
+                | Command::RecentMenu => {}
+            }
+        }
+        interp.set_keys(keyboard.read_keys());
+
+        if let Some(waiting) = pending.take() {
+            // the key wait opcode moves the program counter back until a key
+            // is pressed
+            if let Err(e) = interp.cycle() {
+                println!("{}", e);
+            } else if interp.pc() == waiting.pc {
+                pending = Some(waiting);
+            } else {
+                show_result(&interp, &mut osd, &waiting.before, waiting.opcode);
+                prompt();
+            }
+        } else {
+            match receiver.try_recv() {
+                Ok(line) if matches!(line.trim(), "quit" | "exit") => return Ok(()),
+                Ok(line) => {
+                    pending = handle_line(&mut interp, &mut osd, line.trim());
+                    if pending.is_none() {
+                        prompt();
+                    }
+                }
+                Err(TryRecvError::Empty) => {}
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+
+        interp.update_timers();
+        osd.update();
+        video.render(interp.display_mut(), &mut osd);
+        audio.set_pitch(chip8::audio::buzzer_hz(
+            interp.pitch(),
+            chip8::audio::BUZZER_HZ,
+            0.0,
+        ));
+        audio.set_beep(interp.is_beeping());
+
+        thread::sleep(FRAME_TIME);
+    }
+}
+
+fn prompt() {
+    print!("> ");
+    io::stdout().flush().unwrap();
+}
+
+// run a repl command or an instruction, returns the instruction if it is
+// still waiting for a key press
+fn handle_line(interp: &mut Interpreter, osd: &mut Osd, line: &str) -> Option<Pending> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        [] => {}
+        ["help"] => {
+            println!("{}", tr("repl_welcome"));
+            println!("{}", tr("repl_help"));
+        }
+        ["reset"] => {
+            *interp = Interpreter::new();
+            osd.set_status(Vec::new());
+        }
+        ["regs"] => print_registers(interp),
+        ["mem", addr] => print_memory(interp, addr, None),
+        ["mem", addr, count] => print_memory(interp, addr, Some(count)),
+        _ => match assemble(line) {
+            Ok(instruction) => return execute(interp, osd, instruction),
+            Err(e) => println!("{}", e),
+        },
+    }
+    None
+}
+
+// turn a line into an instruction, it can be a raw opcode in hex, a
+// mnemonic such as "LD VA, 0x02" or an Octo statement such as "va := 2"
+fn assemble(line: &str) -> Result<Instruction, String> {
+    let no_symbols = |_: &str| None;
+
+    if line.len() == 4 && line.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok(Instruction::decode(u16::from_str_radix(line, 16).unwrap()));
+    }
+
+    Instruction::parse(line, &no_symbols).or_else(|mnemonic_error| {
+        octo::parse_statement(line, &no_symbols).map_err(|octo_error| {
+            // mnemonics are usually written in upper case, octo in lower case
+            let name = line.split_whitespace().next().unwrap_or("");
+            if name.chars().any(|c| c.is_ascii_uppercase()) {
+                mnemonic_error
+            } else {
+                octo_error
+            }
+        })
+    })
+}
+
+// write the instruction at the program counter and run it
+fn execute(interp: &mut Interpreter, osd: &mut Osd, instruction: Instruction) -> Option<Pending> {
+    // the interpreter stops on these so they are not run
+    if let Instruction::Sys(_) | Instruction::Invalid(_) = instruction {
+        println!("{}", trf("repl_cannot_run", &[&instruction]));
+        return None;
+    }
+    let pc = interp.pc();
+    if pc + 1 >= MEM_SIZE {
+        println!("{}", tr("repl_end_of_memory"));
+        return None;
+    }
+
+    let opcode = instruction.encode();
+    interp.write_memory(pc, (opcode >> 8) as u8);
+    interp.write_memory(pc + 1, (opcode & 0xFF) as u8);
+
+    let before = CpuState::capture(interp);
+    if let Err(e) = interp.cycle() {
+        println!("{}", e);
+        return None;
+    }
+
+    if let Instruction::LdVxK(_) = instruction {
+        if interp.pc() == pc {
+            println!("{}", tr("repl_waiting_for_key"));
+            return Some(Pending { pc, before, opcode });
+        }
+    }
+
+    show_result(interp, osd, &before, opcode);
+    None
+}
+
+// describe what an instruction did in the terminal and on screen
+fn show_result(interp: &Interpreter, osd: &mut Osd, before: &CpuState, opcode: u16) {
+    let after = CpuState::capture(interp);
+    let lines = classroom::annotate(before, &after, opcode);
+    for line in &lines {
+        println!("{}", line);
+    }
+    osd.set_status(lines);
+}
+
+fn print_registers(interp: &Interpreter) {
+    for line in debugger::registers(interp) {
+        println!("{}", line);
+    }
+}
+
+// print a hex dump of memory, 16 bytes to a line
+fn print_memory(interp: &Interpreter, addr: &str, count: Option<&str>) {
+    let addr = match parse_number(addr) {
+        Ok(addr) if (0..MEM_SIZE as i64).contains(&addr) => addr as usize,
+        Ok(_) => return println!("{}", tr("repl_help")),
+        Err(e) => return println!("{}", e),
+    };
+    let count = match count.map(parse_number) {
+        None => MEM_DEFAULT_COUNT,
+        Some(Ok(count)) if count > 0 => count as usize,
+        Some(Ok(_)) => return println!("{}", tr("repl_help")),
+        Some(Err(e)) => return println!("{}", e),
+    };
+
+    for line in debugger::hexdump(interp, addr, count) {
+        println!("{}", line);
+    }
+}
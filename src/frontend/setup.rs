@@ -0,0 +1,116 @@
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+
+use toml::value::Table;
+use toml::Value;
+
+use chip8::colors;
+use chip8::colors::Theme;
+use chip8::i18n::tr;
+use chip8::i18n::trf;
+use chip8::keymap;
+use chip8::keymap::Keymap;
+
+// ask for the keyboard layout, theme, rom directory and pixel size and write
+// them to the config file, keeping anything else already in it
+pub fn run(path: &Path) {
+    println!("{}", trf("setup_intro", &[&path.display()]));
+
+    let answers = ask_all();
+    let (keymap, theme, rom_dir, pixelsize) = match answers {
+        Some(answers) => answers,
+        None => {
+            println!("{}", tr("setup_cancelled"));
+            return;
+        }
+    };
+
+    let mut config = fs::read_to_string(path)
+        .ok()
+        .and_then(|text| text.parse::<Value>().ok())
+        .and_then(|value| value.as_table().cloned())
+        .unwrap_or_default();
+    config.insert("rom_dir".to_string(), Value::String(rom_dir));
+    let defaults = config
+        .entry("defaults")
+        .or_insert_with(|| Value::Table(Table::new()));
+    if let Value::Table(defaults) = defaults {
+        defaults.insert("keymap".to_string(), Value::String(keymap));
+        defaults.insert("theme".to_string(), Value::String(theme));
+        defaults.insert("pixelsize".to_string(), Value::Integer(pixelsize));
+    }
+
+    let text = toml::to_string(&Value::Table(config)).expect("a table is valid toml");
+    match fs::write(path, text) {
+        Ok(()) => println!("{}", trf("setup_written", &[&path.display()])),
+        Err(e) => println!("{}", trf("file_write_error", &[&path.display(), &e])),
+    }
+}
+
+// the answers to every question, or None if the input ran out
+fn ask_all() -> Option<(String, String, String, i64)> {
+    let keymap = ask(
+        &trf("setup_keymap", &[&keymap::PRESET_NAMES.join(", ")]),
+        "qwerty",
+        |answer| match Keymap::preset(answer) {
+            Some(_) => Ok(()),
+            None => Err(trf(
+                "keymap_unknown_layout",
+                &[&answer, &keymap::PRESET_NAMES.join(", ")],
+            )),
+        },
+    )?;
+    let theme = ask(
+        &trf("setup_theme", &[&colors::THEME_NAMES.join(", ")]),
+        "classic",
+        |answer| match Theme::named(answer) {
+            Some(_) => Ok(()),
+            None => Err(trf(
+                "theme_unknown",
+                &[&answer, &colors::THEME_NAMES.join(", ")],
+            )),
+        },
+    )?;
+    let rom_dir = ask(&tr("setup_rom_dir"), ".", |answer| {
+        if Path::new(answer).is_dir() {
+            Ok(())
+        } else {
+            Err(trf("setup_no_such_dir", &[&answer]))
+        }
+    })?;
+    let pixelsize = ask(&tr("setup_pixelsize"), "8", |answer| {
+        match answer.parse::<i64>() {
+            Ok(size) if (1..=32).contains(&size) => Ok(()),
+            _ => Err(trf("setup_bad_pixelsize", &[&answer])),
+        }
+    })?;
+    Some((keymap, theme, rom_dir, pixelsize.parse().unwrap()))
+}
+
+// ask a question until the answer passes the check, an empty answer takes the
+// default
+fn ask(
+    question: &str,
+    default: &str,
+    check: impl Fn(&str) -> Result<(), String>,
+) -> Option<String> {
+    loop {
+        print!("{} [{}]: ", question, default);
+        io::stdout().flush().ok();
+
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).ok()? == 0 {
+            return None;
+        }
+        let answer = match line.trim() {
+            "" => default,
+            answer => answer,
+        };
+        match check(answer) {
+            Ok(()) => return Some(answer.to_string()),
+            Err(e) => println!("{}", e),
+        }
+    }
+}
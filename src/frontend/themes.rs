@@ -0,0 +1,146 @@
+use std::time::Duration;
+use std::time::Instant;
+
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+
+use chip8::backend::VideoSink;
+use chip8::colors;
+use chip8::colors::Theme;
+use chip8::config::Config;
+use chip8::config::Preset;
+use chip8::config::PRESET_NAMES;
+use chip8::display::Display;
+use chip8::error::Chip8Error;
+use chip8::i18n::tr;
+use chip8::i18n::trf;
+use chip8::osd::Osd;
+use chip8::testpattern::TestPattern;
+
+use super::video::VideoDevice;
+
+// how long the preview shows each theme before moving on
+const THEME_TIME: Duration = Duration::from_secs(3);
+
+// print the built in themes and the colours of the defaults and presets of
+// the config file
+pub fn list(config: &Config) -> Result<(), String> {
+    for (name, theme) in all_themes(config)? {
+        println!(
+            "{}",
+            trf(
+                "themes_line",
+                &[&name, &theme.foreground, &theme.background]
+            )
+        );
+    }
+    Ok(())
+}
+
+// show a test pattern in each theme in turn until the window is closed
+pub fn preview(config: &Config, pixelsize: usize) -> Result<(), String> {
+    let themes = all_themes(config)?;
+    println!("{}", tr("themes_help"));
+
+    let sdl_context = super::init_sdl()?;
+    let mut video = VideoDevice::new(&sdl_context, pixelsize)?;
+    let mut event_pump = sdl_context
+        .event_pump()
+        .map_err(|e| Chip8Error::device("device_keyboard", e))?;
+
+    let mut display = Display::default();
+    TestPattern::Densities.draw(&mut display, 0);
+    let mut osd = Osd::default();
+    let mut selected = 0;
+    let mut paused = false;
+    let mut shown: Option<usize> = None;
+    let mut next_time = Instant::now() + THEME_TIME;
+    loop {
+        if shown != Some(selected) {
+            let (ref name, theme) = themes[selected];
+            let line = trf("themes_line", &[name, &theme.foreground, &theme.background]);
+            video.set_theme(theme);
+            video.set_title(&trf(
+                "themes_title",
+                &[name, &(selected + 1), &themes.len()],
+            ));
+            osd.toast(&line);
+            println!("{}", line);
+            shown = Some(selected);
+        }
+        osd.update();
+        video.render(&mut display, &mut osd);
+
+        let before = selected;
+        match event_pump.wait_event_timeout(16) {
+            Some(Event::Quit { .. })
+            | Some(Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            }) => return Ok(()),
+            Some(Event::KeyDown {
+                keycode: Some(keycode),
+                ..
+            }) => match keycode {
+                Keycode::Left => selected = (selected + themes.len() - 1) % themes.len(),
+                Keycode::Right => selected = (selected + 1) % themes.len(),
+                Keycode::Space => {
+                    paused = !paused;
+                    osd.toast(&tr(if paused {
+                        "themes_paused"
+                    } else {
+                        "themes_cycling"
+                    }));
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        if paused || selected != before {
+            next_time = Instant::now() + THEME_TIME;
+        } else if Instant::now() >= next_time {
+            selected = (selected + 1) % themes.len();
+            next_time = Instant::now() + THEME_TIME;
+        }
+    }
+}
+
+// the built in themes, then the colours of the defaults and every preset of
+// the config file that sets any
+fn all_themes(config: &Config) -> Result<Vec<(String, Theme)>, String> {
+    let mut themes: Vec<(String, Theme)> = colors::THEMES
+        .iter()
+        .map(|(name, theme)| (name.to_string(), *theme))
+        .collect();
+    if let Some(theme) = preset_theme(&config.defaults)? {
+        themes.push(("defaults".to_string(), theme));
+    }
+    let mut names: Vec<&str> = PRESET_NAMES.to_vec();
+    names.extend(
+        config
+            .presets
+            .keys()
+            .map(|name| name.as_str())
+            .filter(|name| !PRESET_NAMES.contains(name)),
+    );
+    for name in names {
+        let preset = config.preset(name).unwrap_or_default();
+        if let Some(theme) = preset_theme(&preset)? {
+            themes.push((name.to_string(), theme));
+        }
+    }
+    Ok(themes)
+}
+
+// the colours of a preset that sets a theme or either colour
+fn preset_theme(preset: &Preset) -> Result<Option<Theme>, String> {
+    if preset.theme.is_none() && preset.foreground.is_none() && preset.background.is_none() {
+        return Ok(None);
+    }
+    super::theme_colors(
+        preset.theme.as_ref(),
+        preset.foreground.as_ref(),
+        preset.background.as_ref(),
+    )
+    .map(Some)
+}
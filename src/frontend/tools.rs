@@ -0,0 +1,287 @@
+use std::fs;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use chip8::asm;
+use chip8::capabilities;
+use chip8::disasm;
+use chip8::extensions;
+use chip8::fonts;
+use chip8::i18n::tr;
+use chip8::i18n::trf;
+use chip8::library;
+use chip8::octo;
+use chip8::quirks;
+use chip8::romdiff;
+use chip8::romdiff::Change;
+use chip8::romid;
+use chip8::savestate;
+use chip8::savestate::SaveState;
+use chip8::selftest;
+
+// ansi colours for the diff when it is shown in a terminal
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+// read a file given on the command line
+fn read_file(path: &str) -> Result<Vec<u8>, String> {
+    fs::read(path).map_err(|e| trf("file_read_error", &[&path, &e]))
+}
+
+// print the changes between two roms, exits with 1 if they differ like diff
+pub fn romdiff(a: &str, b: &str) -> Result<ExitCode, String> {
+    let hunks = romdiff::diff(&read_file(a)?, &read_file(b)?);
+    if hunks.is_empty() {
+        println!("{}", tr("romdiff_identical"));
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let colour = std::io::stdout().is_terminal();
+    println!("--- {}", a);
+    println!("+++ {}", b);
+    for hunk in hunks {
+        println!("...");
+        for change in hunk {
+            match change {
+                Change::Same(line) => println!("  {}", line),
+                Change::Removed(line) if colour => println!("{}- {}{}", RED, line, RESET),
+                Change::Removed(line) => println!("- {}", line),
+                Change::Added(line) if colour => println!("{}+ {}{}", GREEN, line, RESET),
+                Change::Added(line) => println!("+ {}", line),
+            }
+        }
+    }
+    Ok(ExitCode::FAILURE)
+}
+
+// assemble a source file into a rom
+pub fn asm(source: &str, output: Option<&str>) -> Result<(), String> {
+    let text = String::from_utf8_lossy(&read_file(source)?).into_owned();
+    let assembled = if Path::new(source).extension().is_some_and(|ext| ext == "8o") {
+        octo::assemble(&text)
+    } else {
+        asm::assemble(&text)
+    };
+    let rom = assembled?;
+
+    let output = match output {
+        Some(output) => PathBuf::from(output),
+        None => Path::new(source).with_extension("ch8"),
+    };
+    fs::write(&output, &rom).map_err(|e| trf("file_write_error", &[&output.display(), &e]))?;
+    println!("{}", trf("asm_written", &[&rom.len(), &output.display()]));
+    Ok(())
+}
+
+// print an annotated disassembly of a rom, loaded with banking if banked
+pub fn disasm(romfile: &str, banked: bool) -> Result<(), String> {
+    for line in disasm::listing(&read_file(romfile)?, banked) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+// print the checksums, database entry, platform and any problems with a rom
+pub fn id(romfile: &str) -> Result<(), String> {
+    let id = romid::identify(&read_file(romfile)?);
+
+    println!("{}", trf("id_size", &[&id.size]));
+    println!("{}", trf("id_sha1", &[&id.sha1]));
+    println!("{}", trf("id_crc32", &[&id.crc32]));
+    match id.rom_info {
+        Some(rom_info) => {
+            let lines = rom_info.describe();
+            println!("{}", trf("id_database", &[&lines[0]]));
+            for line in lines[1..].iter().filter(|line| !line.is_empty()) {
+                println!("  {}", line);
+            }
+        }
+        None => println!("{}", tr("rom_not_in_database")),
+    }
+    println!("{}", trf("id_platform", &[&id.platform]));
+
+    if id.problems.is_empty() {
+        println!("{}", tr("id_ok"));
+    }
+    for problem in &id.problems {
+        let key = if problem.fatal {
+            "id_error"
+        } else {
+            "id_warning"
+        };
+        println!("{}", trf(key, &[&problem.message]));
+    }
+    Ok(())
+}
+
+// print the save states of a rom, they are found next to where it is run
+// from like the screenshots
+pub fn states(romfile: &str) {
+    let path = Path::new(romfile);
+    let prefix = PathBuf::from(path.file_stem().unwrap_or(path.as_os_str()));
+    let mut found = false;
+    for slot in 0..savestate::SLOTS {
+        let path = savestate::slot_path(&prefix, slot);
+        if !path.exists() {
+            continue;
+        }
+        found = true;
+        match SaveState::load(&path) {
+            Ok(state) => {
+                println!("{}", trf("states_slot", &[&slot]));
+                for line in state.info.describe() {
+                    println!("  {}", line);
+                }
+            }
+            Err(e) => println!("{}", e),
+        }
+    }
+    if !found {
+        println!("{}", trf("states_none", &[&romfile]));
+    }
+}
+
+// print what this build supports, as json for launchers or as a summary
+pub fn capabilities(json: bool) {
+    if json {
+        println!("{}", capabilities::json());
+        return;
+    }
+    let platforms: Vec<String> = capabilities::PLATFORMS
+        .iter()
+        .map(|name| format!("{} ({})", name, capabilities::opcodes(name).len()))
+        .collect();
+    println!(
+        "{}",
+        trf("capabilities_version", &[&env!("CARGO_PKG_VERSION")])
+    );
+    println!(
+        "{}",
+        trf("capabilities_platforms", &[&platforms.join(", ")])
+    );
+    println!(
+        "{}",
+        trf("capabilities_quirks", &[&quirks::QUIRK_NAMES.join(", ")])
+    );
+    println!(
+        "{}",
+        trf("capabilities_presets", &[&quirks::PRESET_NAMES.join(", ")])
+    );
+    println!(
+        "{}",
+        trf(
+            "capabilities_extensions",
+            &[&extensions::EXTENSION_NAMES.join(", ")]
+        )
+    );
+    println!(
+        "{}",
+        trf(
+            "capabilities_frontends",
+            &[&capabilities::frontends().join(", ")]
+        )
+    );
+    println!(
+        "{}",
+        trf("capabilities_roms", &[&library::ROM_EXTENSIONS.join(", ")])
+    );
+    let font_names: Vec<&str> = fonts::FONTS.iter().map(|(name, _)| *name).collect();
+    println!("{}", trf("capabilities_fonts", &[&font_names.join(", ")]));
+    println!("{}", tr("capabilities_json"));
+}
+
+// check every opcode, and the test roms in the directory if there is one,
+// and print how each did, exiting with an error if any failed. Blessing
+// saves the screens of the test roms as the expected ones instead
+pub fn selftest(roms: Option<&str>, bless: bool) -> ExitCode {
+    let mut failed = false;
+    if let Some(dir) = roms {
+        failed = !test_roms(Path::new(dir), bless);
+        if bless {
+            return ExitCode::SUCCESS;
+        }
+    }
+
+    let outcomes = selftest::run();
+    for outcome in &outcomes {
+        match outcome.error {
+            None => println!("{}", trf("selftest_pass", &[&outcome.name])),
+            Some(ref error) => println!("{}", trf("selftest_fail", &[&outcome.name, error])),
+        }
+    }
+    let passed = outcomes
+        .iter()
+        .filter(|outcome| outcome.error.is_none())
+        .count();
+    println!("{}", trf("selftest_summary", &[&passed, &outcomes.len()]));
+    if failed || passed < outcomes.len() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+// run the test roms found in the directory and compare their screens with
+// the expected ones saved next to them, returns false if any failed
+fn test_roms(dir: &Path, bless: bool) -> bool {
+    let mut run = 0;
+    let mut passed = 0;
+    for rom in selftest::TEST_ROMS {
+        let path = dir.join(rom.file);
+        let Ok(data) = fs::read(&path) else {
+            println!(
+                "{}",
+                trf("selftest_rom_missing", &[&rom.file, &dir.display()])
+            );
+            continue;
+        };
+        run += 1;
+        let expected_path = path.with_extension("expected");
+        let screen = selftest::run_test_rom(rom, &data);
+        let error = match screen {
+            Err(e) => Some(e),
+            Ok(ref screen) if bless => {
+                print!("{}", screen);
+                match fs::write(&expected_path, screen) {
+                    Ok(()) => {
+                        println!(
+                            "{}",
+                            trf("selftest_blessed", &[&rom.file, &expected_path.display()])
+                        );
+                        continue;
+                    }
+                    Err(e) => Some(trf("file_write_error", &[&expected_path.display(), &e])),
+                }
+            }
+            Ok(ref screen) => match fs::read_to_string(&expected_path) {
+                Ok(expected) if expected == *screen => None,
+                Ok(_) => {
+                    let actual_path = path.with_extension("actual");
+                    let _ = fs::write(&actual_path, screen);
+                    Some(trf(
+                        "selftest_screen_differs",
+                        &[&expected_path.display(), &actual_path.display()],
+                    ))
+                }
+                Err(_) => {
+                    print!("{}", screen);
+                    Some(tr("selftest_no_expected"))
+                }
+            },
+        };
+        match error {
+            None => {
+                passed += 1;
+                println!("{}", trf("selftest_pass", &[&rom.file]));
+            }
+            Some(error) => println!("{}", trf("selftest_fail", &[&rom.file, &error])),
+        }
+    }
+    if !bless {
+        println!("{}", trf("selftest_rom_summary", &[&passed, &run]));
+    }
+    bless || passed == run
+}
@@ -0,0 +1,487 @@
+use std::collections::HashMap;
+use std::io;
+use std::io::Write;
+use std::time::Duration;
+use std::time::Instant;
+
+use crossterm::cursor;
+use crossterm::event;
+use crossterm::event::Event;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
+use crossterm::event::KeyboardEnhancementFlags;
+use crossterm::queue;
+use crossterm::style;
+use crossterm::style::Color as TermColor;
+use crossterm::terminal;
+use crossterm::terminal::ClearType;
+
+use chip8::backend::AudioSink;
+use chip8::backend::Command;
+use chip8::backend::InputSource;
+use chip8::backend::VideoSink;
+use chip8::colors::Color;
+use chip8::colors::Theme;
+use chip8::display::Display;
+use chip8::error::Chip8Error;
+use chip8::hotkeys::Action;
+use chip8::hotkeys::Hotkeys;
+use chip8::i18n::tr;
+use chip8::keymap::Keymap;
+use chip8::keymap::KEYPAD_LAYOUT;
+use chip8::osd::Osd;
+use chip8::palette::PaletteKey;
+
+// terminals that don't say when a key is let go of only repeat a held key,
+// so a key counts as held for this long after it was last pressed
+const HOLD_TIME: Duration = Duration::from_millis(150);
+
+// the upper half block, the top pixel in the foreground colour and the bottom
+// one in the background colour
+const HALF_BLOCK: char = '\u{2580}';
+
+fn terminal_error(e: io::Error) -> String {
+    Chip8Error::device("device_terminal", e).to_string()
+}
+
+fn term_color(color: Color) -> TermColor {
+    TermColor::Rgb {
+        r: color.r,
+        g: color.g,
+        b: color.b,
+    }
+}
+
+// draws the display in the terminal with two chip8 pixels to a character,
+// and the osd text under it. The terminal is switched to its alternate
+// screen in raw mode from the first frame until this is dropped, so
+// anything printed while starting up can still be read
+#[derive(Default)]
+pub struct TtyVideo {
+    theme: Theme,
+    // set once the terminal has been switched over
+    open: bool,
+    // set when everything has to be drawn again
+    stale: bool,
+}
+
+impl TtyVideo {
+    fn open(&mut self) -> Result<(), String> {
+        terminal::enable_raw_mode().map_err(terminal_error)?;
+        self.open = true;
+        let mut out = io::stdout();
+        queue!(out, terminal::EnterAlternateScreen, cursor::Hide)
+            .and_then(|_| out.flush())
+            .map_err(terminal_error)
+    }
+
+    fn draw(&self, display: &Display, osd: &Osd) -> io::Result<()> {
+        let mut frame = Vec::new();
+        let width = display.get_width();
+        let pixels = display.pixels();
+        for (row, pair) in pixels.chunks(width * 2).enumerate() {
+            queue!(frame, cursor::MoveTo(0, row as u16))?;
+            // colours are only sent when they change
+            let mut colors = None;
+            for x in 0..width {
+                let top = self.theme.pixel(pair[x] != 0);
+                let bottom = self
+                    .theme
+                    .pixel(pair.get(width + x).is_some_and(|p| *p != 0));
+                if colors != Some((top, bottom)) {
+                    queue!(
+                        frame,
+                        style::SetForegroundColor(term_color(top)),
+                        style::SetBackgroundColor(term_color(bottom))
+                    )?;
+                    colors = Some((top, bottom));
+                }
+                queue!(frame, style::Print(HALF_BLOCK))?;
+            }
+            queue!(frame, style::ResetColor)?;
+        }
+
+        // the osd text goes under the display, the lines are cleared so
+        // text from before doesn't show through
+        let lines = osd
+            .status()
+            .iter()
+            .chain(osd.panel().unwrap_or_default())
+            .map(String::as_str)
+            .chain(osd.toasts());
+        for line in lines {
+            queue!(
+                frame,
+                style::Print("\r\n"),
+                terminal::Clear(ClearType::CurrentLine),
+                style::Print(line)
+            )?;
+        }
+        queue!(
+            frame,
+            style::Print("\r\n"),
+            terminal::Clear(ClearType::FromCursorDown)
+        )?;
+
+        let mut out = io::stdout().lock();
+        out.write_all(&frame)?;
+        out.flush()
+    }
+}
+
+impl Drop for TtyVideo {
+    fn drop(&mut self) {
+        if !self.open {
+            return;
+        }
+        let mut out = io::stdout();
+        let _ = queue!(out, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = out.flush();
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+impl VideoSink for TtyVideo {
+    fn render(&mut self, display: &mut Display, osd: &mut Osd) {
+        // both need to be reset so don't short circuit
+        let dirty = display.take_dirty();
+        let osd_dirty = osd.take_dirty();
+        if !self.open {
+            if let Err(e) = self.open() {
+                println!("{}", e);
+            }
+            self.stale = true;
+        }
+        if !(dirty || osd_dirty || self.stale) {
+            return;
+        }
+        // a terminal that went away can't be drawn in, the keys quit
+        if self.draw(display, osd).is_ok() {
+            self.stale = false;
+        }
+    }
+
+    fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.stale = true;
+    }
+}
+
+// rings the terminal bell when the buzzer starts
+#[derive(Default)]
+pub struct TtyBell {
+    on: bool,
+    muted: bool,
+}
+
+impl AudioSink for TtyBell {
+    fn set_beep(&mut self, on: bool) {
+        if on && !self.on && !self.muted {
+            let mut out = io::stdout();
+            let _ = out.write_all(b"\x07").and_then(|_| out.flush());
+        }
+        self.on = on;
+    }
+
+    // the bell can't be made quieter, only silent
+    fn set_volume(&mut self, volume: u8) {
+        self.muted = volume == 0;
+    }
+}
+
+// the modifier keys held with a hotkey
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+struct Modifiers {
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+}
+
+impl Modifiers {
+    fn from_key(modifiers: KeyModifiers) -> Modifiers {
+        Modifiers {
+            ctrl: modifiers.contains(KeyModifiers::CONTROL),
+            shift: modifiers.contains(KeyModifiers::SHIFT),
+            alt: modifiers.contains(KeyModifiers::ALT),
+        }
+    }
+}
+
+// a key name such as "Ctrl+P" split into the modifiers and the lower case
+// key name
+fn parse_key(name: &str) -> (String, Modifiers) {
+    let mut modifiers = Modifiers::default();
+    let mut rest = name.to_lowercase();
+    loop {
+        if let Some(key) = rest.strip_prefix("ctrl+").filter(|key| !key.is_empty()) {
+            modifiers.ctrl = true;
+            rest = key.to_string();
+        } else if let Some(key) = rest.strip_prefix("shift+").filter(|key| !key.is_empty()) {
+            modifiers.shift = true;
+            rest = key.to_string();
+        } else if let Some(key) = rest.strip_prefix("alt+").filter(|key| !key.is_empty()) {
+            modifiers.alt = true;
+            rest = key.to_string();
+        } else {
+            break;
+        }
+    }
+    (rest, modifiers)
+}
+
+// the lower case SDL name of a key typed in the terminal, so the keymap and
+// hotkeys of the config file work in both frontends. Shift is part of a
+// typed character rather than a modifier
+fn key_name(event: &KeyEvent) -> Option<(String, Modifiers)> {
+    let mut modifiers = Modifiers::from_key(event.modifiers);
+    let name = match event.code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => {
+            modifiers.shift = false;
+            c.to_lowercase().to_string()
+        }
+        KeyCode::F(n) => format!("f{}", n),
+        KeyCode::Backspace => "backspace".to_string(),
+        KeyCode::Enter => "return".to_string(),
+        KeyCode::Tab => "tab".to_string(),
+        KeyCode::Esc => "escape".to_string(),
+        KeyCode::Up => "up".to_string(),
+        KeyCode::Down => "down".to_string(),
+        KeyCode::Left => "left".to_string(),
+        KeyCode::Right => "right".to_string(),
+        _ => return None,
+    };
+    Some((name, modifiers))
+}
+
+// reads the keypad keys and hotkeys typed in the terminal
+pub struct TtyInput {
+    // the emulator action for each bound key
+    hotkeys: HashMap<(String, Modifiers), Action>,
+    hotkeys_help: String,
+
+    // the keypad key for each mapped key, and the key names of the keymap
+    // indexed by the keypad key
+    keypad: HashMap<String, u8>,
+    keypad_names: Vec<String>,
+
+    // when each keypad key was pressed, None while it is up
+    pressed: [Option<Instant>; 0x10],
+    // the hotkeys that last while held, such as rewind, and when they were
+    // pressed
+    held: Vec<(Action, Instant)>,
+
+    // set once the terminal has been asked whether it says when keys are
+    // let go of, and if it does. Otherwise they are let go of after
+    // HOLD_TIME
+    started: bool,
+    releases: bool,
+
+    // keys go to the command palette or a menu while one is open
+    palette_open: bool,
+}
+
+impl TtyInput {
+    pub fn new(hotkeys: &Hotkeys, keymap: &Keymap) -> Result<TtyInput, String> {
+        hotkeys.check_conflicts(&keymap.names())?;
+        let keypad = (0..0x10)
+            .map(|key| (keymap.key(key).to_lowercase(), key))
+            .collect();
+        let bindings = hotkeys
+            .bindings()
+            .map(|(action, key)| (parse_key(key), action))
+            .collect();
+        Ok(TtyInput {
+            hotkeys: bindings,
+            hotkeys_help: hotkeys.describe(),
+            keypad,
+            keypad_names: keymap.names().iter().map(|name| name.to_string()).collect(),
+            pressed: [None; 0x10],
+            held: Vec::new(),
+            started: false,
+            releases: false,
+            palette_open: false,
+        })
+    }
+
+    // ask the terminal to say when keys are let go of, only some can
+    fn start(&mut self) {
+        self.started = true;
+        self.releases = terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if self.releases {
+            let mut out = io::stdout();
+            let flags = KeyboardEnhancementFlags::REPORT_EVENT_TYPES;
+            let pushed =
+                queue!(out, event::PushKeyboardEnhancementFlags(flags)).and_then(|_| out.flush());
+            self.releases = pushed.is_ok();
+        }
+    }
+
+    // the commands for a key event, and the keypad keys it presses
+    fn key_event(&mut self, event: KeyEvent, commands: &mut Vec<Command>) {
+        let Some((name, modifiers)) = key_name(&event) else {
+            return;
+        };
+        let now = Instant::now();
+        let pressed = event.kind != KeyEventKind::Release;
+        if self.palette_open {
+            if pressed {
+                commands.extend(self.palette_command(&event, &name, modifiers));
+            }
+            return;
+        }
+
+        if let Some(action) = self.hotkeys.get(&(name.clone(), modifiers)).copied() {
+            let repeat = self.held.iter().any(|(held, _)| *held == action);
+            self.held.retain(|(held, _)| *held != action);
+            if pressed {
+                if action.command(false).is_some() {
+                    self.held.push((action, now));
+                }
+                if repeat || event.kind == KeyEventKind::Repeat {
+                    return;
+                }
+            }
+            if let Some(command) = action.command(pressed) {
+                // the save state and recent roms menus are picked from with
+                // the same keys
+                if matches!(
+                    command,
+                    Command::TogglePalette
+                        | Command::SaveStateMenu
+                        | Command::LoadStateMenu
+                        | Command::RecentMenu
+                ) {
+                    self.set_palette_open(true);
+                }
+                commands.push(command);
+            }
+            return;
+        }
+
+        // raw mode takes ctrl+c from the terminal
+        if name == "c" && modifiers.ctrl {
+            commands.push(Command::Quit);
+            return;
+        }
+        if let Some(key) = self.keypad.get(&name) {
+            self.pressed[*key as usize] = pressed.then_some(now);
+        }
+    }
+
+    fn set_palette_open(&mut self, open: bool) {
+        self.palette_open = open;
+        self.pressed = [None; 0x10];
+    }
+
+    // the keys used to search and pick from the command palette
+    fn palette_command(
+        &mut self,
+        event: &KeyEvent,
+        name: &str,
+        modifiers: Modifiers,
+    ) -> Option<Command> {
+        let key = match event.code {
+            KeyCode::Backspace => PaletteKey::Backspace,
+            KeyCode::Up => PaletteKey::Up,
+            KeyCode::Down => PaletteKey::Down,
+            KeyCode::Left => PaletteKey::Left,
+            KeyCode::Right => PaletteKey::Right,
+            KeyCode::Enter => PaletteKey::Enter,
+            KeyCode::Esc => PaletteKey::Escape,
+            _ => {
+                let action = self.hotkeys.get(&(name.to_string(), modifiers));
+                if action.is_some_and(|action| *action == Action::Palette) {
+                    self.set_palette_open(false);
+                    return Some(Command::TogglePalette);
+                }
+                return match event.code {
+                    KeyCode::Char(c) if !modifiers.ctrl && !modifiers.alt => {
+                        Some(Command::Palette(PaletteKey::Char(c)))
+                    }
+                    _ => None,
+                };
+            }
+        };
+        if matches!(key, PaletteKey::Enter | PaletteKey::Escape) {
+            self.set_palette_open(false);
+        }
+        Some(Command::Palette(key))
+    }
+
+    // let go of the keys and held hotkeys that haven't been repeated
+    // lately, when the terminal doesn't say
+    fn release_stale(&mut self, commands: &mut Vec<Command>) {
+        if self.releases {
+            return;
+        }
+        let now = Instant::now();
+        for pressed in self.pressed.iter_mut() {
+            if pressed.is_some_and(|at| now - at > HOLD_TIME) {
+                *pressed = None;
+            }
+        }
+        let (stale, held): (Vec<_>, Vec<_>) = self
+            .held
+            .drain(..)
+            .partition(|(_, at)| now - *at > HOLD_TIME);
+        self.held = held;
+        commands.extend(stale.iter().filter_map(|(action, _)| action.command(false)));
+    }
+}
+
+impl Drop for TtyInput {
+    fn drop(&mut self) {
+        if self.releases {
+            let mut out = io::stdout();
+            let _ = queue!(out, event::PopKeyboardEnhancementFlags);
+            let _ = out.flush();
+        }
+    }
+}
+
+impl InputSource for TtyInput {
+    fn read_keys(&mut self) -> [bool; 0x10] {
+        self.pressed.map(|pressed| pressed.is_some())
+    }
+
+    fn poll_commands(&mut self) -> Vec<Command> {
+        if !self.started {
+            self.start();
+        }
+        let mut commands = Vec::new();
+        while event::poll(Duration::ZERO).unwrap_or(false) {
+            match event::read() {
+                Ok(Event::Key(key)) => self.key_event(key, &mut commands),
+                Ok(_) => {}
+                Err(_) => {
+                    commands.push(Command::Quit);
+                    break;
+                }
+            }
+        }
+        self.release_stale(&mut commands);
+        commands
+    }
+
+    // the keypad layout next to the keyboard keys each keypad key is mapped
+    // to, for showing in the help overlay
+    fn help_lines(&self) -> Vec<String> {
+        let mut lines = vec![tr("help_header")];
+        for row in KEYPAD_LAYOUT.iter() {
+            let keypad: Vec<String> = row.iter().map(|key| format!("{:X}", key)).collect();
+            let keyboard: Vec<&str> = row
+                .iter()
+                .map(|key| self.keypad_names[*key as usize].as_str())
+                .collect();
+            lines.push(format!("{}   {}", keypad.join(" "), keyboard.join(" ")));
+        }
+        lines
+    }
+
+    fn hotkey_lines(&self) -> Vec<String> {
+        vec![self.hotkeys_help.clone()]
+    }
+}
@@ -0,0 +1,793 @@
+use std::ops::Range;
+
+use sdl2::pixels::Color;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Point;
+use sdl2::rect::Rect;
+use sdl2::render::Texture;
+use sdl2::render::WindowCanvas;
+use sdl2::video::FullscreenType;
+use sdl2::Sdl;
+
+use chip8::backend::VideoSink;
+use chip8::colors;
+use chip8::colors::Theme;
+use chip8::crt;
+use chip8::display::Display;
+use chip8::display::DISPLAY_HEIGHT;
+use chip8::display::DISPLAY_WIDTH;
+use chip8::error::Chip8Error;
+use chip8::i18n::tr;
+use chip8::i18n::trf;
+use chip8::library::Entry;
+use chip8::library::Thumbnail;
+
+use chip8::osd::glyph;
+use chip8::osd::Osd;
+use chip8::osd::Scope;
+use chip8::osd::StateMenu;
+use chip8::osd::GLYPH_HEIGHT;
+use chip8::osd::GLYPH_WIDTH;
+use chip8::savestate;
+use chip8::screenshot::Pixelmap;
+
+const BLACK: Color = to_sdl_color(colors::BLACK);
+const WHITE: Color = to_sdl_color(colors::WHITE);
+
+// the number of thumbnails across the library grid
+const LIBRARY_COLUMNS: usize = 3;
+
+// how much of the display from before loading a state shows through at first
+const GHOST_OPACITY: f32 = 0.5;
+
+// how much of its brightness an unlit pixel keeps each frame with the
+// phosphor on, and the brightness it goes dark below, about 5 frames
+const PHOSPHOR_DECAY: f32 = 0.5;
+const PHOSPHOR_OFF: f32 = 0.05;
+
+const fn to_sdl_color(color: colors::Color) -> Color {
+    Color::RGB(color.r, color.g, color.b)
+}
+
+pub struct VideoDevice {
+    canvas: WindowCanvas,
+
+    // the size display pixels are drawn at, the biggest whole number that
+    // fits the window, and the size the window was opened with that
+    // screenshots and recordings are made at
+    pixelsize: usize,
+    capture_pixelsize: usize,
+
+    // scale the display to fill the window instead of keeping its shape
+    stretch: bool,
+
+    // the size of the window the display was last fitted to
+    output_size: (u32, u32),
+
+    // the colours of lit and unlit display pixels
+    theme: Theme,
+    foreground: Color,
+    background: Color,
+
+    // draw the display through the crt effect
+    crt: bool,
+
+    // fade pixels out over a few frames rather than at once, with the
+    // brightness of each display pixel from 0 to 1
+    phosphor: bool,
+    intensity: Vec<f32>,
+
+    // the display as it was last drawn, one pixel of the frame for each
+    // display pixel, and the texture it is copied to the window through.
+    // Only the rows that changed are drawn again unless the frame is stale,
+    // after the colours or effects changed or the texture held something else
+    frame: Pixelmap,
+    texture: Option<Texture>,
+    stale: bool,
+}
+
+impl VideoDevice {
+    pub fn new(sdl_context: &Sdl, pixelsize: usize) -> Result<VideoDevice, Chip8Error> {
+        let video_subsystem = sdl_context
+            .video()
+            .map_err(|e| Chip8Error::device("device_video", e))?;
+        let window = video_subsystem
+            .window(
+                "CHIP8",
+                (DISPLAY_WIDTH * pixelsize) as u32,
+                (DISPLAY_HEIGHT * pixelsize) as u32,
+            )
+            .position_centered()
+            .resizable()
+            .build()
+            .map_err(|e| Chip8Error::device("device_video", e))?;
+        let canvas = window
+            .into_canvas()
+            .build()
+            .map_err(|e| Chip8Error::device("device_video", e))?;
+        // the display is scaled up from its own resolution, keep the pixels
+        // sharp rather than blurring them
+        sdl2::hint::set("SDL_RENDER_SCALE_QUALITY", "nearest");
+
+        let theme = Theme::default();
+        Ok(VideoDevice {
+            canvas,
+            pixelsize,
+            capture_pixelsize: pixelsize,
+            stretch: false,
+            output_size: (0, 0),
+            theme,
+            foreground: to_sdl_color(theme.foreground),
+            background: to_sdl_color(theme.background),
+            crt: false,
+            phosphor: false,
+            intensity: Vec::new(),
+            frame: Pixelmap {
+                width: 0,
+                height: 0,
+                data: Vec::new(),
+            },
+            texture: None,
+            stale: true,
+        })
+    }
+
+    // scale the display to fill the window, pixels can come out different
+    // sizes and the shape is lost when the window isn't 2:1
+    pub fn set_stretch(&mut self, stretch: bool) {
+        self.stretch = stretch;
+        self.pixelsize = self.capture_pixelsize;
+        self.output_size = (0, 0);
+    }
+
+    // fit the display to the window if its size changed, true if it did. The
+    // display is drawn with the biggest whole number pixel size that fits and
+    // centered with black bars around it, or stretched to fill the window
+    fn fit(&mut self) -> bool {
+        let size = self.canvas.output_size().unwrap_or(self.output_size);
+        if size == self.output_size {
+            return false;
+        }
+        self.output_size = size;
+        let (width, height) = size;
+        if self.stretch {
+            self.canvas
+                .set_scale(
+                    width as f32 / (DISPLAY_WIDTH * self.pixelsize) as f32,
+                    height as f32 / (DISPLAY_HEIGHT * self.pixelsize) as f32,
+                )
+                .ok();
+        } else {
+            self.pixelsize = std::cmp::max(
+                1,
+                std::cmp::min(
+                    width as usize / DISPLAY_WIDTH,
+                    height as usize / DISPLAY_HEIGHT,
+                ),
+            );
+            self.canvas
+                .set_logical_size(
+                    (DISPLAY_WIDTH * self.pixelsize) as u32,
+                    (DISPLAY_HEIGHT * self.pixelsize) as u32,
+                )
+                .ok();
+        }
+        true
+    }
+
+    // the number of columns and rows of thumbnails in the library grid
+    pub fn library_grid(&mut self) -> (usize, usize) {
+        self.fit();
+        let (_, cell_height) = self.library_cell();
+        let rows = std::cmp::max(1, DISPLAY_HEIGHT * self.pixelsize / cell_height);
+        (LIBRARY_COLUMNS, rows)
+    }
+
+    // the size of a thumbnail and its title in the library grid
+    fn library_cell(&self) -> (usize, usize) {
+        let scale = self.text_scale();
+        let line_height = (GLYPH_HEIGHT + 3) * scale;
+        let width = DISPLAY_WIDTH * self.pixelsize / LIBRARY_COLUMNS;
+        let thumbnail_height = (width - 4 * scale) / 2;
+        (width, thumbnail_height + line_height + 4 * scale)
+    }
+
+    // draw a page of the library starting at the entry first, with a frame
+    // around the selected one
+    pub fn render_library(&mut self, entries: &[Entry], first: usize, selected: usize) {
+        let scale = self.text_scale();
+        let (columns, rows) = self.library_grid();
+        let (cell_width, cell_height) = self.library_cell();
+        let thumbnail_width = cell_width - 4 * scale;
+        let thumbnail_height = thumbnail_width / 2;
+        let max_chars = cell_width / ((GLYPH_WIDTH + 1) * scale) - 1;
+
+        self.canvas.set_draw_color(BLACK);
+        self.canvas.clear();
+
+        let page = entries.iter().enumerate().skip(first).take(columns * rows);
+        for (index, entry) in page {
+            let cell = index - first;
+            let x = ((cell % columns) * cell_width + 2 * scale) as i32;
+            let y = ((cell / columns) * cell_height + 2 * scale) as i32;
+
+            if index == selected {
+                self.canvas.set_draw_color(WHITE);
+                self.canvas
+                    .fill_rect(Rect::new(
+                        x - scale as i32,
+                        y - scale as i32,
+                        (thumbnail_width + 2 * scale) as u32,
+                        (thumbnail_height + 2 * scale) as u32,
+                    ))
+                    .unwrap();
+            }
+
+            let area = Rect::new(x, y, thumbnail_width as u32, thumbnail_height as u32);
+            self.canvas.set_draw_color(self.background);
+            self.canvas.fill_rect(area).unwrap();
+            if let Some(ref thumbnail) = entry.thumbnail {
+                self.draw_thumbnail(area, thumbnail);
+            }
+
+            let title: String = entry.title.chars().take(max_chars).collect();
+            self.draw_text(
+                x - scale as i32,
+                y + (thumbnail_height + scale) as i32,
+                &title,
+            );
+        }
+        self.canvas.present();
+    }
+
+    // draw the lit pixels of a thumbnail scaled to fill the area
+    fn draw_thumbnail(&mut self, area: Rect, thumbnail: &Thumbnail) {
+        let (width, height) = (area.width() as usize, area.height() as usize);
+        self.canvas.set_draw_color(self.foreground);
+        for y in 0..thumbnail.height {
+            for x in 0..thumbnail.width {
+                if thumbnail.get_pixel(x, y) == 0 {
+                    continue;
+                }
+                let left = x * width / thumbnail.width;
+                let top = y * height / thumbnail.height;
+                let rect = Rect::new(
+                    area.x() + left as i32,
+                    area.y() + top as i32,
+                    std::cmp::max(1, (x + 1) * width / thumbnail.width - left) as u32,
+                    std::cmp::max(1, (y + 1) * height / thumbnail.height - top) as u32,
+                );
+                self.canvas.fill_rect(rect).unwrap();
+            }
+        }
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        self.canvas.window_mut().set_title(title).ok();
+    }
+
+    // the size of an osd text pixel, text is kept readable at small pixelsizes
+    fn text_scale(&self) -> usize {
+        std::cmp::max(1, self.pixelsize / 4)
+    }
+
+    // draw the osd panel in the middle of the window and the toasts stacked
+    // up from the bottom left of the window
+    fn draw_osd(&mut self, osd: &Osd) {
+        let scale = self.text_scale();
+        let line_height = (GLYPH_HEIGHT + 3) * scale;
+
+        let mut y = scale as i32;
+        let max_chars = self.max_line_chars();
+        for line in osd.status() {
+            let mut wrapped = Vec::new();
+            wrap_text(line, max_chars, &mut wrapped);
+            for text in wrapped {
+                self.draw_text_box(scale as i32, y, &text);
+                y += line_height as i32;
+            }
+        }
+
+        if let Some(scope) = osd.scope() {
+            self.draw_scope(scope);
+        }
+
+        if let Some(lines) = osd.panel() {
+            self.draw_panel(lines);
+        }
+
+        if let Some(menu) = osd.state_menu() {
+            self.draw_state_menu(menu);
+        }
+
+        let toasts: Vec<&str> = osd.toasts().collect();
+
+        let mut y = (DISPLAY_HEIGHT * self.pixelsize) as i32
+            - (toasts.len() * line_height) as i32
+            - scale as i32;
+        for text in toasts {
+            self.draw_text_box(scale as i32, y, text);
+            y += line_height as i32;
+        }
+    }
+
+    // the number of characters that fit across the window in a text box
+    fn max_line_chars(&self) -> usize {
+        let scale = self.text_scale();
+        let char_width = (GLYPH_WIDTH + 1) * scale;
+        (DISPLAY_WIDTH * self.pixelsize - 4 * scale) / char_width - 1
+    }
+
+    // draw lines of text word wrapped to the window width in a box in the
+    // middle of the window
+    fn draw_panel(&mut self, lines: &[String]) {
+        let scale = self.text_scale();
+        let char_width = (GLYPH_WIDTH + 1) * scale;
+        let line_height = (GLYPH_HEIGHT + 3) * scale;
+        let window_width = DISPLAY_WIDTH * self.pixelsize;
+        let window_height = DISPLAY_HEIGHT * self.pixelsize;
+        let max_chars = self.max_line_chars();
+
+        let mut wrapped = Vec::new();
+        for line in lines {
+            wrap_text(line, max_chars, &mut wrapped);
+        }
+
+        let width = wrapped.iter().map(|l| l.len()).max().unwrap_or(0) * char_width + 3 * scale;
+        let height = wrapped.len() * line_height + scale;
+        let x = (window_width.saturating_sub(width) / 2) as i32;
+        let y = (window_height.saturating_sub(height) / 2) as i32;
+
+        self.canvas.set_draw_color(WHITE);
+        self.canvas
+            .fill_rect(Rect::new(x, y, width as u32, height as u32))
+            .unwrap();
+        self.canvas.set_draw_color(BLACK);
+        self.canvas
+            .fill_rect(Rect::new(
+                x + scale as i32,
+                y + scale as i32,
+                (width - 2 * scale) as u32,
+                (height - 2 * scale) as u32,
+            ))
+            .unwrap();
+
+        for (i, line) in wrapped.iter().enumerate() {
+            self.draw_text(x + scale as i32, y + (i * line_height + scale) as i32, line);
+        }
+    }
+
+    // draw the save state slots over the display in two rows, each with its
+    // thumbnail and number and a frame around the selected one. Below them is
+    // the name being typed when saving, or what the selected state is when
+    // loading
+    fn draw_state_menu(&mut self, menu: &StateMenu) {
+        let scale = self.text_scale();
+        let line_height = (GLYPH_HEIGHT + 3) * scale;
+        let window_width = DISPLAY_WIDTH * self.pixelsize;
+        let window_height = DISPLAY_HEIGHT * self.pixelsize;
+        let columns = savestate::SLOTS / 2;
+        let cell_width = (window_width - 2 * scale) / columns;
+        let thumbnail_width = cell_width - 4 * scale;
+        let thumbnail_height = thumbnail_width / 2;
+        let cell_height = thumbnail_height + line_height + 2 * scale;
+        let details = if menu.saving {
+            vec![trf("state_name", &[&menu.name])]
+        } else {
+            match &menu.slots[menu.selected] {
+                Some(state) => state.info.describe(),
+                None => Vec::new(),
+            }
+        };
+        // room for the most lines a state is described in
+        let height = 4 * line_height + 2 * cell_height + 2 * scale;
+        let top = (window_height.saturating_sub(height) / 2) as i32;
+
+        self.canvas.set_draw_color(WHITE);
+        self.canvas
+            .fill_rect(Rect::new(0, top, window_width as u32, height as u32))
+            .unwrap();
+        self.canvas.set_draw_color(BLACK);
+        self.canvas
+            .fill_rect(Rect::new(
+                scale as i32,
+                top + scale as i32,
+                (window_width - 2 * scale) as u32,
+                (height - 2 * scale) as u32,
+            ))
+            .unwrap();
+        let title = if menu.saving {
+            tr("state_menu_save")
+        } else {
+            tr("state_menu_load")
+        };
+        self.draw_text(scale as i32, top + scale as i32, &title);
+
+        let details_top = top + (line_height + 2 * cell_height + scale) as i32;
+        for (i, line) in details.iter().enumerate() {
+            self.draw_text(scale as i32, details_top + (i * line_height) as i32, line);
+        }
+
+        for (slot, state) in menu.slots.iter().enumerate() {
+            let x = (scale + (slot % columns) * cell_width + 2 * scale) as i32;
+            let y = top + (line_height + (slot / columns) * cell_height + 2 * scale) as i32;
+            if slot == menu.selected {
+                self.canvas.set_draw_color(WHITE);
+                self.canvas
+                    .fill_rect(Rect::new(
+                        x - scale as i32,
+                        y - scale as i32,
+                        (thumbnail_width + 2 * scale) as u32,
+                        (thumbnail_height + 2 * scale) as u32,
+                    ))
+                    .unwrap();
+            }
+            let area = Rect::new(x, y, thumbnail_width as u32, thumbnail_height as u32);
+            self.canvas.set_draw_color(self.background);
+            self.canvas.fill_rect(area).unwrap();
+            let label = match state {
+                Some(state) => {
+                    self.draw_thumbnail(area, &state.thumbnail);
+                    slot.to_string()
+                }
+                None => trf("state_slot_empty", &[&slot]),
+            };
+            self.draw_text(
+                x - scale as i32,
+                y + (thumbnail_height + scale) as i32,
+                &label,
+            );
+        }
+    }
+
+    // draw the oscilloscope in a box in the top right corner of the window,
+    // the waveform on top and a bar for the sound timer of each update below
+    fn draw_scope(&mut self, scope: &Scope) {
+        let scale = self.text_scale() as i32;
+        let window_width = (DISPLAY_WIDTH * self.pixelsize) as i32;
+        let width = window_width / 3;
+        let height = (DISPLAY_HEIGHT * self.pixelsize) as i32 / 3;
+        let x = window_width - width - scale;
+        let y = scale;
+
+        self.canvas.set_draw_color(WHITE);
+        self.canvas
+            .fill_rect(Rect::new(x, y, width as u32, height as u32))
+            .unwrap();
+        self.canvas.set_draw_color(BLACK);
+        let inner = Rect::new(
+            x + scale,
+            y + scale,
+            (width - 2 * scale) as u32,
+            (height - 2 * scale) as u32,
+        );
+        self.canvas.fill_rect(inner).unwrap();
+        self.canvas.set_draw_color(WHITE);
+
+        // the waveform fills the top two thirds, scaled to its loudest
+        // sample so quiet sound is still visible
+        let wave_height = inner.height() as i32 * 2 / 3;
+        let middle = inner.y() + wave_height / 2;
+        let peak = scope
+            .samples
+            .iter()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+            .max(0.01);
+        let columns = inner.width() as i32;
+        let points: Vec<Point> = (0..columns)
+            .map(|column| {
+                let sample = scope
+                    .samples
+                    .get(column as usize * scope.samples.len() / columns as usize)
+                    .copied()
+                    .unwrap_or(0.0);
+                let offset = (sample / peak * (wave_height / 2 - scale) as f32) as i32;
+                Point::new(inner.x() + column, middle - offset)
+            })
+            .collect();
+        self.canvas.draw_lines(points.as_slice()).unwrap();
+
+        // the sound timer bars fill the bottom third, scaled to the largest
+        // value so short beeps show up
+        let bars_top = inner.y() + wave_height;
+        let bars_height = inner.height() as i32 - wave_height;
+        let loudest = scope.timers.iter().copied().max().unwrap_or(0).max(1) as i32;
+        let count = scope.timers.len().max(1) as i32;
+        for (i, timer) in scope.timers.iter().enumerate() {
+            if *timer == 0 {
+                continue;
+            }
+            let left = inner.x() + i as i32 * columns / count;
+            let right = inner.x() + (i as i32 + 1) * columns / count;
+            let bar = (*timer as i32 * bars_height / loudest).max(1);
+            self.canvas
+                .fill_rect(Rect::new(
+                    left,
+                    bars_top + bars_height - bar,
+                    (right - left).max(1) as u32,
+                    bar as u32,
+                ))
+                .unwrap();
+        }
+    }
+
+    // draw the display with the crt effect, the frame is made in memory and
+    // copied to the window through a texture in one go
+    fn draw_crt(&mut self, display: &Display, colors: &[colors::Color]) {
+        let (width, height) = (display.get_width(), display.get_height());
+        let window_width = DISPLAY_WIDTH * self.pixelsize;
+        let window_height = DISPLAY_HEIGHT * self.pixelsize;
+        let mut data = Vec::with_capacity(window_width * window_height * 3);
+        for y in 0..window_height {
+            for x in 0..window_width {
+                let color = colors[y * height / window_height * width + x * width / window_width];
+                data.extend_from_slice(&[color.r, color.g, color.b]);
+            }
+        }
+        let frame = crt::apply(
+            &Pixelmap {
+                width: window_width,
+                height: window_height,
+                data,
+            },
+            self.pixelsize,
+        );
+        self.use_texture(frame.width, frame.height);
+        let texture = self.texture.as_mut().unwrap();
+        texture.update(None, &frame.data, frame.width * 3).unwrap();
+        self.canvas.copy(texture, None, None).unwrap();
+        // the texture no longer holds the frame
+        self.stale = true;
+    }
+
+    // draw the rows of the display that changed into the frame and the
+    // texture, which scales it up to fill the window. The window stays the
+    // same size when a super chip rom switches resolution, so the pixels are
+    // scaled to fit it
+    fn draw_pixels(
+        &mut self,
+        display: &Display,
+        ghost: Option<(&Thumbnail, f32)>,
+        mut rows: Range<usize>,
+    ) {
+        let (width, height) = (display.get_width(), display.get_height());
+        if (self.frame.width, self.frame.height) != (width, height) {
+            self.frame = Pixelmap {
+                width,
+                height,
+                data: vec![0; width * height * 3],
+            };
+            rows = 0..height;
+        }
+        if self.use_texture(width, height) {
+            rows = 0..height;
+        }
+        for y in rows.clone() {
+            for x in 0..width {
+                let color = self.pixel_color(display, ghost, y * width + x);
+                let start = (y * width + x) * 3;
+                self.frame.data[start..start + 3].copy_from_slice(&[color.r, color.g, color.b]);
+            }
+        }
+        let texture = self.texture.as_mut().unwrap();
+        if !rows.is_empty() {
+            let area = Rect::new(0, rows.start as i32, width as u32, rows.len() as u32);
+            let data = &self.frame.data[rows.start * width * 3..rows.end * width * 3];
+            texture.update(area, data, width * 3).unwrap();
+        }
+        self.canvas.copy(texture, None, None).unwrap();
+    }
+
+    // make the texture again if it isn't the size of the frame, true if it
+    // was made and has nothing in it yet
+    fn use_texture(&mut self, width: usize, height: usize) -> bool {
+        if let Some(texture) = &self.texture {
+            let query = texture.query();
+            if (query.width, query.height) == (width as u32, height as u32) {
+                return false;
+            }
+        }
+        if let Some(texture) = self.texture.take() {
+            // textures aren't freed by themselves with unsafe_textures
+            unsafe { texture.destroy() };
+        }
+        let texture = self
+            .canvas
+            .create_texture_streaming(PixelFormatEnum::RGB24, width as u32, height as u32)
+            .unwrap();
+        self.texture = Some(texture);
+        true
+    }
+
+    // the colour of each display pixel, faded by the phosphor and mixed with
+    // the display from before loading a state while it fades out
+    fn pixel_colors(
+        &self,
+        display: &Display,
+        ghost: Option<(&Thumbnail, f32)>,
+    ) -> Vec<colors::Color> {
+        (0..display.get_width() * display.get_height())
+            .map(|i| self.pixel_color(display, ghost, i))
+            .collect()
+    }
+
+    // the colour of the display pixel at index i
+    fn pixel_color(
+        &self,
+        display: &Display,
+        ghost: Option<(&Thumbnail, f32)>,
+        i: usize,
+    ) -> colors::Color {
+        let width = display.get_width();
+        let height = display.get_height();
+        let (x, y) = (i % width, i / width);
+        let lit = display.get_pixel(x as u8, y as u8) != 0x0;
+        let color = match self.intensity.get(i) {
+            Some(intensity) if self.phosphor => {
+                self.theme.background.mix(self.theme.foreground, *intensity)
+            }
+            _ => self.theme.pixel(lit),
+        };
+        match ghost {
+            Some((ghost, left)) => {
+                let was_lit =
+                    ghost.get_pixel(x * ghost.width / width, y * ghost.height / height) != 0;
+                color.mix(self.theme.pixel(was_lit), GHOST_OPACITY * left)
+            }
+            None => color,
+        }
+    }
+
+    // light the lit pixels fully and fade the rest a frame's worth, returns
+    // true while any pixel is still fading
+    fn fade_phosphor(&mut self, display: &Display) -> bool {
+        let size = display.get_width() * display.get_height();
+        if self.intensity.len() != size {
+            self.intensity = vec![0.0; size];
+        }
+        let width = display.get_width();
+        let mut fading = false;
+        for (i, intensity) in self.intensity.iter_mut().enumerate() {
+            if display.get_pixel((i % width) as u8, (i / width) as u8) != 0x0 {
+                *intensity = 1.0;
+                continue;
+            }
+            *intensity *= PHOSPHOR_DECAY;
+            if *intensity < PHOSPHOR_OFF {
+                *intensity = 0.0;
+            }
+            fading |= *intensity > 0.0;
+        }
+        fading
+    }
+
+    // draw a line of text on a black box with the top left corner at x,y
+    fn draw_text_box(&mut self, x: i32, y: i32, text: &str) {
+        let scale = self.text_scale();
+        let char_width = (GLYPH_WIDTH + 1) * scale;
+        let width = text.chars().count() * char_width + scale;
+        let height = (GLYPH_HEIGHT + 2) * scale;
+
+        self.canvas.set_draw_color(BLACK);
+        self.canvas
+            .fill_rect(Rect::new(x, y, width as u32, height as u32))
+            .unwrap();
+
+        self.draw_text(x, y, text);
+    }
+
+    // draw a line of text in white with the top left corner at x,y
+    fn draw_text(&mut self, x: i32, y: i32, text: &str) {
+        let scale = self.text_scale();
+        let char_width = (GLYPH_WIDTH + 1) * scale;
+
+        self.canvas.set_draw_color(WHITE);
+        let mut rect = Rect::new(0, 0, scale as u32, scale as u32);
+        for (i, c) in text.chars().enumerate() {
+            let glyph_x = x + (scale + i * char_width) as i32;
+            for (row, bits) in glyph(c).iter().enumerate() {
+                for col in 0..GLYPH_WIDTH {
+                    if (bits >> (GLYPH_WIDTH - 1 - col)) & 0x1 == 1 {
+                        rect.set_x(glyph_x + (col * scale) as i32);
+                        rect.set_y(y + ((row + 1) * scale) as i32);
+                        self.canvas.fill_rect(rect).unwrap();
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl VideoSink for VideoDevice {
+    fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.foreground = to_sdl_color(theme.foreground);
+        self.background = to_sdl_color(theme.background);
+        self.stale = true;
+    }
+
+    // draw the display and the osd on top of it if either changed, or
+    // while the phosphor is fading. The whole window is presented each time
+    // but only the rows of the display that changed are drawn again
+    fn render(&mut self, display: &mut Display, osd: &mut Osd) {
+        // both need to be reset so don't short circuit
+        let dirty_rows = display.take_dirty_rows();
+        let osd_dirty = osd.take_dirty();
+        let fading = self.phosphor && self.fade_phosphor(display);
+        let resized = self.fit();
+        if !(dirty_rows.is_some() || osd_dirty || fading || resized) {
+            return;
+        }
+
+        // clear the bars around the display
+        self.canvas.set_draw_color(BLACK);
+        self.canvas.clear();
+        let ghost = osd.ghost();
+        if self.crt {
+            let colors = self.pixel_colors(display, ghost);
+            self.draw_crt(display, &colors);
+        } else {
+            // every pixel changes colour while fading
+            let rows = if self.stale || self.phosphor || ghost.is_some() || resized {
+                0..display.get_height()
+            } else {
+                dirty_rows.unwrap_or(0..0)
+            };
+            self.draw_pixels(display, ghost, rows);
+            // the frame after the ghost is gone has to be drawn in full
+            self.stale = ghost.is_some();
+        }
+        self.draw_osd(osd);
+        self.canvas.present();
+    }
+
+    fn pixelmap(&self, display: &Display) -> Option<Pixelmap> {
+        Some(Pixelmap::render(
+            display,
+            self.capture_pixelsize,
+            self.theme,
+        ))
+    }
+
+    fn set_crt(&mut self, on: bool) {
+        self.crt = on;
+        self.stale = true;
+    }
+
+    fn set_fullscreen(&mut self, on: bool) {
+        let mode = if on {
+            FullscreenType::Desktop
+        } else {
+            FullscreenType::Off
+        };
+        self.canvas.window_mut().set_fullscreen(mode).ok();
+    }
+
+    fn set_phosphor(&mut self, on: bool) {
+        self.phosphor = on;
+        self.intensity.clear();
+        self.stale = true;
+    }
+}
+
+// split a line into lines of at most max_chars characters, breaking at spaces
+// where possible
+fn wrap_text(line: &str, max_chars: usize, output: &mut Vec<String>) {
+    let mut current = String::new();
+    for word in line.split(' ') {
+        let length = current.chars().count();
+        if length > 0 && length + 1 + word.chars().count() > max_chars {
+            output.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+        // split words too long for a line at a character, not a byte
+        while let Some((split, _)) = current.char_indices().nth(max_chars) {
+            let rest = current.split_off(split);
+            output.push(std::mem::replace(&mut current, rest));
+        }
+    }
+    output.push(current);
+}
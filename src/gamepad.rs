@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+
+use crate::i18n::trf;
+
+// the default gamepad bindings, the d-pad and left stick move with 5 7 8 9
+// like WASD in most games and the face buttons press the usual action keys
+const DEFAULT_BINDINGS: &[(&str, u8)] = &[
+    ("dpup", 0x5),
+    ("dpleft", 0x7),
+    ("dpdown", 0x8),
+    ("dpright", 0x9),
+    ("lefty-", 0x5),
+    ("leftx-", 0x7),
+    ("lefty+", 0x8),
+    ("leftx+", 0x9),
+    ("a", 0x6),
+    ("b", 0x4),
+    ("x", 0x1),
+    ("y", 0x2),
+    ("start", 0xF),
+    ("back", 0x0),
+];
+
+// gamepad buttons and stick directions to the keypad keys they press. The
+// inputs are SDL game controller names, with + or - after an axis for its
+// direction, e.g. "a", "dpup" or "leftx-"
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GamepadMap {
+    bindings: BTreeMap<String, u8>,
+}
+
+impl Default for GamepadMap {
+    fn default() -> Self {
+        GamepadMap {
+            bindings: DEFAULT_BINDINGS
+                .iter()
+                .map(|(input, key)| (input.to_string(), *key))
+                .collect(),
+        }
+    }
+}
+
+impl GamepadMap {
+    // the default bindings changed by the gamepad table of the config file,
+    // which maps inputs to keypad keys "0" to "F". An empty key unbinds the
+    // input
+    pub fn from_config(config: &BTreeMap<String, String>) -> Result<GamepadMap, String> {
+        let mut map = GamepadMap::default();
+        for (input, key) in config {
+            if key.is_empty() {
+                map.bindings.remove(input);
+                continue;
+            }
+            match u8::from_str_radix(key, 16) {
+                Ok(key) if key < 0x10 => {
+                    map.bindings.insert(input.clone(), key);
+                }
+                _ => return Err(trf("gamepad_unknown_keypad_key", &[key, input])),
+            }
+        }
+        Ok(map)
+    }
+
+    // the inputs and the keypad keys they press
+    pub fn bindings(&self) -> impl Iterator<Item = (&str, u8)> {
+        self.bindings
+            .iter()
+            .map(|(input, key)| (input.as_str(), *key))
+    }
+}
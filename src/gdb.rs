@@ -0,0 +1,477 @@
+use std::collections::BTreeSet;
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+
+use crate::debugger::DebugHook;
+use crate::error::Chip8Error;
+use crate::expr::MachineView;
+use crate::i18n::tr;
+use crate::i18n::trf;
+use crate::interpreter::Interpreter;
+use crate::interpreter::MEM_SIZE;
+
+// the registers in the order gdb numbers them, V0-VF are 0-15
+const REG_I: usize = 16;
+const REG_PC: usize = 17;
+const REG_SP: usize = 18;
+const REG_DT: usize = 19;
+const REG_ST: usize = 20;
+const REG_COUNT: usize = 21;
+
+// the stop signals sent to gdb, SIGINT for a break from gdb, SIGTRAP for
+// breakpoints and steps, and SIGILL and SIGSEGV for faults
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGSEGV: u8 = 11;
+
+// the longest packet gdb is told it can send or will be sent, in bytes
+const PACKET_SIZE: usize = 0x1000;
+
+// describes the registers to gdb, chip8 is not an architecture gdb knows
+const TARGET_XML: &str = concat!(
+    r#"<?xml version="1.0"?><!DOCTYPE target SYSTEM "gdb-target.dtd">"#,
+    r#"<target version="1.0"><feature name="org.chip8.core">"#,
+    r#"<reg name="v0" bitsize="8"/><reg name="v1" bitsize="8"/>"#,
+    r#"<reg name="v2" bitsize="8"/><reg name="v3" bitsize="8"/>"#,
+    r#"<reg name="v4" bitsize="8"/><reg name="v5" bitsize="8"/>"#,
+    r#"<reg name="v6" bitsize="8"/><reg name="v7" bitsize="8"/>"#,
+    r#"<reg name="v8" bitsize="8"/><reg name="v9" bitsize="8"/>"#,
+    r#"<reg name="va" bitsize="8"/><reg name="vb" bitsize="8"/>"#,
+    r#"<reg name="vc" bitsize="8"/><reg name="vd" bitsize="8"/>"#,
+    r#"<reg name="ve" bitsize="8"/><reg name="vf" bitsize="8"/>"#,
+    r#"<reg name="i" bitsize="16" type="data_ptr"/>"#,
+    r#"<reg name="pc" bitsize="16" type="code_ptr"/>"#,
+    r#"<reg name="sp" bitsize="8"/><reg name="dt" bitsize="8"/>"#,
+    r#"<reg name="st" bitsize="8"/>"#,
+    r#"</feature></target>"#
+);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum State {
+    Running,
+    Paused,
+    Stepping,
+}
+
+// a gdb remote serial protocol server, gdb or another debugger connects over
+// tcp to read and write registers and memory, set breakpoints and step.
+// The machine is paused until a debugger connects
+pub struct GdbStub {
+    listener: TcpListener,
+    stream: Option<TcpStream>,
+
+    // bytes received that don't make a whole packet yet
+    input: Vec<u8>,
+
+    breakpoints: BTreeSet<usize>,
+
+    state: State,
+
+    // a breakpoint at this address is not hit, so continuing from a
+    // breakpoint doesn't stop straight away on it again
+    resume_pc: Option<usize>,
+
+    // set once the debugger killed the program
+    quit: bool,
+}
+
+impl GdbStub {
+    // listen for a debugger on the port of this machine
+    pub fn new(port: u16) -> Result<GdbStub, String> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+            .map_err(|e| trf("gdb_listen_error", &[&port, &e]))?;
+        Ok(GdbStub {
+            listener,
+            stream: None,
+            input: Vec::new(),
+            breakpoints: BTreeSet::new(),
+            state: State::Paused,
+            resume_pc: None,
+            quit: false,
+        })
+    }
+
+    fn accept(&mut self) {
+        if let Ok((stream, addr)) = self.listener.accept() {
+            if stream.set_nonblocking(true).is_ok() {
+                let _ = stream.set_nodelay(true);
+                println!("{}", trf("gdb_connected", &[&addr]));
+                self.stream = Some(stream);
+                self.input.clear();
+                self.state = State::Paused;
+            }
+        }
+    }
+
+    // let the machine run on when the debugger goes away
+    fn disconnect(&mut self) {
+        println!("{}", tr("gdb_disconnected"));
+        self.stream = None;
+        self.breakpoints.clear();
+        self.state = State::Running;
+    }
+
+    // read whatever has arrived, returns false if the connection closed
+    fn receive(&mut self) -> bool {
+        let Some(stream) = self.stream.as_mut() else {
+            return true;
+        };
+        let mut buffer = [0; 1024];
+        loop {
+            match stream.read(&mut buffer) {
+                Ok(0) => return false,
+                Ok(count) => self.input.extend_from_slice(&buffer[..count]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => return true,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(_) => return false,
+            }
+        }
+    }
+
+    fn send_raw(&mut self, data: &[u8]) {
+        let failed = match self.stream.as_mut() {
+            Some(stream) => stream.write_all(data).is_err(),
+            None => false,
+        };
+        if failed {
+            self.disconnect();
+        }
+    }
+
+    // send a packet, "$data#checksum"
+    fn send(&mut self, data: &str) {
+        let packet = format!("${}#{:02x}", data, checksum(data.as_bytes()));
+        self.send_raw(packet.as_bytes());
+    }
+
+    fn stop(&mut self, signal: u8) {
+        self.state = State::Paused;
+        self.send(&format!("S{:02x}", signal));
+    }
+
+    // take the next whole packet from the input, acks are dropped and a bad
+    // checksum asks for the packet again
+    fn next_packet(&mut self) -> Option<Packet> {
+        loop {
+            match self.input.first()? {
+                b'$' => {}
+                // gdb sends a break as a lone ctrl-c
+                0x03 => {
+                    self.input.remove(0);
+                    return Some(Packet::Interrupt);
+                }
+                _ => {
+                    self.input.remove(0);
+                    continue;
+                }
+            }
+            let end = self.input.iter().position(|b| *b == b'#')?;
+            if self.input.len() < end + 3 {
+                return None;
+            }
+            let packet: Vec<u8> = self.input.drain(..end + 3).collect();
+            let data = &packet[1..end];
+            let sum = std::str::from_utf8(&packet[end + 1..])
+                .ok()
+                .and_then(|sum| u8::from_str_radix(sum, 16).ok());
+            if sum == Some(checksum(data)) {
+                self.send_raw(b"+");
+                return Some(Packet::Command(String::from_utf8_lossy(data).into_owned()));
+            }
+            self.send_raw(b"-");
+        }
+    }
+
+    // handle a packet, returns the reply or None if the reply is sent when
+    // the machine stops
+    fn handle(&mut self, packet: &str, interp: &mut Interpreter) -> Option<String> {
+        let (kind, args) = packet.split_at(packet.chars().next().map_or(0, char::len_utf8));
+        let reply = match kind {
+            "?" => format!("S{:02x}", SIGTRAP),
+            "g" => (0..REG_COUNT)
+                .map(|reg| read_register(interp, reg))
+                .collect(),
+            "G" => {
+                let mut rest = args;
+                for reg in 0..REG_COUNT {
+                    let width = 2 * register_size(reg);
+                    if rest.len() < width {
+                        break;
+                    }
+                    write_register(interp, reg, &rest[..width]);
+                    rest = &rest[width..];
+                }
+                "OK".to_string()
+            }
+            "p" => match usize::from_str_radix(args, 16) {
+                Ok(reg) if reg < REG_COUNT => read_register(interp, reg),
+                _ => "E01".to_string(),
+            },
+            "P" => match args.split_once('=') {
+                Some((reg, val)) => match usize::from_str_radix(reg, 16) {
+                    Ok(reg) if reg < REG_COUNT && write_register(interp, reg, val) => {
+                        "OK".to_string()
+                    }
+                    _ => "E01".to_string(),
+                },
+                None => "E01".to_string(),
+            },
+            // each byte is two hex digits of the reply, reads past the end
+            // of memory are refused rather than wrapped
+            "m" => match checked_range(args, PACKET_SIZE / 2) {
+                Some((start, end)) if end <= MEM_SIZE => (start..end)
+                    .map(|addr| format!("{:02x}", interp.read_memory(addr)))
+                    .collect(),
+                _ => "E01".to_string(),
+            },
+            "M" => match args.split_once(':') {
+                Some((range, data)) => match (parse_range(range), decode_hex(data)) {
+                    (Some((addr, _)), Some(bytes))
+                        if addr
+                            .checked_add(bytes.len())
+                            .is_some_and(|end| end <= MEM_SIZE) =>
+                    {
+                        for (offset, byte) in bytes.iter().enumerate() {
+                            interp.write_memory(addr + offset, *byte);
+                        }
+                        "OK".to_string()
+                    }
+                    _ => "E01".to_string(),
+                },
+                None => "E01".to_string(),
+            },
+            "c" | "s" => {
+                if let Ok(addr) = usize::from_str_radix(args, 16) {
+                    interp.set_pc(addr);
+                }
+                self.resume_pc = Some(interp.pc());
+                self.state = if kind == "c" {
+                    State::Running
+                } else {
+                    State::Stepping
+                };
+                return None;
+            }
+            // software and hardware breakpoints are the same thing here
+            "Z" | "z" => {
+                let mut parts = args.split(',');
+                let breakpoint_type = parts.next();
+                let addr = parts
+                    .next()
+                    .and_then(|addr| usize::from_str_radix(addr, 16).ok());
+                match (breakpoint_type, addr) {
+                    (Some("0") | Some("1"), Some(addr)) => {
+                        if kind == "Z" {
+                            self.breakpoints.insert(addr);
+                        } else {
+                            self.breakpoints.remove(&addr);
+                        }
+                        "OK".to_string()
+                    }
+                    _ => String::new(),
+                }
+            }
+            "H" => "OK".to_string(),
+            "D" => {
+                self.send("OK");
+                self.disconnect();
+                return None;
+            }
+            "k" => {
+                self.quit = true;
+                self.disconnect();
+                return None;
+            }
+            "q" if args.starts_with("Supported") => {
+                format!("PacketSize={:x};qXfer:features:read+", PACKET_SIZE)
+            }
+            "q" if args == "Attached" => "1".to_string(),
+            "q" if args.starts_with("Xfer:features:read:target.xml:") => {
+                let range = &args["Xfer:features:read:target.xml:".len()..];
+                // the reply starts with m or l
+                match checked_range(range, PACKET_SIZE - 1) {
+                    Some((start, end)) => {
+                        let start = start.min(TARGET_XML.len());
+                        let end = end.min(TARGET_XML.len());
+                        let more = if end < TARGET_XML.len() { "m" } else { "l" };
+                        format!("{}{}", more, &TARGET_XML[start..end])
+                    }
+                    None => "E01".to_string(),
+                }
+            }
+            // an empty reply tells gdb the packet is not supported
+            _ => String::new(),
+        };
+        Some(reply)
+    }
+}
+
+enum Packet {
+    Command(String),
+    Interrupt,
+}
+
+impl DebugHook for GdbStub {
+    fn is_paused(&self) -> bool {
+        self.state == State::Paused
+    }
+
+    fn before_opcode(&mut self, interp: &Interpreter) -> bool {
+        match self.state {
+            State::Paused => false,
+            State::Stepping => true,
+            State::Running => {
+                let pc = interp.pc();
+                if self.breakpoints.contains(&pc) && self.resume_pc != Some(pc) {
+                    self.stop(SIGTRAP);
+                    false
+                } else {
+                    true
+                }
+            }
+        }
+    }
+
+    fn after_opcode(&mut self, _interp: &Interpreter) {
+        self.resume_pc = None;
+        if self.state == State::Stepping {
+            self.stop(SIGTRAP);
+        }
+    }
+
+    fn poll(&mut self, interp: &mut Interpreter) {
+        if self.stream.is_none() {
+            self.accept();
+            return;
+        }
+        if !self.receive() {
+            self.disconnect();
+            return;
+        }
+        while let Some(packet) = self.next_packet() {
+            match packet {
+                Packet::Interrupt => self.stop(SIGINT),
+                Packet::Command(packet) => {
+                    if let Some(reply) = self.handle(&packet, interp) {
+                        self.send(&reply);
+                    }
+                }
+            }
+            if self.stream.is_none() {
+                break;
+            }
+        }
+    }
+
+    fn wants_quit(&self) -> bool {
+        self.quit
+    }
+
+    // opcodes that can't run are illegal instructions, the rest go wrong
+    // with memory
+    fn fault(&mut self, _interp: &Interpreter, fault: &Chip8Error) -> bool {
+        match fault {
+            Chip8Error::InvalidOpcode { .. } | Chip8Error::MachineCode { .. } => self.stop(SIGILL),
+            _ => self.stop(SIGSEGV),
+        }
+        true
+    }
+}
+
+fn checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+// "addr,len" in hex
+fn parse_range(text: &str) -> Option<(usize, usize)> {
+    let (addr, len) = text.split_once(',')?;
+    Some((
+        usize::from_str_radix(addr, 16).ok()?,
+        usize::from_str_radix(len, 16).ok()?,
+    ))
+}
+
+// the start and end of an address and length such as "200,10", the length
+// cut to at most max_len. None if it isn't one or the end is past any address
+fn checked_range(text: &str, max_len: usize) -> Option<(usize, usize)> {
+    let (start, len) = parse_range(text)?;
+    Some((start, start.checked_add(len.min(max_len))?))
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+// the number of bytes of a register, I and PC are 16 bits
+fn register_size(reg: usize) -> usize {
+    if reg == REG_I || reg == REG_PC {
+        2
+    } else {
+        1
+    }
+}
+
+// a register as hex, most significant byte first as chip8 is big endian
+fn read_register(interp: &Interpreter, reg: usize) -> String {
+    match reg {
+        0..=15 => format!("{:02x}", interp.register(reg)),
+        REG_I => format!("{:04x}", interp.i()),
+        REG_PC => format!("{:04x}", interp.pc()),
+        REG_SP => format!("{:02x}", interp.sp()),
+        REG_DT => format!("{:02x}", interp.delay_timer()),
+        REG_ST => format!("{:02x}", interp.sound_timer()),
+        _ => String::new(),
+    }
+}
+
+// set a register from hex, returns false if the value is not valid. The
+// stack pointer follows the calls that were made so it can't be written
+fn write_register(interp: &mut Interpreter, reg: usize, hex: &str) -> bool {
+    let Ok(val) = usize::from_str_radix(hex, 16) else {
+        return false;
+    };
+    match reg {
+        0..=15 => interp.set_register(reg, val as u8),
+        REG_I => interp.set_i(val),
+        REG_PC => interp.set_pc(val),
+        REG_SP => {}
+        REG_DT => interp.set_delay_timer(val as u8),
+        REG_ST => interp.set_sound_timer(val as u8),
+        _ => return false,
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_is_decoded_to_bytes() {
+        assert_eq!(decode_hex("00ff7A"), Some(vec![0x00, 0xFF, 0x7A]));
+        assert_eq!(decode_hex(""), Some(Vec::new()));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+        // a multibyte character can't split a pair of digits
+        assert_eq!(decode_hex("\u{e9}"), None);
+    }
+
+    #[test]
+    fn ranges_are_cut_to_the_packet() {
+        assert_eq!(checked_range("200,10", PACKET_SIZE), Some((0x200, 0x210)));
+        assert_eq!(checked_range("0,ffff", 0x800), Some((0, 0x800)));
+        assert_eq!(checked_range("ffffffffffffffff,10", 0x800), None);
+        assert_eq!(checked_range("200", 0x800), None);
+    }
+}
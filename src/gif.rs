@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::i18n::trf;
+use crate::screenshot::Pixelmap;
+
+// the shortest delay between frames in hundredths of a second, browsers slow
+// anything shorter right down
+const MIN_DELAY: f32 = 2.0;
+
+// the largest lzw code
+const MAX_CODES: u16 = 4096;
+
+// records frames to an animated gif as they are captured. Frames that don't
+// change the picture make the one before last longer, and some frames are
+// dropped when they come faster than the gif format allows
+pub struct GifRecorder {
+    writer: BufWriter<File>,
+    path: PathBuf,
+    width: usize,
+    height: usize,
+
+    // the last frame, written once it is known how long it is shown for
+    pending: Option<Pixelmap>,
+
+    // how long the pending frame has been shown in hundredths of a second
+    pending_time: f32,
+}
+
+impl GifRecorder {
+    // start a gif of frames of the given size that loops forever
+    pub fn create(path: &Path, width: usize, height: usize) -> Result<GifRecorder, String> {
+        let file =
+            File::create(path).map_err(|e| trf("file_create_error", &[&path.display(), &e]))?;
+        let mut recorder = GifRecorder {
+            writer: BufWriter::new(file),
+            path: path.to_path_buf(),
+            width,
+            height,
+            pending: None,
+            pending_time: 0.0,
+        };
+
+        let mut header = Vec::new();
+        header.extend_from_slice(b"GIF89a");
+        push_u16(&mut header, width);
+        push_u16(&mut header, height);
+        // no global colour table, each frame has its own
+        header.extend_from_slice(&[0x00, 0x00, 0x00]);
+        // the netscape extension that makes the gif loop
+        header.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+        header.extend_from_slice(b"NETSCAPE2.0");
+        header.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+        recorder.write(&header)?;
+        Ok(recorder)
+    }
+
+    // add a frame shown for the given number of hundredths of a second
+    pub fn add_frame(&mut self, frame: Pixelmap, time: f32) -> Result<(), String> {
+        if frame.width != self.width || frame.height != self.height {
+            return Ok(());
+        }
+        match self.pending.take() {
+            Some(pending) if pending.data == frame.data => {
+                self.pending = Some(pending);
+            }
+            Some(pending) if self.pending_time > MIN_DELAY / 2.0 => {
+                // the difference is carried over so the gif keeps time, a
+                // frame shown for longer than it was makes the next frames
+                // shorter or drops them
+                let delay = self.pending_time.round().max(MIN_DELAY);
+                self.write_frame(&pending, delay as u16)?;
+                self.pending_time -= delay;
+                self.pending = Some(frame);
+            }
+            _ => self.pending = Some(frame),
+        }
+        self.pending_time += time;
+        Ok(())
+    }
+
+    // write the last frame and the end of the gif, returns the path of the
+    // file
+    pub fn finish(mut self) -> Result<PathBuf, String> {
+        if let Some(pending) = self.pending.take() {
+            let delay = self.pending_time.round().max(MIN_DELAY);
+            self.write_frame(&pending, delay as u16)?;
+        }
+        self.write(&[0x3B])?;
+        self.writer
+            .flush()
+            .map_err(|e| trf("file_write_error", &[&self.path.display(), &e]))?;
+        Ok(self.path)
+    }
+
+    fn write_frame(&mut self, frame: &Pixelmap, delay: u16) -> Result<(), String> {
+        // the colours of the frame, a gif colour table holds up to 256
+        let mut colors: Vec<[u8; 3]> = Vec::new();
+        let mut indices = Vec::with_capacity(frame.width * frame.height);
+        for rgb in frame.data.chunks(3) {
+            let rgb = [rgb[0], rgb[1], rgb[2]];
+            let index = match colors.iter().position(|color| *color == rgb) {
+                Some(index) => index,
+                None if colors.len() < 256 => {
+                    colors.push(rgb);
+                    colors.len() - 1
+                }
+                None => 0,
+            };
+            indices.push(index as u8);
+        }
+        // the table size is a power of two of at least 4 colours
+        let bits = (1..=8)
+            .find(|bits| colors.len() <= 1 << bits)
+            .unwrap_or(8)
+            .max(2);
+        colors.resize(1 << bits, [0, 0, 0]);
+
+        let mut block = Vec::new();
+        // graphic control extension with the delay, frames are drawn over
+        // the one before
+        block.extend_from_slice(&[0x21, 0xF9, 0x04, 0x04]);
+        push_u16(&mut block, delay as usize);
+        block.extend_from_slice(&[0x00, 0x00]);
+        // image descriptor covering the whole gif with a local colour table
+        block.push(0x2C);
+        push_u16(&mut block, 0);
+        push_u16(&mut block, 0);
+        push_u16(&mut block, self.width);
+        push_u16(&mut block, self.height);
+        block.push(0x80 | (bits as u8 - 1));
+        for color in &colors {
+            block.extend_from_slice(color);
+        }
+        block.push(bits as u8);
+        // the compressed pixels in blocks of up to 255 bytes
+        for chunk in lzw_compress(&indices, bits).chunks(255) {
+            block.push(chunk.len() as u8);
+            block.extend_from_slice(chunk);
+        }
+        block.push(0x00);
+        self.write(&block)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), String> {
+        self.writer
+            .write_all(data)
+            .map_err(|e| trf("file_write_error", &[&self.path.display(), &e]))
+    }
+}
+
+fn push_u16(data: &mut Vec<u8>, value: usize) {
+    data.extend_from_slice(&(value as u16).to_le_bytes());
+}
+
+// the variable length lzw codes gifs are compressed with, packed from the
+// lowest bit up
+fn lzw_compress(indices: &[u8], min_code_size: usize) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut output = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next = end + 1;
+    let mut code_size = min_code_size + 1;
+    output.write(clear, code_size);
+
+    let mut current: Option<u16> = None;
+    for &index in indices {
+        let Some(prefix) = current else {
+            current = Some(index as u16);
+            continue;
+        };
+        if let Some(&code) = table.get(&(prefix, index)) {
+            current = Some(code);
+            continue;
+        }
+        output.write(prefix, code_size);
+        if next < MAX_CODES {
+            table.insert((prefix, index), next);
+            next += 1;
+            if next > 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        } else {
+            // the table is full, start again
+            output.write(clear, code_size);
+            table.clear();
+            next = end + 1;
+            code_size = min_code_size + 1;
+        }
+        current = Some(index as u16);
+    }
+    if let Some(prefix) = current {
+        output.write(prefix, code_size);
+    }
+    output.write(end, code_size);
+    output.finish()
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: usize) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+
+use crate::backend::Command;
+use crate::i18n::tr;
+use crate::i18n::trf;
+
+// an emulator action that can be bound to a key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Action {
+    Help,
+    RomInfo,
+    Pause,
+    StepOpcode,
+    StepFrame,
+    Reset,
+    Screenshot,
+    Record,
+    Scope,
+    Theme,
+    Crt,
+    Fullscreen,
+    SaveState,
+    LoadState,
+    Undo,
+    Faster,
+    Slower,
+    VolumeUp,
+    VolumeDown,
+    Rewind,
+    Turbo,
+    Palette,
+    Recent,
+    Quit,
+}
+
+// the names used for the actions in the config file
+pub const ACTION_NAMES: &[&str] = &[
+    "help",
+    "rom-info",
+    "pause",
+    "step",
+    "frame",
+    "reset",
+    "screenshot",
+    "record",
+    "scope",
+    "theme",
+    "crt",
+    "fullscreen",
+    "save-state",
+    "load-state",
+    "undo",
+    "faster",
+    "slower",
+    "volume-up",
+    "volume-down",
+    "rewind",
+    "turbo",
+    "palette",
+    "recent",
+    "quit",
+];
+
+const ACTIONS: &[Action] = &[
+    Action::Help,
+    Action::RomInfo,
+    Action::Pause,
+    Action::StepOpcode,
+    Action::StepFrame,
+    Action::Reset,
+    Action::Screenshot,
+    Action::Record,
+    Action::Scope,
+    Action::Theme,
+    Action::Crt,
+    Action::Fullscreen,
+    Action::SaveState,
+    Action::LoadState,
+    Action::Undo,
+    Action::Faster,
+    Action::Slower,
+    Action::VolumeUp,
+    Action::VolumeDown,
+    Action::Rewind,
+    Action::Turbo,
+    Action::Palette,
+    Action::Recent,
+    Action::Quit,
+];
+
+impl Action {
+    // every action, in the order of ACTION_NAMES
+    pub fn all() -> &'static [Action] {
+        ACTIONS
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTION_NAMES
+            .iter()
+            .position(|action_name| *action_name == name)
+            .map(|index| ACTIONS[index])
+    }
+
+    pub fn name(&self) -> &'static str {
+        ACTION_NAMES[*self as usize]
+    }
+
+    // the command sent when the key is pressed or let go of, most actions
+    // only happen on the press
+    pub fn command(&self, pressed: bool) -> Option<Command> {
+        match (self, pressed) {
+            (Action::Help, true) => Some(Command::ToggleHelp),
+            (Action::RomInfo, true) => Some(Command::ToggleRomInfo),
+            (Action::Pause, true) => Some(Command::TogglePause),
+            (Action::StepOpcode, true) => Some(Command::StepOpcode),
+            (Action::StepFrame, true) => Some(Command::StepFrame),
+            (Action::Reset, true) => Some(Command::Reset),
+            (Action::Screenshot, true) => Some(Command::Screenshot),
+            (Action::Record, true) => Some(Command::ToggleRecording),
+            (Action::Scope, true) => Some(Command::ToggleScope),
+            (Action::Theme, true) => Some(Command::CycleTheme),
+            (Action::Crt, true) => Some(Command::ToggleCrt),
+            (Action::Fullscreen, true) => Some(Command::ToggleFullscreen),
+            (Action::SaveState, true) => Some(Command::SaveStateMenu),
+            (Action::LoadState, true) => Some(Command::LoadStateMenu),
+            (Action::Undo, true) => Some(Command::Undo),
+            (Action::Faster, true) => Some(Command::Faster),
+            (Action::Slower, true) => Some(Command::Slower),
+            (Action::VolumeUp, true) => Some(Command::VolumeUp),
+            (Action::VolumeDown, true) => Some(Command::VolumeDown),
+            (Action::Rewind, pressed) => Some(Command::Rewind(pressed)),
+            (Action::Turbo, pressed) => Some(Command::Turbo(pressed)),
+            (Action::Palette, true) => Some(Command::TogglePalette),
+            (Action::Recent, true) => Some(Command::RecentMenu),
+            (Action::Quit, true) => Some(Command::Quit),
+            _ => None,
+        }
+    }
+
+    // actions that only last while the key is held can't be run from the
+    // command palette, nor can the recent roms menu which takes the keys
+    // the palette had
+    pub fn in_palette(&self) -> bool {
+        !matches!(
+            self,
+            Action::Rewind | Action::Turbo | Action::Palette | Action::Recent
+        )
+    }
+
+    // what the action does, e.g. "Show the controls"
+    pub fn description(&self) -> String {
+        tr(&format!("action_{}", self.name().replace('-', "_")))
+    }
+
+    // e.g. "F1 help" for the help overlay
+    fn describe(&self, key: &str) -> String {
+        let name = format!("hotkey_{}", self.name().replace('-', "_"));
+        trf(&name, &[&key])
+    }
+}
+
+// which key each action is bound to. Key names are the SDL names, e.g. "F1",
+// "Backspace" or "Left Ctrl", optionally after "Ctrl+", "Shift+" or "Alt+"
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hotkeys {
+    bindings: BTreeMap<Action, String>,
+}
+
+impl Default for Hotkeys {
+    fn default() -> Hotkeys {
+        let bindings = [
+            (Action::Help, "F1"),
+            (Action::RomInfo, "F2"),
+            (Action::Pause, "Space"),
+            (Action::StepOpcode, "F7"),
+            (Action::StepFrame, "F6"),
+            (Action::Reset, "F5"),
+            (Action::Screenshot, "F12"),
+            (Action::Record, "F9"),
+            (Action::Scope, "F8"),
+            (Action::Theme, "F4"),
+            (Action::Crt, "F3"),
+            (Action::Fullscreen, "F11"),
+            (Action::SaveState, "Ctrl+S"),
+            (Action::LoadState, "Ctrl+L"),
+            (Action::Undo, "Ctrl+Z"),
+            (Action::Faster, "="),
+            (Action::Slower, "-"),
+            (Action::VolumeUp, "]"),
+            (Action::VolumeDown, "["),
+            (Action::Rewind, "Backspace"),
+            (Action::Turbo, "Tab"),
+            (Action::Palette, "Ctrl+P"),
+            (Action::Recent, "Escape"),
+        ];
+        Hotkeys {
+            bindings: bindings
+                .iter()
+                .map(|(action, key)| (*action, key.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl Hotkeys {
+    // the default bindings changed by the hotkeys table of the config file,
+    // an empty key name unbinds an action. A key the config file binds is
+    // taken from the action it is bound to by default
+    pub fn from_config(config: &BTreeMap<String, String>) -> Result<Hotkeys, String> {
+        let mut hotkeys = Hotkeys::default();
+        let mut configured = Vec::new();
+        for (name, key) in config {
+            let action = Action::from_name(name)
+                .ok_or_else(|| trf("hotkey_unknown_action", &[name, &ACTION_NAMES.join(", ")]))?;
+            configured.push(action);
+            if key.is_empty() {
+                hotkeys.bindings.remove(&action);
+            } else {
+                hotkeys.bindings.insert(action, key.clone());
+            }
+        }
+        let taken: Vec<String> = config.values().map(|key| key.to_lowercase()).collect();
+        hotkeys.bindings.retain(|action, key| {
+            configured.contains(action) || !taken.contains(&key.to_lowercase())
+        });
+        Ok(hotkeys)
+    }
+
+    // the key an action is bound to
+    pub fn key(&self, action: Action) -> Option<&str> {
+        self.bindings.get(&action).map(|key| key.as_str())
+    }
+
+    // the bound actions and their key names
+    pub fn bindings(&self) -> impl Iterator<Item = (Action, &str)> {
+        self.bindings
+            .iter()
+            .map(|(action, key)| (*action, key.as_str()))
+    }
+
+    // a key can only do one thing, so it can't be bound to two actions or be
+    // one of the keypad keys
+    pub fn check_conflicts(&self, keypad_keys: &[&str]) -> Result<(), String> {
+        let mut seen: BTreeMap<String, Action> = BTreeMap::new();
+        for (action, key) in self.bindings() {
+            let lower = key.to_lowercase();
+            if let Some(other) = seen.get(&lower) {
+                return Err(trf(
+                    "hotkey_conflict",
+                    &[&key, &other.name(), &action.name()],
+                ));
+            }
+            if keypad_keys.iter().any(|k| k.to_lowercase() == lower) {
+                return Err(trf("hotkey_keypad_conflict", &[&key, &action.name()]));
+            }
+            seen.insert(lower, action);
+        }
+        Ok(())
+    }
+
+    // a line describing the bound hotkeys for the help overlay
+    pub fn describe(&self) -> String {
+        let descriptions: Vec<String> = self
+            .bindings()
+            .map(|(action, key)| action.describe(key))
+            .collect();
+        descriptions.join(", ")
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+// the english strings, used when there is no translation for a string
+const ENGLISH: &str = include_str!("../lang/en.toml");
+
+static ENGLISH_STRINGS: OnceLock<HashMap<String, String>> = OnceLock::new();
+static TRANSLATION: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+fn english() -> &'static HashMap<String, String> {
+    ENGLISH_STRINGS.get_or_init(|| toml::from_str(ENGLISH).expect("Invalid built in strings"))
+}
+
+// load a translation file, this can only be done once before any strings are
+// looked up
+pub fn load(path: &Path) -> Result<(), String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| trf("file_read_error", &[&path.display(), &e]))?;
+    let strings: HashMap<String, String> =
+        toml::from_str(&text).map_err(|e| trf("file_invalid", &[&path.display(), &e]))?;
+    TRANSLATION
+        .set(strings)
+        .map_err(|_| tr("translation_already_loaded"))
+}
+
+// look up a user facing string
+pub fn tr(key: &str) -> String {
+    TRANSLATION
+        .get()
+        .and_then(|strings| strings.get(key))
+        .or_else(|| english().get(key))
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+// look up a user facing string and replace each {} with the next argument
+pub fn trf(key: &str, args: &[&dyn Display]) -> String {
+    let template = tr(key);
+    let mut output = String::new();
+    let mut args = args.iter();
+    let mut parts = template.split("{}").peekable();
+    while let Some(part) = parts.next() {
+        output.push_str(part);
+        if parts.peek().is_some() {
+            if let Some(arg) = args.next() {
+                output.push_str(&arg.to_string());
+            }
+        }
+    }
+    output
+}
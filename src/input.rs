@@ -0,0 +1,77 @@
+use crate::backend::Command;
+use crate::backend::InputSource;
+
+// how the keys of a source combine with the other sources
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    // the keys are or'd with the other merged sources, e.g. a keyboard and a
+    // gamepad can both be used at the same time
+    Merge,
+
+    // the keys of every other source are ignored while this one is active,
+    // e.g. a replay must not be mixed with live input
+    Exclusive,
+}
+
+// several input sources that are active at the same time and act as one.
+// Commands from every source are always used so the emulator can still be
+// quit or paused during a replay
+#[derive(Default)]
+pub struct InputMux<'a> {
+    sources: Vec<(Box<dyn InputSource + 'a>, Priority)>,
+}
+
+impl<'a> InputMux<'a> {
+    pub fn add(&mut self, source: Box<dyn InputSource + 'a>, priority: Priority) {
+        self.sources.push((source, priority));
+    }
+}
+
+impl<'a> InputSource for InputMux<'a> {
+    // the or of the pressed keys of the merged sources, or the keys of the
+    // first exclusive source if there is one
+    fn read_keys(&mut self) -> [bool; 0x10] {
+        let highest = self.sources.iter().map(|(_, priority)| *priority).max();
+
+        let mut keys = [false; 0x10];
+        let mut exclusive_read = false;
+        for (source, priority) in self.sources.iter_mut() {
+            // every source is read so none of them fall behind
+            let source_keys = source.read_keys();
+            if Some(*priority) != highest || exclusive_read {
+                continue;
+            }
+            for (key, pressed) in keys.iter_mut().zip(source_keys.iter()) {
+                *key |= *pressed;
+            }
+            exclusive_read = *priority == Priority::Exclusive;
+        }
+        keys
+    }
+
+    fn poll_commands(&mut self) -> Vec<Command> {
+        self.sources
+            .iter_mut()
+            .flat_map(|(source, _)| source.poll_commands())
+            .collect()
+    }
+
+    fn help_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for (source, _) in &self.sources {
+            let source_lines = source.help_lines();
+            if !source_lines.is_empty() && !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.extend(source_lines);
+        }
+        lines
+    }
+
+    fn hotkey_lines(&self) -> Vec<String> {
+        self.sources
+            .iter()
+            .flat_map(|(source, _)| source.hotkey_lines())
+            .collect()
+    }
+}
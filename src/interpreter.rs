@@ -0,0 +1,1291 @@
+use rand::Rng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use std::num::Wrapping;
+
+use crate::banking;
+use crate::banking::BANK_SIZE;
+use crate::banking::WINDOW_START;
+use crate::display::Display;
+use crate::display::DISPLAY_SIZE;
+use crate::display::HIRES_DISPLAY_SIZE;
+use crate::error::Chip8Error;
+use crate::expr::MachineView;
+use crate::extensions::Extensions;
+use crate::opcode::Instruction;
+use crate::quirks::Quirks;
+use crate::romdb;
+
+// define constants for using the memory
+// Chip 8 has 4096 bytes
+pub const MEM_SIZE: usize = 0x1000;
+
+// 16 registers
+const REGISTERS_SIZE: usize = 0x10;
+
+pub const PROGRAM_START: usize = 0x200;
+
+// roms written for the ETI-660 are loaded and start at 0x600
+pub const ETI660_START: usize = 0x600;
+
+// the load addresses that have names, for --load-address
+pub const LOAD_ADDRESS_PRESETS: &[(&str, usize)] =
+    &[("chip8", PROGRAM_START), ("eti660", ETI660_START)];
+
+const STACK_SLOTS: usize = 64;
+
+// the version of the machine state save_state writes
+pub const STATE_VERSION: u32 = 2;
+
+// fonts will be loaded into memory location 0
+const FONT_START: usize = 0x0;
+// each font character is 5 bytes in size
+const FONT_CHAR_SIZE: usize = 5;
+// there are character 0,1,2,3,4,5,6,7,8,9,A,B,C,D,E,F available
+const FONT_CHAR_COUNT: usize = 0x10;
+// the size of a font, 5 bytes for each hex digit
+pub const FONT_SIZE: usize = FONT_CHAR_SIZE * FONT_CHAR_COUNT;
+// the static font data that will be loaded into the memory
+pub const FONT_DATA: &[u8; FONT_SIZE] = &[
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // "0"
+    0x20, 0x60, 0x20, 0x20, 0x70, // "1"
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // "2"
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // "3"
+    0x90, 0x90, 0xF0, 0x10, 0x10, // "4"
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // "5"
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // "6"
+    0xF0, 0x10, 0x20, 0x40, 0x40, // "7"
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // "8"
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // "9"
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // "A"
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // "B"
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // "C"
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // "D"
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // "E"
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // "F"
+];
+
+// the super-chip big font goes after the small one, 8x10 pixels a character
+const BIG_FONT_START: usize = FONT_START + FONT_SIZE;
+const BIG_FONT_CHAR_SIZE: usize = 10;
+const BIG_FONT_DATA: &[u8; BIG_FONT_CHAR_SIZE * FONT_CHAR_COUNT] = &[
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // "0"
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // "1"
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // "2"
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // "3"
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // "4"
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // "5"
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // "6"
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // "7"
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // "8"
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // "9"
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // "A"
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // "B"
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // "C"
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // "D"
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // "E"
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // "F"
+];
+
+// the number of keys on the hex keypad
+pub const KEY_COUNT: usize = 0x10;
+
+// the chip8 machine: memory, registers, timers, display and keypad state.
+// This has no knowledge of windows, sound or wall clock time, the frontend
+// calls cycle() at the clock speed and update_timers() at 60hz
+pub struct Interpreter {
+    // the display the sprites are drawn to
+    display: Display,
+
+    // the state of the keys on the hex keypad
+    keys: [bool; KEY_COUNT],
+
+    // the memory
+    memory: [u8; MEM_SIZE],
+
+    // 16 variables variables
+    registers: [Wrapping<u8>; REGISTERS_SIZE],
+
+    // stack for function entry/return
+    stack: Vec<usize>,
+
+    // the stack pointer
+    sp: usize,
+
+    // Program counter - represents the current position in execution of the
+    // program
+    pc: usize,
+
+    // 16 bit register for memory addresses
+    i: usize,
+
+    // timers
+    delay_timer: u8,
+    sound_timer: u8,
+
+    // the xo-chip buzzer pitch, None until the rom sets it
+    pitch: Option<u8>,
+
+    // the random numbers for CXNN, seeded with set_seed for runs that can be
+    // repeated. This is the generator behind rand's StdRng, used directly so
+    // a save state can write out exactly where it is
+    rng: ChaCha12Rng,
+
+    // the behaviour of opcodes that differ between interpreters
+    quirks: Quirks,
+
+    // the opcodes added to the instruction set
+    extensions: Extensions,
+
+    // with banking, the rom after the fixed part and the bank of it that is
+    // in the window
+    banks: Vec<u8>,
+    bank: usize,
+
+    // set by the opcode being processed when it can't go on
+    fault: Option<Chip8Error>,
+
+    // where the rom is loaded and starts running
+    load_address: usize,
+}
+
+// a copy of the machine state that can be restored later. The display is
+// packed to a bit per pixel to keep it small as many are kept for rewinding
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    memory: Vec<u8>,
+    registers: [u8; REGISTERS_SIZE],
+    stack: Vec<usize>,
+    sp: usize,
+    pc: usize,
+    i: usize,
+    delay_timer: u8,
+    sound_timer: u8,
+    pitch: Option<u8>,
+    rng: ChaCha12Rng,
+    hires: bool,
+    pixels: Vec<u8>,
+    bank: usize,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        let mut interp = Interpreter {
+            display: Display::default(),
+            keys: [false; KEY_COUNT],
+            memory: [0; MEM_SIZE],
+            registers: [Wrapping(0); REGISTERS_SIZE],
+            stack: Vec::new(),
+            sp: 0,
+            pc: PROGRAM_START,
+            i: 0,
+            delay_timer: 0,
+            sound_timer: 0,
+            pitch: None,
+            rng: ChaCha12Rng::from_entropy(),
+            quirks: Quirks::default(),
+            extensions: Extensions::default(),
+            banks: Vec::new(),
+            bank: 0,
+            fault: None,
+            load_address: PROGRAM_START,
+        };
+
+        // load the fonts into interpretter area of memory
+        interp.memory[FONT_START..FONT_START + FONT_DATA.len()].copy_from_slice(FONT_DATA);
+        interp.memory[BIG_FONT_START..BIG_FONT_START + BIG_FONT_DATA.len()]
+            .copy_from_slice(BIG_FONT_DATA);
+
+        interp
+    }
+
+    // load the rom into the program data in the interpretter memory at the
+    // load address. With banking the part after the fixed part is kept and
+    // bank 0 of it is mapped, so set the extensions first, and the rom always
+    // goes at 0x200 as the window is fixed. A rom too big for memory isn't
+    // loaded
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<(), Chip8Error> {
+        let (data, start) = if self.extensions.banking {
+            let fixed = std::cmp::min(data.len(), banking::FIXED_SIZE);
+            self.banks = data[fixed..].to_vec();
+            self.map_bank(0);
+            (&data[..fixed], PROGRAM_START)
+        } else {
+            (data, self.load_address)
+        };
+        let max = MEM_SIZE - start;
+        if data.len() > max {
+            return Err(Chip8Error::RomTooBig {
+                size: data.len(),
+                max,
+            });
+        }
+        self.memory[start..start + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    // replace the font the FX29 opcode points I at, such as one of the
+    // fonts::FONTS. Set it before the machine is saved for resets
+    pub fn set_font(&mut self, font: &[u8; FONT_SIZE]) {
+        self.memory[FONT_START..FONT_START + FONT_SIZE].copy_from_slice(font);
+    }
+
+    // where the rom is loaded and starts running, 0x200 unless set
+    pub fn load_address(&self) -> usize {
+        self.load_address
+    }
+
+    // load the rom at another address and start running there, such as 0x600
+    // for ETI-660 roms. Set it before loading the rom
+    pub fn set_load_address(&mut self, address: usize) {
+        self.load_address = address % MEM_SIZE;
+        self.pc = self.load_address;
+    }
+
+    // the bank mapped into the window, None without banking
+    pub fn bank(&self) -> Option<usize> {
+        Some(self.bank).filter(|_| self.extensions.banking)
+    }
+
+    // the number of banks of the loaded rom
+    fn bank_count(&self) -> usize {
+        banking::bank_count(banking::FIXED_SIZE + self.banks.len())
+    }
+
+    // copy a bank into the window, past the end of the rom is zeros
+    fn map_bank(&mut self, bank: usize) {
+        let start = std::cmp::min(bank * BANK_SIZE, self.banks.len());
+        let end = std::cmp::min(start + BANK_SIZE, self.banks.len());
+        let window = &mut self.memory[WINDOW_START..];
+        window.fill(0);
+        window[..end - start].copy_from_slice(&self.banks[start..end]);
+        self.bank = bank;
+    }
+
+    // process the next opcode. If it faults nothing changes and the program
+    // counter stays on it
+    pub fn cycle(&mut self) -> Result<(), Chip8Error> {
+        self.process_opcode()
+    }
+
+    // process the next opcode and return it, for tools that follow the
+    // machine one opcode at a time
+    pub fn step(&mut self) -> Result<u16, Chip8Error> {
+        let opcode = self.current_opcode();
+        self.process_opcode()?;
+        Ok(opcode)
+    }
+
+    // decrement the timers, this should be called at 60hz
+    pub fn update_timers(&mut self) {
+        self.dec_delay_timer();
+        self.dec_sound_timer();
+    }
+
+    pub fn display(&self) -> &Display {
+        &self.display
+    }
+
+    pub fn display_mut(&mut self) -> &mut Display {
+        &mut self.display
+    }
+
+    // set the state of every key on the keypad
+    pub fn set_keys(&mut self, keys: [bool; KEY_COUNT]) {
+        self.keys = keys;
+    }
+
+    // the keys held on the keypad
+    pub fn keys(&self) -> [bool; KEY_COUNT] {
+        self.keys
+    }
+
+    // the opcode at the program counter, this is the next one to be processed
+    pub fn current_opcode(&self) -> u16 {
+        // we do some weird shit to deal with endian-ness
+        let op1 = self.memory[self.pc] as u16;
+        let op2 = self.memory[(self.pc + 1) % MEM_SIZE] as u16;
+        (op1 << 8) | op2
+    }
+
+    // write a byte into memory, addresses wrap around the end of memory
+    pub fn write_memory(&mut self, addr: usize, val: u8) {
+        self.memory[addr % MEM_SIZE] = val;
+    }
+
+    pub fn set_register(&mut self, index: usize, val: u8) {
+        self.registers[index] = Wrapping(val);
+    }
+
+    // I only ever points inside memory, like the program counter
+    pub fn set_i(&mut self, i: usize) {
+        self.i = i % MEM_SIZE;
+    }
+
+    // move the program counter, addresses wrap around the end of memory
+    pub fn set_pc(&mut self, pc: usize) {
+        self.pc = pc % MEM_SIZE;
+    }
+
+    pub fn set_delay_timer(&mut self, val: u8) {
+        self.delay_timer = val;
+    }
+
+    pub fn set_sound_timer(&mut self, val: u8) {
+        self.sound_timer = val;
+    }
+
+    // the V0 to VF registers
+    pub fn registers(&self) -> [u8; REGISTERS_SIZE] {
+        let mut registers = [0; REGISTERS_SIZE];
+        for (register, value) in registers.iter_mut().zip(self.registers.iter()) {
+            *register = value.0;
+        }
+        registers
+    }
+
+    // all of memory, from the font at 0 to the end of the program area
+    pub fn memory(&self) -> &[u8] {
+        &self.memory
+    }
+
+    // the pixels of the display row by row, 1 for lit and 0 for dark. The
+    // rows are display().get_width() pixels long
+    pub fn framebuffer(&self) -> &[u8] {
+        self.display.pixels()
+    }
+
+    // the return addresses of the subroutines that have been called
+    pub fn stack(&self) -> &[usize] {
+        &self.stack
+    }
+
+    // save the machine state, the keys and quirks are not part of it
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            memory: self.memory.to_vec(),
+            registers: self.registers(),
+            stack: self.stack.clone(),
+            sp: self.sp,
+            pc: self.pc,
+            i: self.i,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            pitch: self.pitch,
+            rng: self.rng.clone(),
+            hires: self.display.is_hires(),
+            pixels: self.display.pack(),
+            bank: self.bank,
+        }
+    }
+
+    // go back to a saved machine state
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.memory.copy_from_slice(&snapshot.memory);
+        for (index, register) in snapshot.registers.iter().enumerate() {
+            self.registers[index] = Wrapping(*register);
+        }
+        self.stack = snapshot.stack.clone();
+        self.sp = snapshot.sp;
+        self.pc = snapshot.pc;
+        self.i = snapshot.i;
+        self.delay_timer = snapshot.delay_timer;
+        self.sound_timer = snapshot.sound_timer;
+        self.pitch = snapshot.pitch;
+        self.rng = snapshot.rng.clone();
+        self.display.unpack(snapshot.hires, &snapshot.pixels);
+        self.bank = snapshot.bank;
+    }
+
+    // a crc32 of the memory, registers, timers and display, to check two
+    // runs are in step without comparing whole snapshots
+    pub fn checksum(&self) -> u32 {
+        let mut state = self.memory.to_vec();
+        state.extend(self.registers());
+        state.extend(
+            self.stack[..self.sp]
+                .iter()
+                .flat_map(|addr| addr.to_le_bytes()),
+        );
+        state.extend(self.pc.to_le_bytes());
+        state.extend(self.i.to_le_bytes());
+        state.extend([self.delay_timer, self.sound_timer]);
+        state.extend(self.display.pack());
+        state.extend(self.bank().map(|bank| bank as u8));
+        romdb::crc32(&state)
+    }
+
+    // the machine state as bytes for a save state file, with the seed and
+    // position of the random numbers so they play out the same after loading
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&self.memory);
+        data.extend(self.registers());
+        data.push(self.stack.len() as u8);
+        for addr in &self.stack {
+            data.extend((*addr as u16).to_le_bytes());
+        }
+        data.extend((self.pc as u16).to_le_bytes());
+        data.extend((self.i as u16).to_le_bytes());
+        data.extend([self.delay_timer, self.sound_timer]);
+        data.extend([self.pitch.is_some() as u8, self.pitch.unwrap_or(0)]);
+        data.extend(self.rng.get_seed());
+        data.extend(self.rng.get_word_pos().to_le_bytes());
+        data.push(self.display.is_hires() as u8);
+        data.extend(self.display.pack());
+        data.extend(self.bank().map(|bank| bank as u8));
+        data
+    }
+
+    // go back to a machine state written by save_state, None if the data is
+    // cut short or isn't a machine state. Nothing changes unless it loads. A
+    // state saved with banking ends with the bank, which has to be in the rom
+    // if this machine has banking too
+    pub fn load_state(&mut self, data: &[u8]) -> Option<()> {
+        self.load_state_version(data, STATE_VERSION)
+    }
+
+    // load_state for the states of older versions, version 1 has a seed the
+    // random numbers start again from in place of their exact position
+    pub fn load_state_version(&mut self, data: &[u8], version: u32) -> Option<()> {
+        let mut data = data;
+        let mut take = |count: usize| {
+            let (taken, rest) = (data.get(..count)?, data.get(count..)?);
+            data = rest;
+            Some(taken)
+        };
+        let memory = take(MEM_SIZE)?.to_vec();
+        let registers = take(REGISTERS_SIZE)?.to_vec();
+        let depth = take(1)?[0] as usize;
+        if depth >= STACK_SLOTS {
+            return None;
+        }
+        let mut stack = Vec::with_capacity(depth);
+        for _ in 0..depth {
+            let addr = take(2)?;
+            stack.push(u16::from_le_bytes([addr[0], addr[1]]) as usize % MEM_SIZE);
+        }
+        let mut word = || take(2).map(|word| u16::from_le_bytes([word[0], word[1]]) as usize);
+        let (pc, i) = (word()? % MEM_SIZE, word()? % MEM_SIZE);
+        let timers = take(2)?;
+        let pitch = take(2)?;
+        let rng = if version == 1 {
+            ChaCha12Rng::seed_from_u64(u64::from_le_bytes(take(8)?.try_into().ok()?))
+        } else {
+            let mut rng = ChaCha12Rng::from_seed(take(32)?.try_into().ok()?);
+            rng.set_word_pos(u128::from_le_bytes(take(16)?.try_into().ok()?));
+            rng
+        };
+        let hires = take(1)?[0] == 1;
+        let size = if hires {
+            HIRES_DISPLAY_SIZE
+        } else {
+            DISPLAY_SIZE
+        };
+        let pixels = take(size / 8)?;
+        let bank = match data {
+            [] => 0,
+            [bank] => *bank as usize,
+            _ => return None,
+        };
+        if self.extensions.banking && bank >= self.bank_count() {
+            return None;
+        }
+
+        self.memory.copy_from_slice(&memory);
+        for (index, register) in registers.iter().enumerate() {
+            self.registers[index] = Wrapping(*register);
+        }
+        self.sp = stack.len();
+        self.stack = stack;
+        self.pc = pc;
+        self.i = i;
+        self.delay_timer = timers[0];
+        self.sound_timer = timers[1];
+        self.pitch = Some(pitch[1]).filter(|_| pitch[0] == 1);
+        self.rng = rng;
+        self.display.unpack(hires, pixels);
+        self.bank = bank;
+        Some(())
+    }
+
+    // make CXNN give the same numbers each run for the same seed
+    pub fn set_seed(&mut self, seed: u64) {
+        self.rng = ChaCha12Rng::seed_from_u64(seed);
+    }
+
+    pub fn quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    pub fn set_quirks(&mut self, quirks: Quirks) {
+        self.quirks = quirks;
+    }
+
+    pub fn extensions(&self) -> Extensions {
+        self.extensions
+    }
+
+    pub fn set_extensions(&mut self, extensions: Extensions) {
+        self.extensions = extensions;
+    }
+
+    // the xo-chip pitch register, None if the rom hasn't set it
+    pub fn pitch(&self) -> Option<u8> {
+        self.pitch
+    }
+
+    // the buzzer sounds while the sound timer is not 0
+    pub fn is_beeping(&self) -> bool {
+        self.sound_timer > 0
+    }
+
+    fn process_opcode(&mut self) -> Result<(), Chip8Error> {
+        let pc = self.pc;
+        let opcode = self.current_opcode();
+
+        self.inc_pc();
+
+        match Instruction::decode(opcode) {
+            Instruction::Sys(nnn) => self.call_machine_code_routine(nnn),
+            Instruction::Cls => self.disp_clear(),
+            Instruction::Ret => self.flow_return(),
+            Instruction::Scd(n) => self.disp_scroll_down(n),
+            Instruction::Scr => self.disp_scroll_right(),
+            Instruction::Scl => self.disp_scroll_left(),
+            Instruction::Exit => self.flow_exit(),
+            Instruction::Low => self.disp_set_hires(false),
+            Instruction::High => self.disp_set_hires(true),
+            Instruction::Jp(nnn) => self.flow_goto(nnn),
+            Instruction::Call(nnn) => self.flow_call_subroutine(nnn),
+            Instruction::SeVxNn(x, nn) => self.cond_if_vx_nn_eq_skip(x, nn),
+            Instruction::SneVxNn(x, nn) => self.cond_if_vx_nn_neq_skip(x, nn),
+            Instruction::SeVxVy(x, y) => self.cond_if_vx_vy_eq_skip(x, y),
+            Instruction::LdVxNn(x, nn) => self.const_set_vx_nn(x, nn),
+            Instruction::AddVxNn(x, nn) => self.const_set_add_vx_nn(x, nn),
+            Instruction::LdVxVy(x, y) => self.assig_vx_to_vy(x, y),
+            Instruction::Or(x, y) => self.bitop_vx_oreq_vy(x, y),
+            Instruction::And(x, y) => self.bitop_vx_andeq_vy(x, y),
+            Instruction::Xor(x, y) => self.bitop_vx_xoreq_vy(x, y),
+            Instruction::AddVxVy(x, y) => self.math_vx_pleq_vy(x, y),
+            Instruction::Sub(x, y) => self.math_vx_mieq_vy(x, y),
+            Instruction::Shr(x, y) => self.bitop_vx_rsh(x, y),
+            Instruction::Subn(x, y) => self.math_vx_eq_vy_mi_vx(x, y),
+            Instruction::Shl(x, y) => self.bitop_vx_lsh(x, y),
+            Instruction::SneVxVy(x, y) => self.cond_if_vx_vy_neq_skip(x, y),
+            Instruction::LdI(nnn) => self.mem_set_i(nnn),
+            Instruction::JpV0(nnn) => self.flow_jump_v0_pl(nnn),
+            Instruction::Rnd(x, nn) => self.rand_vx_rand_and_nn(x, nn),
+            Instruction::Drw(x, y, n) => self.display_draw(x, y, n),
+            Instruction::Skp(x) => self.keyop_if_vx_pressed_skip(x),
+            Instruction::Sknp(x) => self.keyop_if_vx_not_pressed_skip(x),
+            Instruction::LdVxDt(x) => self.timer_set_vx_delay(x),
+            Instruction::LdVxK(x) => self.keyop_vx_set_key(x),
+            Instruction::LdDtVx(x) => self.timer_set_delay_vx(x),
+            Instruction::LdStVx(x) => self.sound_set_timer_vx(x),
+            Instruction::AddIVx(x) => self.mem_i_pleq_vx(x),
+            Instruction::LdFVx(x) => self.mem_set_i_sprite_addr_vx(x),
+            Instruction::LdHfVx(x) => self.mem_set_i_big_sprite_addr_vx(x),
+            Instruction::LdBVx(x) => self.bcd_set_i_vx(x),
+            Instruction::LdPitchVx(x) => self.sound_set_pitch_vx(x),
+            Instruction::LdBankVx(x) => self.mem_set_bank_vx(x),
+            Instruction::LdIVx(x) => self.mem_reg_dump(x),
+            Instruction::LdVxI(x) => self.mem_reg_load(x),
+            Instruction::Invalid(_) => self.invalid_opcode(),
+        }
+
+        match self.fault.take() {
+            Some(fault) => {
+                self.pc = pc;
+                Err(fault)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn clear_keys(&mut self) {
+        self.keys = [false; KEY_COUNT];
+    }
+
+    fn get_key_press(&self) -> Option<u8> {
+        self.keys
+            .iter()
+            .position(|pressed| *pressed)
+            .map(|key| key as u8)
+    }
+
+    // the address of the opcode being processed, the program counter has
+    // already moved past it
+    fn opcode_pc(&self) -> usize {
+        (self.pc + MEM_SIZE - 2) % MEM_SIZE
+    }
+
+    fn invalid_opcode(&mut self) {
+        let pc = self.opcode_pc();
+        let opcode = (self.memory[pc] as u16) << 8 | self.memory[(pc + 1) % MEM_SIZE] as u16;
+        self.fault = Some(Chip8Error::InvalidOpcode { pc, opcode });
+    }
+
+    // decrement the delay timer if delay timer is not 0
+    fn dec_delay_timer(&mut self) {
+        if self.delay_timer != 0 {
+            self.delay_timer -= 1;
+        }
+    }
+
+    // decrement the sound timer if sounds timer is not 0
+    fn dec_sound_timer(&mut self) {
+        if self.sound_timer != 0 {
+            self.sound_timer -= 1;
+        }
+    }
+
+    // go the next instruction - as instructions are 2 bytes long that means
+    // moving the program counter along by 2
+    fn inc_pc(&mut self) {
+        self.pc = (self.pc + 2) % MEM_SIZE;
+    }
+
+    // go to the previous instruction - as instruction are 2 bytes long that
+    // means moving theh program counter back by 2
+    fn dec_pc(&mut self) {
+        self.pc = (self.pc + MEM_SIZE - 2) % MEM_SIZE;
+    }
+
+    fn cond_inc_pc(&mut self, val: bool) {
+        if val {
+            self.inc_pc();
+        }
+    }
+
+    // add rhs to lhs
+    // if lhs + rhs > u8::max then set VF to 1 else set VF to 0
+    fn add_with_carry(&mut self, lhs: Wrapping<u8>, rhs: Wrapping<u8>) -> Wrapping<u8> {
+        let max = Wrapping(u8::MAX);
+        self.set_carry((max - lhs) < rhs);
+        lhs + rhs
+    }
+
+    // subtract rhs from lhs.
+    // if lhs < rhs then set VF to 1 else set VF to 0
+    fn subtract_with_borrow(&mut self, lhs: Wrapping<u8>, rhs: Wrapping<u8>) -> Wrapping<u8> {
+        self.set_carry(lhs >= rhs);
+        lhs - rhs
+    }
+
+    // if val == true set VF=1 else VF=0
+    fn set_carry(&mut self, val: bool) {
+        if val {
+            self.registers[0xF] = Wrapping(1);
+        } else {
+            self.registers[0xF] = Wrapping(0);
+        }
+    }
+
+    // the register that is shifted, the original interpreter shifted VY
+    fn shift_source(&self, vxindex: usize, vyindex: usize) -> Wrapping<u8> {
+        if self.quirks.shift_vy {
+            self.registers[vyindex]
+        } else {
+            self.registers[vxindex]
+        }
+    }
+
+    // the original interpreter left VF at 0 after a logic opcode
+    fn quirk_vf_reset(&mut self) {
+        if self.quirks.vf_reset {
+            self.registers[0xF] = Wrapping(0);
+        }
+    }
+
+    // the original interpreter incremented I as it stored or loaded registers
+    fn quirk_increment_i(&mut self, vxindex: usize) {
+        if self.quirks.load_store_increment_i {
+            self.i = (self.i + vxindex + 1) % MEM_SIZE;
+        }
+    }
+
+    // push the 12 bit memory address to the stack and increment the
+    // stack pointer
+    // if no more space on the stack then fault
+    fn push_stack(&mut self, addr: usize) {
+        if self.sp == STACK_SLOTS - 1 {
+            self.fault = Some(Chip8Error::StackOverflow {
+                pc: self.opcode_pc(),
+            });
+            return;
+        }
+
+        self.stack.push(addr);
+        self.sp += 1;
+    }
+
+    // pop the 12 bit memory address from the stack and decrement the stack
+    // pointer
+    // if nothing is on the stack then fault
+    fn pop_stack(&mut self) -> usize {
+        match self.stack.pop() {
+            Some(addr) => {
+                self.sp -= 1;
+                addr
+            }
+            None => {
+                self.fault = Some(Chip8Error::StackUnderflow {
+                    pc: self.opcode_pc(),
+                });
+                self.pc
+            }
+        }
+    }
+
+    // xor the pixel at the coordinate
+    // return true if pixel was set from 1 to 0 (collision)
+    fn xor_display_pixel(&mut self, x: u8, y: u8, val: u8) -> bool {
+        let pixel_bit_cur = self.display.get_pixel(x, y);
+        self.display.set_pixel(x, y, val);
+
+        // collision happened
+        (val == 1) && pixel_bit_cur == 1
+    }
+
+    // xor the row of pixels starting at coordinate x,y with the first width
+    // pixels defined in row_val, starting from the top bit
+    // return true if any pixel in the row was set from 1 to 0 (collision)
+    fn xor_display_row(&mut self, x: usize, y: usize, row_val: u16, width: usize) -> bool {
+        let mut output = false;
+        for i in 0..width {
+            // if wrap to other side of screen happens then skip, unless the
+            // wrap quirk is on
+            let mut xpixel = x + i;
+            if self.quirks.wrap_sprites {
+                xpixel %= self.display.get_width();
+            } else if xpixel >= self.display.get_width() {
+                break;
+            }
+            if self.xor_display_pixel(xpixel as u8, y as u8, ((row_val >> (15 - i)) & 0x1) as u8) {
+                output = true;
+            }
+        }
+        output
+    }
+
+    // functions to process opcodes
+    // Call machine code routine at addres NNN
+    // Op code: 0NNN
+    fn call_machine_code_routine(&mut self, addr: usize) {
+        self.fault = Some(Chip8Error::MachineCode {
+            pc: self.opcode_pc(),
+            addr,
+        });
+    }
+
+    // Clear the screen
+    // Op code: 00E0
+    fn disp_clear(&mut self) {
+        self.display.clear();
+    }
+
+    // Scroll the display down by N pixels
+    // Op code: 00CN
+    fn disp_scroll_down(&mut self, n: u8) {
+        self.display.scroll_down(n as usize);
+    }
+
+    // Scroll the display right by 4 pixels
+    // Op code: 00FB
+    fn disp_scroll_right(&mut self) {
+        self.display.scroll_right(4);
+    }
+
+    // Scroll the display left by 4 pixels
+    // Op code: 00FC
+    fn disp_scroll_left(&mut self) {
+        self.display.scroll_left(4);
+    }
+
+    // Stop the program, the program counter stays on this opcode
+    // Op code: 00FD
+    fn flow_exit(&mut self) {
+        self.dec_pc();
+    }
+
+    // Switch between the low and high resolution display
+    // Op code: 00FE, 00FF
+    fn disp_set_hires(&mut self, hires: bool) {
+        self.display.set_hires(hires);
+    }
+
+    // return from a subroutine
+    // Op code: 00EE
+    fn flow_return(&mut self) {
+        self.pc = self.pop_stack();
+    }
+
+    // Jump to the addr at NNN
+    // Op code: 1NNN
+    fn flow_goto(&mut self, addr: usize) {
+        self.pc = addr;
+    }
+
+    // Call subroutine at NNN
+    // Op code: 2NNN
+    fn flow_call_subroutine(&mut self, addr: usize) {
+        self.push_stack(self.pc);
+        self.pc = addr;
+    }
+
+    // Skip the next instruction if VX eq NN
+    // Op code: 3XNN
+    fn cond_if_vx_nn_eq_skip(&mut self, vxindex: usize, val: u8) {
+        self.cond_inc_pc(self.registers[vxindex].0 == val);
+    }
+
+    // Skip the next instruction if VX neq NN
+    // Op code: 4XNN
+    fn cond_if_vx_nn_neq_skip(&mut self, vxindex: usize, val: u8) {
+        self.cond_inc_pc(self.registers[vxindex].0 != val);
+    }
+
+    // Skip the next instruction if VX eq VY
+    // Op code: 5XY0
+    fn cond_if_vx_vy_eq_skip(&mut self, vxindex: usize, vyindex: usize) {
+        self.cond_inc_pc(self.registers[vxindex].0 == self.registers[vyindex].0);
+    }
+
+    // Set VX to NN
+    // Op code: 6XNN
+    fn const_set_vx_nn(&mut self, vxindex: usize, val: u8) {
+        self.registers[vxindex] = Wrapping(val);
+    }
+
+    // Add NN to VX (Carry flag not changed)
+    // Op code: 7XNN
+    fn const_set_add_vx_nn(&mut self, vxindex: usize, val: u8) {
+        self.registers[vxindex] += Wrapping(val);
+    }
+
+    // Assign VY to VX
+    // Op code: 8XY0
+    fn assig_vx_to_vy(&mut self, vxindex: usize, vyindex: usize) {
+        self.registers[vxindex] = self.registers[vyindex];
+    }
+
+    // Set VX to VX or VY
+    // Op code: 8XY1
+    fn bitop_vx_oreq_vy(&mut self, vxindex: usize, vyindex: usize) {
+        self.registers[vxindex] |= self.registers[vyindex];
+        self.quirk_vf_reset();
+    }
+
+    // Set VX to VX and VY
+    // Op code: 8XY2
+    fn bitop_vx_andeq_vy(&mut self, vxindex: usize, vyindex: usize) {
+        self.registers[vxindex] &= self.registers[vyindex];
+        self.quirk_vf_reset();
+    }
+
+    // Set VX to VX xor VY
+    // Op code: 8XY3
+    fn bitop_vx_xoreq_vy(&mut self, vxindex: usize, vyindex: usize) {
+        self.registers[vxindex] ^= self.registers[vyindex];
+        self.quirk_vf_reset();
+    }
+
+    // Set VX to VX plus VY
+    // Op code: 8XY4
+    fn math_vx_pleq_vy(&mut self, vxindex: usize, vyindex: usize) {
+        self.registers[vxindex] =
+            self.add_with_carry(self.registers[vxindex], self.registers[vyindex]);
+    }
+
+    // Set VX to VX minus VY
+    // Op code: 8XY5
+    fn math_vx_mieq_vy(&mut self, vxindex: usize, vyindex: usize) {
+        self.registers[vxindex] =
+            self.subtract_with_borrow(self.registers[vxindex], self.registers[vyindex]);
+    }
+
+    // Store least significant bit of VX in VF then right shift VX
+    // Op code: 8XY6
+    fn bitop_vx_rsh(&mut self, vxindex: usize, vyindex: usize) {
+        let val = self.shift_source(vxindex, vyindex);
+        self.registers[vxindex] = val >> 1;
+        self.registers[0xF] = Wrapping(val.0 & 0x1);
+    }
+
+    // Set VX to VY minus VX
+    // Op code: 8XY7
+    fn math_vx_eq_vy_mi_vx(&mut self, vxindex: usize, vyindex: usize) {
+        self.registers[vxindex] =
+            self.subtract_with_borrow(self.registers[vyindex], self.registers[vxindex]);
+    }
+
+    // Store most significant bit of VX in VF then left shift VX
+    // Op code: 8XYE
+    fn bitop_vx_lsh(&mut self, vxindex: usize, vyindex: usize) {
+        let val = self.shift_source(vxindex, vyindex);
+        self.registers[vxindex] = val << 1;
+        self.registers[0xF] = Wrapping((val.0 >> 7) & 0x1);
+    }
+
+    // Skip the next instruction if VX neq VY
+    // Op code: 9XY0
+    fn cond_if_vx_vy_neq_skip(&mut self, vxindex: usize, vyindex: usize) {
+        self.cond_inc_pc(self.registers[vxindex] != self.registers[vyindex]);
+    }
+
+    // Set I to NNN
+    // Op code: ANNN
+    fn mem_set_i(&mut self, addr: usize) {
+        self.i = addr;
+    }
+
+    // Jump to the address V0 + NNN
+    // Op code: BNNN
+    fn flow_jump_v0_pl(&mut self, addr: usize) {
+        // with the jump quirk the X in BXNN picks the register instead of V0
+        let vindex = if self.quirks.jump_vx { addr >> 8 } else { 0 };
+        self.pc = ((self.registers[vindex].0 as usize) + addr) % MEM_SIZE;
+    }
+
+    // Set VX to rand() and NN
+    // Op code: CXNN
+    fn rand_vx_rand_and_nn(&mut self, vxindex: usize, val: u8) {
+        let random_val: u8 = self.rng.gen();
+        self.registers[vxindex] = Wrapping(random_val & val);
+    }
+
+    // Draw a sprite at coordinate VX, VY with width 8: height: N
+    // Pixels are read from memory location I. I remains unchanged
+    // A height of 0 draws a 16x16 super chip sprite of 2 bytes per row
+    // The start coordinate wraps around the display, the sprite is clipped
+    // at the edges unless the wrap quirk is on
+    // VF set to one if any screen pixels are unset due to xor or 0 if not. In
+    // high resolution VF is the number of rows that collided or were clipped
+    // off the bottom of the display
+    // Op code: DXYN
+    fn display_draw(&mut self, vxindex: usize, vyindex: usize, height: u8) {
+        let vx = self.registers[vxindex].0 as usize % self.display.get_width();
+        let vy = self.registers[vyindex].0 as usize % self.display.get_height();
+        let (rows, width) = if height == 0 {
+            (16, 16)
+        } else {
+            (height as usize, 8)
+        };
+
+        let mut collided_rows = 0;
+        let mut clipped_rows = 0;
+        for i in 0..rows {
+            let mut row_index = vy + i;
+            if self.quirks.wrap_sprites {
+                row_index %= self.display.get_height();
+            } else if row_index >= self.display.get_height() {
+                clipped_rows = rows - i;
+                break;
+            }
+
+            let row_val = if width == 16 {
+                let high = self.memory[(self.i + 2 * i) % MEM_SIZE] as u16;
+                let low = self.memory[(self.i + 2 * i + 1) % MEM_SIZE] as u16;
+                (high << 8) | low
+            } else {
+                (self.memory[(self.i + i) % MEM_SIZE] as u16) << 8
+            };
+            if self.xor_display_row(vx, row_index, row_val, width) {
+                collided_rows += 1;
+            }
+        }
+
+        if self.display.is_hires() {
+            self.registers[0xF] = Wrapping((collided_rows + clipped_rows) as u8);
+        } else {
+            self.set_carry(collided_rows > 0);
+        }
+    }
+
+    // Skip the next instruction if key at VX is pressed, only the low 4 bits
+    // of VX pick the key
+    // Op code: EX9E
+    fn keyop_if_vx_pressed_skip(&mut self, vxindex: usize) {
+        let vx = self.registers[vxindex];
+        let key_pressed = self.keys[(vx.0 & 0xF) as usize];
+
+        if key_pressed {
+            self.inc_pc();
+        }
+    }
+
+    // Skip the next is instruction if key at VX is not pressed
+    // Op code: EXA1
+    fn keyop_if_vx_not_pressed_skip(&mut self, vxindex: usize) {
+        let vx = self.registers[vxindex];
+        let key_pressed = self.keys[(vx.0 & 0xF) as usize];
+
+        if !key_pressed {
+            self.inc_pc();
+        }
+    }
+
+    // Set VX to the value of the delay timer
+    // Op code: FX07
+    fn timer_set_vx_delay(&mut self, vxindex: usize) {
+        self.registers[vxindex] = Wrapping(self.delay_timer);
+    }
+
+    // Set VX to next key press - blocking operation until key is pressed
+    // Op code: FX0A
+    fn keyop_vx_set_key(&mut self, vxindex: usize) {
+        match self.get_key_press() {
+            Some(key) => {
+                self.registers[vxindex] = Wrapping(key);
+                self.clear_keys();
+            }
+            None => {
+                self.dec_pc();
+            }
+        }
+    }
+
+    // Set the delay timer to VX
+    // Op code: FX15
+    fn timer_set_delay_vx(&mut self, vxindex: usize) {
+        self.delay_timer = self.registers[vxindex].0;
+    }
+
+    // Set the sound timer to VX
+    // Op code: FX18
+    fn sound_set_timer_vx(&mut self, vxindex: usize) {
+        self.sound_timer = self.registers[vxindex].0;
+    }
+
+    // Set the pitch of the buzzer to VX
+    // Op code: FX3A
+    fn sound_set_pitch_vx(&mut self, vxindex: usize) {
+        self.pitch = Some(self.registers[vxindex].0);
+    }
+
+    // Map bank VX of the rom into memory from 0x800, with the banking
+    // extension. Anything written to the window before is lost
+    // Op code: FXB0
+    fn mem_set_bank_vx(&mut self, vxindex: usize) {
+        if !self.extensions.banking {
+            return self.invalid_opcode();
+        }
+        let bank = self.registers[vxindex].0 as usize;
+        if bank >= self.bank_count() {
+            self.fault = Some(Chip8Error::BankOutOfRange {
+                pc: self.opcode_pc(),
+                bank,
+            });
+            return;
+        }
+        self.map_bank(bank);
+    }
+
+    // Add VX to I. VF is not affected
+    // Op code: FX1E
+    fn mem_i_pleq_vx(&mut self, vxindex: usize) {
+        self.i += self.registers[vxindex].0 as usize;
+    }
+
+    // Set I to the location of the sprite for the character in VX
+    // Op code: FX29
+    fn mem_set_i_sprite_addr_vx(&mut self, vxindex: usize) {
+        let vx = self.registers[vxindex].0;
+        self.i = FONT_START + (FONT_CHAR_SIZE * vx as usize);
+    }
+
+    // Set I to the location of the 8x10 sprite for the character in VX, for
+    // drawing with DXYA
+    // Op code: FX30
+    fn mem_set_i_big_sprite_addr_vx(&mut self, vxindex: usize) {
+        let vx = self.registers[vxindex].0;
+        self.i = BIG_FONT_START + (BIG_FONT_CHAR_SIZE * vx as usize);
+    }
+
+    // Store the binary-coded decimal repsentation of VX to the location at I
+    // *(I+0) = BCD(3) -> VX hundreds
+    // *(I+1) = BCD(2) -> VX tens
+    // *(I+2) = BCD(1) -> VX ones
+    // Op code: FX33
+    fn bcd_set_i_vx(&mut self, vxindex: usize) {
+        let mut vx = self.registers[vxindex].0;
+
+        for i in (0..3).rev() {
+            self.memory[(self.i + i) % MEM_SIZE] = vx % 10;
+            vx /= 10;
+        }
+    }
+
+    // Store from V0 to VX to memory starting at I. I remains unchanged
+    // Op code: FX55
+    fn mem_reg_dump(&mut self, vxindex: usize) {
+        for i in 0..vxindex + 1 {
+            self.memory[(self.i + i) % MEM_SIZE] = self.registers[i].0;
+        }
+        self.quirk_increment_i(vxindex);
+    }
+
+    // Load from I to V0 through VX. I remains unchaged
+    // Op code: FX65
+    fn mem_reg_load(&mut self, vxindex: usize) {
+        for i in 0..vxindex + 1 {
+            self.registers[i] = Wrapping(self.memory[(self.i + i) % MEM_SIZE]);
+        }
+        self.quirk_increment_i(vxindex);
+    }
+}
+
+impl MachineView for Interpreter {
+    fn pc(&self) -> usize {
+        self.pc
+    }
+
+    fn i(&self) -> usize {
+        self.i
+    }
+
+    fn sp(&self) -> usize {
+        self.sp
+    }
+
+    fn register(&self, index: usize) -> u8 {
+        self.registers[index].0
+    }
+
+    fn delay_timer(&self) -> u8 {
+        self.delay_timer
+    }
+
+    fn sound_timer(&self) -> u8 {
+        self.sound_timer
+    }
+
+    fn read_memory(&self, addr: usize) -> u8 {
+        self.memory[addr % MEM_SIZE]
+    }
+
+    fn bank(&self) -> Option<usize> {
+        Interpreter::bank(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a machine with the rom loaded and only the given key held
+    fn holding(rom: &[u8], key: usize) -> Interpreter {
+        let mut interp = Interpreter::new();
+        interp.load_rom(rom).unwrap();
+        let mut keys = [false; KEY_COUNT];
+        keys[key] = true;
+        interp.set_keys(keys);
+        interp
+    }
+
+    #[test]
+    fn a_held_key_is_seen_by_every_skip_in_a_frame() {
+        // V0 = 5, then EX9E on it twice, each skipping a jump back to the
+        // start while key 5 is held
+        let rom = [0x60, 0x05, 0xE0, 0x9E, 0x12, 0x00, 0xE0, 0x9E, 0x12, 0x00];
+        let mut interp = holding(&rom, 5);
+        for _ in 0..3 {
+            interp.step().unwrap();
+        }
+        assert_eq!(interp.pc(), 0x20A);
+    }
+
+    #[test]
+    fn a_held_key_is_seen_by_every_not_pressed_skip_in_a_frame() {
+        // EXA1 doesn't skip a held key, the jumps after it to the end run
+        let rom = [0x60, 0x05, 0xE0, 0xA1, 0x12, 0x06, 0xE0, 0xA1, 0x12, 0x0A];
+        let mut interp = holding(&rom, 5);
+        for _ in 0..5 {
+            interp.step().unwrap();
+        }
+        assert_eq!(interp.pc(), 0x20A);
+    }
+
+    #[test]
+    fn waiting_for_a_key_lets_go_of_the_keys() {
+        // FX0A takes the key held and lets go so the next FX0A waits again
+        let rom = [0xF0, 0x0A, 0xF1, 0x0A];
+        let mut interp = holding(&rom, 7);
+        interp.step().unwrap();
+        assert_eq!(interp.registers()[0], 7);
+        interp.step().unwrap();
+        assert_eq!(interp.pc(), 0x202);
+    }
+
+    #[test]
+    fn setting_i_past_memory_wraps_like_the_program_counter() {
+        let mut interp = Interpreter::new();
+        interp.set_i(MEM_SIZE + 0x123);
+        interp.set_pc(MEM_SIZE + 0x123);
+        assert_eq!(interp.i(), 0x123);
+        assert_eq!(interp.pc(), 0x123);
+    }
+
+    // a machine part way through a rom, in a subroutine with a random number
+    // drawn, the delay timer running and I set
+    fn part_way() -> Interpreter {
+        let rom = [
+            0x60, 0x12, 0xA3, 0x45, 0xC1, 0xFF, 0x22, 0x0A, 0x12, 0x08, 0xF0, 0x15, 0x12, 0x0C,
+        ];
+        let mut interp = Interpreter::new();
+        interp.set_seed(7);
+        interp.load_rom(&rom).unwrap();
+        for _ in 0..5 {
+            interp.step().unwrap();
+        }
+        interp
+    }
+
+    #[test]
+    fn a_saved_state_loads_back() {
+        let interp = part_way();
+        let mut loaded = Interpreter::new();
+        assert_eq!(loaded.load_state(&interp.save_state()), Some(()));
+        assert_eq!(loaded.snapshot(), interp.snapshot());
+    }
+
+    #[test]
+    fn a_version_1_state_starts_the_random_numbers_from_its_seed() {
+        let interp = part_way();
+        // version 1 has a seed of 8 bytes where version 2 has the 48 bytes
+        // of the random numbers' position, after the memory, registers, a
+        // stack of one, the pc, I, the timers and the pitch
+        let at = MEM_SIZE + REGISTERS_SIZE + 3 + 8;
+        let mut data = interp.save_state();
+        data.splice(at..at + 48, 42u64.to_le_bytes());
+
+        let mut loaded = Interpreter::new();
+        assert_eq!(loaded.load_state_version(&data, 1), Some(()));
+        let mut expected = part_way();
+        expected.set_seed(42);
+        assert_eq!(loaded.snapshot(), expected.snapshot());
+    }
+
+    #[test]
+    fn a_bad_state_changes_nothing() {
+        let interp = part_way();
+        let data = interp.save_state();
+        let mut loaded = Interpreter::new();
+        let before = loaded.snapshot();
+        assert_eq!(loaded.load_state(&data[..data.len() - 1]), None);
+        assert_eq!(
+            loaded.load_state(&[data.as_slice(), &[0, 0]].concat()),
+            None
+        );
+        // a version 2 state is too long for version 1
+        assert_eq!(loaded.load_state_version(&data, 1), None);
+        assert_eq!(loaded.snapshot(), before);
+    }
+}
@@ -0,0 +1,120 @@
+// a json value, enough to write reports and stats without another dependency
+pub enum Json {
+    Null,
+    Num(u64),
+    Str(String),
+    Bool(bool),
+    List(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn strings(items: &[&str]) -> Json {
+        Json::List(
+            items
+                .iter()
+                .map(|item| Json::Str(item.to_string()))
+                .collect(),
+        )
+    }
+
+    // the value with each item of a list or object on its own line
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        self.write(0, &mut out);
+        out
+    }
+
+    // the value on a single line, e.g. for a file of json lines
+    pub fn line(&self) -> String {
+        let mut out = String::new();
+        self.write_line(&mut out);
+        out
+    }
+
+    fn write(&self, indent: usize, out: &mut String) {
+        let pad = "  ".repeat(indent + 1);
+        match self {
+            Json::List(items) if !items.is_empty() => {
+                out.push_str("[\n");
+                for (index, item) in items.iter().enumerate() {
+                    out.push_str(&pad);
+                    item.write(indent + 1, out);
+                    out.push_str(if index + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                out.push_str(&"  ".repeat(indent));
+                out.push(']');
+            }
+            Json::Object(fields) => {
+                out.push_str("{\n");
+                for (index, (name, value)) in fields.iter().enumerate() {
+                    out.push_str(&pad);
+                    write_str(name, out);
+                    out.push_str(": ");
+                    value.write(indent + 1, out);
+                    out.push_str(if index + 1 < fields.len() {
+                        ",\n"
+                    } else {
+                        "\n"
+                    });
+                }
+                out.push_str(&"  ".repeat(indent));
+                out.push('}');
+            }
+            value => value.write_line(out),
+        }
+    }
+
+    fn write_line(&self, out: &mut String) {
+        match self {
+            Json::Null => out.push_str("null"),
+            Json::Num(value) => out.push_str(&value.to_string()),
+            Json::Str(text) => write_str(text, out),
+            Json::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+            Json::List(items) => {
+                out.push('[');
+                for (index, item) in items.iter().enumerate() {
+                    if index > 0 {
+                        out.push(',');
+                    }
+                    item.write_line(out);
+                }
+                out.push(']');
+            }
+            Json::Object(fields) => {
+                out.push('{');
+                for (index, (name, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        out.push(',');
+                    }
+                    write_str(name, out);
+                    out.push(':');
+                    value.write_line(out);
+                }
+                out.push('}');
+            }
+        }
+    }
+}
+
+fn write_str(text: &str, out: &mut String) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+pub fn object(fields: Vec<(&str, Json)>) -> Json {
+    Json::Object(
+        fields
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect(),
+    )
+}
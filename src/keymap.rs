@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use crate::i18n::trf;
+
+// the names of the keymap presets, qwerty is the default
+pub const PRESET_NAMES: &[&str] = &["qwerty", "qwertz", "azerty", "dvorak", "colemak", "numpad"];
+
+// the layout of the keys on the original hex keypad
+pub const KEYPAD_LAYOUT: [[u8; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+
+// the keyboard keys of each preset in the same places as KEYPAD_LAYOUT, so
+// the keypad is the 4x4 block under the 1 to 4 keys whatever the layout
+const PRESETS: &[[[&str; 4]; 4]] = &[
+    // qwerty
+    [
+        ["1", "2", "3", "4"],
+        ["Q", "W", "E", "R"],
+        ["A", "S", "D", "F"],
+        ["Z", "X", "C", "V"],
+    ],
+    // qwertz
+    [
+        ["1", "2", "3", "4"],
+        ["Q", "W", "E", "R"],
+        ["A", "S", "D", "F"],
+        ["Y", "X", "C", "V"],
+    ],
+    // azerty, the number row types these without shift
+    [
+        ["&", "é", "\"", "'"],
+        ["A", "Z", "E", "R"],
+        ["Q", "S", "D", "F"],
+        ["W", "X", "C", "V"],
+    ],
+    // dvorak
+    [
+        ["1", "2", "3", "4"],
+        ["'", ",", ".", "P"],
+        ["A", "O", "E", "U"],
+        [";", "Q", "J", "K"],
+    ],
+    // colemak
+    [
+        ["1", "2", "3", "4"],
+        ["Q", "W", "F", "P"],
+        ["A", "R", "S", "T"],
+        ["Z", "X", "C", "D"],
+    ],
+    // numpad
+    [
+        ["Keypad 7", "Keypad 8", "Keypad 9", "Keypad /"],
+        ["Keypad 4", "Keypad 5", "Keypad 6", "Keypad *"],
+        ["Keypad 1", "Keypad 2", "Keypad 3", "Keypad -"],
+        ["Keypad 0", "Keypad .", "Keypad Enter", "Keypad +"],
+    ],
+];
+
+// the keyboard key names the 16 keypad keys are mapped to, indexed by the
+// keypad key
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keymap {
+    keys: [String; 0x10],
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::preset("qwerty").unwrap()
+    }
+}
+
+impl Keymap {
+    pub fn preset(name: &str) -> Option<Keymap> {
+        let index = PRESET_NAMES.iter().position(|preset| *preset == name)?;
+        let mut keys: [String; 0x10] = Default::default();
+        for (row, names) in KEYPAD_LAYOUT.iter().zip(PRESETS[index].iter()) {
+            for (key, name) in row.iter().zip(names.iter()) {
+                keys[*key as usize] = name.to_string();
+            }
+        }
+        Some(Keymap { keys })
+    }
+
+    // this keymap changed by the keymap table of the config file, which maps
+    // keypad keys "0" to "F" to key names
+    pub fn with_config(mut self, config: &BTreeMap<String, String>) -> Result<Keymap, String> {
+        for (key, name) in config {
+            let index = match u8::from_str_radix(key, 16) {
+                Ok(index) if key.len() == 1 => index as usize,
+                _ => return Err(trf("keymap_unknown_keypad_key", &[key])),
+            };
+            self.keys[index] = name.clone();
+        }
+        self.check_duplicates()?;
+        Ok(self)
+    }
+
+    // the keyboard key a keypad key is mapped to
+    pub fn key(&self, key: u8) -> &str {
+        &self.keys[key as usize]
+    }
+
+    // the key names indexed by the keypad key
+    pub fn names(&self) -> Vec<&str> {
+        self.keys.iter().map(|name| name.as_str()).collect()
+    }
+
+    // one keyboard key can't press two keypad keys
+    fn check_duplicates(&self) -> Result<(), String> {
+        for (i, name) in self.keys.iter().enumerate() {
+            let lower = name.to_lowercase();
+            if let Some(j) = self.keys[..i]
+                .iter()
+                .position(|other| other.to_lowercase() == lower)
+            {
+                return Err(trf(
+                    "keymap_duplicate",
+                    &[name, &format!("{:X}", j), &format!("{:X}", i)],
+                ));
+            }
+        }
+        Ok(())
+    }
+}
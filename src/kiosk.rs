@@ -0,0 +1,60 @@
+// locks the emulator hotkeys so visitors at a public kiosk can only use the
+// keypad. Typing the passcode, a sequence of key names, unlocks them and
+// typing it again locks them
+pub struct Kiosk {
+    passcode: Vec<String>,
+
+    // the last keys pressed, as many as there are in the passcode
+    recent: Vec<String>,
+
+    locked: bool,
+}
+
+impl Kiosk {
+    // the key names are separated by spaces, e.g. "F12 1 2 3 4". With no
+    // passcode the hotkeys stay locked
+    pub fn new(passcode: &str) -> Kiosk {
+        Kiosk {
+            passcode: passcode
+                .split_whitespace()
+                .map(|key| key.to_string())
+                .collect(),
+            recent: Vec::new(),
+            locked: true,
+        }
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    // the key names of the passcode
+    pub fn passcode(&self) -> impl Iterator<Item = &str> {
+        self.passcode.iter().map(|key| key.as_str())
+    }
+
+    // follow the passcode as keys are pressed, returns true when it was
+    // completed and the lock changed
+    pub fn key_pressed(&mut self, name: &str) -> bool {
+        if self.passcode.is_empty() {
+            return false;
+        }
+        if self.recent.len() == self.passcode.len() {
+            self.recent.remove(0);
+        }
+        self.recent.push(name.to_string());
+
+        let typed = self.recent.len() == self.passcode.len()
+            && self
+                .recent
+                .iter()
+                .zip(self.passcode.iter())
+                .all(|(key, code)| key.eq_ignore_ascii_case(code));
+        if !typed {
+            return false;
+        }
+        self.recent.clear();
+        self.locked = !self.locked;
+        true
+    }
+}
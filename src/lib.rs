@@ -0,0 +1,113 @@
+//! A chip8 interpreter core with no dependency on a windowing or sound library.
+//!
+//! The `Interpreter` holds the memory, registers, timers, display and keypad
+//! state of the machine. A frontend feeds it key presses, calls `cycle()` at
+//! the clock speed and `update_timers()` at 60hz, and draws its `display()`.
+//!
+//! Tools and tests can drive it one opcode at a time with no window and no
+//! wall clock. `step()` runs the next opcode and returns it, and the machine
+//! can be looked at between steps with `registers()`, `memory()`,
+//! `framebuffer()` and the `MachineView` methods such as `pc()`:
+//!
+//! ```
+//! use chip8::{Interpreter, MachineView};
+//!
+//! let mut interp = Interpreter::new();
+//! // V0 = 5, I = the 0 glyph of the font, draw it at V0,V0
+//! interp.load_rom(&[0x60, 0x05, 0xF1, 0x29, 0xD0, 0x05]).unwrap();
+//! assert_eq!(interp.step(), Ok(0x6005));
+//! assert_eq!(interp.registers()[0], 5);
+//! interp.step().unwrap();
+//! interp.step().unwrap();
+//! assert_eq!(interp.pc(), 0x206);
+//!
+//! let width = interp.display().get_width();
+//! assert_eq!(interp.framebuffer()[5 * width + 5], 1);
+//! assert_eq!(interp.memory()[0x200], 0x60);
+//! ```
+//!
+//! Loading a rom too big for memory, or an opcode that can't run such as an
+//! invalid one or a call with the stack full, gives a `Chip8Error` rather
+//! than a panic. A faulting opcode changes nothing and leaves the program
+//! counter on it.
+//!
+//! `Emulator` runs an `Interpreter` with the devices of `backend`, in real
+//! time with `update()` or `catch_up()` for a frontend called back each
+//! frame such as the browser, or straight away with `run_opcodes()` and
+//! `run_frame()`. `update()` keeps to a `clock::Clock`, the `Instant` the
+//! run started for real time.
+
+pub mod achievements;
+pub mod asm;
+pub mod audio;
+pub mod backend;
+pub mod banking;
+pub mod budget;
+pub mod capabilities;
+pub mod classroom;
+pub mod clock;
+pub mod colors;
+pub mod config;
+pub mod crash;
+pub mod crt;
+pub mod debugger;
+pub mod delta;
+pub mod disasm;
+pub mod display;
+pub mod emulator;
+pub mod error;
+pub mod expr;
+pub mod extensions;
+pub mod fault;
+pub mod ffmpeg;
+pub mod fonts;
+pub mod gamepad;
+pub mod gdb;
+pub mod gif;
+pub mod hotkeys;
+pub mod i18n;
+pub mod input;
+pub mod interpreter;
+pub mod json;
+pub mod keymap;
+pub mod kiosk;
+pub mod library;
+pub mod memmap;
+#[cfg(feature = "midi")]
+pub mod midi;
+pub mod netplay;
+pub mod octo;
+pub mod opcode;
+pub mod osc;
+pub mod osd;
+pub mod palette;
+pub mod pipe;
+pub mod playtime;
+pub mod profiles;
+pub mod quirkhints;
+pub mod quirks;
+pub mod recent;
+pub mod replay;
+pub mod rewind;
+pub mod romdb;
+pub mod romdiff;
+pub mod romid;
+pub mod savestate;
+pub mod screenshot;
+pub mod selftest;
+pub mod service;
+pub mod spectate;
+pub mod stats;
+#[cfg(feature = "strict")]
+pub mod strict;
+pub mod stuck;
+pub mod testpattern;
+pub mod tracepoint;
+#[cfg(all(feature = "wasm", target_arch = "wasm32"))]
+pub mod wasm;
+pub mod watch;
+
+pub use emulator::Emulator;
+pub use error::Chip8Error;
+pub use expr::MachineView;
+pub use interpreter::Interpreter;
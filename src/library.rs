@@ -0,0 +1,206 @@
+use std::fs;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::display::Display;
+use crate::i18n::trf;
+use crate::interpreter::Interpreter;
+use crate::octo;
+use crate::romdb;
+
+// the file extensions of the roms shown in the library
+pub const ROM_EXTENSIONS: &[&str] = &["ch8", "c8", "sc8", "xo8", "8o"];
+
+// the thumbnails are kept in this directory inside the rom directory, named
+// after the sha1 of the rom so a changed rom gets a new one
+pub const CACHE_DIR: &str = ".chip8-thumbnails";
+
+// roms are run for this many frames to get to a screen worth showing
+const THUMBNAIL_FRAMES: u32 = 60;
+
+// the speed roms that aren't in the database are run at
+const DEFAULT_CLOCKSPEED: u32 = 400;
+
+// what a rom showed on screen after running for a second
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Thumbnail {
+    pub width: usize,
+    pub height: usize,
+
+    // one byte per pixel that is either 0 or 1, row by row
+    pub pixels: Vec<u8>,
+}
+
+impl Thumbnail {
+    pub fn from_display(display: &Display) -> Thumbnail {
+        Thumbnail {
+            width: display.get_width(),
+            height: display.get_height(),
+            pixels: display.pixels().to_vec(),
+        }
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[x + y * self.width]
+    }
+
+    // the thumbnail at most width pixels wide, halved until it fits. A pixel
+    // is lit if any of the pixels it covers are so thin lines stay
+    pub fn shrink(&self, width: usize) -> Thumbnail {
+        if self.width <= width || self.width < 2 || self.height < 2 {
+            return self.clone();
+        }
+        let half = Thumbnail {
+            width: self.width / 2,
+            height: self.height / 2,
+            pixels: (0..self.height / 2)
+                .flat_map(|y| (0..self.width / 2).map(move |x| (x, y)))
+                .map(|(x, y)| {
+                    self.get_pixel(2 * x, 2 * y)
+                        | self.get_pixel(2 * x + 1, 2 * y)
+                        | self.get_pixel(2 * x, 2 * y + 1)
+                        | self.get_pixel(2 * x + 1, 2 * y + 1)
+                })
+                .collect(),
+        };
+        half.shrink(width)
+    }
+}
+
+// a rom in the library
+pub struct Entry {
+    pub path: PathBuf,
+
+    // the title from the rom database, or the file name
+    pub title: String,
+
+    // the sha1 of the rom, None if it couldn't be read
+    pub sha1: Option<String>,
+
+    // None if the rom couldn't be read or crashed before drawing anything
+    pub thumbnail: Option<Thumbnail>,
+}
+
+// the romfiles in a directory sorted by name, roms often come without an
+// extension so every file without one is taken to be a rom
+pub fn scan(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let entries = fs::read_dir(dir).map_err(|e| trf("file_read_error", &[&dir.display(), &e]))?;
+    let mut roms: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) => ROM_EXTENSIONS.contains(&ext.to_lowercase().as_str()),
+            None => !path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.')),
+        })
+        .collect();
+    roms.sort();
+    Ok(roms)
+}
+
+// every rom in the directory with its thumbnail, from the cache if it has
+// been made before
+pub fn load(dir: &Path) -> Result<Vec<Entry>, String> {
+    let cache_dir = dir.join(CACHE_DIR);
+    let mut entries = Vec::new();
+    for path in scan(dir)? {
+        let rom = read(&path);
+        let sha1 = rom.as_ref().map(|rom| romdb::sha1_hex(rom));
+        let info = rom.as_ref().and_then(|rom| romdb::lookup(rom));
+        let title = match info {
+            Some(ref info) => info.title.clone(),
+            None => path
+                .file_stem()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .to_string(),
+        };
+        let thumbnail = rom.and_then(|rom| {
+            let cached = cache_dir.join(format!("{}.png", sha1.as_ref()?));
+            load_png(&cached).or_else(|| {
+                let clockspeed = info
+                    .and_then(|info| info.clockspeed)
+                    .unwrap_or(DEFAULT_CLOCKSPEED);
+                let thumbnail = render(&rom, clockspeed);
+                // a missing thumbnail is made again next time
+                if let Some(ref thumbnail) = thumbnail {
+                    let _ = fs::create_dir_all(&cache_dir);
+                    let _ = save_png(&cached, thumbnail);
+                }
+                thumbnail
+            })
+        });
+        entries.push(Entry {
+            path,
+            title,
+            sha1,
+            thumbnail,
+        });
+    }
+    Ok(entries)
+}
+
+// the rom data, Octo programs are assembled first
+fn read(path: &Path) -> Option<Vec<u8>> {
+    if path.extension().is_some_and(|ext| ext == "8o") {
+        let source = fs::read_to_string(path).ok()?;
+        octo::assemble(&source).ok()
+    } else {
+        fs::read(path).ok()
+    }
+}
+
+// run the rom headlessly for a second with no keys pressed. A rom that faults
+// quietly gives no thumbnail
+pub fn render(rom: &[u8], clockspeed: u32) -> Option<Thumbnail> {
+    let mut interp = Interpreter::new();
+    interp.load_rom(rom).ok()?;
+    for _ in 0..THUMBNAIL_FRAMES {
+        for _ in 0..clockspeed / 60 {
+            interp.cycle().ok()?;
+        }
+        interp.update_timers();
+    }
+    Some(Thumbnail::from_display(interp.display()))
+        .filter(|thumbnail| thumbnail.pixels.contains(&1))
+}
+
+// the thumbnails are cached as greyscale pngs at one pixel per chip8 pixel
+fn save_png(path: &Path, thumbnail: &Thumbnail) -> Result<(), String> {
+    let data: Vec<u8> = thumbnail.pixels.iter().map(|pixel| pixel * 0xFF).collect();
+    let file = File::create(path).map_err(|e| trf("file_create_error", &[&path.display(), &e]))?;
+    let mut encoder = png::Encoder::new(
+        BufWriter::new(file),
+        thumbnail.width as u32,
+        thumbnail.height as u32,
+    );
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .map_err(|e| trf("file_write_error", &[&path.display(), &e]))
+}
+
+// a cached thumbnail, None if there isn't one or it can't be read
+fn load_png(path: &Path) -> Option<Thumbnail> {
+    let decoder = png::Decoder::new(File::open(path).ok()?);
+    let mut reader = decoder.read_info().ok()?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).ok()?;
+    if info.color_type != png::ColorType::Grayscale || info.bit_depth != png::BitDepth::Eight {
+        return None;
+    }
+    let (width, height) = (info.width as usize, info.height as usize);
+    Some(Thumbnail {
+        width,
+        height,
+        pixels: data[..width * height]
+            .iter()
+            .map(|value| (*value >= 0x80) as u8)
+            .collect(),
+    })
+}
@@ -1,5 +1,7 @@
-mod chip8;
+use std::process::ExitCode;
 
-fn main() {
-    chip8::start();
+mod frontend;
+
+fn main() -> ExitCode {
+    frontend::start()
 }
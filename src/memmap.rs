@@ -0,0 +1,175 @@
+use std::ffi::c_void;
+
+use crate::expr::MachineView;
+use crate::interpreter::Interpreter;
+use crate::interpreter::MEM_SIZE;
+
+// the flat memory map achievement runtimes such as rcheevos read the machine
+// through, little endian like the consoles they support. Addresses never
+// move between versions so achievement sets written against them keep
+// working, new things are only added at the end
+//
+// 0000-0FFF  memory
+// 1000-100F  V0-VF
+// 1010-1011  I
+// 1012-1013  PC
+// 1014       SP
+// 1015       DT
+// 1016       ST
+// 1017       the bank in the window with the banking extension, else 0
+// 1018-1019  the keys held, bit N for key N
+// 1020-109F  the stack, 64 return addresses of 2 bytes, 0 past SP
+
+pub const REGISTERS_START: u32 = 0x1000;
+pub const I_ADDR: u32 = 0x1010;
+pub const PC_ADDR: u32 = 0x1012;
+pub const SP_ADDR: u32 = 0x1014;
+pub const DT_ADDR: u32 = 0x1015;
+pub const ST_ADDR: u32 = 0x1016;
+pub const BANK_ADDR: u32 = 0x1017;
+pub const KEYS_ADDR: u32 = 0x1018;
+pub const STACK_START: u32 = 0x1020;
+pub const STACK_SLOTS: u32 = 64;
+
+// the size of the map, reads past it are 0
+pub const SIZE: u32 = STACK_START + STACK_SLOTS * 2;
+
+// a part of the map for runtimes and tools that list them
+pub struct Region {
+    pub start: u32,
+    pub size: u32,
+    pub name: &'static str,
+    pub writable: bool,
+}
+
+pub const REGIONS: &[Region] = &[
+    Region {
+        start: 0,
+        size: MEM_SIZE as u32,
+        name: "memory",
+        writable: true,
+    },
+    Region {
+        start: REGISTERS_START,
+        size: 0x10,
+        name: "registers",
+        writable: true,
+    },
+    Region {
+        start: I_ADDR,
+        size: 2,
+        name: "I",
+        writable: true,
+    },
+    Region {
+        start: PC_ADDR,
+        size: 2,
+        name: "PC",
+        writable: true,
+    },
+    Region {
+        start: SP_ADDR,
+        size: 1,
+        name: "SP",
+        writable: false,
+    },
+    Region {
+        start: DT_ADDR,
+        size: 1,
+        name: "DT",
+        writable: true,
+    },
+    Region {
+        start: ST_ADDR,
+        size: 1,
+        name: "ST",
+        writable: true,
+    },
+    Region {
+        start: BANK_ADDR,
+        size: 1,
+        name: "bank",
+        writable: false,
+    },
+    Region {
+        start: KEYS_ADDR,
+        size: 2,
+        name: "keys",
+        writable: false,
+    },
+    Region {
+        start: STACK_START,
+        size: STACK_SLOTS * 2,
+        name: "stack",
+        writable: false,
+    },
+];
+
+// the byte at an address of the map
+pub fn peek_byte(interp: &Interpreter, address: u32) -> u8 {
+    let low = |value: usize, addr: u32| (value >> (8 * (address - addr))) as u8;
+    match address {
+        a if a < MEM_SIZE as u32 => interp.read_memory(a as usize),
+        a if (REGISTERS_START..I_ADDR).contains(&a) => {
+            interp.register((a - REGISTERS_START) as usize)
+        }
+        I_ADDR | 0x1011 => low(interp.i(), I_ADDR),
+        PC_ADDR | 0x1013 => low(interp.pc(), PC_ADDR),
+        SP_ADDR => interp.sp() as u8,
+        DT_ADDR => interp.delay_timer(),
+        ST_ADDR => interp.sound_timer(),
+        BANK_ADDR => interp.bank().unwrap_or(0) as u8,
+        KEYS_ADDR | 0x1019 => {
+            let keys = interp
+                .keys()
+                .iter()
+                .rev()
+                .fold(0, |mask, held| (mask << 1) | *held as usize);
+            low(keys, KEYS_ADDR)
+        }
+        a if (STACK_START..SIZE).contains(&a) => {
+            let slot = ((a - STACK_START) / 2) as usize;
+            let value = interp.stack().get(slot).copied().unwrap_or(0);
+            low(value, STACK_START + slot as u32 * 2)
+        }
+        _ => 0,
+    }
+}
+
+// read 1 to 4 bytes from an address of the map as a little endian number,
+// what rcheevos asks for with its peek callback
+pub fn peek(interp: &Interpreter, address: u32, num_bytes: u32) -> u32 {
+    (0..num_bytes.min(4)).fold(0, |value, n| {
+        value | (peek_byte(interp, address.wrapping_add(n)) as u32) << (8 * n)
+    })
+}
+
+// write a byte to an address of the map, false if it can't be written
+pub fn poke(interp: &mut Interpreter, address: u32, value: u8) -> bool {
+    let replace_byte = |old: usize, addr: u32| {
+        let shift = 8 * (address - addr);
+        (old & !(0xFF << shift)) | ((value as usize) << shift)
+    };
+    match address {
+        a if a < MEM_SIZE as u32 => interp.write_memory(a as usize, value),
+        a if (REGISTERS_START..I_ADDR).contains(&a) => {
+            interp.set_register((a - REGISTERS_START) as usize, value)
+        }
+        I_ADDR | 0x1011 => interp.set_i(replace_byte(interp.i(), I_ADDR) % MEM_SIZE),
+        PC_ADDR | 0x1013 => interp.set_pc(replace_byte(interp.pc(), PC_ADDR) % MEM_SIZE),
+        DT_ADDR => interp.set_delay_timer(value),
+        ST_ADDR => interp.set_sound_timer(value),
+        _ => return false,
+    }
+    true
+}
+
+/// The peek callback of rcheevos, `rc_peek_t`, for an embedder to pass to
+/// `rc_runtime_do_frame` with the interpreter as the user data.
+///
+/// # Safety
+///
+/// `ud` has to point to an `Interpreter` that lives for the call.
+pub unsafe extern "C" fn peek_callback(address: u32, num_bytes: u32, ud: *mut c_void) -> u32 {
+    peek(&*(ud as *const Interpreter), address, num_bytes)
+}
@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+use std::collections::VecDeque;
+use std::sync::mpsc::Receiver;
+
+use crate::backend::Command;
+use crate::backend::InputSource;
+use crate::i18n::trf;
+
+// the default note bindings for a 4x4 pad controller, which usually sends
+// notes 36 to 51 from the bottom left pad along each row and up. The pads
+// press the keys in the same places as on the hex keypad
+const DEFAULT_BINDINGS: &[(u8, u8)] = &[
+    (36, 0xA),
+    (37, 0x0),
+    (38, 0xB),
+    (39, 0xF),
+    (40, 0x7),
+    (41, 0x8),
+    (42, 0x9),
+    (43, 0xE),
+    (44, 0x4),
+    (45, 0x5),
+    (46, 0x6),
+    (47, 0xD),
+    (48, 0x1),
+    (49, 0x2),
+    (50, 0x3),
+    (51, 0xC),
+];
+
+const NOTE_NAMES: &[&str] = &[
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+// midi notes to the keypad keys they press. The notes are numbers from 0 to
+// 127 or names with an octave where C4 is middle C, e.g. "36", "C2" or "F#3"
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MidiMap {
+    bindings: BTreeMap<u8, u8>,
+}
+
+impl Default for MidiMap {
+    fn default() -> Self {
+        MidiMap {
+            bindings: DEFAULT_BINDINGS.iter().copied().collect(),
+        }
+    }
+}
+
+impl MidiMap {
+    // the default bindings changed by the midi table of the config file,
+    // which maps notes to keypad keys "0" to "F". An empty key unbinds the
+    // note
+    pub fn from_config(config: &BTreeMap<String, String>) -> Result<MidiMap, String> {
+        let mut map = MidiMap::default();
+        for (note, key) in config {
+            let number = parse_note(note).ok_or_else(|| trf("midi_unknown_note", &[note]))?;
+            if key.is_empty() {
+                map.bindings.remove(&number);
+                continue;
+            }
+            match u8::from_str_radix(key, 16) {
+                Ok(key) if key < 0x10 => {
+                    map.bindings.insert(number, key);
+                }
+                _ => return Err(trf("midi_unknown_keypad_key", &[key, note])),
+            }
+        }
+        Ok(map)
+    }
+
+    // the keypad key a note presses
+    pub fn key(&self, note: u8) -> Option<u8> {
+        self.bindings.get(&note).copied()
+    }
+
+    // the notes and the keypad keys they press
+    pub fn bindings(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        self.bindings.iter().map(|(note, key)| (*note, *key))
+    }
+}
+
+// a note number, or a name such as "C4" or "Bb2" where C-1 is note 0
+fn parse_note(name: &str) -> Option<u8> {
+    if let Ok(number) = name.parse::<u8>() {
+        return Some(number).filter(|number| *number < 0x80);
+    }
+    let split = name.find(|c: char| c == '-' || c.is_ascii_digit())?;
+    let (pitch, octave) = name.split_at(split);
+    let octave: i32 = octave.parse().ok()?;
+    let mut chars = pitch.chars();
+    let letter = chars.next()?.to_ascii_uppercase().to_string();
+    let mut semitone = NOTE_NAMES.iter().position(|n| *n == letter)? as i32;
+    match chars.as_str() {
+        "" => {}
+        "#" => semitone += 1,
+        "b" => semitone -= 1,
+        _ => return None,
+    }
+    let number = (octave + 1) * 12 + semitone;
+    u8::try_from(number).ok().filter(|number| *number < 0x80)
+}
+
+// the name of a note number, e.g. "C4" for 60
+pub fn note_name(note: u8) -> String {
+    format!("{}{}", NOTE_NAMES[note as usize % 12], note as i32 / 12 - 1)
+}
+
+// turns the bytes from a midi device into note presses, with running status
+// where the status byte is left out of messages that repeat it
+#[derive(Default)]
+struct Parser {
+    status: Option<u8>,
+    data: Vec<u8>,
+}
+
+impl Parser {
+    // the note and whether it was pressed, once a byte completes a note on or
+    // note off message
+    fn push(&mut self, byte: u8) -> Option<(u8, bool)> {
+        match byte {
+            // clock and other real time bytes can come between any others
+            0xF8..=0xFF => None,
+            // system messages end running status and aren't used
+            0xF0..=0xF7 => {
+                self.status = None;
+                None
+            }
+            0x80..=0xEF => {
+                self.status = Some(byte);
+                self.data.clear();
+                None
+            }
+            _ => {
+                let status = self.status?;
+                self.data.push(byte);
+                let length = match status & 0xF0 {
+                    0xC0 | 0xD0 => 1,
+                    _ => 2,
+                };
+                if self.data.len() < length {
+                    return None;
+                }
+                let (note, velocity) = (self.data[0], self.data[length - 1]);
+                self.data.clear();
+                match status & 0xF0 {
+                    // a note on with no velocity is a note off
+                    0x90 => Some((note, velocity > 0)),
+                    0x80 => Some((note, false)),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+// presses keypad keys with the notes played on a midi device on any channel,
+// the bytes are read from the device on another thread
+pub struct MidiInput {
+    bytes: Receiver<Vec<u8>>,
+    parser: Parser,
+    map: MidiMap,
+
+    // the notes held down
+    notes: [bool; 0x80],
+
+    // notes not played yet, see read_keys
+    pending: VecDeque<(u8, bool)>,
+}
+
+impl MidiInput {
+    pub fn new(bytes: Receiver<Vec<u8>>, map: MidiMap) -> MidiInput {
+        MidiInput {
+            bytes,
+            parser: Parser::default(),
+            map,
+            notes: [false; 0x80],
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+impl InputSource for MidiInput {
+    // play the notes that came since the last read. Drum pads send the note
+    // off straight after the note on, so a note let go of in the same read it
+    // was pressed in waits for the next one for the rom to see the press
+    fn read_keys(&mut self) -> [bool; 0x10] {
+        while let Ok(bytes) = self.bytes.try_recv() {
+            for byte in bytes {
+                if let Some(event) = self.parser.push(byte) {
+                    self.pending.push_back(event);
+                }
+            }
+        }
+
+        let mut pressed = [false; 0x80];
+        while let Some(&(note, on)) = self.pending.front() {
+            let note = note as usize;
+            if !on && pressed[note] {
+                break;
+            }
+            self.pending.pop_front();
+            self.notes[note] = on;
+            pressed[note] |= on;
+        }
+
+        let mut keys = [false; 0x10];
+        for (note, key) in self.map.bindings() {
+            keys[key as usize] |= self.notes[note as usize];
+        }
+        keys
+    }
+
+    fn poll_commands(&mut self) -> Vec<Command> {
+        Vec::new()
+    }
+
+    fn help_lines(&self) -> Vec<String> {
+        let bindings: Vec<String> = self
+            .map
+            .bindings()
+            .map(|(note, key)| format!("{} {:X}", note_name(note), key))
+            .collect();
+        vec![trf("midi_help", &[&bindings.join(", ")])]
+    }
+}
@@ -0,0 +1,428 @@
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::TryRecvError;
+use std::thread;
+use std::time::Duration;
+
+use crate::i18n::tr;
+use crate::i18n::trf;
+
+// the first line each side sends
+const HEADER: &str = "chip8-netplay 1";
+
+// the most frames of input delay either player can ask for, half a second at
+// 60hz
+pub const MAX_INPUT_DELAY: u32 = 30;
+
+// the frames between comparing checksums of the two machines
+const CHECKSUM_FRAMES: u64 = 60;
+
+// how long the players wait for each other's side of the negotiation, and
+// how long sending keys may take before the other player is given up on
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// the updates waited for the other player's keys before saying so on screen,
+// shorter waits are hidden by the input delay or pass unnoticed
+const STALL_UPDATES: u32 = 15;
+
+// what the two machines have to agree on to play out the same, picked by the
+// host. The input delay is the larger of the two players' so the one with
+// the slower connection is covered
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Settings {
+    pub seed: u64,
+    pub refresh: u32,
+    pub clockspeed: u32,
+    pub delay: u32,
+}
+
+// a game for two players on two machines over tcp, run in lockstep without
+// rollback. Both machines start from the same rom with the same settings and
+// run a frame only once they have the keys of both players for it, so they
+// never go out of step and nothing has to be undone. Each player's keys are
+// sent delay frames before they are used, which hides a connection that
+// takes up to that long at the cost of the keys taking that long to act.
+// The keypad of the machine is both players' keys held at once.
+//
+// The players negotiate before the game with a few lines of text, the
+// joining player first and then the host, e.g.
+//
+// chip8-netplay 1          chip8-netplay 1
+// rom 2f1c...              seed 1234
+// delay 3                  refresh 60
+//                          clockspeed 400
+//                          delay 4
+//
+// The host answers "refused rom" to a player with another rom. Then each
+// sends "k <frame> <keys>" for every frame and "c <frame> <checksum>" every
+// CHECKSUM_FRAMES frames, to notice the machines going their own ways
+pub struct Netplay {
+    stream: TcpStream,
+    // the other player, for messages
+    peer: String,
+    settings: Settings,
+    // the lines from the other player, read on another thread
+    incoming: Receiver<String>,
+
+    // the frames run so far, the next one to run is numbered this
+    frame: u64,
+    // the keys of each player for the frames from the next one on, the
+    // local ones have already been sent
+    local: VecDeque<[bool; 0x10]>,
+    remote: VecDeque<[bool; 0x10]>,
+    // the checksums at frames one player has sent and the other hasn't yet
+    checksums: VecDeque<(u64, u32)>,
+    remote_checksums: VecDeque<(u64, u32)>,
+    // the updates in a row spent waiting for the other player
+    stalled: u32,
+}
+
+impl Netplay {
+    // wait on a port of every address for a player to join with the same
+    // rom. Players that can't are turned away and the wait goes on
+    pub fn host(port: u16, sha1: &str, settings: Settings) -> Result<Netplay, String> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .map_err(|e| trf("netplay_listen_error", &[&port, &e]))?;
+        println!("{}", trf("netplay_waiting_player", &[&port]));
+        loop {
+            let (stream, addr) = listener
+                .accept()
+                .map_err(|e| trf("netplay_listen_error", &[&port, &e]))?;
+            match Netplay::welcome(stream, &addr.to_string(), sha1, settings) {
+                Ok(netplay) => return Ok(netplay),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+
+    // the host's side of the negotiation with a player that connected
+    fn welcome(
+        mut stream: TcpStream,
+        peer: &str,
+        sha1: &str,
+        settings: Settings,
+    ) -> Result<Netplay, String> {
+        let error = |e: std::io::Error| trf("netplay_error", &[&peer, &e]);
+        stream
+            .set_read_timeout(Some(HELLO_TIMEOUT))
+            .map_err(error)?;
+        let mut reader = BufReader::new(stream.try_clone().map_err(error)?);
+        let hello = read_hello(&mut reader, peer, &["rom", "delay"])?;
+        if hello[0] != sha1 {
+            let _ = stream.write_all(b"refused rom\n");
+            return Err(trf("netplay_refused_rom", &[&peer]));
+        }
+        let delay = hello[1]
+            .parse::<u32>()
+            .ok()
+            .filter(|delay| *delay <= MAX_INPUT_DELAY)
+            .ok_or_else(|| trf("netplay_invalid", &[&peer, &hello[1]]))?;
+        let settings = Settings {
+            delay: settings.delay.max(delay),
+            ..settings
+        };
+        let answer = format!(
+            "{}\nseed {}\nrefresh {}\nclockspeed {}\ndelay {}\n",
+            HEADER, settings.seed, settings.refresh, settings.clockspeed, settings.delay
+        );
+        stream.write_all(answer.as_bytes()).map_err(error)?;
+        Netplay::start(stream, reader, peer, settings)
+    }
+
+    // join a game hosted at an address such as "192.168.1.5:7001", asking
+    // for an input delay. This gives the settings of the host to start the
+    // machine with
+    pub fn join(address: &str, sha1: &str, delay: u32) -> Result<Netplay, String> {
+        let error = |e: std::io::Error| trf("netplay_error", &[&address, &e]);
+        let mut stream = TcpStream::connect(address).map_err(error)?;
+        stream
+            .set_read_timeout(Some(HELLO_TIMEOUT))
+            .map_err(error)?;
+        let hello = format!("{}\nrom {}\ndelay {}\n", HEADER, sha1, delay);
+        stream.write_all(hello.as_bytes()).map_err(error)?;
+
+        let mut reader = BufReader::new(stream.try_clone().map_err(error)?);
+        let answer = read_hello(
+            &mut reader,
+            address,
+            &["seed", "refresh", "clockspeed", "delay"],
+        )?;
+        let invalid = |value: &String| trf("netplay_invalid", &[&address, value]);
+        let number = |index: usize| {
+            answer[index]
+                .parse::<u64>()
+                .map_err(|_| invalid(&answer[index]))
+        };
+        let small = |index: usize| {
+            number(index)
+                .and_then(|value| u32::try_from(value).map_err(|_| invalid(&answer[index])))
+        };
+        let settings = Settings {
+            seed: number(0)?,
+            refresh: small(1)?,
+            clockspeed: small(2)?,
+            delay: small(3)?,
+        };
+        let usable = (1..=1000).contains(&settings.refresh)
+            && settings.clockspeed > 0
+            && settings.delay <= MAX_INPUT_DELAY;
+        if !usable {
+            return Err(invalid(&answer.join(" ")));
+        }
+        Netplay::start(stream, reader, address, settings)
+    }
+
+    // start the game once the settings are agreed, with the keys of the
+    // frames before either player's first keys arrive let go
+    fn start(
+        stream: TcpStream,
+        reader: BufReader<TcpStream>,
+        peer: &str,
+        settings: Settings,
+    ) -> Result<Netplay, String> {
+        let error = |e: std::io::Error| trf("netplay_error", &[&peer, &e]);
+        stream.set_read_timeout(None).map_err(error)?;
+        stream
+            .set_write_timeout(Some(WRITE_TIMEOUT))
+            .map_err(error)?;
+        let _ = stream.set_nodelay(true);
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        println!("{}", trf("netplay_started", &[&peer, &settings.delay]));
+        let released = VecDeque::from(vec![[false; 0x10]; settings.delay as usize]);
+        Ok(Netplay {
+            stream,
+            peer: peer.to_string(),
+            settings,
+            incoming: receiver,
+            frame: 0,
+            local: released.clone(),
+            remote: released,
+            checksums: VecDeque::new(),
+            remote_checksums: VecDeque::new(),
+            stalled: 0,
+        })
+    }
+
+    // the settings both machines run with
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    // true once the other player's keys have been late long enough to say so
+    pub fn is_stalled(&self) -> bool {
+        self.stalled > STALL_UPDATES
+    }
+
+    // hand over the local keypad once an update, it is sent to the other
+    // player for the frame delay frames on. This gives the keys of both
+    // players for the next frame once the other player's have come, None
+    // while the machine has to wait for them. An error ends the game, the
+    // other player left or the machines went out of step
+    pub fn next_keys(&mut self, keys: [bool; 0x10]) -> Result<Option<[bool; 0x10]>, String> {
+        self.receive()?;
+        // only run ahead of the other player by the delay
+        if self.local.len() <= self.settings.delay as usize {
+            let frame = self.frame + self.local.len() as u64;
+            self.send(&format!("k {} {:04X}\n", frame, key_mask(&keys)))?;
+            self.local.push_back(keys);
+        }
+        let (Some(&local), Some(&remote)) = (self.local.front(), self.remote.front()) else {
+            self.stalled += 1;
+            return Ok(None);
+        };
+        self.local.pop_front();
+        self.remote.pop_front();
+        self.stalled = 0;
+        self.frame += 1;
+        let mut both = local;
+        for (key, pressed) in both.iter_mut().zip(remote) {
+            *key |= pressed;
+        }
+        Ok(Some(both))
+    }
+
+    // note the checksum of the machine at the end of the frame just run,
+    // sending it to the other player if one is due
+    pub fn frame_done(&mut self, checksum: u32) -> Result<(), String> {
+        if !self.frame.is_multiple_of(CHECKSUM_FRAMES) {
+            return Ok(());
+        }
+        self.send(&format!("c {} {:08x}\n", self.frame, checksum))?;
+        self.checksums.push_back((self.frame, checksum));
+        self.compare_checksums()
+    }
+
+    // read what the other player sent since the last update
+    fn receive(&mut self) -> Result<(), String> {
+        loop {
+            match self.incoming.try_recv() {
+                Ok(line) => self.parse_line(&line)?,
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => return Err(trf("netplay_left", &[&self.peer])),
+            }
+        }
+    }
+
+    // take in a line from the other player, the keys of each frame come in
+    // order
+    fn parse_line(&mut self, line: &str) -> Result<(), String> {
+        let invalid = || trf("netplay_invalid", &[&self.peer, &line]);
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["k", frame, mask] => {
+                let next = self.frame + self.remote.len() as u64;
+                let frame = frame.parse::<u64>().map_err(|_| invalid())?;
+                let mask = u16::from_str_radix(mask, 16).map_err(|_| invalid())?;
+                if frame != next {
+                    return Err(invalid());
+                }
+                let mut keys = [false; 0x10];
+                for (key, pressed) in keys.iter_mut().enumerate() {
+                    *pressed = mask & (1 << key) != 0;
+                }
+                self.remote.push_back(keys);
+            }
+            ["c", frame, checksum] => {
+                let frame = frame.parse::<u64>().map_err(|_| invalid())?;
+                let checksum = u32::from_str_radix(checksum, 16).map_err(|_| invalid())?;
+                self.remote_checksums.push_back((frame, checksum));
+                self.compare_checksums()?;
+            }
+            _ => return Err(invalid()),
+        }
+        Ok(())
+    }
+
+    // compare the checksums both players have sent for the same frames
+    fn compare_checksums(&mut self) -> Result<(), String> {
+        while let (Some(&(frame, ours)), Some(&(_, theirs))) =
+            (self.checksums.front(), self.remote_checksums.front())
+        {
+            if ours != theirs {
+                return Err(trf("netplay_desync", &[&frame]));
+            }
+            self.checksums.pop_front();
+            self.remote_checksums.pop_front();
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, text: &str) -> Result<(), String> {
+        self.stream
+            .write_all(text.as_bytes())
+            .map_err(|e| trf("netplay_error", &[&self.peer, &e]))
+    }
+}
+
+// read the header and the lines of the other player's side of the
+// negotiation, which start with the given names, giving what follows each
+fn read_hello(
+    reader: &mut BufReader<TcpStream>,
+    peer: &str,
+    names: &[&str],
+) -> Result<Vec<String>, String> {
+    let mut lines = reader.lines();
+    let mut next = || match lines.next() {
+        Some(Ok(line)) => Ok(line),
+        Some(Err(e)) => Err(trf("netplay_error", &[&peer, &e])),
+        None => Err(trf("netplay_error", &[&peer, &tr("netplay_closed")])),
+    };
+    let header = next()?;
+    if header == "refused rom" {
+        return Err(tr("netplay_other_rom"));
+    }
+    if header != HEADER {
+        return Err(trf("netplay_invalid", &[&peer, &header]));
+    }
+    let mut values = Vec::new();
+    for name in names {
+        let line = next()?;
+        match line.split_once(' ') {
+            Some((found, value)) if found == *name => values.push(value.to_string()),
+            _ => return Err(trf("netplay_invalid", &[&peer, &line])),
+        }
+    }
+    Ok(values)
+}
+
+// the keys held as a bit each, key 0 the lowest
+fn key_mask(keys: &[bool; 0x10]) -> u16 {
+    keys.iter()
+        .enumerate()
+        .filter(|(_, pressed)| **pressed)
+        .fold(0u16, |mask, (key, _)| mask | 1 << key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a game with an input delay of two frames, over a connection to
+    // itself as nothing is sent in these tests
+    fn netplay() -> Netplay {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let reader = BufReader::new(listener.accept().unwrap().0);
+        let settings = Settings {
+            seed: 1,
+            refresh: 60,
+            clockspeed: 500,
+            delay: 2,
+        };
+        Netplay::start(stream, reader, "peer", settings).unwrap()
+    }
+
+    #[test]
+    fn keys_come_for_the_frame_after_the_delay() {
+        let mut netplay = netplay();
+        assert_eq!(netplay.parse_line("k 2 8001"), Ok(()));
+        assert_eq!(netplay.parse_line("k 3 0000"), Ok(()));
+        let mut keys = [false; 0x10];
+        keys[0] = true;
+        keys[15] = true;
+        assert_eq!(
+            netplay.remote,
+            [[false; 0x10], [false; 0x10], keys, [false; 0x10]]
+        );
+    }
+
+    #[test]
+    fn keys_out_of_order_or_garbled_are_refused() {
+        let mut netplay = netplay();
+        for line in ["k 3 0001", "k 2 zz", "k x 0001", "k 2", "hello"] {
+            assert_eq!(
+                netplay.parse_line(line),
+                Err(trf("netplay_invalid", &[&"peer", &line]))
+            );
+        }
+        assert_eq!(netplay.remote.len(), 2);
+    }
+
+    #[test]
+    fn checksums_are_compared() {
+        let mut netplay = netplay();
+        netplay.checksums.extend([(60, 0xabc), (120, 0xdef)]);
+        assert_eq!(netplay.parse_line("c 60 00000abc"), Ok(()));
+        assert_eq!(netplay.checksums, [(120, 0xdef)]);
+        assert!(netplay.remote_checksums.is_empty());
+        assert_eq!(
+            netplay.parse_line("c 120 00000123"),
+            Err(trf("netplay_desync", &[&120]))
+        );
+    }
+}
@@ -0,0 +1,584 @@
+use std::collections::HashMap;
+
+use crate::expr::parse_number;
+use crate::expr::parse_register;
+use crate::i18n::trf;
+use crate::interpreter::MEM_SIZE;
+use crate::interpreter::PROGRAM_START;
+use crate::opcode::Instruction;
+
+// parse a single Octo statement that assembles to one instruction, e.g.
+// "v3 := 0x0A" or "sprite v0 v1 5". Names that are not numbers or registers
+// are looked up with symbols
+pub fn parse_statement(
+    text: &str,
+    symbols: &dyn Fn(&str) -> Option<i64>,
+) -> Result<Instruction, String> {
+    let tokens: Vec<&str> = text.split_whitespace().collect();
+
+    let number = |token: &str| {
+        parse_number(token)
+            .ok()
+            .or_else(|| symbols(token))
+            .ok_or(format!("Unknown name \"{}\" in \"{}\"", token, text))
+    };
+    let addr = |token: &str| {
+        let val = number(token)?;
+        if (0..0x1000).contains(&val) {
+            Ok(val as usize)
+        } else {
+            Err(format!("Address {} out of range in \"{}\"", val, text))
+        }
+    };
+    // bytes may be written as negative numbers, which wrap around
+    let byte = |token: &str| {
+        let val = number(token)?;
+        if (-0x80..0x100).contains(&val) {
+            Ok(val as u8)
+        } else {
+            Err(format!("{} does not fit in a byte in \"{}\"", val, text))
+        }
+    };
+    let nibble = |token: &str| {
+        let val = number(token)?;
+        if (0..0x10).contains(&val) {
+            Ok(val as u8)
+        } else {
+            Err(format!("{} does not fit in 4 bits in \"{}\"", val, text))
+        }
+    };
+    let reg = |token: &str| parse_register(token);
+
+    let instruction = match tokens.as_slice() {
+        ["clear"] => Instruction::Cls,
+        ["return"] | [";"] => Instruction::Ret,
+        ["scroll-down", n] => Instruction::Scd(nibble(n)?),
+        ["scroll-right"] => Instruction::Scr,
+        ["scroll-left"] => Instruction::Scl,
+        ["exit"] => Instruction::Exit,
+        ["lores"] => Instruction::Low,
+        ["hires"] => Instruction::High,
+        ["jump", nnn] => Instruction::Jp(addr(nnn)?),
+        ["jump0", nnn] => Instruction::JpV0(addr(nnn)?),
+        [":call", nnn] => Instruction::Call(addr(nnn)?),
+        ["native", nnn] => Instruction::Sys(addr(nnn)?),
+        ["sprite", x, y, n] => match (reg(x), reg(y)) {
+            (Some(x), Some(y)) => Instruction::Drw(x, y, nibble(n)?),
+            _ => return Err(format!("Invalid sprite statement \"{}\"", text)),
+        },
+        ["save", x] if reg(x).is_some() => Instruction::LdIVx(reg(x).unwrap()),
+        ["load", x] if reg(x).is_some() => Instruction::LdVxI(reg(x).unwrap()),
+        ["bcd", x] if reg(x).is_some() => Instruction::LdBVx(reg(x).unwrap()),
+        ["delay", ":=", x] if reg(x).is_some() => Instruction::LdDtVx(reg(x).unwrap()),
+        ["buzzer", ":=", x] if reg(x).is_some() => Instruction::LdStVx(reg(x).unwrap()),
+        ["pitch", ":=", x] if reg(x).is_some() => Instruction::LdPitchVx(reg(x).unwrap()),
+        ["i", ":=", "hex", x] if reg(x).is_some() => Instruction::LdFVx(reg(x).unwrap()),
+        ["i", ":=", "bighex", x] if reg(x).is_some() => Instruction::LdHfVx(reg(x).unwrap()),
+        ["i", "+=", x] if reg(x).is_some() => Instruction::AddIVx(reg(x).unwrap()),
+        ["i", ":=", nnn] => Instruction::LdI(addr(nnn)?),
+        // the statement after "if ... then" only runs if the condition is
+        // true, so the condition is compiled to a skip on the opposite
+        ["if", x, op, y, "then"] => match (reg(x), *op, reg(y)) {
+            (Some(x), "==", Some(y)) => Instruction::SneVxVy(x, y),
+            (Some(x), "!=", Some(y)) => Instruction::SeVxVy(x, y),
+            (Some(x), "==", None) => Instruction::SneVxNn(x, byte(y)?),
+            (Some(x), "!=", None) => Instruction::SeVxNn(x, byte(y)?),
+            _ => return Err(format!("Invalid condition \"{}\"", text)),
+        },
+        ["if", x, "key", "then"] if reg(x).is_some() => Instruction::Sknp(reg(x).unwrap()),
+        ["if", x, "-key", "then"] if reg(x).is_some() => Instruction::Skp(reg(x).unwrap()),
+        [x, op, rest @ ..] if reg(x).is_some() => {
+            let x = reg(x).unwrap();
+            match (*op, rest) {
+                (":=", ["random", nn]) => Instruction::Rnd(x, byte(nn)?),
+                (":=", ["delay"]) => Instruction::LdVxDt(x),
+                (":=", ["key"]) => Instruction::LdVxK(x),
+                (":=", [y]) => match reg(y) {
+                    Some(y) => Instruction::LdVxVy(x, y),
+                    None => Instruction::LdVxNn(x, byte(y)?),
+                },
+                ("+=", [y]) => match reg(y) {
+                    Some(y) => Instruction::AddVxVy(x, y),
+                    None => Instruction::AddVxNn(x, byte(y)?),
+                },
+                // there is no subtract immediate, so add the negated byte
+                ("-=", [y]) => match reg(y) {
+                    Some(y) => Instruction::Sub(x, y),
+                    None => Instruction::AddVxNn(x, byte(y)?.wrapping_neg()),
+                },
+                ("=-", [y]) if reg(y).is_some() => Instruction::Subn(x, reg(y).unwrap()),
+                ("|=", [y]) if reg(y).is_some() => Instruction::Or(x, reg(y).unwrap()),
+                ("&=", [y]) if reg(y).is_some() => Instruction::And(x, reg(y).unwrap()),
+                ("^=", [y]) if reg(y).is_some() => Instruction::Xor(x, reg(y).unwrap()),
+                (">>=", [y]) if reg(y).is_some() => Instruction::Shr(x, reg(y).unwrap()),
+                ("<<=", [y]) if reg(y).is_some() => Instruction::Shl(x, reg(y).unwrap()),
+                _ => return Err(format!("Unknown statement \"{}\"", text)),
+            }
+        }
+        _ => return Err(format!("Unknown statement \"{}\"", text)),
+    };
+    Ok(instruction)
+}
+
+// a word of Octo source and the line it is on
+struct Token<'a> {
+    text: &'a str,
+    line: usize,
+}
+
+// the words of the source with the "#" comments taken out
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for (index, line) in source.lines().enumerate() {
+        let line = match line.find('#') {
+            Some(pos) => &line[..pos],
+            None => line,
+        };
+        tokens.extend(line.split_whitespace().map(|text| Token {
+            text,
+            line: index + 1,
+        }));
+    }
+    tokens
+}
+
+// something that goes in the rom, statements and bytes are encoded once
+// every label is known
+enum Item {
+    // a statement that assembles to one instruction, a condition that is
+    // inverted skips when it is true
+    Statement {
+        tokens: Vec<String>,
+        line: usize,
+        invert: bool,
+    },
+    Byte {
+        token: String,
+        line: usize,
+    },
+    // a jump made by the control flow, the target is filled in when the end
+    // of the block is reached
+    Jump(usize),
+    // pad with zeros up to an address
+    Org(usize),
+}
+
+impl Item {
+    fn size(&self) -> usize {
+        match self {
+            Item::Byte { .. } => 1,
+            Item::Org(_) => 0,
+            _ => 2,
+        }
+    }
+}
+
+// add an item at the address, which moves on past it
+fn push(items: &mut Vec<Item>, addr: &mut usize, item: Item) {
+    *addr += item.size();
+    items.push(item);
+}
+
+// an open loop, if or else, closed by again or end
+enum Block {
+    Loop { start: usize, breaks: Vec<usize> },
+    If { jump: usize },
+    Else { jump: usize },
+}
+
+// the number of tokens in the statement that starts with these tokens
+fn statement_len(tokens: &[&str], is_reg: &dyn Fn(&str) -> bool) -> usize {
+    match tokens {
+        ["i", ":=", "hex", ..] => 4,
+        ["i", ..] | ["delay", ..] | ["buzzer", ..] => 3,
+        ["sprite", ..] => 4,
+        [x, ":=", "random", ..] if is_reg(x) => 4,
+        [x, ..] if is_reg(x) => 3,
+        ["save" | "load" | "bcd" | "jump" | "jump0" | ":call" | "native" | "scroll-down", ..] => 2,
+        _ => 1,
+    }
+}
+
+// the skip that is the opposite of a condition
+fn invert(instruction: Instruction) -> Instruction {
+    match instruction {
+        Instruction::SeVxNn(x, nn) => Instruction::SneVxNn(x, nn),
+        Instruction::SneVxNn(x, nn) => Instruction::SeVxNn(x, nn),
+        Instruction::SeVxVy(x, y) => Instruction::SneVxVy(x, y),
+        Instruction::SneVxVy(x, y) => Instruction::SeVxVy(x, y),
+        Instruction::Skp(x) => Instruction::Sknp(x),
+        Instruction::Sknp(x) => Instruction::Skp(x),
+        instruction => instruction,
+    }
+}
+
+// assemble an Octo program into a rom. Supported are labels, :const, :alias,
+// :call, :org, :byte, :unpack, sprite data written as numbers, calls made by
+// naming a label, the statements of parse_statement and the loop, while,
+// if then and if begin else end control flow. Execution starts at the main
+// label
+pub fn assemble(source: &str) -> Result<Vec<u8>, String> {
+    let tokens = tokenize(source);
+    let error = |line: usize, e: &dyn std::fmt::Display| trf("asm_error", &[&line, e]);
+
+    let mut labels: HashMap<String, i64> = HashMap::new();
+    let mut consts: HashMap<String, i64> = HashMap::new();
+    let mut aliases: HashMap<String, String> = HashMap::new();
+
+    // the first item is the jump to main
+    let mut items: Vec<Item> = vec![Item::Statement {
+        tokens: vec!["jump".to_string(), "main".to_string()],
+        line: 1,
+        invert: false,
+    }];
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut addr = PROGRAM_START + 2;
+
+    let mut pos = 0;
+    while pos < tokens.len() {
+        let line = tokens[pos].line;
+        // registers can be referred to by their aliases
+        let resolved: Vec<&str> = tokens[pos..]
+            .iter()
+            .take(5)
+            .map(|token| {
+                aliases
+                    .get(token.text)
+                    .map_or(token.text, |reg| reg.as_str())
+            })
+            .collect();
+        let operand = |index: usize| {
+            resolved
+                .get(index)
+                .copied()
+                .ok_or_else(|| error(line, &trf("octo_missing_operand", &[&resolved[0]])))
+        };
+        let value = |text: &str| {
+            parse_number(text)
+                .ok()
+                .or_else(|| consts.get(text).copied())
+                .ok_or_else(|| error(line, &trf("asm_unknown_value", &[&text])))
+        };
+        let is_reg = |text: &str| parse_register(text).is_some();
+
+        let statement = |tokens: &[&str], invert: bool| Item::Statement {
+            tokens: tokens.iter().map(|token| token.to_string()).collect(),
+            line,
+            invert,
+        };
+
+        let used = match resolved[0] {
+            ":" => {
+                let name = operand(1)?;
+                if labels.insert(name.to_string(), addr as i64).is_some() {
+                    return Err(error(line, &trf("asm_duplicate_label", &[&name])));
+                }
+                2
+            }
+            ":const" => {
+                let val = value(operand(2)?)?;
+                consts.insert(operand(1)?.to_string(), val);
+                3
+            }
+            ":alias" => {
+                let reg = operand(2)?;
+                if !is_reg(reg) {
+                    return Err(error(line, &trf("octo_not_a_register", &[&reg])));
+                }
+                aliases.insert(operand(1)?.to_string(), reg.to_string());
+                3
+            }
+            ":org" => {
+                let target = value(operand(1)?)?;
+                if target < addr as i64 || target > MEM_SIZE as i64 {
+                    return Err(error(line, &trf("octo_bad_org", &[&operand(1)?])));
+                }
+                push(&mut items, &mut addr, Item::Org(target as usize));
+                addr = target as usize;
+                2
+            }
+            ":byte" => {
+                push(
+                    &mut items,
+                    &mut addr,
+                    Item::Byte {
+                        token: operand(1)?.to_string(),
+                        line,
+                    },
+                );
+                2
+            }
+            // v0 := the high nibble and the top 4 bits of the address, v1 :=
+            // the low byte of the address
+            ":unpack" => {
+                let nibble = value(operand(1)?)?;
+                let label = operand(2)?;
+                let high = format!("{}>>8|{}", label, nibble << 4);
+                let low = format!("{}&255", label);
+                push(
+                    &mut items,
+                    &mut addr,
+                    statement(&["v0", ":=", &high], false),
+                );
+                push(&mut items, &mut addr, statement(&["v1", ":=", &low], false));
+                3
+            }
+            "loop" => {
+                blocks.push(Block::Loop {
+                    start: addr,
+                    breaks: Vec::new(),
+                });
+                1
+            }
+            "again" => match blocks.pop() {
+                Some(Block::Loop { start, breaks }) => {
+                    push(&mut items, &mut addr, Item::Jump(start));
+                    for index in breaks {
+                        items[index] = Item::Jump(addr);
+                    }
+                    1
+                }
+                _ => return Err(error(line, &trf("octo_unmatched", &[&"again"]))),
+            },
+            "while" | "if" => {
+                let len = if matches!(resolved.get(2), Some(&"key") | Some(&"-key")) {
+                    3
+                } else {
+                    4
+                };
+                let condition: Vec<&str> = (0..len).map(&operand).collect::<Result<_, _>>()?;
+                let mut condition = condition;
+                condition[0] = "if";
+                condition.push("then");
+
+                if resolved[0] == "while" {
+                    // leave the loop when the condition is false
+                    push(&mut items, &mut addr, statement(&condition, true));
+                    let index = items.len();
+                    push(&mut items, &mut addr, Item::Jump(0));
+                    match blocks
+                        .iter_mut()
+                        .rev()
+                        .find(|block| matches!(block, Block::Loop { .. }))
+                    {
+                        Some(Block::Loop { breaks, .. }) => breaks.push(index),
+                        _ => return Err(error(line, &trf("octo_unmatched", &[&"while"]))),
+                    }
+                    len
+                } else {
+                    match operand(len)? {
+                        "then" => {
+                            push(&mut items, &mut addr, statement(&condition, false));
+                        }
+                        "begin" => {
+                            push(&mut items, &mut addr, statement(&condition, true));
+                            blocks.push(Block::If { jump: items.len() });
+                            push(&mut items, &mut addr, Item::Jump(0));
+                        }
+                        other => return Err(error(line, &trf("octo_expected_then", &[&other]))),
+                    }
+                    len + 1
+                }
+            }
+            "else" => match blocks.pop() {
+                Some(Block::If { jump }) => {
+                    blocks.push(Block::Else { jump: items.len() });
+                    push(&mut items, &mut addr, Item::Jump(0));
+                    items[jump] = Item::Jump(addr);
+                    1
+                }
+                _ => return Err(error(line, &trf("octo_unmatched", &[&"else"]))),
+            },
+            "end" => match blocks.pop() {
+                Some(Block::If { jump }) | Some(Block::Else { jump }) => {
+                    items[jump] = Item::Jump(addr);
+                    1
+                }
+                _ => return Err(error(line, &trf("octo_unmatched", &[&"end"]))),
+            },
+            text if parse_number(text).is_ok() || consts.contains_key(text) => {
+                push(
+                    &mut items,
+                    &mut addr,
+                    Item::Byte {
+                        token: text.to_string(),
+                        line,
+                    },
+                );
+                1
+            }
+            _ => {
+                let len = statement_len(&resolved, &is_reg);
+                let words: Vec<&str> = (0..len).map(&operand).collect::<Result<_, _>>()?;
+                // a name on its own calls the label
+                let known = parse_statement(&words.join(" "), &|_| Some(0)).is_ok();
+                if len == 1 && !known {
+                    push(
+                        &mut items,
+                        &mut addr,
+                        statement(&[":call", words[0]], false),
+                    );
+                } else {
+                    push(&mut items, &mut addr, statement(&words, false));
+                }
+                len
+            }
+        };
+        pos += used;
+    }
+
+    if !blocks.is_empty() {
+        return Err(trf("octo_unclosed", &[]));
+    }
+    if !labels.contains_key("main") {
+        return Err(trf("octo_no_main", &[]));
+    }
+    if addr > MEM_SIZE {
+        return Err(trf("asm_too_big", &[&(addr - PROGRAM_START)]));
+    }
+
+    // the second pass encodes everything with every label known
+    let symbols = |name: &str| {
+        labels
+            .get(name)
+            .or_else(|| consts.get(name))
+            .copied()
+            .or_else(|| unpack_value(name, &labels, &consts))
+    };
+    let mut rom = Vec::new();
+    for item in &items {
+        match item {
+            Item::Statement {
+                tokens,
+                line,
+                invert: inverted,
+            } => {
+                let instruction =
+                    parse_statement(&tokens.join(" "), &symbols).map_err(|e| error(*line, &e))?;
+                let instruction = if *inverted {
+                    invert(instruction)
+                } else {
+                    instruction
+                };
+                let opcode = instruction.encode();
+                rom.extend([(opcode >> 8) as u8, opcode as u8]);
+            }
+            Item::Byte { token, line } => {
+                let val = parse_number(token)
+                    .ok()
+                    .or_else(|| symbols(token))
+                    .ok_or_else(|| error(*line, &trf("asm_unknown_value", &[token])))?;
+                if !(-0x80..0x100).contains(&val) {
+                    return Err(error(*line, &trf("asm_out_of_range", &[token])));
+                }
+                rom.push(val as u8);
+            }
+            Item::Jump(target) => {
+                let opcode = Instruction::Jp(*target).encode();
+                rom.extend([(opcode >> 8) as u8, opcode as u8]);
+            }
+            Item::Org(target) => rom.resize(target - PROGRAM_START, 0),
+        }
+    }
+    Ok(rom)
+}
+
+// the parts of a label address made by :unpack, "name>>8|nibble" or
+// "name&255"
+fn unpack_value(
+    text: &str,
+    labels: &HashMap<String, i64>,
+    consts: &HashMap<String, i64>,
+) -> Option<i64> {
+    let lookup = |name: &str| labels.get(name).or_else(|| consts.get(name)).copied();
+    if let Some((name, nibble)) = text.split_once(">>8|") {
+        Some((lookup(name)? >> 8) | parse_number(nibble).ok()?)
+    } else {
+        let name = text.strip_suffix("&255")?;
+        Some(lookup(name)? & 0xFF)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unpack_loads_a_label_address() {
+        let source = "
+            : main
+              :unpack 0xA sprite
+              i := sprite
+              loop again
+            : sprite
+              0xF0 0x90
+        ";
+        // v0 gets the nibble and the top of the address, v1 the rest
+        assert_eq!(
+            assemble(source),
+            Ok(vec![
+                0x12, 0x02, 0x60, 0xA2, 0x61, 0x0A, 0xA2, 0x0A, 0x12, 0x08, 0xF0, 0x90,
+            ])
+        );
+    }
+
+    #[test]
+    fn control_flow_becomes_skips_and_jumps() {
+        let source = "
+            :const LIMIT 5
+            :alias count v3
+            : main
+              count := 0
+              loop
+                count += 1
+                while count != LIMIT
+                if count == 2 then v4 := 1
+                if count key begin
+                  draw
+                else
+                  v5 := 2
+                end
+              again
+              jump main
+            : draw
+              return
+        ";
+        assert_eq!(
+            assemble(source),
+            Ok(vec![
+                0x12, 0x02, 0x63, 0x00, 0x73, 0x01, 0x43, 0x05, 0x12, 0x1A, 0x43, 0x02, 0x64, 0x01,
+                0xE3, 0x9E, 0x12, 0x16, 0x22, 0x1C, 0x12, 0x18, 0x65, 0x02, 0x12, 0x04, 0x12, 0x02,
+                0x00, 0xEE,
+            ])
+        );
+    }
+
+    #[test]
+    fn statements_parse_to_instructions() {
+        let symbols = |name: &str| (name == "x").then_some(0x345);
+        let parse = |text: &str| parse_statement(text, &symbols);
+        assert_eq!(parse("sprite v0 v1 5"), Ok(Instruction::Drw(0, 1, 5)));
+        assert_eq!(parse("i := x"), Ok(Instruction::LdI(0x345)));
+        assert_eq!(parse("v2 -= 3"), Ok(Instruction::AddVxNn(2, 0xFD)));
+        assert_eq!(parse("va := random 0x0F"), Ok(Instruction::Rnd(0xA, 0x0F)));
+        assert!(parse("v0 := 256").is_err());
+        assert!(parse("jump y").is_err());
+    }
+
+    #[test]
+    fn mistakes_are_errors() {
+        assert_eq!(assemble(": start clear"), Err(trf("octo_no_main", &[])));
+        assert_eq!(assemble(": main loop"), Err(trf("octo_unclosed", &[])));
+        assert_eq!(
+            assemble(": main\nagain"),
+            Err(trf("asm_error", &[&2, &trf("octo_unmatched", &[&"again"])]))
+        );
+        assert_eq!(
+            assemble(": main\n:alias x 5"),
+            Err(trf(
+                "asm_error",
+                &[&2, &trf("octo_not_a_register", &[&"5"])]
+            ))
+        );
+    }
+}
@@ -0,0 +1,502 @@
+use std::fmt;
+
+use crate::expr::parse_number;
+use crate::expr::parse_register;
+
+// a decoded chip8 instruction, the interpreter, disassembler and debugger all
+// decode opcodes through this so they can never disagree
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    // 0NNN
+    Sys(usize),
+    // 00E0
+    Cls,
+    // 00EE
+    Ret,
+    // 00CN, super chip
+    Scd(u8),
+    // 00FB, super chip
+    Scr,
+    // 00FC, super chip
+    Scl,
+    // 00FD, super chip
+    Exit,
+    // 00FE, super chip
+    Low,
+    // 00FF, super chip
+    High,
+    // 1NNN
+    Jp(usize),
+    // 2NNN
+    Call(usize),
+    // 3XNN
+    SeVxNn(usize, u8),
+    // 4XNN
+    SneVxNn(usize, u8),
+    // 5XY0
+    SeVxVy(usize, usize),
+    // 6XNN
+    LdVxNn(usize, u8),
+    // 7XNN
+    AddVxNn(usize, u8),
+    // 8XY0
+    LdVxVy(usize, usize),
+    // 8XY1
+    Or(usize, usize),
+    // 8XY2
+    And(usize, usize),
+    // 8XY3
+    Xor(usize, usize),
+    // 8XY4
+    AddVxVy(usize, usize),
+    // 8XY5
+    Sub(usize, usize),
+    // 8XY6
+    Shr(usize, usize),
+    // 8XY7
+    Subn(usize, usize),
+    // 8XYE
+    Shl(usize, usize),
+    // 9XY0
+    SneVxVy(usize, usize),
+    // ANNN
+    LdI(usize),
+    // BNNN
+    JpV0(usize),
+    // CXNN
+    Rnd(usize, u8),
+    // DXYN, a height of 0 draws a 16x16 super chip sprite
+    Drw(usize, usize, u8),
+    // EX9E
+    Skp(usize),
+    // EXA1
+    Sknp(usize),
+    // FX07
+    LdVxDt(usize),
+    // FX0A
+    LdVxK(usize),
+    // FX15
+    LdDtVx(usize),
+    // FX18
+    LdStVx(usize),
+    // FX1E
+    AddIVx(usize),
+    // FX29
+    LdFVx(usize),
+    // FX30, super-chip
+    LdHfVx(usize),
+    // FX33
+    LdBVx(usize),
+    // FX3A, xo-chip
+    LdPitchVx(usize),
+    // FXB0, the banking extension
+    LdBankVx(usize),
+    // FX55
+    LdIVx(usize),
+    // FX65
+    LdVxI(usize),
+    // anything else
+    Invalid(u16),
+}
+
+impl Instruction {
+    pub fn decode(opcode: u16) -> Instruction {
+        let a = ((opcode >> 12) & 0xF) as u8;
+        let x = ((opcode >> 8) & 0xF) as usize;
+        let y = ((opcode >> 4) & 0xF) as usize;
+        let n = (opcode & 0xF) as u8;
+
+        let nn = (opcode & 0xFF) as u8;
+        let nnn = (opcode & 0xFFF) as usize;
+
+        match a {
+            0x0 => match nnn {
+                0x0E0 => Instruction::Cls,
+                0x0EE => Instruction::Ret,
+                0x0C0..=0x0CF => Instruction::Scd(n),
+                0x0FB => Instruction::Scr,
+                0x0FC => Instruction::Scl,
+                0x0FD => Instruction::Exit,
+                0x0FE => Instruction::Low,
+                0x0FF => Instruction::High,
+                _ => Instruction::Sys(nnn),
+            },
+            0x1 => Instruction::Jp(nnn),
+            0x2 => Instruction::Call(nnn),
+            0x3 => Instruction::SeVxNn(x, nn),
+            0x4 => Instruction::SneVxNn(x, nn),
+            0x5 => Instruction::SeVxVy(x, y),
+            0x6 => Instruction::LdVxNn(x, nn),
+            0x7 => Instruction::AddVxNn(x, nn),
+            0x8 => match n {
+                0x0 => Instruction::LdVxVy(x, y),
+                0x1 => Instruction::Or(x, y),
+                0x2 => Instruction::And(x, y),
+                0x3 => Instruction::Xor(x, y),
+                0x4 => Instruction::AddVxVy(x, y),
+                0x5 => Instruction::Sub(x, y),
+                0x6 => Instruction::Shr(x, y),
+                0x7 => Instruction::Subn(x, y),
+                0xE => Instruction::Shl(x, y),
+                _ => Instruction::Invalid(opcode),
+            },
+            0x9 => Instruction::SneVxVy(x, y),
+            0xA => Instruction::LdI(nnn),
+            0xB => Instruction::JpV0(nnn),
+            0xC => Instruction::Rnd(x, nn),
+            0xD => Instruction::Drw(x, y, n),
+            0xE => match nn {
+                0x9E => Instruction::Skp(x),
+                0xA1 => Instruction::Sknp(x),
+                _ => Instruction::Invalid(opcode),
+            },
+            0xF => match nn {
+                0x07 => Instruction::LdVxDt(x),
+                0x0A => Instruction::LdVxK(x),
+                0x15 => Instruction::LdDtVx(x),
+                0x18 => Instruction::LdStVx(x),
+                0x1E => Instruction::AddIVx(x),
+                0x29 => Instruction::LdFVx(x),
+                0x30 => Instruction::LdHfVx(x),
+                0x33 => Instruction::LdBVx(x),
+                0x3A => Instruction::LdPitchVx(x),
+                0xB0 => Instruction::LdBankVx(x),
+                0x55 => Instruction::LdIVx(x),
+                0x65 => Instruction::LdVxI(x),
+                _ => Instruction::Invalid(opcode),
+            },
+            _ => Instruction::Invalid(opcode),
+        }
+    }
+
+    // a one line description of what the instruction does
+    pub fn explain(&self) -> String {
+        match *self {
+            Instruction::Sys(nnn) => format!("Call machine code routine at {:#05X}", nnn),
+            Instruction::Cls => "Clear the screen".to_string(),
+            Instruction::Ret => "Return from a subroutine".to_string(),
+            Instruction::Scd(n) => format!("Scroll the display down by {} pixels", n),
+            Instruction::Scr => "Scroll the display right by 4 pixels".to_string(),
+            Instruction::Scl => "Scroll the display left by 4 pixels".to_string(),
+            Instruction::Exit => "Stop the program".to_string(),
+            Instruction::Low => "Switch to the 64x32 low resolution display".to_string(),
+            Instruction::High => "Switch to the 128x64 high resolution display".to_string(),
+            Instruction::Jp(nnn) => format!("Jump to {:#05X}", nnn),
+            Instruction::Call(nnn) => format!("Call the subroutine at {:#05X}", nnn),
+            Instruction::SeVxNn(x, nn) => {
+                format!("Skip the next instruction if V{:X} == {:#04X}", x, nn)
+            }
+            Instruction::SneVxNn(x, nn) => {
+                format!("Skip the next instruction if V{:X} != {:#04X}", x, nn)
+            }
+            Instruction::SeVxVy(x, y) => {
+                format!("Skip the next instruction if V{:X} == V{:X}", x, y)
+            }
+            Instruction::LdVxNn(x, nn) => format!("Set V{:X} to {:#04X}", x, nn),
+            Instruction::AddVxNn(x, nn) => {
+                format!("Add {:#04X} to V{:X}, the carry flag is not changed", nn, x)
+            }
+            Instruction::LdVxVy(x, y) => format!("Set V{:X} to V{:X}", x, y),
+            Instruction::Or(x, y) => format!("Set V{:X} to V{:X} or V{:X}", x, x, y),
+            Instruction::And(x, y) => format!("Set V{:X} to V{:X} and V{:X}", x, x, y),
+            Instruction::Xor(x, y) => format!("Set V{:X} to V{:X} xor V{:X}", x, x, y),
+            Instruction::AddVxVy(x, y) => {
+                format!("Add V{:X} to V{:X}, VF is set to the carry", y, x)
+            }
+            Instruction::Sub(x, y) => {
+                format!("Subtract V{:X} from V{:X}, VF is set to not borrow", y, x)
+            }
+            Instruction::Shr(x, _) => format!(
+                "Shift V{:X} right by one, VF is set to the bit shifted out",
+                x
+            ),
+            Instruction::Subn(x, y) => format!(
+                "Set V{:X} to V{:X} minus V{:X}, VF is set to not borrow",
+                x, y, x
+            ),
+            Instruction::Shl(x, _) => format!(
+                "Shift V{:X} left by one, VF is set to the bit shifted out",
+                x
+            ),
+            Instruction::SneVxVy(x, y) => {
+                format!("Skip the next instruction if V{:X} != V{:X}", x, y)
+            }
+            Instruction::LdI(nnn) => format!("Set I to {:#05X}", nnn),
+            Instruction::JpV0(nnn) => format!("Jump to {:#05X} plus V0", nnn),
+            Instruction::Rnd(x, nn) => format!("Set V{:X} to a random number and {:#04X}", x, nn),
+            Instruction::Drw(x, y, 0) => format!(
+                "Draw the 16x16 sprite at I to V{:X}, V{:X}, VF is set on collision",
+                x, y
+            ),
+            Instruction::Drw(x, y, n) => format!(
+                "Draw the {} byte sprite at I to V{:X}, V{:X}, VF is set on collision",
+                n, x, y
+            ),
+            Instruction::Skp(x) => format!(
+                "Skip the next instruction if the key in V{:X} is pressed",
+                x
+            ),
+            Instruction::Sknp(x) => format!(
+                "Skip the next instruction if the key in V{:X} is not pressed",
+                x
+            ),
+            Instruction::LdVxDt(x) => format!("Set V{:X} to the delay timer", x),
+            Instruction::LdVxK(x) => format!("Wait for a key press and store it in V{:X}", x),
+            Instruction::LdDtVx(x) => format!("Set the delay timer to V{:X}", x),
+            Instruction::LdStVx(x) => format!("Set the sound timer to V{:X}", x),
+            Instruction::AddIVx(x) => format!("Add V{:X} to I", x),
+            Instruction::LdFVx(x) => format!("Set I to the font sprite for the digit in V{:X}", x),
+            Instruction::LdHfVx(x) => {
+                format!("Set I to the big font sprite for the digit in V{:X}", x)
+            }
+            Instruction::LdBVx(x) => {
+                format!("Store the decimal digits of V{:X} at I, I+1 and I+2", x)
+            }
+            Instruction::LdPitchVx(x) => format!("Set the buzzer pitch to V{:X}", x),
+            Instruction::LdBankVx(x) => {
+                format!("Map bank V{:X} of the rom into memory from 0x800", x)
+            }
+            Instruction::LdIVx(x) => format!("Store V0 to V{:X} in memory starting at I", x),
+            Instruction::LdVxI(x) => format!("Load V0 to V{:X} from memory starting at I", x),
+            Instruction::Invalid(opcode) => format!("Invalid opcode {:04X}", opcode),
+        }
+    }
+}
+
+// the instruction as an assembly mnemonic, e.g. "LD V3, 0x0A"
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::Sys(nnn) => write!(f, "SYS {:#05X}", nnn),
+            Instruction::Cls => write!(f, "CLS"),
+            Instruction::Ret => write!(f, "RET"),
+            Instruction::Scd(n) => write!(f, "SCD {}", n),
+            Instruction::Scr => write!(f, "SCR"),
+            Instruction::Scl => write!(f, "SCL"),
+            Instruction::Exit => write!(f, "EXIT"),
+            Instruction::Low => write!(f, "LOW"),
+            Instruction::High => write!(f, "HIGH"),
+            Instruction::Jp(nnn) => write!(f, "JP {:#05X}", nnn),
+            Instruction::Call(nnn) => write!(f, "CALL {:#05X}", nnn),
+            Instruction::SeVxNn(x, nn) => write!(f, "SE V{:X}, {:#04X}", x, nn),
+            Instruction::SneVxNn(x, nn) => write!(f, "SNE V{:X}, {:#04X}", x, nn),
+            Instruction::SeVxVy(x, y) => write!(f, "SE V{:X}, V{:X}", x, y),
+            Instruction::LdVxNn(x, nn) => write!(f, "LD V{:X}, {:#04X}", x, nn),
+            Instruction::AddVxNn(x, nn) => write!(f, "ADD V{:X}, {:#04X}", x, nn),
+            Instruction::LdVxVy(x, y) => write!(f, "LD V{:X}, V{:X}", x, y),
+            Instruction::Or(x, y) => write!(f, "OR V{:X}, V{:X}", x, y),
+            Instruction::And(x, y) => write!(f, "AND V{:X}, V{:X}", x, y),
+            Instruction::Xor(x, y) => write!(f, "XOR V{:X}, V{:X}", x, y),
+            Instruction::AddVxVy(x, y) => write!(f, "ADD V{:X}, V{:X}", x, y),
+            Instruction::Sub(x, y) => write!(f, "SUB V{:X}, V{:X}", x, y),
+            Instruction::Shr(x, y) => write!(f, "SHR V{:X}, V{:X}", x, y),
+            Instruction::Subn(x, y) => write!(f, "SUBN V{:X}, V{:X}", x, y),
+            Instruction::Shl(x, y) => write!(f, "SHL V{:X}, V{:X}", x, y),
+            Instruction::SneVxVy(x, y) => write!(f, "SNE V{:X}, V{:X}", x, y),
+            Instruction::LdI(nnn) => write!(f, "LD I, {:#05X}", nnn),
+            Instruction::JpV0(nnn) => write!(f, "JP V0, {:#05X}", nnn),
+            Instruction::Rnd(x, nn) => write!(f, "RND V{:X}, {:#04X}", x, nn),
+            Instruction::Drw(x, y, n) => write!(f, "DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::Skp(x) => write!(f, "SKP V{:X}", x),
+            Instruction::Sknp(x) => write!(f, "SKNP V{:X}", x),
+            Instruction::LdVxDt(x) => write!(f, "LD V{:X}, DT", x),
+            Instruction::LdVxK(x) => write!(f, "LD V{:X}, K", x),
+            Instruction::LdDtVx(x) => write!(f, "LD DT, V{:X}", x),
+            Instruction::LdStVx(x) => write!(f, "LD ST, V{:X}", x),
+            Instruction::AddIVx(x) => write!(f, "ADD I, V{:X}", x),
+            Instruction::LdFVx(x) => write!(f, "LD F, V{:X}", x),
+            Instruction::LdHfVx(x) => write!(f, "LD HF, V{:X}", x),
+            Instruction::LdBVx(x) => write!(f, "LD B, V{:X}", x),
+            Instruction::LdPitchVx(x) => write!(f, "LD PITCH, V{:X}", x),
+            Instruction::LdBankVx(x) => write!(f, "LD BANK, V{:X}", x),
+            Instruction::LdIVx(x) => write!(f, "LD [I], V{:X}", x),
+            Instruction::LdVxI(x) => write!(f, "LD V{:X}, [I]", x),
+            Instruction::Invalid(opcode) => write!(f, "DW {:#06X}", opcode),
+        }
+    }
+}
+
+// an operand of an assembly mnemonic
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Operand {
+    V(usize),
+    I,
+    IndirectI,
+    Dt,
+    St,
+    K,
+    F,
+    Hf,
+    B,
+    Pitch,
+    Bank,
+    Num(i64),
+}
+
+fn parse_operand(text: &str, symbols: &dyn Fn(&str) -> Option<i64>) -> Result<Operand, String> {
+    if let Some(index) = parse_register(text) {
+        return Ok(Operand::V(index));
+    }
+
+    match text.to_ascii_uppercase().as_str() {
+        "I" => Ok(Operand::I),
+        "[I]" => Ok(Operand::IndirectI),
+        "DT" => Ok(Operand::Dt),
+        "ST" => Ok(Operand::St),
+        "K" => Ok(Operand::K),
+        "F" => Ok(Operand::F),
+        "HF" => Ok(Operand::Hf),
+        "B" => Ok(Operand::B),
+        "PITCH" => Ok(Operand::Pitch),
+        "BANK" => Ok(Operand::Bank),
+        _ => parse_number(text)
+            .ok()
+            .or_else(|| symbols(text))
+            .map(Operand::Num)
+            .ok_or(format!("Unknown operand \"{}\"", text)),
+    }
+}
+
+// check that a number fits in the given number of bits
+fn fit(val: i64, bits: u32, text: &str) -> Result<i64, String> {
+    if val < 0 || val >= (1 << bits) {
+        Err(format!(
+            "{} does not fit in {} bits in \"{}\"",
+            val, bits, text
+        ))
+    } else {
+        Ok(val)
+    }
+}
+
+impl Instruction {
+    // parse an assembly mnemonic, e.g. "LD V3, 0x0A". Operands that are not
+    // numbers or registers are looked up with symbols, which lets an
+    // assembler resolve labels
+    pub fn parse(text: &str, symbols: &dyn Fn(&str) -> Option<i64>) -> Result<Instruction, String> {
+        let text = text.trim();
+        let (name, rest) = match text.find(char::is_whitespace) {
+            Some(pos) => (&text[..pos], text[pos..].trim()),
+            None => (text, ""),
+        };
+        let operands = if rest.is_empty() {
+            Vec::new()
+        } else {
+            rest.split(',')
+                .map(|operand| parse_operand(operand.trim(), symbols))
+                .collect::<Result<Vec<Operand>, String>>()?
+        };
+
+        use Operand::*;
+        let addr = |val: i64| fit(val, 12, text).map(|val| val as usize);
+        let byte = |val: i64| fit(val, 8, text).map(|val| val as u8);
+        let nibble = |val: i64| fit(val, 4, text).map(|val| val as u8);
+
+        let instruction = match (name.to_ascii_uppercase().as_str(), operands.as_slice()) {
+            ("CLS", []) => Instruction::Cls,
+            ("RET", []) => Instruction::Ret,
+            ("SCD", [Num(n)]) => Instruction::Scd(nibble(*n)?),
+            ("SCR", []) => Instruction::Scr,
+            ("SCL", []) => Instruction::Scl,
+            ("EXIT", []) => Instruction::Exit,
+            ("LOW", []) => Instruction::Low,
+            ("HIGH", []) => Instruction::High,
+            ("SYS", [Num(nnn)]) => Instruction::Sys(addr(*nnn)?),
+            ("JP", [Num(nnn)]) => Instruction::Jp(addr(*nnn)?),
+            ("JP", [V(0), Num(nnn)]) => Instruction::JpV0(addr(*nnn)?),
+            ("CALL", [Num(nnn)]) => Instruction::Call(addr(*nnn)?),
+            ("SE", [V(x), Num(nn)]) => Instruction::SeVxNn(*x, byte(*nn)?),
+            ("SE", [V(x), V(y)]) => Instruction::SeVxVy(*x, *y),
+            ("SNE", [V(x), Num(nn)]) => Instruction::SneVxNn(*x, byte(*nn)?),
+            ("SNE", [V(x), V(y)]) => Instruction::SneVxVy(*x, *y),
+            ("LD", [V(x), Num(nn)]) => Instruction::LdVxNn(*x, byte(*nn)?),
+            ("LD", [V(x), V(y)]) => Instruction::LdVxVy(*x, *y),
+            ("LD", [I, Num(nnn)]) => Instruction::LdI(addr(*nnn)?),
+            ("LD", [V(x), Dt]) => Instruction::LdVxDt(*x),
+            ("LD", [V(x), K]) => Instruction::LdVxK(*x),
+            ("LD", [Dt, V(x)]) => Instruction::LdDtVx(*x),
+            ("LD", [St, V(x)]) => Instruction::LdStVx(*x),
+            ("LD", [F, V(x)]) => Instruction::LdFVx(*x),
+            ("LD", [Hf, V(x)]) => Instruction::LdHfVx(*x),
+            ("LD", [B, V(x)]) => Instruction::LdBVx(*x),
+            ("LD", [Pitch, V(x)]) => Instruction::LdPitchVx(*x),
+            ("LD", [Bank, V(x)]) => Instruction::LdBankVx(*x),
+            ("LD", [IndirectI, V(x)]) => Instruction::LdIVx(*x),
+            ("LD", [V(x), IndirectI]) => Instruction::LdVxI(*x),
+            ("ADD", [V(x), Num(nn)]) => Instruction::AddVxNn(*x, byte(*nn)?),
+            ("ADD", [V(x), V(y)]) => Instruction::AddVxVy(*x, *y),
+            ("ADD", [I, V(x)]) => Instruction::AddIVx(*x),
+            ("OR", [V(x), V(y)]) => Instruction::Or(*x, *y),
+            ("AND", [V(x), V(y)]) => Instruction::And(*x, *y),
+            ("XOR", [V(x), V(y)]) => Instruction::Xor(*x, *y),
+            ("SUB", [V(x), V(y)]) => Instruction::Sub(*x, *y),
+            ("SUBN", [V(x), V(y)]) => Instruction::Subn(*x, *y),
+            ("SHR", [V(x)]) => Instruction::Shr(*x, *x),
+            ("SHR", [V(x), V(y)]) => Instruction::Shr(*x, *y),
+            ("SHL", [V(x)]) => Instruction::Shl(*x, *x),
+            ("SHL", [V(x), V(y)]) => Instruction::Shl(*x, *y),
+            ("RND", [V(x), Num(nn)]) => Instruction::Rnd(*x, byte(*nn)?),
+            ("DRW", [V(x), V(y), Num(n)]) => Instruction::Drw(*x, *y, nibble(*n)?),
+            ("SKP", [V(x)]) => Instruction::Skp(*x),
+            ("SKNP", [V(x)]) => Instruction::Sknp(*x),
+            ("DW", [Num(word)]) => Instruction::decode(fit(*word, 16, text)? as u16),
+            _ => return Err(format!("Unknown instruction \"{}\"", text)),
+        };
+        Ok(instruction)
+    }
+
+    // the opcode for the instruction, the inverse of decode()
+    pub fn encode(&self) -> u16 {
+        let xy =
+            |op: u16, x: usize, y: usize, n: u16| op | ((x as u16) << 8) | ((y as u16) << 4) | n;
+        let xnn = |op: u16, x: usize, nn: u8| op | ((x as u16) << 8) | nn as u16;
+
+        match *self {
+            Instruction::Sys(nnn) => nnn as u16,
+            Instruction::Cls => 0x00E0,
+            Instruction::Ret => 0x00EE,
+            Instruction::Scd(n) => 0x00C0 | n as u16,
+            Instruction::Scr => 0x00FB,
+            Instruction::Scl => 0x00FC,
+            Instruction::Exit => 0x00FD,
+            Instruction::Low => 0x00FE,
+            Instruction::High => 0x00FF,
+            Instruction::Jp(nnn) => 0x1000 | nnn as u16,
+            Instruction::Call(nnn) => 0x2000 | nnn as u16,
+            Instruction::SeVxNn(x, nn) => xnn(0x3000, x, nn),
+            Instruction::SneVxNn(x, nn) => xnn(0x4000, x, nn),
+            Instruction::SeVxVy(x, y) => xy(0x5000, x, y, 0x0),
+            Instruction::LdVxNn(x, nn) => xnn(0x6000, x, nn),
+            Instruction::AddVxNn(x, nn) => xnn(0x7000, x, nn),
+            Instruction::LdVxVy(x, y) => xy(0x8000, x, y, 0x0),
+            Instruction::Or(x, y) => xy(0x8000, x, y, 0x1),
+            Instruction::And(x, y) => xy(0x8000, x, y, 0x2),
+            Instruction::Xor(x, y) => xy(0x8000, x, y, 0x3),
+            Instruction::AddVxVy(x, y) => xy(0x8000, x, y, 0x4),
+            Instruction::Sub(x, y) => xy(0x8000, x, y, 0x5),
+            Instruction::Shr(x, y) => xy(0x8000, x, y, 0x6),
+            Instruction::Subn(x, y) => xy(0x8000, x, y, 0x7),
+            Instruction::Shl(x, y) => xy(0x8000, x, y, 0xE),
+            Instruction::SneVxVy(x, y) => xy(0x9000, x, y, 0x0),
+            Instruction::LdI(nnn) => 0xA000 | nnn as u16,
+            Instruction::JpV0(nnn) => 0xB000 | nnn as u16,
+            Instruction::Rnd(x, nn) => xnn(0xC000, x, nn),
+            Instruction::Drw(x, y, n) => xy(0xD000, x, y, n as u16),
+            Instruction::Skp(x) => xnn(0xE000, x, 0x9E),
+            Instruction::Sknp(x) => xnn(0xE000, x, 0xA1),
+            Instruction::LdVxDt(x) => xnn(0xF000, x, 0x07),
+            Instruction::LdVxK(x) => xnn(0xF000, x, 0x0A),
+            Instruction::LdDtVx(x) => xnn(0xF000, x, 0x15),
+            Instruction::LdStVx(x) => xnn(0xF000, x, 0x18),
+            Instruction::AddIVx(x) => xnn(0xF000, x, 0x1E),
+            Instruction::LdFVx(x) => xnn(0xF000, x, 0x29),
+            Instruction::LdHfVx(x) => xnn(0xF000, x, 0x30),
+            Instruction::LdBVx(x) => xnn(0xF000, x, 0x33),
+            Instruction::LdPitchVx(x) => xnn(0xF000, x, 0x3A),
+            Instruction::LdBankVx(x) => xnn(0xF000, x, 0xB0),
+            Instruction::LdIVx(x) => xnn(0xF000, x, 0x55),
+            Instruction::LdVxI(x) => xnn(0xF000, x, 0x65),
+            Instruction::Invalid(opcode) => opcode,
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::VecDeque;
+use std::net::UdpSocket;
+
+use crate::backend::Command;
+use crate::backend::InputSource;
+use crate::i18n::trf;
+
+// the largest udp packet read
+const MAX_PACKET: usize = 65536;
+
+// a message to the emulator from an open sound control address
+enum Message {
+    Key(usize, bool),
+    Command(Command),
+    // a button let go of, or a message missing its argument
+    Ignore,
+}
+
+// a remote control that listens for open sound control messages over udp,
+// so live performance and installation setups can press keys and control
+// the emulator. The addresses are
+//
+// /chip8/key/5 1       press key 5, 0 lets go of it and no argument taps it
+// /chip8/speed 800     set the clock speed
+// /chip8/turbo 1       hold or let go of the turbo, the same for rewind
+// /chip8/pause         and faster, slower, reset, screenshot and quit
+pub struct OscInput {
+    socket: UdpSocket,
+    keys: [bool; 0x10],
+
+    // key presses not made yet, see read_keys
+    pending: VecDeque<(usize, bool)>,
+}
+
+impl OscInput {
+    // listen on an address such as "127.0.0.1:9000"
+    pub fn bind(address: &str) -> Result<OscInput, String> {
+        let socket = UdpSocket::bind(address)
+            .and_then(|socket| socket.set_nonblocking(true).map(|_| socket))
+            .map_err(|e| trf("osc_bind_error", &[&address, &e]))?;
+        Ok(OscInput {
+            socket,
+            keys: [false; 0x10],
+            pending: VecDeque::new(),
+        })
+    }
+
+    // read the packets that came since the last poll
+    fn receive(&mut self) -> Vec<Command> {
+        let mut commands = Vec::new();
+        let mut buffer = vec![0; MAX_PACKET];
+        while let Ok(size) = self.socket.recv(&mut buffer) {
+            let mut messages = Vec::new();
+            parse_packet(&buffer[..size], &mut messages);
+            for (address, arguments) in messages {
+                match message(&address, &arguments) {
+                    Some(Message::Key(key, pressed)) => self.pending.push_back((key, pressed)),
+                    Some(Message::Command(command)) => commands.push(command),
+                    Some(Message::Ignore) => {}
+                    None => println!("{}", trf("osc_unknown_address", &[&address])),
+                }
+            }
+        }
+        commands
+    }
+}
+
+impl InputSource for OscInput {
+    // make the key presses that came since the last read. A key let go of in
+    // the same read it was pressed in waits for the next one, so the rom sees
+    // the press however quickly the messages come
+    fn read_keys(&mut self) -> [bool; 0x10] {
+        let mut pressed = [false; 0x10];
+        while let Some(&(key, down)) = self.pending.front() {
+            if !down && pressed[key] {
+                break;
+            }
+            self.pending.pop_front();
+            self.keys[key] = down;
+            pressed[key] |= down;
+        }
+        self.keys
+    }
+
+    fn poll_commands(&mut self) -> Vec<Command> {
+        self.receive()
+    }
+}
+
+// what an address does, buttons send 1 when pressed and 0 when let go so
+// the commands only happen on a 1 or with no argument
+fn message(address: &str, arguments: &[f32]) -> Option<Message> {
+    let path = address.strip_prefix("/chip8/")?;
+    let on = arguments.first().map(|value| *value != 0.0);
+    if let Some(key) = path.strip_prefix("key/") {
+        let key = usize::from_str_radix(key, 16)
+            .ok()
+            .filter(|key| *key < 0x10)?;
+        return match on {
+            Some(pressed) => Some(Message::Key(key, pressed)),
+            // tapping is a press straight away followed by a let go
+            None => Some(Message::Key(key, true)),
+        };
+    }
+    let command = match path {
+        "speed" => match arguments.first() {
+            Some(speed) => Command::SetClockspeed(*speed as u32),
+            None => return Some(Message::Ignore),
+        },
+        "turbo" => Command::Turbo(on.unwrap_or(true)),
+        "rewind" => Command::Rewind(on.unwrap_or(true)),
+        _ if on == Some(false) => return Some(Message::Ignore),
+        "pause" => Command::TogglePause,
+        "faster" => Command::Faster,
+        "slower" => Command::Slower,
+        "reset" => Command::Reset,
+        "screenshot" => Command::Screenshot,
+        "quit" => Command::Quit,
+        _ => return None,
+    };
+    Some(Message::Command(command))
+}
+
+// the messages of a packet, a bundle holds any number of packets. Only
+// number and true or false arguments are kept, other arguments are skipped
+fn parse_packet(data: &[u8], messages: &mut Vec<(String, Vec<f32>)>) {
+    if data.starts_with(b"#bundle\0") {
+        // the time tag is ignored, bundles play straight away
+        let mut offset = 16;
+        while let Some(size) = read_i32(data, offset) {
+            let start = offset + 4;
+            let Some(element) = data.get(start..start + size.max(0) as usize) else {
+                return;
+            };
+            parse_packet(element, messages);
+            offset = start + element.len();
+        }
+        return;
+    }
+
+    let Some((address, offset)) = read_string(data, 0) else {
+        return;
+    };
+    let mut arguments = Vec::new();
+    if let Some((tags, mut offset)) = read_string(data, offset) {
+        for tag in tags.chars().skip_while(|c| *c == ',') {
+            let argument = match tag {
+                'i' => read_i32(data, offset).map(|value| (value as f32, 4)),
+                'f' => read_i32(data, offset).map(|value| (f32::from_bits(value as u32), 4)),
+                'T' => Some((1.0, 0)),
+                'F' => Some((0.0, 0)),
+                's' | 'S' => {
+                    let Some((_, next)) = read_string(data, offset) else {
+                        break;
+                    };
+                    offset = next;
+                    continue;
+                }
+                'b' => {
+                    let Some(size) = read_i32(data, offset) else {
+                        break;
+                    };
+                    offset += 4 + (size.max(0) as usize).next_multiple_of(4);
+                    continue;
+                }
+                _ => break,
+            };
+            let Some((value, size)) = argument else {
+                break;
+            };
+            arguments.push(value);
+            offset += size;
+        }
+    }
+    messages.push((address, arguments));
+}
+
+// a string padded with zeros to a multiple of 4 bytes, and the offset after
+// it
+fn read_string(data: &[u8], offset: usize) -> Option<(String, usize)> {
+    let rest = data.get(offset..)?;
+    let end = rest.iter().position(|byte| *byte == 0)?;
+    let text = String::from_utf8_lossy(&rest[..end]).into_owned();
+    Some((text, offset + (end + 1).next_multiple_of(4)))
+}
+
+fn read_i32(data: &[u8], offset: usize) -> Option<i32> {
+    let bytes = data.get(offset..offset + 4)?;
+    Some(i32::from_be_bytes(bytes.try_into().ok()?))
+}
@@ -0,0 +1,276 @@
+use std::time::Duration;
+
+use crate::clock::Instant;
+use crate::library::Thumbnail;
+use crate::savestate::SaveState;
+
+// on screen display text is drawn with a 5x7 font, each glyph is stored as 7
+// rows where the low 5 bits are the pixels of the row
+pub const GLYPH_WIDTH: usize = 5;
+pub const GLYPH_HEIGHT: usize = 7;
+
+// glyphs for the printable ascii characters 0x20 (space) to 0x5F (underscore)
+// lowercase letters are drawn with the uppercase glyphs
+const GLYPH_FIRST: u8 = 0x20;
+const GLYPH_LAST: u8 = 0x5F;
+const GLYPHS: &[[u8; GLYPH_HEIGHT]; (GLYPH_LAST - GLYPH_FIRST + 1) as usize] = &[
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // " "
+    [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04], // "!"
+    [0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00], // """
+    [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A], // "#"
+    [0x04, 0x0F, 0x14, 0x0E, 0x05, 0x1E, 0x04], // "$"
+    [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03], // "%"
+    [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D], // "&"
+    [0x04, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00], // "'"
+    [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02], // "("
+    [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08], // ")"
+    [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00], // "*"
+    [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00], // "+"
+    [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08], // ","
+    [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00], // "-"
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C], // "."
+    [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00], // "/"
+    [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E], // "0"
+    [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E], // "1"
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F], // "2"
+    [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E], // "3"
+    [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02], // "4"
+    [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E], // "5"
+    [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E], // "6"
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08], // "7"
+    [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E], // "8"
+    [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C], // "9"
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00], // ":"
+    [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x04, 0x08], // ";"
+    [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02], // "<"
+    [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00], // "="
+    [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08], // ">"
+    [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // "?"
+    [0x0E, 0x11, 0x17, 0x15, 0x17, 0x10, 0x0F], // "@"
+    [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11], // "A"
+    [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E], // "B"
+    [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E], // "C"
+    [0x1E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1E], // "D"
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F], // "E"
+    [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10], // "F"
+    [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F], // "G"
+    [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11], // "H"
+    [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E], // "I"
+    [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C], // "J"
+    [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11], // "K"
+    [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F], // "L"
+    [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11], // "M"
+    [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11], // "N"
+    [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // "O"
+    [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10], // "P"
+    [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D], // "Q"
+    [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11], // "R"
+    [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E], // "S"
+    [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04], // "T"
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E], // "U"
+    [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04], // "V"
+    [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A], // "W"
+    [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11], // "X"
+    [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04], // "Y"
+    [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F], // "Z"
+    [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E], // "["
+    [0x00, 0x10, 0x08, 0x04, 0x02, 0x01, 0x00], // "\"
+    [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E], // "]"
+    [0x04, 0x0A, 0x11, 0x00, 0x00, 0x00, 0x00], // "^"
+    [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F], // "_"
+];
+
+// how long a toast message stays on screen
+const TOAST_DURATION: Duration = Duration::from_secs(3);
+
+// the maximum number of toasts shown at once, older ones are dropped
+const MAX_TOASTS: usize = 4;
+
+// get the glyph rows for a character, unknown characters are drawn as "?"
+pub fn glyph(c: char) -> &'static [u8; GLYPH_HEIGHT] {
+    let c = c.to_ascii_uppercase();
+    if c.is_ascii() && (GLYPH_FIRST..=GLYPH_LAST).contains(&(c as u8)) {
+        &GLYPHS[(c as u8 - GLYPH_FIRST) as usize]
+    } else {
+        &GLYPHS[(b'?' - GLYPH_FIRST) as usize]
+    }
+}
+
+// the recent sound drawn by the oscilloscope overlay
+#[derive(Clone, Debug, Default)]
+pub struct Scope {
+    // the last samples played, from -1 to 1
+    pub samples: Vec<f32>,
+
+    // the sound timer at each of the last updates, oldest first
+    pub timers: Vec<u8>,
+}
+
+// the save state slots shown to pick one to save to or load from
+#[derive(Clone, Debug, Default)]
+pub struct StateMenu {
+    // true to save to the picked slot, false to load from it
+    pub saving: bool,
+
+    // the state saved in each slot, None for an empty slot
+    pub slots: Vec<Option<SaveState>>,
+    pub selected: usize,
+
+    // the name typed for the slot when saving
+    pub name: String,
+}
+
+// how long the display from before a state was loaded takes to fade out
+const GHOST_DURATION: Duration = Duration::from_secs(1);
+
+// the display from before a state was loaded, fading out over the new one
+struct Ghost {
+    display: Thumbnail,
+    started: Instant,
+}
+
+struct Toast {
+    text: String,
+    expires: Instant,
+}
+
+// text drawn on top of the chip8 display
+#[derive(Default)]
+pub struct Osd {
+    toasts: Vec<Toast>,
+
+    // lines of text shown at the top of the screen until replaced
+    status: Vec<String>,
+
+    // a named block of text shown in the middle of the screen until hidden
+    panel: Option<(&'static str, Vec<String>)>,
+
+    // the oscilloscope shown in the top right corner, None if hidden
+    scope: Option<Scope>,
+
+    // the save state slots shown over the display, None if hidden
+    state_menu: Option<StateMenu>,
+
+    // the display before the last state was loaded while it fades out
+    ghost: Option<Ghost>,
+
+    // set when the visible text changed and the screen needs to be redrawn
+    dirty: bool,
+}
+
+impl Osd {
+    // show a short lived message at the bottom of the screen
+    pub fn toast(&mut self, text: &str) {
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+        self.toasts.push(Toast {
+            text: text.to_string(),
+            expires: Instant::now() + TOAST_DURATION,
+        });
+        self.dirty = true;
+    }
+
+    // remove any expired messages and fade the ghost
+    pub fn update(&mut self) {
+        let now = Instant::now();
+        let count = self.toasts.len();
+        self.toasts.retain(|toast| toast.expires > now);
+        if self.toasts.len() != count {
+            self.dirty = true;
+        }
+        if let Some(ref ghost) = self.ghost {
+            if now >= ghost.started + GHOST_DURATION {
+                self.ghost = None;
+            }
+            self.dirty = true;
+        }
+    }
+
+    // fade the display as it was out over the display for a second, shown
+    // after loading a state to see what changed
+    pub fn set_ghost(&mut self, display: Thumbnail) {
+        self.ghost = Some(Ghost {
+            display,
+            started: Instant::now(),
+        });
+        self.dirty = true;
+    }
+
+    // the display fading out and how much of it is left, from 1 down to 0
+    pub fn ghost(&self) -> Option<(&Thumbnail, f32)> {
+        self.ghost.as_ref().map(|ghost| {
+            let faded = ghost.started.elapsed().as_secs_f32() / GHOST_DURATION.as_secs_f32();
+            (&ghost.display, (1.0 - faded).max(0.0))
+        })
+    }
+
+    // show the panel, or hide it if the panel with this name is already shown
+    pub fn toggle_panel(&mut self, name: &'static str, lines: Vec<String>) {
+        match self.panel {
+            Some((shown, _)) if shown == name => self.panel = None,
+            _ => self.panel = Some((name, lines)),
+        }
+        self.dirty = true;
+    }
+
+    // show the panel, replacing any other one
+    pub fn show_panel(&mut self, name: &'static str, lines: Vec<String>) {
+        self.panel = Some((name, lines));
+        self.dirty = true;
+    }
+
+    // hide the panel if the panel with this name is shown
+    pub fn hide_panel(&mut self, name: &'static str) {
+        if matches!(self.panel, Some((shown, _)) if shown == name) {
+            self.panel = None;
+            self.dirty = true;
+        }
+    }
+
+    // replace the lines shown at the top of the screen, an empty list hides
+    // them
+    pub fn set_status(&mut self, lines: Vec<String>) {
+        self.status = lines;
+        self.dirty = true;
+    }
+
+    // replace the oscilloscope, None hides it
+    pub fn set_scope(&mut self, scope: Option<Scope>) {
+        self.scope = scope;
+        self.dirty = true;
+    }
+
+    pub fn scope(&self) -> Option<&Scope> {
+        self.scope.as_ref()
+    }
+
+    // replace the save state menu, None hides it
+    pub fn set_state_menu(&mut self, state_menu: Option<StateMenu>) {
+        self.state_menu = state_menu;
+        self.dirty = true;
+    }
+
+    pub fn state_menu(&self) -> Option<&StateMenu> {
+        self.state_menu.as_ref()
+    }
+
+    pub fn status(&self) -> &[String] {
+        &self.status
+    }
+
+    pub fn panel(&self) -> Option<&[String]> {
+        self.panel.as_ref().map(|(_, lines)| lines.as_slice())
+    }
+
+    pub fn toasts(&self) -> impl Iterator<Item = &str> {
+        self.toasts.iter().map(|toast| toast.text.as_str())
+    }
+
+    // returns true if the screen needs to be redrawn and resets the flag
+    pub fn take_dirty(&mut self) -> bool {
+        let dirty = self.dirty;
+        self.dirty = false;
+        dirty
+    }
+}
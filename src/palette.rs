@@ -0,0 +1,114 @@
+use crate::hotkeys::Action;
+use crate::hotkeys::Hotkeys;
+use crate::i18n::tr;
+
+// the most actions listed at once
+const MAX_MATCHES: usize = 8;
+
+// keys typed while the palette is open
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaletteKey {
+    Char(char),
+    Backspace,
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Escape,
+}
+
+// how well the query matches the text, or None if the letters of the query
+// don't all appear in order in the text. Letters next to each other and at
+// the start of words score higher
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last: Option<usize> = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = pos + text[pos..].iter().position(|t| *t == c)?;
+        if last.is_some_and(|last| last + 1 == found) {
+            score += 3;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        score -= (found - pos) as i32;
+        last = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+// a search box over the emulator actions, the best matches are listed below
+// it and the selected one runs on enter
+#[derive(Debug, Default)]
+pub struct Palette {
+    query: String,
+    selected: usize,
+}
+
+impl Palette {
+    // the actions that match the query, best first
+    pub fn matches(&self) -> Vec<Action> {
+        let mut scored: Vec<(i32, Action)> = Action::all()
+            .iter()
+            .filter(|action| action.in_palette())
+            .filter_map(|action| {
+                let text = format!("{} {}", action.description(), action.name());
+                fuzzy_score(&self.query, &text).map(|score| (score, *action))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        scored
+            .into_iter()
+            .map(|(_, action)| action)
+            .take(MAX_MATCHES)
+            .collect()
+    }
+
+    pub fn selected(&self) -> Option<Action> {
+        self.matches().get(self.selected).copied()
+    }
+
+    // edit the query or move the selection
+    pub fn key(&mut self, key: PaletteKey) {
+        match key {
+            PaletteKey::Char(c) => {
+                self.query.push(c);
+                self.selected = 0;
+            }
+            PaletteKey::Backspace => {
+                self.query.pop();
+                self.selected = 0;
+            }
+            PaletteKey::Up => self.selected = self.selected.saturating_sub(1),
+            PaletteKey::Down => {
+                let count = self.matches().len();
+                if self.selected + 1 < count {
+                    self.selected += 1;
+                }
+            }
+            PaletteKey::Left | PaletteKey::Right | PaletteKey::Enter | PaletteKey::Escape => {}
+        }
+    }
+
+    // the query and the matching actions with their keys, for the osd
+    pub fn lines(&self, hotkeys: &Hotkeys) -> Vec<String> {
+        let mut lines = vec![format!("> {}_", self.query)];
+        let matches = self.matches();
+        if matches.is_empty() {
+            lines.push(tr("palette_no_matches"));
+        }
+        for (index, action) in matches.iter().enumerate() {
+            let marker = if index == self.selected { ">" } else { " " };
+            let line = match hotkeys.key(*action) {
+                Some(key) => format!("{} {} ({})", marker, action.description(), key),
+                None => format!("{} {}", marker, action.description()),
+            };
+            lines.push(line);
+        }
+        lines
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::mpsc::Receiver;
+
+use crate::backend::Command;
+use crate::backend::InputSource;
+use crate::i18n::trf;
+
+// presses and lets go of keys from lines like "KEYDOWN 5" and "KEYUP 5" sent
+// by a script on stdin or a named pipe. The key is a hex digit
+pub struct PipeInput {
+    lines: Receiver<String>,
+    keys: [bool; 0x10],
+
+    // a line held back for the next read, see read_keys
+    held: Option<String>,
+}
+
+enum Event {
+    Down(usize),
+    Up(usize),
+}
+
+impl PipeInput {
+    pub fn new(lines: Receiver<String>) -> PipeInput {
+        PipeInput {
+            lines,
+            keys: [false; 0x10],
+            held: None,
+        }
+    }
+}
+
+impl InputSource for PipeInput {
+    // apply the lines sent since the last read. A key let go of in the same
+    // read it was pressed in is held back until the next one, so the rom
+    // sees the press however quickly the lines come
+    fn read_keys(&mut self) -> [bool; 0x10] {
+        let mut pressed = [false; 0x10];
+        while let Some(line) = self.held.take().or_else(|| self.lines.try_recv().ok()) {
+            match parse(&line) {
+                Some(Event::Down(key)) => {
+                    self.keys[key] = true;
+                    pressed[key] = true;
+                }
+                Some(Event::Up(key)) if pressed[key] => {
+                    self.held = Some(line);
+                    break;
+                }
+                Some(Event::Up(key)) => self.keys[key] = false,
+                None if line.trim().is_empty() => {}
+                None => println!("{}", trf("pipe_invalid_line", &[&line.trim()])),
+            }
+        }
+        self.keys
+    }
+
+    fn poll_commands(&mut self) -> Vec<Command> {
+        Vec::new()
+    }
+}
+
+fn parse(line: &str) -> Option<Event> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let [event, key] = words.as_slice() else {
+        return None;
+    };
+    let key = u8::from_str_radix(key.trim_start_matches("0x"), 16).ok()? as usize;
+    if key >= 0x10 {
+        return None;
+    }
+    match event.to_ascii_uppercase().as_str() {
+        "KEYDOWN" => Some(Event::Down(key)),
+        "KEYUP" => Some(Event::Up(key)),
+        _ => None,
+    }
+}
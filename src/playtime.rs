@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::i18n::trf;
+
+// the playtime is kept next to the config file in this file
+pub const FILE_NAME: &str = "chip8-playtime.toml";
+
+// the total number of seconds each rom has been played, e.g.
+//
+// [seconds]
+// 8a2b2b3a2e1b0a9e... = 3725
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Playtime {
+    // the sha1 of the rom to the seconds played
+    #[serde(default)]
+    seconds: BTreeMap<String, u64>,
+}
+
+impl Playtime {
+    // the total seconds the rom with this sha1 has been played
+    pub fn get(&self, sha1: &str) -> u64 {
+        self.seconds.get(sha1).copied().unwrap_or(0)
+    }
+
+    pub fn add(&mut self, sha1: &str, seconds: u64) {
+        *self.seconds.entry(sha1.to_string()).or_insert(0) += seconds;
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string(self).expect("a table is valid toml");
+        fs::write(path, text).map_err(|e| trf("file_write_error", &[&path.display(), &e]))
+    }
+}
+
+// the playtime file, nothing has been played if it doesn't exist yet
+pub fn load(path: &Path) -> Result<Playtime, String> {
+    if !path.exists() {
+        return Ok(Playtime::default());
+    }
+    let text =
+        fs::read_to_string(path).map_err(|e| trf("file_read_error", &[&path.display(), &e]))?;
+    toml::from_str(&text).map_err(|e| trf("file_invalid", &[&path.display(), &e]))
+}
+
+// a playtime as hours and minutes, e.g. "1h 05m" or "12m"
+pub fn format(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    if minutes < 60 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::config::Preset;
+use crate::i18n::trf;
+
+// the profiles are kept next to the config file in this file
+pub const FILE_NAME: &str = "chip8-profiles.toml";
+
+// the settings for each game, applied whenever the same rom is loaded. The
+// tables are named after the sha1 of the rom and hold the same settings as a
+// preset, e.g.
+//
+// [0a1b2c3d...]
+// clockspeed = 700
+// quirks = "schip"
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Profiles {
+    roms: BTreeMap<String, Preset>,
+}
+
+impl Profiles {
+    // the profile of the rom with this sha1
+    pub fn get(&self, sha1: &str) -> Option<&Preset> {
+        self.roms.get(sha1)
+    }
+
+    // change some of the settings of a rom's profile, making it if needed
+    pub fn update(&mut self, sha1: &str, changes: Preset) {
+        let profile = self.roms.remove(sha1).unwrap_or_default();
+        self.roms.insert(sha1.to_string(), profile.merge(changes));
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string(self).expect("a table is valid toml");
+        fs::write(path, text).map_err(|e| trf("file_write_error", &[&path.display(), &e]))
+    }
+}
+
+// the profiles file, there are no profiles if it doesn't exist yet
+pub fn load(path: &Path) -> Result<Profiles, String> {
+    if !path.exists() {
+        return Ok(Profiles::default());
+    }
+    let text =
+        fs::read_to_string(path).map_err(|e| trf("file_read_error", &[&path.display(), &e]))?;
+    toml::from_str(&text).map_err(|e| trf("file_invalid", &[&path.display(), &e]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn changed_settings_are_read_back() {
+        let path = std::env::temp_dir().join(format!("chip8-profiles-{}.toml", std::process::id()));
+        let mut profiles = Profiles::default();
+        profiles.update(
+            "abc",
+            Preset {
+                clockspeed: Some(700),
+                quirks: Some("schip".to_string()),
+                ..Preset::default()
+            },
+        );
+        // a later change keeps the settings it doesn't touch
+        profiles.update(
+            "abc",
+            Preset {
+                theme: Some("amber".to_string()),
+                volume: Some(40),
+                crt: Some(true),
+                clockspeed: Some(1000),
+                ..Preset::default()
+            },
+        );
+        profiles.save(&path).unwrap();
+        let loaded = load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let expected = Preset {
+            clockspeed: Some(1000),
+            quirks: Some("schip".to_string()),
+            theme: Some("amber".to_string()),
+            volume: Some(40),
+            crt: Some(true),
+            ..Preset::default()
+        };
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.get("abc"), Some(&expected));
+        assert_eq!(loaded.get("def"), None);
+    }
+
+    #[test]
+    fn a_missing_file_has_no_profiles() {
+        let profiles = load(Path::new("/nonexistent/chip8-profiles.toml")).unwrap();
+        assert_eq!(profiles.get("abc"), None);
+    }
+}
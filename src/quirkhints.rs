@@ -0,0 +1,166 @@
+use crate::i18n::trf;
+use crate::opcode::Instruction;
+use crate::quirks;
+use crate::quirks::Quirks;
+
+// the number of times a pattern has to be seen before it counts, and how many
+// times more often than the opposite pattern
+const MIN_EVIDENCE: u32 = 8;
+const MIN_RATIO: u32 = 4;
+
+// the last FX55 or FX65 since I was set
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Store,
+    Load,
+}
+
+// watches the opcodes a rom runs for patterns that only make sense with some
+// of the quirks on, to suggest other quirks when the rom looks like it was
+// written for a different interpreter
+#[derive(Default)]
+pub struct QuirkHints {
+    // 8XY6/8XYE with a VY that isn't VX or V0, so VY is meant to be shifted
+    shift_vy: u32,
+    // 8X06/8X0E, V0 is a placeholder and VX is meant to be shifted
+    shift_vx: u32,
+    // FX55 after FX55 or FX65 after FX65 without setting I, walking through
+    // memory only works if I moves on
+    walk: u32,
+    // FX65 after FX55 or the other way around without setting I, reading back
+    // what was just saved only works if I stays put
+    round_trip: u32,
+    access: Option<Access>,
+    suggested: bool,
+}
+
+// a change to the quirks and what it is based on
+pub struct Suggestion {
+    pub fix: String,
+    pub reasons: Vec<String>,
+}
+
+impl QuirkHints {
+    pub fn new() -> QuirkHints {
+        QuirkHints::default()
+    }
+
+    // note the opcode that is about to run
+    pub fn observe(&mut self, opcode: u16) {
+        match Instruction::decode(opcode) {
+            Instruction::Shr(x, y) | Instruction::Shl(x, y) if x != y => {
+                if y == 0 {
+                    self.shift_vx += 1;
+                } else {
+                    self.shift_vy += 1;
+                }
+            }
+            Instruction::LdIVx(_) => self.access(Access::Store),
+            Instruction::LdVxI(_) => self.access(Access::Load),
+            Instruction::LdI(_)
+            | Instruction::AddIVx(_)
+            | Instruction::LdFVx(_)
+            | Instruction::LdHfVx(_)
+            | Instruction::LdBVx(_)
+            | Instruction::Drw(_, _, _) => self.access = None,
+            _ => {}
+        }
+    }
+
+    fn access(&mut self, access: Access) {
+        match self.access {
+            Some(last) if last == access => self.walk += 1,
+            Some(_) => self.round_trip += 1,
+            None => {}
+        }
+        self.access = Some(access);
+    }
+
+    // a change to the quirks if the rom has run enough to tell that they are
+    // wrong, only given once
+    pub fn suggest(&mut self, current: Quirks) -> Option<Suggestion> {
+        if self.suggested {
+            return None;
+        }
+        let mut wanted = current;
+        let mut reasons = Vec::new();
+        if let Some(on) = verdict(self.shift_vy, self.shift_vx) {
+            if on != current.shift_vy {
+                wanted.shift_vy = on;
+                reasons.push(if on {
+                    trf("quirks_hint_shift_vy", &[&self.shift_vy])
+                } else {
+                    trf("quirks_hint_shift_vx", &[&self.shift_vx])
+                });
+            }
+        }
+        if let Some(on) = verdict(self.walk, self.round_trip) {
+            if on != current.load_store_increment_i {
+                wanted.load_store_increment_i = on;
+                reasons.push(if on {
+                    trf("quirks_hint_walk", &[&self.walk])
+                } else {
+                    trf("quirks_hint_round_trip", &[&self.round_trip])
+                });
+            }
+        }
+        if reasons.is_empty() {
+            return None;
+        }
+        self.suggested = true;
+        Some(Suggestion {
+            fix: fix(current, wanted),
+            reasons,
+        })
+    }
+}
+
+// whether the quirk is wanted on or off, if one side clearly wins
+fn verdict(on: u32, off: u32) -> Option<bool> {
+    if on >= MIN_EVIDENCE && on >= off * MIN_RATIO {
+        Some(true)
+    } else if off >= MIN_EVIDENCE && off >= on * MIN_RATIO {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+// the command line options that give the wanted quirks, the preset closest to
+// the current quirks if one has the wanted shift and load-store quirks, or
+// else the single quirks to turn on or off
+fn fix(current: Quirks, wanted: Quirks) -> String {
+    let closest = quirks::PRESET_NAMES
+        .iter()
+        .filter_map(|name| Quirks::preset(name).map(|preset| (name, preset)))
+        .filter(|(_, preset)| {
+            preset.shift_vy == wanted.shift_vy
+                && preset.load_store_increment_i == wanted.load_store_increment_i
+        })
+        .min_by_key(|(_, preset)| {
+            [
+                preset.jump_vx != current.jump_vx,
+                preset.vf_reset != current.vf_reset,
+                preset.wrap_sprites != current.wrap_sprites,
+            ]
+            .iter()
+            .filter(|differs| **differs)
+            .count()
+        });
+    if let Some((name, _)) = closest {
+        return format!("--quirks {}", name);
+    }
+
+    let onoff = |on: bool| if on { "on" } else { "off" };
+    let mut options = Vec::new();
+    if wanted.shift_vy != current.shift_vy {
+        options.push(format!("--quirk shift={}", onoff(wanted.shift_vy)));
+    }
+    if wanted.load_store_increment_i != current.load_store_increment_i {
+        options.push(format!(
+            "--quirk load-store={}",
+            onoff(wanted.load_store_increment_i)
+        ));
+    }
+    options.join(" ")
+}
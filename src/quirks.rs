@@ -0,0 +1,96 @@
+// behaviour that differs between chip8 interpreters, roms written for one
+// interpreter can glitch on another if these don't match
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Quirks {
+    // 8XY6 and 8XYE shift VY into VX instead of shifting VX
+    pub shift_vy: bool,
+
+    // FX55 and FX65 leave I pointing after the last register
+    pub load_store_increment_i: bool,
+
+    // BXNN jumps to XNN plus VX instead of NNN plus V0
+    pub jump_vx: bool,
+
+    // 8XY1, 8XY2 and 8XY3 reset VF to 0
+    pub vf_reset: bool,
+
+    // sprites wrap around the edges of the display instead of being clipped
+    pub wrap_sprites: bool,
+}
+
+// the preset names accepted by preset()
+pub const PRESET_NAMES: &[&str] = &["cosmac", "schip", "xochip"];
+
+// the quirk names accepted by apply_override()
+pub const QUIRK_NAMES: &[&str] = &["shift", "load-store", "jump", "vf-reset", "wrap"];
+
+impl Quirks {
+    // the quirks of a well known interpreter: the original COSMAC VIP, SUPER-CHIP
+    // 1.1 or Octo's XO-CHIP
+    pub fn preset(name: &str) -> Option<Quirks> {
+        match name {
+            "cosmac" => Some(Quirks {
+                shift_vy: true,
+                load_store_increment_i: true,
+                jump_vx: false,
+                vf_reset: true,
+                wrap_sprites: false,
+            }),
+            "schip" => Some(Quirks {
+                shift_vy: false,
+                load_store_increment_i: false,
+                jump_vx: true,
+                vf_reset: false,
+                wrap_sprites: false,
+            }),
+            "xochip" => Some(Quirks {
+                shift_vy: true,
+                load_store_increment_i: true,
+                jump_vx: false,
+                vf_reset: false,
+                wrap_sprites: true,
+            }),
+            _ => None,
+        }
+    }
+
+    // each quirk name from QUIRK_NAMES with whether it is on
+    pub fn values(&self) -> Vec<(&'static str, bool)> {
+        vec![
+            ("shift", self.shift_vy),
+            ("load-store", self.load_store_increment_i),
+            ("jump", self.jump_vx),
+            ("vf-reset", self.vf_reset),
+            ("wrap", self.wrap_sprites),
+        ]
+    }
+
+    // turn a single quirk on or off, e.g. "shift" or "wrap=off"
+    pub fn apply_override(&mut self, text: &str) -> Result<(), String> {
+        let (name, value) = match text.split_once('=') {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => (text.trim(), "on"),
+        };
+        let value = match value {
+            "on" | "true" | "1" => true,
+            "off" | "false" | "0" => false,
+            _ => return Err(format!("Invalid value \"{}\" for quirk {}", value, name)),
+        };
+
+        match name {
+            "shift" => self.shift_vy = value,
+            "load-store" => self.load_store_increment_i = value,
+            "jump" => self.jump_vx = value,
+            "vf-reset" => self.vf_reset = value,
+            "wrap" => self.wrap_sprites = value,
+            _ => {
+                return Err(format!(
+                    "Unknown quirk \"{}\", expected one of {}",
+                    name,
+                    QUIRK_NAMES.join(", ")
+                ))
+            }
+        }
+        Ok(())
+    }
+}
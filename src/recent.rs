@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::i18n::tr;
+use crate::i18n::trf;
+use crate::palette::PaletteKey;
+
+// the recent roms are kept next to the config file in this file
+pub const FILE_NAME: &str = "chip8-recent.toml";
+
+// the most roms remembered
+const MAX_ROMS: usize = 10;
+
+// the romfiles played last, newest first, e.g.
+//
+// roms = ["/home/me/roms/BRIX", "/home/me/roms/PONG"]
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct RecentRoms {
+    #[serde(default)]
+    roms: Vec<PathBuf>,
+}
+
+impl RecentRoms {
+    pub fn roms(&self) -> &[PathBuf] {
+        &self.roms
+    }
+
+    // put a romfile first, the oldest is forgotten once there are too many
+    pub fn add(&mut self, path: &Path) {
+        self.roms.retain(|rom| rom != path);
+        self.roms.insert(0, path.to_path_buf());
+        self.roms.truncate(MAX_ROMS);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = toml::to_string(self).expect("a table is valid toml");
+        fs::write(path, text).map_err(|e| trf("file_write_error", &[&path.display(), &e]))
+    }
+}
+
+// the recent roms file, nothing has been played if it doesn't exist yet
+pub fn load(path: &Path) -> Result<RecentRoms, String> {
+    if !path.exists() {
+        return Ok(RecentRoms::default());
+    }
+    let text =
+        fs::read_to_string(path).map_err(|e| trf("file_read_error", &[&path.display(), &e]))?;
+    toml::from_str(&text).map_err(|e| trf("file_invalid", &[&path.display(), &e]))
+}
+
+// a list of the recent roms to switch to, picked with the arrow keys and
+// enter
+#[derive(Clone, Debug, Default)]
+pub struct RecentMenu {
+    roms: Vec<PathBuf>,
+    selected: usize,
+}
+
+impl RecentMenu {
+    pub fn new(roms: Vec<PathBuf>) -> RecentMenu {
+        RecentMenu { roms, selected: 0 }
+    }
+
+    pub fn selected(&self) -> Option<&Path> {
+        self.roms.get(self.selected).map(|rom| rom.as_path())
+    }
+
+    // move the selection, enter and escape are up to the emulator
+    pub fn key(&mut self, key: PaletteKey) {
+        match key {
+            PaletteKey::Up => self.selected = self.selected.saturating_sub(1),
+            PaletteKey::Down => {
+                self.selected = (self.selected + 1).min(self.roms.len().saturating_sub(1))
+            }
+            _ => {}
+        }
+    }
+
+    // the title and a line for each rom with the file name and where it is
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![tr("recent_title")];
+        if self.roms.is_empty() {
+            lines.push(tr("recent_none"));
+        }
+        for (index, rom) in self.roms.iter().enumerate() {
+            let marker = if index == self.selected { ">" } else { " " };
+            let name = rom.file_name().unwrap_or(rom.as_os_str()).to_string_lossy();
+            let dir = rom.parent().unwrap_or(Path::new("")).display();
+            lines.push(format!("{} {} ({})", marker, name, dir));
+        }
+        lines
+    }
+}
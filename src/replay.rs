@@ -0,0 +1,412 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Write;
+use std::net::TcpStream;
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::TryRecvError;
+use std::thread;
+
+use crate::i18n::trf;
+
+// the first line of a replay file
+const HEADER: &str = "chip8-replay 1";
+
+// the frames between checksums of the machine state, one a second at 60hz
+const CHECKSUM_FRAMES: u64 = 60;
+
+// something that happened while recording, at the number of opcodes run
+// since the rom was loaded. Keys change at the opcode they changed before
+// rather than at the frame, so roms that read the keys several times a frame
+// play back the same
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    // the keypad before this opcode
+    Keys(u64, [bool; 0x10]),
+    // the end of an update, the timers counted down this many times
+    Frame(u64, u32),
+    // the machine went back to power on
+    Reset(u64),
+    // the checksum of the machine at the end of this frame, counted from 1
+    Checksum(u64, u32),
+}
+
+// a recording of the keys and timing of a run, played back on a machine
+// started from the same rom with the same seed and refresh rate. The file is
+// text, a header and then a line for each event, e.g.
+//
+// chip8-replay 1
+// rom 2f1c...
+// seed 1234
+// refresh 60
+// k 520 0020
+// f 1000 1
+// r 4000
+// c 60 8d3f01a2
+//
+// A live replay is read from a host streaming it to spectators as it plays,
+// see ReplayRecorder::stream
+pub struct Replay {
+    pub sha1: String,
+    pub seed: u64,
+    pub refresh: u32,
+    events: VecDeque<Event>,
+
+    // the lines still coming from the host of a live replay
+    live: Option<Receiver<String>>,
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let text =
+            fs::read_to_string(path).map_err(|e| trf("file_read_error", &[&path.display(), &e]))?;
+        let invalid = |line: &str| trf("replay_invalid", &[&path.display(), &line]);
+
+        let mut lines = text.lines();
+        if lines.next() != Some(HEADER) {
+            return Err(invalid(HEADER));
+        }
+        let mut replay = Replay::empty();
+        for line in lines {
+            replay.parse_line(line).ok_or_else(|| invalid(line))?;
+        }
+        Ok(replay)
+    }
+
+    // join a host streaming its replay to spectators at an address such as
+    // "192.168.1.5:7000". This waits for the header, the events come from
+    // power on as the host plays
+    pub fn connect(address: &str) -> Result<Replay, String> {
+        let stream =
+            TcpStream::connect(address).map_err(|e| trf("spectate_error", &[&address, &e]))?;
+        let invalid = |line: &str| trf("replay_invalid", &[&address, &line]);
+        let mut lines = BufReader::new(stream).lines().map_while(Result::ok);
+        if lines.next().as_deref() != Some(HEADER) {
+            return Err(invalid(HEADER));
+        }
+
+        // the refresh line ends the header
+        let mut replay = Replay::empty();
+        for line in lines.by_ref() {
+            replay.parse_line(&line).ok_or_else(|| invalid(&line))?;
+            if line.starts_with("refresh ") {
+                break;
+            }
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in lines {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        replay.live = Some(receiver);
+        Ok(replay)
+    }
+
+    fn empty() -> Replay {
+        Replay {
+            sha1: String::new(),
+            seed: 0,
+            refresh: 60,
+            events: VecDeque::new(),
+            live: None,
+        }
+    }
+
+    // read a line of the replay, None if it isn't valid
+    fn parse_line(&mut self, line: &str) -> Option<()> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let number = |index: usize| words.get(index).and_then(|word| word.parse::<u64>().ok());
+        match words.as_slice() {
+            [] => {}
+            ["rom", sha1] => self.sha1 = sha1.to_string(),
+            ["seed", _] => self.seed = number(1)?,
+            ["refresh", _] => {
+                // the machine divides by it, and takes no more than 1000
+                let refresh = u32::try_from(number(1)?).ok()?;
+                self.refresh = Some(refresh).filter(|refresh| (1..=1000).contains(refresh))?;
+            }
+            ["k", _, mask] => {
+                let cycle = number(1)?;
+                let mask = u16::from_str_radix(mask, 16).ok()?;
+                let mut keys = [false; 0x10];
+                for (key, pressed) in keys.iter_mut().enumerate() {
+                    *pressed = mask & (1 << key) != 0;
+                }
+                self.events.push_back(Event::Keys(cycle, keys));
+            }
+            ["f", _, _] => {
+                let cycle = number(1)?;
+                let count = u32::try_from(number(2)?).ok()?;
+                self.events.push_back(Event::Frame(cycle, count));
+            }
+            ["r", _] => self.events.push_back(Event::Reset(number(1)?)),
+            ["c", _, checksum] => {
+                let frame = number(1)?;
+                let checksum = u32::from_str_radix(checksum, 16).ok()?;
+                self.events.push_back(Event::Checksum(frame, checksum));
+            }
+            _ => return None,
+        }
+        Some(())
+    }
+
+    // the events up to and including the end of the next update, None once
+    // the replay is over. A live replay gives no events while it waits for
+    // the host, and is over when the host stops or sends a bad line
+    pub fn next_frame(&mut self) -> Option<Vec<Event>> {
+        if let Some(live) = self.live.take() {
+            let mut ended = false;
+            loop {
+                match live.try_recv() {
+                    Ok(line) => {
+                        if self.parse_line(&line).is_none() {
+                            ended = true;
+                            break;
+                        }
+                    }
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        ended = true;
+                        break;
+                    }
+                }
+            }
+            if !ended {
+                self.live = Some(live);
+            }
+        }
+
+        match self
+            .events
+            .iter()
+            .position(|event| matches!(event, Event::Frame(..)))
+        {
+            Some(end) => Some(self.events.drain(..=end).collect()),
+            None if self.live.is_some() => Some(Vec::new()),
+            None => None,
+        }
+    }
+
+    // the frames of a live replay that came and haven't been played, a
+    // spectator far behind the host plays several an update to catch up
+    pub fn frames_behind(&self) -> usize {
+        if self.live.is_none() {
+            return 0;
+        }
+        self.events
+            .iter()
+            .filter(|event| matches!(event, Event::Frame(..)))
+            .count()
+    }
+}
+
+// writes a replay as the machine runs, to a file or to spectators
+pub struct ReplayRecorder {
+    writer: Box<dyn Write>,
+    // the file or address the replay goes to, for messages
+    name: String,
+
+    // the keys last written, a line is only written when they change
+    keys: [bool; 0x10],
+
+    // the frames written so far
+    frames: u64,
+}
+
+impl ReplayRecorder {
+    pub fn create(
+        path: &Path,
+        sha1: &str,
+        seed: u64,
+        refresh: u32,
+    ) -> Result<ReplayRecorder, String> {
+        let file =
+            File::create(path).map_err(|e| trf("file_create_error", &[&path.display(), &e]))?;
+        let writer = Box::new(BufWriter::new(file));
+        ReplayRecorder::stream(writer, &path.display().to_string(), sha1, seed, refresh)
+    }
+
+    // write the replay to anything, such as the spectators watching a run.
+    // It is flushed at the end of each frame
+    pub fn stream(
+        writer: Box<dyn Write>,
+        name: &str,
+        sha1: &str,
+        seed: u64,
+        refresh: u32,
+    ) -> Result<ReplayRecorder, String> {
+        let mut recorder = ReplayRecorder {
+            writer,
+            name: name.to_string(),
+            keys: [false; 0x10],
+            frames: 0,
+        };
+        recorder.write(&format!(
+            "{}\nrom {}\nseed {}\nrefresh {}\n",
+            HEADER, sha1, seed, refresh
+        ))?;
+        Ok(recorder)
+    }
+
+    // note the keys before the opcode at this cycle, if they changed
+    pub fn keys(&mut self, cycle: u64, keys: [bool; 0x10]) -> Result<(), String> {
+        if keys == self.keys {
+            return Ok(());
+        }
+        self.keys = keys;
+        let mask = keys
+            .iter()
+            .enumerate()
+            .filter(|(_, pressed)| **pressed)
+            .fold(0u16, |mask, (key, _)| mask | 1 << key);
+        self.write(&format!("k {} {:04X}\n", cycle, mask))
+    }
+
+    // note the end of an update and how many times the timers counted down
+    pub fn frame(&mut self, cycle: u64, count: u32) -> Result<(), String> {
+        self.frames += 1;
+        self.write(&format!("f {} {}\n", cycle, count))?;
+        self.writer
+            .flush()
+            .map_err(|e| trf("file_write_error", &[&self.name, &e]))
+    }
+
+    pub fn reset(&mut self, cycle: u64) -> Result<(), String> {
+        self.write(&format!("r {}\n", cycle))
+    }
+
+    // the frame to write a checksum for if one is due after the last frame
+    pub fn checksum_due(&self) -> Option<u64> {
+        Some(self.frames).filter(|frames| frames % CHECKSUM_FRAMES == 0)
+    }
+
+    // note the checksum of the machine at the end of a frame
+    pub fn checksum(&mut self, frame: u64, checksum: u32) -> Result<(), String> {
+        self.write(&format!("c {} {:08x}\n", frame, checksum))
+    }
+
+    // write the rest of the replay, returns the name of the file
+    pub fn finish(mut self) -> Result<String, String> {
+        self.writer
+            .flush()
+            .map_err(|e| trf("file_write_error", &[&self.name, &e]))?;
+        Ok(self.name)
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), String> {
+        self.writer
+            .write_all(text.as_bytes())
+            .map_err(|e| trf("file_write_error", &[&self.name, &e]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(pressed: &[usize]) -> [bool; 0x10] {
+        let mut keys = [false; 0x10];
+        for key in pressed {
+            keys[*key] = true;
+        }
+        keys
+    }
+
+    // a replay read from its lines after the header
+    fn parse(lines: &[&str]) -> Option<Replay> {
+        let mut replay = Replay::empty();
+        for line in lines {
+            replay.parse_line(line)?;
+        }
+        Some(replay)
+    }
+
+    #[test]
+    fn a_written_replay_is_read_back() {
+        let path = std::env::temp_dir().join(format!("chip8-replay-{}.txt", std::process::id()));
+        let mut recorder = ReplayRecorder::create(&path, "2f1c", 1234, 120).unwrap();
+        recorder.keys(10, keys(&[5])).unwrap();
+        // unchanged keys aren't written
+        recorder.keys(12, keys(&[5])).unwrap();
+        recorder.frame(20, 1).unwrap();
+        recorder.keys(25, keys(&[])).unwrap();
+        recorder.reset(30).unwrap();
+        recorder.frame(40, 2).unwrap();
+        recorder.checksum(2, 0x8d3f01a2).unwrap();
+        recorder.finish().unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let mut replay = loaded.unwrap();
+        assert_eq!(replay.sha1, "2f1c");
+        assert_eq!(replay.seed, 1234);
+        assert_eq!(replay.refresh, 120);
+        assert_eq!(
+            replay.next_frame(),
+            Some(vec![Event::Keys(10, keys(&[5])), Event::Frame(20, 1)])
+        );
+        assert_eq!(
+            replay.next_frame(),
+            Some(vec![
+                Event::Keys(25, keys(&[])),
+                Event::Reset(30),
+                Event::Frame(40, 2),
+            ])
+        );
+        // the checksum is kept for the frame after, and there is none
+        assert_eq!(replay.next_frame(), None);
+        assert_eq!(replay.events, [Event::Checksum(2, 0x8d3f01a2)]);
+    }
+
+    #[test]
+    fn lines_are_parsed() {
+        let mut replay = parse(&["seed 7", "", "k 520 8001", "f 1000 3", "c 60 ff"]).unwrap();
+        assert_eq!(replay.seed, 7);
+        assert_eq!(
+            replay.next_frame(),
+            Some(vec![
+                Event::Keys(520, keys(&[0, 15])),
+                Event::Frame(1000, 3)
+            ])
+        );
+        assert_eq!(replay.events, [Event::Checksum(60, 0xff)]);
+    }
+
+    #[test]
+    fn bad_lines_are_refused() {
+        for line in [
+            "k 520",
+            "k x 0020",
+            "k 520 zz",
+            "f 1000",
+            "f 1000 99999999999",
+            "refresh 0",
+            "refresh 1001",
+            "seed -1",
+            "jump 5",
+        ] {
+            assert!(parse(&[line]).is_none(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn a_file_without_the_header_is_refused() {
+        let path = std::env::temp_dir().join(format!("chip8-noheader-{}.txt", std::process::id()));
+        fs::write(&path, "seed 1\n").unwrap();
+        let loaded = Replay::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            loaded.err(),
+            Some(trf("replay_invalid", &[&path.display(), &HEADER]))
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use std::collections::VecDeque;
+
+use crate::interpreter::Snapshot;
+
+// the most seconds that can be kept for rewinding, at 60hz this is tens of
+// thousands of snapshots
+pub const MAX_REWIND_SECONDS: u32 = 600;
+
+// the most recent snapshots of the machine, the oldest is dropped when a new
+// one is added to a full buffer
+pub struct RewindBuffer {
+    snapshots: VecDeque<Snapshot>,
+    capacity: usize,
+}
+
+impl RewindBuffer {
+    // the buffer grows as snapshots are added, a long rewind that is never
+    // used takes no memory
+    pub fn new(capacity: usize) -> RewindBuffer {
+        RewindBuffer {
+            snapshots: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, snapshot: Snapshot) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(snapshot);
+    }
+
+    // take the most recent snapshot
+    pub fn pop(&mut self) -> Option<Snapshot> {
+        self.snapshots.pop_back()
+    }
+}
@@ -0,0 +1,85 @@
+use serde::Deserialize;
+
+use crate::i18n::trf;
+
+// the database of known roms that is built into the binary
+const ROMDB: &str = include_str!("romdb.toml");
+
+#[derive(Deserialize)]
+struct RomDb {
+    #[serde(rename = "rom")]
+    roms: Vec<RomInfo>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RomInfo {
+    pub sha1: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub year: Option<u32>,
+
+    // how to play the game using the chip8 keypad keys
+    pub controls: Option<String>,
+
+    // the recommended clock speed
+    pub clockspeed: Option<u32>,
+}
+
+impl RomInfo {
+    // the lines shown in the rom info panel
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = vec![self.title.clone()];
+
+        match (&self.author, self.year) {
+            (Some(author), Some(year)) => lines.push(trf("info_by_author_year", &[author, &year])),
+            (Some(author), None) => lines.push(trf("info_by_author", &[author])),
+            (None, Some(year)) => lines.push(format!("{}", year)),
+            (None, None) => {}
+        }
+
+        if let Some(controls) = &self.controls {
+            lines.push(String::new());
+            lines.push(trf("info_controls", &[controls]));
+        }
+
+        if let Some(clockspeed) = self.clockspeed {
+            lines.push(String::new());
+            lines.push(trf("info_recommended_clockspeed", &[&clockspeed]));
+        }
+
+        lines
+    }
+}
+
+// the sha1 of the data as a lowercase hex string
+pub fn sha1_hex(data: &[u8]) -> String {
+    sha1_smol::Sha1::from(data).digest().to_string()
+}
+
+// the crc32 of the data as a lowercase hex string, this is the checksum most
+// rom lists use alongside the sha1
+pub fn crc32_hex(data: &[u8]) -> String {
+    format!("{:08x}", crc32(data))
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+// find the rom in the database
+pub fn lookup(data: &[u8]) -> Option<RomInfo> {
+    let db: RomDb = toml::from_str(ROMDB).expect("Invalid built in rom database");
+    let sha1 = sha1_hex(data);
+    db.roms.into_iter().find(|info| info.sha1 == sha1)
+}
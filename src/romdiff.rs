@@ -0,0 +1,100 @@
+use crate::disasm;
+use crate::disasm::Line;
+
+// the number of unchanged lines shown around each change
+const CONTEXT_LINES: usize = 2;
+
+// a line of the diff, unchanged lines are shown for context
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Same(Line),
+    Removed(Line),
+    Added(Line),
+}
+
+// compare the disassembly of two roms, returns a list of hunks of changes
+// with a few unchanged lines around them. Both roms are disassembled first
+// so an instruction that changed is shown as one line rather than two bytes
+pub fn diff(a: &[u8], b: &[u8]) -> Vec<Vec<Change>> {
+    let a = disasm::disassemble(a, false);
+    let b = disasm::disassemble(b, false);
+    let changes = diff_lines(&a, &b);
+
+    // group the changes into hunks, keeping the context lines around them
+    let mut hunks = Vec::new();
+    let mut hunk: Vec<Change> = Vec::new();
+    let mut same_run = 0;
+    for (index, change) in changes.iter().enumerate() {
+        if let Change::Same(_) = change {
+            same_run += 1;
+            let near_next = changes[index + 1..]
+                .iter()
+                .take(CONTEXT_LINES)
+                .any(|c| !matches!(c, Change::Same(_)));
+            if (!hunk.is_empty() && same_run <= CONTEXT_LINES) || near_next {
+                hunk.push(change.clone());
+            } else if !hunk.is_empty() {
+                hunks.push(std::mem::take(&mut hunk));
+            }
+        } else {
+            same_run = 0;
+            hunk.push(change.clone());
+        }
+    }
+    if !hunk.is_empty() {
+        hunks.push(hunk);
+    }
+    hunks
+}
+
+// the longest common subsequence of the two listings, with the lines that
+// are not part of it as removed or added
+fn diff_lines(a: &[Line], b: &[Line]) -> Vec<Change> {
+    let same = |x: &Line, y: &Line| x.bytes == y.bytes && x.text == y.text;
+
+    // most roms being compared only differ in a few places, so skip the
+    // common start and end before the quadratic part
+    let prefix = a.iter().zip(b).take_while(|(x, y)| same(x, y)).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| same(x, y))
+        .count();
+    let mid_a = &a[prefix..a.len() - suffix];
+    let mid_b = &b[prefix..b.len() - suffix];
+
+    // lengths[i][j] is the length of the common subsequence of mid_a[i..]
+    // and mid_b[j..]
+    let width = mid_b.len() + 1;
+    let mut lengths = vec![0u16; (mid_a.len() + 1) * width];
+    for i in (0..mid_a.len()).rev() {
+        for j in (0..mid_b.len()).rev() {
+            lengths[i * width + j] = if same(&mid_a[i], &mid_b[j]) {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                std::cmp::max(lengths[(i + 1) * width + j], lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut changes: Vec<Change> = a[..prefix].iter().cloned().map(Change::Same).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < mid_a.len() || j < mid_b.len() {
+        if i < mid_a.len() && j < mid_b.len() && same(&mid_a[i], &mid_b[j]) {
+            changes.push(Change::Same(mid_a[i].clone()));
+            i += 1;
+            j += 1;
+        } else if j == mid_b.len()
+            || (i < mid_a.len() && lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+        {
+            changes.push(Change::Removed(mid_a[i].clone()));
+            i += 1;
+        } else {
+            changes.push(Change::Added(mid_b[j].clone()));
+            j += 1;
+        }
+    }
+    changes.extend(a[a.len() - suffix..].iter().cloned().map(Change::Same));
+    changes
+}
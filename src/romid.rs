@@ -0,0 +1,241 @@
+use std::fmt;
+
+use crate::disasm;
+use crate::i18n::tr;
+use crate::i18n::trf;
+use crate::interpreter::MEM_SIZE;
+use crate::interpreter::PROGRAM_START;
+use crate::opcode::Instruction;
+use crate::romdb;
+use crate::romdb::RomInfo;
+
+// the chip8 variant a rom was most likely written for
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Platform {
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Platform::Chip8 => write!(f, "CHIP-8"),
+            Platform::SuperChip => write!(f, "SUPER-CHIP"),
+            Platform::XoChip => write!(f, "XO-CHIP"),
+        }
+    }
+}
+
+// everything that can be worked out about a rom file without running it
+pub struct RomId {
+    pub size: usize,
+    pub sha1: String,
+    pub crc32: String,
+    pub rom_info: Option<RomInfo>,
+    pub platform: Platform,
+
+    // problems that suggest a bad dump or a file that is not a rom
+    pub problems: Vec<Problem>,
+}
+
+pub fn identify(rom: &[u8]) -> RomId {
+    RomId {
+        size: rom.len(),
+        sha1: romdb::sha1_hex(rom),
+        crc32: romdb::crc32_hex(rom),
+        rom_info: romdb::lookup(rom),
+        platform: detect_platform(rom),
+        problems: check(rom, PROGRAM_START, MEM_SIZE - PROGRAM_START),
+    }
+}
+
+// guess the platform from the opcodes in the code that can be reached, roms
+// that use a later platform's opcodes or are too big for chip8 memory need
+// that platform
+pub fn detect_platform(rom: &[u8]) -> Platform {
+    if rom.len() > MEM_SIZE - PROGRAM_START {
+        return Platform::XoChip;
+    }
+
+    let code = disasm::trace_code(rom);
+    let mut platform = Platform::Chip8;
+    for (offset, _) in code.iter().enumerate().filter(|(_, is_code)| **is_code) {
+        let opcode = ((rom[offset] as u16) << 8) | rom[offset + 1] as u16;
+        match opcode & 0xF00F {
+            // 5XY2 and 5XY3 save and load a range of registers
+            0x5002 | 0x5003 => return Platform::XoChip,
+            _ => {}
+        }
+        match opcode & 0xF0FF {
+            // F000 NNNN long I, FN01 plane, F002 audio, FX3A pitch
+            0xF000 | 0xF001 | 0xF002 | 0xF03A => return Platform::XoChip,
+            // FX30 big font, FX75 and FX85 flag registers
+            0xF030 | 0xF075 | 0xF085 => platform = Platform::SuperChip,
+            _ => {}
+        }
+        match Instruction::decode(opcode) {
+            Instruction::Scd(_)
+            | Instruction::Scr
+            | Instruction::Scl
+            | Instruction::Exit
+            | Instruction::Low
+            | Instruction::High
+            | Instruction::Drw(_, _, 0) => platform = Platform::SuperChip,
+            _ => {}
+        }
+    }
+    platform
+}
+
+// something wrong with a rom file. Fatal problems mean the file can't be run,
+// e.g. it is empty or a web page saved by a failed download
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    pub message: String,
+    pub fatal: bool,
+}
+
+impl Problem {
+    fn fatal(message: String) -> Problem {
+        Problem {
+            message,
+            fatal: true,
+        }
+    }
+
+    fn warning(message: String) -> Problem {
+        Problem {
+            message,
+            fatal: false,
+        }
+    }
+}
+
+// sanity checks on a rom loaded at load_address, a good dump fits in the
+// max_size bytes that can be loaded, starts with a valid instruction and
+// doesn't run off its end
+pub fn check(rom: &[u8], load_address: usize, max_size: usize) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    if rom.is_empty() {
+        problems.push(Problem::fatal(tr("id_empty")));
+        return problems;
+    }
+    if rom.len() < 2 {
+        problems.push(Problem::fatal(tr("id_too_small")));
+        return problems;
+    }
+    if rom.len() > max_size {
+        problems.push(Problem::fatal(trf("id_too_big", &[&rom.len(), &max_size])));
+    }
+
+    if is_html(rom) {
+        problems.push(Problem::fatal(tr("id_html")));
+        return problems;
+    }
+    if let Some(format) = file_format(rom) {
+        problems.push(Problem::fatal(trf("id_format", &[&format])));
+        return problems;
+    }
+    if rom
+        .iter()
+        .all(|b| b.is_ascii_graphic() || b.is_ascii_whitespace())
+    {
+        problems.push(Problem::fatal(tr("id_text")));
+        return problems;
+    }
+    if rom.iter().all(|b| *b == rom[0]) {
+        problems.push(Problem::fatal(trf(
+            "id_filled",
+            &[&format!("{:02X}", rom[0])],
+        )));
+        return problems;
+    }
+
+    // the interpreter stops on these so the rom would not run at all
+    let opcode = ((rom[0] as u16) << 8) | rom[1] as u16;
+    match Instruction::decode(opcode) {
+        Instruction::Invalid(_) => problems.push(Problem::fatal(trf(
+            "id_invalid_first",
+            &[&format!("{:04X}", opcode)],
+        ))),
+        Instruction::Sys(nnn) => problems.push(Problem::fatal(trf(
+            "id_machine_code_first",
+            &[&format!("{:03X}", nnn)],
+        ))),
+        Instruction::Jp(nnn) | Instruction::Call(nnn)
+            if nnn < load_address || nnn >= load_address + rom.len() =>
+        {
+            problems.push(Problem::warning(trf(
+                "id_jump_outside",
+                &[&format!("{:03X}", nnn)],
+            )))
+        }
+        _ => {}
+    }
+
+    // instructions are 2 bytes, an odd size is fine when the rom ends with
+    // data but can mean the last byte was cut off
+    if rom.len() % 2 == 1 {
+        problems.push(Problem::warning(trf("id_odd_size", &[&rom.len()])));
+    }
+
+    if let Some(addr) = truncated_at(rom) {
+        problems.push(Problem::fatal(trf(
+            "id_truncated",
+            &[&format!("{:03X}", addr)],
+        )));
+    }
+
+    problems
+}
+
+// a web page saved in place of the rom, usually from a failed download
+fn is_html(rom: &[u8]) -> bool {
+    let start: Vec<u8> = rom
+        .iter()
+        .skip_while(|b| b.is_ascii_whitespace())
+        .take(16)
+        .map(|b| b.to_ascii_lowercase())
+        .collect();
+    start.starts_with(b"<!doctype html")
+        || start.starts_with(b"<html")
+        || start.starts_with(b"<?xml")
+}
+
+// the kind of file from the magic number at its start, for the files most
+// often mistaken for roms
+fn file_format(rom: &[u8]) -> Option<&'static str> {
+    const FORMATS: &[(&[u8], &str)] = &[
+        (b"PK\x03\x04", "zip"),
+        (b"\x1F\x8B\x08", "gzip"),
+        (b"7z\xBC\xAF\x27\x1C", "7z"),
+        (b"Rar!", "rar"),
+        (b"\x89PNG", "png"),
+        (b"GIF8", "gif"),
+        (b"\xFF\xD8\xFF", "jpeg"),
+        (b"%PDF", "pdf"),
+        (b"\x7FELF", "elf"),
+    ];
+    FORMATS
+        .iter()
+        .find(|(magic, _)| rom.starts_with(magic))
+        .map(|(_, format)| *format)
+}
+
+// the address where the code runs off the end of the rom or into its last
+// odd byte, this usually means the dump was cut short
+fn truncated_at(rom: &[u8]) -> Option<usize> {
+    let end = PROGRAM_START + rom.len();
+    let code = disasm::trace_code(rom);
+    code.iter()
+        .enumerate()
+        .filter(|(_, is_code)| **is_code)
+        .flat_map(|(offset, _)| {
+            let opcode = ((rom[offset] as u16) << 8) | rom[offset + 1] as u16;
+            disasm::successors(PROGRAM_START + offset, Instruction::decode(opcode))
+        })
+        .filter(|addr| *addr == end - 1 || *addr == end)
+        .min()
+}
@@ -0,0 +1,271 @@
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::display::DISPLAY_WIDTH;
+use crate::i18n::trf;
+use crate::interpreter::Interpreter;
+use crate::interpreter::STATE_VERSION;
+use crate::library::Thumbnail;
+use crate::playtime;
+
+// the number of save state slots, numbered from 0
+pub const SLOTS: usize = 10;
+
+// the first line of a save state file is this and the version of the
+// machine state, see Interpreter::save_state
+const HEADER: &str = "chip8-state";
+
+// the longest name a slot can be given
+pub const MAX_NAME: usize = 24;
+
+// a saved machine with a small picture of the display to tell the slots
+// apart. The file is a few lines of text ending with an empty line, then the
+// thumbnail and the machine state, e.g.
+//
+// chip8-state 2
+// rom 2f1c...
+// title Blitz
+// name before the boss
+// saved 1792065600
+// playtime 3725
+//
+// <width> <height> <pixels packed 8 to a byte> <Interpreter::save_state>
+#[derive(Clone, Debug)]
+pub struct SaveState {
+    pub info: StateInfo,
+    pub thumbnail: Thumbnail,
+    state: Vec<u8>,
+    version: u32,
+}
+
+// what the header of a save state says about it
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StateInfo {
+    // the sha1 of the rom the state was saved from
+    pub rom: String,
+
+    // the title from the rom database, or the romfile name
+    pub title: String,
+
+    // the name given to the slot when saving, empty if none
+    pub name: String,
+
+    // when it was saved in seconds since 1970
+    pub saved: u64,
+
+    // the seconds the rom had been played for when saved
+    pub playtime: u64,
+}
+
+impl StateInfo {
+    // the name if there is one, the title and when the state was saved, e.g.
+    // "before the boss", "Blitz" and "2026-10-15 14:03 UTC, played 1h 05m"
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if !self.name.is_empty() {
+            lines.push(self.name.clone());
+        }
+        lines.push(self.title.clone());
+        lines.push(trf(
+            "state_details",
+            &[&format_time(self.saved), &playtime::format(self.playtime)],
+        ));
+        lines
+    }
+}
+
+// a time in seconds since 1970 as a utc date and time, e.g.
+// "2026-10-15 14:03"
+pub fn format_time(seconds: u64) -> String {
+    // the days since 1970 to a date of the gregorian calendar, counting
+    // 400 year eras from the 1st of march 0000
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    let minutes = seconds % 86400 / 60;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        minutes / 60,
+        minutes % 60
+    )
+}
+
+// the file of a slot, named after the rom like the screenshots, e.g.
+// "PONG-state-3.state"
+pub fn slot_path(prefix: &Path, slot: usize) -> PathBuf {
+    let mut name = prefix.as_os_str().to_os_string();
+    name.push(format!("-state-{}.state", slot));
+    PathBuf::from(name)
+}
+
+impl SaveState {
+    // save the machine as it is
+    pub fn capture(interp: &Interpreter, mut info: StateInfo) -> SaveState {
+        // a name or title can't break the header over two lines
+        info.name = info.name.replace(['\r', '\n'], " ");
+        info.title = info.title.replace(['\r', '\n'], " ");
+        SaveState {
+            info,
+            thumbnail: Thumbnail::from_display(interp.display()).shrink(DISPLAY_WIDTH),
+            state: interp.save_state(),
+            version: STATE_VERSION,
+        }
+    }
+
+    // put the machine back as it was saved. The state was checked when it
+    // was loaded, but None if it still doesn't fit this machine, such as a
+    // bank its rom doesn't have. Nothing changes then
+    pub fn restore(&self, interp: &mut Interpreter) -> Option<()> {
+        interp.load_state_version(&self.state, self.version)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let info = &self.info;
+        let mut data = format!(
+            "{} {}\nrom {}\ntitle {}\nname {}\nsaved {}\nplaytime {}\n\n",
+            HEADER, self.version, info.rom, info.title, info.name, info.saved, info.playtime
+        )
+        .into_bytes();
+        data.push(self.thumbnail.width as u8);
+        data.push(self.thumbnail.height as u8);
+        data.extend(
+            self.thumbnail
+                .pixels
+                .chunks(8)
+                .map(|chunk| chunk.iter().fold(0, |byte, pixel| (byte << 1) | pixel)),
+        );
+        data.extend_from_slice(&self.state);
+        fs::write(path, data).map_err(|e| trf("file_write_error", &[&path.display(), &e]))
+    }
+
+    pub fn load(path: &Path) -> Result<SaveState, String> {
+        let data = fs::read(path).map_err(|e| trf("file_read_error", &[&path.display(), &e]))?;
+        let invalid = || trf("state_invalid", &[&path.display()]);
+
+        // the text header ends at the first empty line
+        let end = data
+            .windows(2)
+            .position(|pair| pair == b"\n\n")
+            .ok_or_else(invalid)?;
+        let text = std::str::from_utf8(&data[..end]).map_err(|_| invalid())?;
+        let mut lines = text.lines();
+        let version = lines
+            .next()
+            .and_then(|line| line.strip_prefix(HEADER))
+            .and_then(|version| version.trim().parse::<u32>().ok())
+            .filter(|version| (1..=STATE_VERSION).contains(version))
+            .ok_or_else(invalid)?;
+        // lines that aren't known are skipped so newer files still load
+        let mut info = StateInfo::default();
+        for line in lines {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            match key {
+                "rom" => info.rom = value.to_string(),
+                "title" => info.title = value.to_string(),
+                "name" => info.name = value.to_string(),
+                "saved" => info.saved = value.parse().map_err(|_| invalid())?,
+                "playtime" => info.playtime = value.parse().map_err(|_| invalid())?,
+                _ => {}
+            }
+        }
+
+        let rest = &data[end + 2..];
+        let (width, height) = match rest {
+            [width, height, ..] => (*width as usize, *height as usize),
+            _ => return Err(invalid()),
+        };
+        let packed = (width * height).div_ceil(8);
+        let pixels = rest.get(2..2 + packed).ok_or_else(invalid)?;
+        let thumbnail = Thumbnail {
+            width,
+            height,
+            pixels: (0..width * height)
+                .map(|index| (pixels[index / 8] >> (7 - index % 8)) & 1)
+                .collect(),
+        };
+        let state = rest[2 + packed..].to_vec();
+        Interpreter::new()
+            .load_state_version(&state, version)
+            .ok_or_else(invalid)?;
+        Ok(SaveState {
+            info,
+            thumbnail,
+            state,
+            version,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a machine with the 0 of the font drawn in the corner
+    fn drawn() -> Interpreter {
+        let mut interp = Interpreter::new();
+        interp
+            .load_rom(&[0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x12, 0x06])
+            .unwrap();
+        for _ in 0..3 {
+            interp.step().unwrap();
+        }
+        interp
+    }
+
+    #[test]
+    fn a_saved_state_loads_back() {
+        let path = std::env::temp_dir().join(format!("chip8-{}.state", std::process::id()));
+        let interp = drawn();
+        let info = StateInfo {
+            rom: "2f1c".to_string(),
+            title: "Blitz".to_string(),
+            name: "before\nthe boss".to_string(),
+            saved: 1792065600,
+            playtime: 3725,
+        };
+        let state = SaveState::capture(&interp, info);
+        state.save(&path).unwrap();
+        let loaded = SaveState::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.info, state.info);
+        assert_eq!(loaded.info.name, "before the boss");
+        assert_eq!(loaded.thumbnail, state.thumbnail);
+        assert!(loaded.thumbnail.pixels.contains(&1));
+        let mut restored = Interpreter::new();
+        assert_eq!(loaded.restore(&mut restored), Some(()));
+        assert_eq!(restored.snapshot(), interp.snapshot());
+    }
+
+    #[test]
+    fn a_state_of_a_newer_version_is_refused() {
+        let path = std::env::temp_dir().join(format!("chip8-new-{}.state", std::process::id()));
+        fs::write(&path, format!("{} {}\n\n", HEADER, STATE_VERSION + 1)).unwrap();
+        let loaded = SaveState::load(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.err(), Some(trf("state_invalid", &[&path.display()])));
+    }
+
+    #[test]
+    fn times_are_utc_dates() {
+        assert_eq!(format_time(0), "1970-01-01 00:00");
+        assert_eq!(format_time(1792065600), "2026-10-15 12:00");
+        assert_eq!(format_time(951782400 + 3599), "2000-02-29 00:59");
+    }
+}
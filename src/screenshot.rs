@@ -0,0 +1,143 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::colors::Theme;
+use crate::display::Display;
+use crate::display::DISPLAY_HEIGHT;
+use crate::display::DISPLAY_WIDTH;
+use crate::expr::MachineView;
+use crate::i18n::trf;
+use crate::watch::Watchpoint;
+
+// the display as the rgb pixels of the window, ready to be encoded
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pixelmap {
+    pub width: usize,
+    pub height: usize,
+
+    // three bytes per pixel, row by row
+    pub data: Vec<u8>,
+}
+
+impl Pixelmap {
+    // the display drawn in the theme colours at the size of the window, a
+    // 64x32 display with each chip8 pixel a square of pixelsize pixels. A
+    // super chip high resolution display is scaled to the same size
+    pub fn render(display: &Display, pixelsize: usize, theme: Theme) -> Pixelmap {
+        let width = DISPLAY_WIDTH * pixelsize;
+        let height = DISPLAY_HEIGHT * pixelsize;
+        let (display_width, display_height) = (display.get_width(), display.get_height());
+
+        let mut data = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = display.get_pixel(
+                    (x * display_width / width) as u8,
+                    (y * display_height / height) as u8,
+                );
+                let color = if pixel == 0x0 {
+                    theme.background
+                } else {
+                    theme.foreground
+                };
+                data.extend_from_slice(&[color.r, color.g, color.b]);
+            }
+        }
+        Pixelmap {
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        let file =
+            File::create(path).map_err(|e| trf("file_create_error", &[&path.display(), &e]))?;
+        let mut encoder =
+            png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.data))
+            .map_err(|e| trf("file_write_error", &[&path.display(), &e]))
+    }
+}
+
+// write the current display to a png in white on black, each chip8 pixel is
+// scaled up to a square of pixelsize pixels
+pub fn save_png(path: &Path, display: &Display, pixelsize: usize) -> Result<(), String> {
+    Pixelmap::render(display, pixelsize, Theme::default()).save_png(path)
+}
+
+// the first of <prefix>-<kind>-1.<extension>, <prefix>-<kind>-2.<extension>
+// and so on that doesn't exist yet
+pub fn next_free_path(prefix: &Path, kind: &str, extension: &str) -> PathBuf {
+    (1..)
+        .map(|count| {
+            let mut name = prefix.as_os_str().to_owned();
+            name.push(format!("-{}-{}.{}", kind, count, extension));
+            PathBuf::from(name)
+        })
+        .find(|path| !path.exists())
+        .expect("there is always a free name")
+}
+
+// takes a screenshot the first time any of its conditions becomes true
+pub struct ScreenshotTriggers {
+    watchpoints: Vec<Watchpoint>,
+    fired: Vec<bool>,
+
+    // screenshots are written to <prefix>-<n>.png
+    prefix: PathBuf,
+    count: usize,
+
+    // the size of each chip8 pixel in the screenshot
+    pixelsize: usize,
+}
+
+impl ScreenshotTriggers {
+    pub fn new(
+        watchpoints: Vec<Watchpoint>,
+        prefix: PathBuf,
+        pixelsize: usize,
+    ) -> ScreenshotTriggers {
+        let fired = vec![false; watchpoints.len()];
+        ScreenshotTriggers {
+            watchpoints,
+            fired,
+            prefix,
+            count: 0,
+            pixelsize,
+        }
+    }
+
+    // check the conditions and write a screenshot for each that first became
+    // true
+    pub fn check(&mut self, machine: &dyn MachineView, display: &Display) {
+        for (i, watchpoint) in self.watchpoints.iter_mut().enumerate() {
+            if self.fired[i] || !watchpoint.check(machine) {
+                continue;
+            }
+            self.fired[i] = true;
+
+            self.count += 1;
+            let mut name = self.prefix.as_os_str().to_owned();
+            name.push(format!("-{}.png", self.count));
+            let path = PathBuf::from(name);
+
+            match save_png(&path, display, self.pixelsize) {
+                Ok(()) => println!(
+                    "{}",
+                    trf(
+                        "screenshot_triggered",
+                        &[&path.display(), &watchpoint.expr()]
+                    )
+                ),
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+}
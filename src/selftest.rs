@@ -0,0 +1,521 @@
+use std::fmt::Display;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+
+use crate::crash;
+use crate::display;
+use crate::expr::MachineView;
+use crate::i18n::trf;
+use crate::interpreter::Interpreter;
+use crate::quirks::Quirks;
+
+// one opcode checked by running a short program from the start of the
+// program area, with the keys held down, and then looking at the machine
+struct Case {
+    name: &'static str,
+    program: &'static [u16],
+    keys: &'static [u8],
+    check: fn(&Interpreter) -> Result<(), String>,
+}
+
+// how one opcode did, the error is None if it passed
+pub struct Outcome {
+    pub name: &'static str,
+    pub error: Option<String>,
+}
+
+// every implemented opcode with the default quirks. 0NNN isn't implemented
+// so it isn't tested
+const CASES: &[Case] = &[
+    Case {
+        name: "00CN SCD N",
+        program: &[0x6000, 0xF029, 0xD005, 0x00C1],
+        keys: &[],
+        check: |m| {
+            pixel(m, 0, 0, 0)?;
+            pixel(m, 0, 1, 1)
+        },
+    },
+    Case {
+        name: "00E0 CLS",
+        program: &[0x6000, 0xF029, 0xD005, 0x00E0],
+        keys: &[],
+        check: |m| expect("lit pixels", m.display().pixels().iter().sum::<u8>(), 0),
+    },
+    Case {
+        name: "00EE RET",
+        program: &[0x2204, 0x1202, 0x00EE],
+        keys: &[],
+        check: |m| {
+            expect("PC", hex(m.pc()), hex(0x202))?;
+            expect("SP", m.sp(), 0)
+        },
+    },
+    Case {
+        name: "00FB SCR",
+        program: &[0x6000, 0xF029, 0xD005, 0x00FB],
+        keys: &[],
+        check: |m| {
+            pixel(m, 0, 0, 0)?;
+            pixel(m, 4, 0, 1)
+        },
+    },
+    Case {
+        name: "00FC SCL",
+        program: &[0x6004, 0x6100, 0xF129, 0xD015, 0x00FC],
+        keys: &[],
+        check: |m| {
+            pixel(m, 0, 0, 1)?;
+            pixel(m, 4, 0, 0)
+        },
+    },
+    Case {
+        name: "00FD EXIT",
+        program: &[0x00FD],
+        keys: &[],
+        check: |m| expect("PC", hex(m.pc()), hex(0x200)),
+    },
+    Case {
+        name: "00FE LOW",
+        program: &[0x00FF, 0x00FE],
+        keys: &[],
+        check: |m| expect("width", m.display().get_width(), 64),
+    },
+    Case {
+        name: "00FF HIGH",
+        program: &[0x00FF],
+        keys: &[],
+        check: |m| expect("width", m.display().get_width(), 128),
+    },
+    Case {
+        name: "1NNN JP NNN",
+        program: &[0x1204],
+        keys: &[],
+        check: |m| expect("PC", hex(m.pc()), hex(0x204)),
+    },
+    Case {
+        name: "2NNN CALL NNN",
+        program: &[0x2208],
+        keys: &[],
+        check: |m| {
+            expect("PC", hex(m.pc()), hex(0x208))?;
+            expect(
+                "stack",
+                format!("{:X?}", m.stack()),
+                format!("{:X?}", [0x202]),
+            )
+        },
+    },
+    Case {
+        name: "3XNN SE VX, NN",
+        program: &[0x6005, 0x3005],
+        keys: &[],
+        check: |m| expect("PC", hex(m.pc()), hex(0x206)),
+    },
+    Case {
+        name: "4XNN SNE VX, NN",
+        program: &[0x6005, 0x4006],
+        keys: &[],
+        check: |m| expect("PC", hex(m.pc()), hex(0x206)),
+    },
+    Case {
+        name: "5XY0 SE VX, VY",
+        program: &[0x6005, 0x6105, 0x5010],
+        keys: &[],
+        check: |m| expect("PC", hex(m.pc()), hex(0x208)),
+    },
+    Case {
+        name: "6XNN LD VX, NN",
+        program: &[0x6A42],
+        keys: &[],
+        check: |m| register(m, 0xA, 0x42),
+    },
+    Case {
+        name: "7XNN ADD VX, NN",
+        program: &[0x60FF, 0x7002],
+        keys: &[],
+        check: |m| {
+            register(m, 0x0, 0x01)?;
+            register(m, 0xF, 0x00)
+        },
+    },
+    Case {
+        name: "8XY0 LD VX, VY",
+        program: &[0x6107, 0x8010],
+        keys: &[],
+        check: |m| register(m, 0x0, 0x07),
+    },
+    Case {
+        name: "8XY1 OR VX, VY",
+        program: &[0x600C, 0x610A, 0x8011],
+        keys: &[],
+        check: |m| register(m, 0x0, 0x0E),
+    },
+    Case {
+        name: "8XY2 AND VX, VY",
+        program: &[0x600C, 0x610A, 0x8012],
+        keys: &[],
+        check: |m| register(m, 0x0, 0x08),
+    },
+    Case {
+        name: "8XY3 XOR VX, VY",
+        program: &[0x600C, 0x610A, 0x8013],
+        keys: &[],
+        check: |m| register(m, 0x0, 0x06),
+    },
+    Case {
+        name: "8XY4 ADD VX, VY",
+        program: &[0x60FF, 0x6102, 0x8014],
+        keys: &[],
+        check: |m| {
+            register(m, 0x0, 0x01)?;
+            register(m, 0xF, 0x01)
+        },
+    },
+    Case {
+        name: "8XY5 SUB VX, VY",
+        program: &[0x6005, 0x6107, 0x8015],
+        keys: &[],
+        check: |m| {
+            register(m, 0x0, 0xFE)?;
+            register(m, 0xF, 0x00)
+        },
+    },
+    Case {
+        name: "8XY6 SHR VX",
+        program: &[0x6005, 0x8006],
+        keys: &[],
+        check: |m| {
+            register(m, 0x0, 0x02)?;
+            register(m, 0xF, 0x01)
+        },
+    },
+    Case {
+        name: "8XY7 SUBN VX, VY",
+        program: &[0x6005, 0x6107, 0x8017],
+        keys: &[],
+        check: |m| {
+            register(m, 0x0, 0x02)?;
+            register(m, 0xF, 0x01)
+        },
+    },
+    Case {
+        name: "8XYE SHL VX",
+        program: &[0x6081, 0x800E],
+        keys: &[],
+        check: |m| {
+            register(m, 0x0, 0x02)?;
+            register(m, 0xF, 0x01)
+        },
+    },
+    Case {
+        name: "9XY0 SNE VX, VY",
+        program: &[0x6005, 0x6106, 0x9010],
+        keys: &[],
+        check: |m| expect("PC", hex(m.pc()), hex(0x208)),
+    },
+    Case {
+        name: "ANNN LD I, NNN",
+        program: &[0xA123],
+        keys: &[],
+        check: |m| expect("I", hex(m.i()), hex(0x123)),
+    },
+    Case {
+        name: "BNNN JP V0, NNN",
+        program: &[0x6004, 0xB300],
+        keys: &[],
+        check: |m| expect("PC", hex(m.pc()), hex(0x304)),
+    },
+    Case {
+        name: "CXNN RND VX, NN",
+        program: &[0x60FF, 0xC000, 0x61FF, 0xC10F],
+        keys: &[],
+        check: |m| {
+            register(m, 0x0, 0x00)?;
+            expect("V1 & F0", m.register(0x1) & 0xF0, 0x00)
+        },
+    },
+    Case {
+        name: "DXYN DRW VX, VY, N",
+        program: &[0x6000, 0xF029, 0xD015, 0xD015],
+        keys: &[],
+        check: |m| {
+            pixel(m, 0, 0, 0)?;
+            register(m, 0xF, 0x01)
+        },
+    },
+    Case {
+        name: "EX9E SKP VX",
+        program: &[0x6005, 0xE09E],
+        keys: &[0x5],
+        check: |m| expect("PC", hex(m.pc()), hex(0x206)),
+    },
+    Case {
+        name: "EXA1 SKNP VX",
+        program: &[0x6005, 0xE0A1],
+        keys: &[],
+        check: |m| expect("PC", hex(m.pc()), hex(0x206)),
+    },
+    Case {
+        name: "FX07 LD VX, DT",
+        program: &[0x6009, 0xF015, 0xF107],
+        keys: &[],
+        check: |m| register(m, 0x1, 0x09),
+    },
+    Case {
+        name: "FX0A LD VX, K",
+        program: &[0xF00A],
+        keys: &[0x7],
+        check: |m| {
+            register(m, 0x0, 0x07)?;
+            expect("PC", hex(m.pc()), hex(0x202))
+        },
+    },
+    Case {
+        name: "FX15 LD DT, VX",
+        program: &[0x6009, 0xF015],
+        keys: &[],
+        check: |m| expect("DT", m.delay_timer(), 9),
+    },
+    Case {
+        name: "FX18 LD ST, VX",
+        program: &[0x6009, 0xF018],
+        keys: &[],
+        check: |m| expect("ST", m.sound_timer(), 9),
+    },
+    Case {
+        name: "FX1E ADD I, VX",
+        program: &[0xA100, 0x6005, 0xF01E],
+        keys: &[],
+        check: |m| expect("I", hex(m.i()), hex(0x105)),
+    },
+    Case {
+        name: "FX29 LD F, VX",
+        program: &[0x6003, 0xF029],
+        keys: &[],
+        // the font starts at 0 with 5 bytes a character
+        check: |m| expect("I", hex(m.i()), hex(0xF)),
+    },
+    Case {
+        name: "FX30 LD HF, VX",
+        program: &[0x6003, 0xF030],
+        keys: &[],
+        // the big font follows the small one with 10 bytes a character
+        check: |m| expect("I", hex(m.i()), hex(0x6E)),
+    },
+    Case {
+        name: "FX33 LD B, VX",
+        program: &[0x607B, 0xA300, 0xF033],
+        keys: &[],
+        check: |m| memory(m, 0x300, &[1, 2, 3]),
+    },
+    Case {
+        name: "FX3A LD PITCH, VX",
+        program: &[0x6070, 0xF03A],
+        keys: &[],
+        check: |m| {
+            expect(
+                "pitch",
+                format!("{:?}", m.pitch()),
+                format!("{:?}", Some(0x70)),
+            )
+        },
+    },
+    Case {
+        name: "FX55 LD [I], VX",
+        program: &[0x6001, 0x6102, 0x6203, 0xA300, 0xF255],
+        keys: &[],
+        check: |m| {
+            memory(m, 0x300, &[1, 2, 3])?;
+            expect("I", hex(m.i()), hex(0x300))
+        },
+    },
+    Case {
+        name: "FX65 LD VX, [I]",
+        program: &[0x6001, 0x6102, 0xA300, 0xF155, 0x6000, 0x6100, 0xF165],
+        keys: &[],
+        check: |m| {
+            register(m, 0x0, 0x01)?;
+            register(m, 0x1, 0x02)
+        },
+    },
+];
+
+// one of Timendus' chip8 test roms, which aren't bundled as they have their
+// own licence. The roms that wait for keys to be pressed aren't run
+pub struct TestRom {
+    pub file: &'static str,
+
+    // written to 0x1FF before running, the roms that test several platforms
+    // skip their menu and test the one given: 1 for chip8 and 2 for
+    // SUPER-CHIP
+    platform: Option<u8>,
+
+    // the quirks preset the rom is run with
+    quirks: Option<&'static str>,
+}
+
+pub const TEST_ROMS: &[TestRom] = &[
+    TestRom {
+        file: "1-chip8-logo.ch8",
+        platform: None,
+        quirks: None,
+    },
+    TestRom {
+        file: "2-ibm-logo.ch8",
+        platform: None,
+        quirks: None,
+    },
+    TestRom {
+        file: "3-corax+.ch8",
+        platform: None,
+        quirks: None,
+    },
+    TestRom {
+        file: "4-flags.ch8",
+        platform: None,
+        quirks: None,
+    },
+    TestRom {
+        file: "5-quirks.ch8",
+        platform: Some(1),
+        quirks: Some("cosmac"),
+    },
+    TestRom {
+        file: "8-scrolling.ch8",
+        platform: Some(2),
+        quirks: Some("schip"),
+    },
+];
+
+// the most opcodes a test rom runs for if it doesn't finish first
+const TEST_ROM_MAX_CYCLES: u64 = 5_000_000;
+
+// the opcodes run between the timers counting down, about 1000 a second
+const TEST_ROM_CYCLES_PER_FRAME: u64 = 16;
+
+// run every case and check the outcome
+pub fn run() -> Vec<Outcome> {
+    CASES
+        .iter()
+        .map(|case| Outcome {
+            name: case.name,
+            error: catch_panic(|| run_case(case)).err(),
+        })
+        .collect()
+}
+
+// run a test rom until it finishes and return its screen, see screen_text.
+// The rom has finished once an opcode leaves the program counter where it
+// was, which is the loop the test roms end in, 00FD or waiting for a key
+pub fn run_test_rom(rom: &TestRom, data: &[u8]) -> Result<String, String> {
+    catch_panic(|| {
+        let mut interp = Interpreter::new();
+        interp.load_rom(data)?;
+        if let Some(platform) = rom.platform {
+            interp.write_memory(0x1FF, platform);
+        }
+        if let Some(quirks) = rom.quirks.and_then(Quirks::preset) {
+            interp.set_quirks(quirks);
+        }
+        for cycle in 1..=TEST_ROM_MAX_CYCLES {
+            let pc = interp.pc();
+            interp.cycle()?;
+            if interp.pc() == pc {
+                break;
+            }
+            if cycle % TEST_ROM_CYCLES_PER_FRAME == 0 {
+                interp.update_timers();
+            }
+        }
+        Ok(screen_text(interp.display()))
+    })
+}
+
+// the display as lines of # for lit pixels and . for dark ones, to compare
+// and to show on the console
+pub fn screen_text(display: &display::Display) -> String {
+    let mut text = String::new();
+    for y in 0..display.get_height() {
+        for x in 0..display.get_width() {
+            text.push(if display.get_pixel(x as u8, y as u8) == 0 {
+                '.'
+            } else {
+                '#'
+            });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+// a panic is a failure, the hook is quietened so it isn't printed too
+fn catch_panic<T>(f: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    panic::set_hook(hook);
+    match result {
+        Ok(result) => result,
+        Err(payload) => Err(crash::panic_message(&*payload)),
+    }
+}
+
+fn run_case(case: &Case) -> Result<(), String> {
+    let rom: Vec<u8> = case
+        .program
+        .iter()
+        .flat_map(|opcode| opcode.to_be_bytes())
+        .collect();
+    let mut interp = Interpreter::new();
+    interp.load_rom(&rom)?;
+    // one cycle for each opcode in the program
+    for _ in case.program {
+        let mut keys = [false; 0x10];
+        for key in case.keys {
+            keys[*key as usize] = true;
+        }
+        interp.set_keys(keys);
+        interp.cycle()?;
+    }
+    (case.check)(&interp)
+}
+
+fn expect<T: Display + PartialEq>(what: &str, actual: T, expected: T) -> Result<(), String> {
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(trf("selftest_expected", &[&what, &actual, &expected]))
+    }
+}
+
+fn hex(value: usize) -> String {
+    format!("{:03X}", value)
+}
+
+fn register(m: &Interpreter, index: usize, expected: u8) -> Result<(), String> {
+    expect(
+        &format!("V{:X}", index),
+        format!("{:02X}", m.register(index)),
+        format!("{:02X}", expected),
+    )
+}
+
+fn pixel(m: &Interpreter, x: u8, y: u8, expected: u8) -> Result<(), String> {
+    expect(
+        &format!("pixel {},{}", x, y),
+        m.display().get_pixel(x, y),
+        expected,
+    )
+}
+
+fn memory(m: &Interpreter, addr: usize, expected: &[u8]) -> Result<(), String> {
+    for (offset, value) in expected.iter().enumerate() {
+        expect(
+            &hex(addr + offset),
+            format!("{:02X}", m.read_memory(addr + offset)),
+            format!("{:02X}", value),
+        )?;
+    }
+    Ok(())
+}
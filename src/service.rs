@@ -0,0 +1,210 @@
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::error::Chip8Error;
+use crate::extensions::Extensions;
+use crate::i18n::tr;
+use crate::interpreter::Interpreter;
+use crate::interpreter::Snapshot;
+use crate::interpreter::KEY_COUNT;
+use crate::interpreter::PROGRAM_START;
+use crate::quirks::Quirks;
+
+// the opcodes run between countdowns of the timers when a job doesn't say,
+// 1000hz like the emulator
+const DEFAULT_CYCLES_PER_FRAME: u32 = 1000 / 60;
+
+// a rom to run with no window or wall clock, the keypad changing at the given
+// numbers of opcodes run like a replay
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub rom: Vec<u8>,
+    // the keypad from the given opcode on, in order
+    pub inputs: Vec<(u64, [bool; KEY_COUNT])>,
+    pub cycles: u64,
+    pub seed: u64,
+    pub quirks: Quirks,
+    pub extensions: Extensions,
+    pub cycles_per_frame: u32,
+    // where the rom is loaded and starts, 0x200 or 0x600 for the ETI-660
+    pub load_address: usize,
+}
+
+impl Job {
+    pub fn new(rom: Vec<u8>, cycles: u64) -> Job {
+        Job {
+            rom,
+            inputs: Vec::new(),
+            cycles,
+            seed: 0,
+            quirks: Quirks::default(),
+            extensions: Extensions::default(),
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            load_address: PROGRAM_START,
+        }
+    }
+}
+
+// how a job went
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+    pub cycles: u64,
+    pub frames: u64,
+    // the rom stopped itself with 00FD before running all its cycles
+    pub exited: bool,
+    pub elapsed: Duration,
+}
+
+// what a job left behind, the framebuffer is a byte per pixel row by row like
+// Interpreter::framebuffer()
+#[derive(Clone, Debug)]
+pub struct JobResult {
+    pub framebuffer: Vec<u8>,
+    pub width: usize,
+    pub height: usize,
+    pub state: Snapshot,
+    pub stats: Stats,
+}
+
+// a core kept between jobs, put back to power on before each
+pub struct Core {
+    interp: Interpreter,
+    power_on: Snapshot,
+}
+
+impl Default for Core {
+    fn default() -> Self {
+        Core::new()
+    }
+}
+
+impl Core {
+    pub fn new() -> Core {
+        let interp = Interpreter::new();
+        let power_on = interp.snapshot();
+        Core { interp, power_on }
+    }
+
+    // run a job on this thread, a rom that faults gives the fault
+    pub fn run(&mut self, job: &Job) -> Result<JobResult, Chip8Error> {
+        let start = Instant::now();
+        let interp = &mut self.interp;
+        interp.restore(&self.power_on);
+        interp.set_keys([false; KEY_COUNT]);
+        interp.set_quirks(job.quirks);
+        interp.set_extensions(job.extensions);
+        interp.set_seed(job.seed);
+        interp.set_load_address(job.load_address);
+        interp.load_rom(&job.rom)?;
+
+        let per_frame = job.cycles_per_frame.max(1) as u64;
+        let mut inputs = job.inputs.iter().peekable();
+        let mut stats = Stats::default();
+        while stats.cycles < job.cycles {
+            while let Some((_, keys)) = inputs.next_if(|(at, _)| *at <= stats.cycles) {
+                interp.set_keys(*keys);
+            }
+            if interp.current_opcode() == 0x00FD {
+                stats.exited = true;
+                break;
+            }
+            interp.cycle()?;
+            stats.cycles += 1;
+            if stats.cycles % per_frame == 0 {
+                interp.update_timers();
+                stats.frames += 1;
+            }
+        }
+        stats.elapsed = start.elapsed();
+
+        let display = interp.display();
+        Ok(JobResult {
+            framebuffer: interp.framebuffer().to_vec(),
+            width: display.get_width(),
+            height: display.get_height(),
+            state: interp.snapshot(),
+            stats,
+        })
+    }
+}
+
+type Work = (Job, Sender<Result<JobResult, String>>);
+
+// runs jobs on a pool of threads, each with its own core kept between jobs,
+// for batch runs, web services and training on many runs of a rom at once
+pub struct Service {
+    sender: Option<Sender<Work>>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl Service {
+    // a service with the given number of threads, at least 1
+    pub fn new(threads: usize) -> Service {
+        let (sender, receiver) = mpsc::channel::<Work>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let workers = (0..threads.max(1))
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                thread::spawn(move || {
+                    let mut core = Core::new();
+                    loop {
+                        // the lock is let go before the job runs
+                        let work = receiver.lock().unwrap().recv();
+                        let Ok((job, reply)) = work else {
+                            break;
+                        };
+                        reply.send(core.run(&job).map_err(String::from)).ok();
+                    }
+                })
+            })
+            .collect();
+        Service {
+            sender: Some(sender),
+            workers,
+        }
+    }
+
+    // a service with a thread for each cpu
+    pub fn with_available_parallelism() -> Service {
+        Service::new(thread::available_parallelism().map_or(1, |n| n.get()))
+    }
+
+    // queue a job, the result comes on the returned receiver when it is done
+    pub fn submit(&self, job: Job) -> Receiver<Result<JobResult, String>> {
+        let (reply, result) = mpsc::channel();
+        if let Some(sender) = &self.sender {
+            sender.send((job, reply)).ok();
+        }
+        result
+    }
+
+    // run the jobs and wait for all of them, the results are in job order
+    pub fn run(&self, jobs: Vec<Job>) -> Vec<Result<JobResult, String>> {
+        let pending: Vec<_> = jobs.into_iter().map(|job| self.submit(job)).collect();
+        pending
+            .into_iter()
+            .map(|result| {
+                result
+                    .recv()
+                    .unwrap_or_else(|_| Err(tr("service_worker_stopped")))
+            })
+            .collect()
+    }
+}
+
+impl Drop for Service {
+    // let the workers finish the queued jobs and stop
+    fn drop(&mut self) {
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            worker.join().ok();
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::io;
+use std::io::ErrorKind;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::SyncSender;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+use std::thread;
+use std::time::Duration;
+
+use crate::i18n::trf;
+
+// how long a write to a spectator may take before they're dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+// how often the port is checked for spectators joining
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+// the frames a spectator can fall behind before they're dropped, ten
+// seconds at 60hz
+const MAX_QUEUED: usize = 600;
+// the most of the replay kept for spectators joining late, hours of play.
+// Past it spectators can't join, they'd be missing the start
+const MAX_HISTORY: usize = 32 << 20;
+
+// sends the replay of a run to spectators over tcp as it is played. They
+// get everything from power on when they join, so they play the run from
+// the start and catch up, then follow along a frame at a time. Spectators
+// can only watch, nothing they send is read. Joining and sending happen on
+// threads of their own so a slow spectator never holds up the game
+pub struct SpectatorServer {
+    shared: Arc<Mutex<Shared>>,
+    // written since the last flush, sent at the end of each frame
+    pending: Vec<u8>,
+}
+
+// what the game and the thread letting spectators in both use
+struct Shared {
+    // the whole replay so far for spectators joining late, None once it got
+    // too long to keep
+    history: Option<Vec<u8>>,
+    // the frames for each spectator's thread to send
+    spectators: Vec<SyncSender<Arc<[u8]>>>,
+}
+
+impl SpectatorServer {
+    // listen for spectators on a port of every address
+    pub fn bind(port: u16) -> Result<SpectatorServer, String> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .and_then(|listener| listener.set_nonblocking(true).map(|_| listener))
+            .map_err(|e| trf("spectators_listen_error", &[&port, &e]))?;
+        let shared = Arc::new(Mutex::new(Shared {
+            history: Some(Vec::new()),
+            spectators: Vec::new(),
+        }));
+        let weak = Arc::downgrade(&shared);
+        thread::spawn(move || accept(listener, weak));
+        Ok(SpectatorServer {
+            shared,
+            pending: Vec::new(),
+        })
+    }
+}
+
+// let in spectators until the server is dropped, each gets a thread sending
+// them the replay so far and then each frame
+fn accept(listener: TcpListener, shared: Weak<Mutex<Shared>>) {
+    loop {
+        let Some(shared) = shared.upgrade() else {
+            return;
+        };
+        match listener.accept() {
+            Ok((stream, addr)) => {
+                let mut shared = shared.lock().unwrap_or_else(|e| e.into_inner());
+                let Some(history) = shared.history.as_ref() else {
+                    println!("{}", trf("spectator_too_late", &[&addr]));
+                    continue;
+                };
+                let (sender, receiver) = mpsc::sync_channel(MAX_QUEUED);
+                // the history goes first, anything after it is queued
+                let _ = sender.try_send(Arc::from(history.as_slice()));
+                shared.spectators.push(sender);
+                println!("{}", trf("spectator_joined", &[&addr]));
+                let addr = addr.to_string();
+                thread::spawn(move || send(stream, &addr, receiver));
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                drop(shared);
+                thread::sleep(ACCEPT_INTERVAL);
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+// send a spectator what is queued for them until they or the game go
+fn send(mut stream: TcpStream, addr: &str, receiver: Receiver<Arc<[u8]>>) {
+    let ready = stream.set_nonblocking(false).is_ok()
+        && stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_ok();
+    if ready {
+        let _ = stream.set_nodelay(true);
+        for chunk in receiver {
+            if stream.write_all(&chunk).is_err() {
+                break;
+            }
+        }
+    }
+    println!("{}", trf("spectator_left", &[&addr]));
+}
+
+impl Write for SpectatorServer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    // queue what was written for the spectators, dropping those that have
+    // gone or fallen too far behind
+    fn flush(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let pending = std::mem::take(&mut self.pending);
+        let mut shared = self.shared.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(history) = shared.history.as_mut() {
+            history.extend_from_slice(&pending);
+            if history.len() > MAX_HISTORY {
+                shared.history = None;
+            }
+        }
+        let chunk: Arc<[u8]> = Arc::from(pending);
+        shared
+            .spectators
+            .retain(|sender| sender.try_send(chunk.clone()).is_ok());
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::i18n::trf;
+use crate::json::object;
+use crate::json::Json;
+
+// a rom played, kept as a line of json in the stats file. Nothing is sent
+// anywhere, the file is only for the user to look at or feed into their own
+// tools, e.g.
+//
+// {"rom":"PONG","sha1":"...","started":1760000000,"seconds":312,"opcodes":124800,"faults":0,"fault":null}
+pub struct Session<'a> {
+    // the file name of the rom and its sha1
+    pub rom: &'a str,
+    pub sha1: &'a str,
+    // the unix time it started and how long it ran for
+    pub started: u64,
+    pub seconds: u64,
+    pub opcodes: u64,
+    // the faults skipped or reset after, and the fault that stopped the rom
+    pub faults: u64,
+    pub fault: Option<&'a str>,
+}
+
+impl Session<'_> {
+    pub fn line(&self) -> String {
+        object(vec![
+            ("rom", Json::Str(self.rom.to_string())),
+            ("sha1", Json::Str(self.sha1.to_string())),
+            ("started", Json::Num(self.started)),
+            ("seconds", Json::Num(self.seconds)),
+            ("opcodes", Json::Num(self.opcodes)),
+            ("faults", Json::Num(self.faults)),
+            (
+                "fault",
+                self.fault
+                    .map_or(Json::Null, |fault| Json::Str(fault.to_string())),
+            ),
+        ])
+        .line()
+    }
+}
+
+// the seconds since 1970
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs())
+}
+
+// add a session to the end of the stats file, which is made the first time
+pub fn append(path: &Path, session: &Session) -> Result<(), String> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| trf("file_write_error", &[&path.display(), &e]))?;
+    writeln!(file, "{}", session.line())
+        .map_err(|e| trf("file_write_error", &[&path.display(), &e]))
+}
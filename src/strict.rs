@@ -0,0 +1,201 @@
+use crate::error::Chip8Error;
+use crate::expr::MachineView;
+use crate::i18n::tr;
+use crate::i18n::trf;
+use crate::interpreter::Interpreter;
+use crate::interpreter::MEM_SIZE;
+use crate::interpreter::PROGRAM_START;
+use crate::opcode::Instruction;
+
+// the stack of the COSMAC VIP holds 16 return addresses
+const STACK_DEPTH: usize = 16;
+
+// the COSMAC VIP keeps its stack and the display at the top of memory, roms
+// can only write between the interpreter and them
+const WRITABLE_END: usize = 0xEA0;
+
+// checks every opcode before it runs for anything the most conservative
+// interpreters would do differently or not at all: opcodes beyond the
+// original instruction set and undefined encodings, memory accesses out of
+// bounds, more than 16 nested calls and anything the quirks change the
+// outcome of. What it finds is a fault at the opcode
+#[derive(Debug, Default)]
+pub struct Strict {
+    // I after FX55 or FX65, which moves it on only on some interpreters
+    i_ambiguous: bool,
+    // VF after 8XY1, 8XY2 or 8XY3, which reset it only on some interpreters
+    vf_ambiguous: bool,
+}
+
+impl Strict {
+    // the fault the opcode about to run would be in strict mode, if any
+    pub fn check(&mut self, interp: &Interpreter) -> Result<(), Chip8Error> {
+        let pc = interp.pc();
+        let opcode = interp.current_opcode();
+        let fault = |message: String| Err(Chip8Error::Strict { pc, message });
+        if pc + 1 >= MEM_SIZE {
+            return fault(tr("strict_pc"));
+        }
+
+        let instruction = Instruction::decode(opcode);
+        match instruction {
+            Instruction::Scd(_)
+            | Instruction::Scr
+            | Instruction::Scl
+            | Instruction::Exit
+            | Instruction::Low
+            | Instruction::High
+            | Instruction::LdHfVx(_)
+            | Instruction::LdPitchVx(_)
+            | Instruction::LdBankVx(_) => {
+                return fault(trf("strict_not_chip8", &[&format!("{:04X}", opcode)]))
+            }
+            Instruction::SeVxVy(..) | Instruction::SneVxVy(..) if opcode & 0xF != 0 => {
+                return Err(Chip8Error::InvalidOpcode { pc, opcode })
+            }
+            Instruction::Call(_) if interp.stack().len() >= STACK_DEPTH => {
+                return Err(Chip8Error::StackOverflow { pc })
+            }
+            _ => {}
+        }
+
+        if self.i_ambiguous && uses_i(&instruction) {
+            return fault(tr("strict_load_store"));
+        }
+        if self.vf_ambiguous && reads_vf(&instruction) {
+            return fault(tr("strict_vf_reset"));
+        }
+        let i = interp.i();
+        match instruction {
+            Instruction::Shr(x, y) | Instruction::Shl(x, y) if x != y => {
+                return fault(tr("strict_shift"));
+            }
+            Instruction::JpV0(nnn) => {
+                let x = nnn >> 8;
+                if x != 0 && interp.register(x) != interp.register(0) {
+                    return fault(tr("strict_jump"));
+                }
+            }
+            Instruction::Drw(_, _, 0) => {
+                return fault(trf("strict_not_chip8", &[&format!("{:04X}", opcode)]))
+            }
+            Instruction::Drw(x, y, n) => {
+                if i + n as usize > MEM_SIZE {
+                    return fault(trf("strict_memory", &[&format!("{:03X}", i)]));
+                }
+                // only the pixels the sprite sets can wrap
+                let display = interp.display();
+                let (width, height) = (display.get_width(), display.get_height());
+                let left = interp.register(x) as usize % width;
+                let top = interp.register(y) as usize % height;
+                let wraps = (0..n as usize).any(|row| {
+                    let bits = interp.read_memory(i + row);
+                    let past_edge = if left + 8 > width {
+                        bits << (width - left)
+                    } else {
+                        0
+                    };
+                    bits != 0 && (top + row >= height || past_edge != 0)
+                });
+                if wraps {
+                    return fault(tr("strict_wrap"));
+                }
+            }
+            Instruction::Skp(x) | Instruction::Sknp(x) if interp.register(x) > 0xF => {
+                return fault(trf("strict_key", &[&format!("{:02X}", interp.register(x))]));
+            }
+            Instruction::AddIVx(x) if i + interp.register(x) as usize >= MEM_SIZE => {
+                return fault(trf("strict_memory", &[&format!("{:03X}", i)]));
+            }
+            Instruction::LdVxI(x) if i + x >= MEM_SIZE => {
+                return fault(trf("strict_memory", &[&format!("{:03X}", i)]));
+            }
+            Instruction::LdBVx(_) | Instruction::LdIVx(_) => {
+                let end = match instruction {
+                    Instruction::LdIVx(x) => i + x + 1,
+                    _ => i + 3,
+                };
+                if i < PROGRAM_START || end > WRITABLE_END {
+                    return fault(trf("strict_write", &[&format!("{:03X}", i)]));
+                }
+            }
+            _ => {}
+        }
+
+        match instruction {
+            Instruction::LdIVx(_) | Instruction::LdVxI(_) => self.i_ambiguous = true,
+            Instruction::LdI(_) | Instruction::LdFVx(_) => self.i_ambiguous = false,
+            _ => {}
+        }
+        match instruction {
+            Instruction::Or(..) | Instruction::And(..) | Instruction::Xor(..) => {
+                self.vf_ambiguous = true
+            }
+            instruction if writes_vf(&instruction) => self.vf_ambiguous = false,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+// instructions whose outcome depends on I
+fn uses_i(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Drw(..)
+            | Instruction::AddIVx(_)
+            | Instruction::LdBVx(_)
+            | Instruction::LdIVx(_)
+            | Instruction::LdVxI(_)
+    )
+}
+
+// instructions that read VF
+fn reads_vf(instruction: &Instruction) -> bool {
+    match *instruction {
+        Instruction::SeVxNn(x, _)
+        | Instruction::SneVxNn(x, _)
+        | Instruction::AddVxNn(x, _)
+        | Instruction::Skp(x)
+        | Instruction::Sknp(x)
+        | Instruction::LdDtVx(x)
+        | Instruction::LdStVx(x)
+        | Instruction::AddIVx(x)
+        | Instruction::LdFVx(x)
+        | Instruction::LdBVx(x)
+        | Instruction::LdIVx(x) => x == 0xF,
+        Instruction::SeVxVy(x, y)
+        | Instruction::SneVxVy(x, y)
+        | Instruction::Or(x, y)
+        | Instruction::And(x, y)
+        | Instruction::Xor(x, y)
+        | Instruction::AddVxVy(x, y)
+        | Instruction::Sub(x, y)
+        | Instruction::Subn(x, y)
+        | Instruction::Shr(x, y)
+        | Instruction::Shl(x, y)
+        | Instruction::Drw(x, y, _) => x == 0xF || y == 0xF,
+        Instruction::LdVxVy(_, y) => y == 0xF,
+        _ => false,
+    }
+}
+
+// instructions that set VF without reading it
+fn writes_vf(instruction: &Instruction) -> bool {
+    match *instruction {
+        Instruction::LdVxNn(x, _)
+        | Instruction::LdVxVy(x, _)
+        | Instruction::Rnd(x, _)
+        | Instruction::LdVxDt(x)
+        | Instruction::LdVxK(x)
+        | Instruction::LdVxI(x) => x == 0xF,
+        // the flag is set by arithmetic and drawing
+        Instruction::AddVxVy(..)
+        | Instruction::Sub(..)
+        | Instruction::Subn(..)
+        | Instruction::Shr(..)
+        | Instruction::Shl(..)
+        | Instruction::Drw(..) => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,113 @@
+use crate::expr::MachineView;
+use crate::i18n::trf;
+use crate::opcode::Instruction;
+
+// a program counter that stays within this many bytes is going round the
+// same few opcodes
+const LOOP_SPAN: usize = 16;
+
+// where a rom got stuck and for how long
+pub struct Stuck {
+    pub start: usize,
+    pub end: usize,
+    pub seconds: u32,
+}
+
+impl Stuck {
+    // the diagnostic and a line for each opcode of the loop, e.g. "2A0: JP 2A0"
+    pub fn lines(&self, machine: &dyn MachineView) -> Vec<String> {
+        let range = format!("{:03X}-{:03X}", self.start, self.end);
+        let mut lines = vec![trf("stuck", &[&range, &self.seconds])];
+        for addr in (self.start..=self.end).step_by(2) {
+            let opcode =
+                (machine.read_memory(addr) as u16) << 8 | machine.read_memory(addr + 1) as u16;
+            lines.push(format!("  {:03X}: {}", addr, Instruction::decode(opcode)));
+        }
+        lines
+    }
+}
+
+// notices a rom that has hung, its program counter going round the same few
+// opcodes for a while without drawing, reading the keys, using the timers or
+// changing the display. Waiting for a key with FX0A and stopping with 00FD
+// aren't hangs
+pub struct StuckDetector {
+    seconds: u32,
+
+    // the lowest and highest address run since the rom last did something,
+    // and in the last update which is the loop it is stuck in
+    low: usize,
+    high: usize,
+    loop_low: usize,
+    loop_high: usize,
+
+    // set when the rom did something since the last update
+    active: bool,
+    // the updates since the rom last did something
+    quiet: u32,
+    // set once the rom has been found stuck until it does something again
+    reported: bool,
+}
+
+impl StuckDetector {
+    pub fn new(seconds: u32) -> StuckDetector {
+        StuckDetector {
+            seconds: seconds.max(1),
+            low: usize::MAX,
+            high: 0,
+            loop_low: usize::MAX,
+            loop_high: 0,
+            active: true,
+            quiet: 0,
+            reported: false,
+        }
+    }
+
+    // note the opcode that is about to run at pc
+    pub fn observe(&mut self, pc: usize, opcode: u16) {
+        let does_something = opcode & 0xF000 == 0xD000
+            || opcode & 0xFFF0 == 0x00C0
+            || opcode & 0xFFF0 == 0x00D0
+            || matches!(opcode, 0x00E0 | 0x00FB | 0x00FC | 0x00FD)
+            || matches!(opcode & 0xF0FF, 0xE09E | 0xE0A1 | 0xF00A | 0xF015 | 0xF018);
+        self.active |= does_something;
+        if self.active {
+            return;
+        }
+        self.low = self.low.min(pc);
+        self.high = self.high.max(pc);
+        self.loop_low = self.loop_low.min(pc);
+        self.loop_high = self.loop_high.max(pc);
+        if self.high - self.low >= LOOP_SPAN {
+            self.active = true;
+        }
+    }
+
+    // end a number of updates, giving where the rom is stuck the first time
+    // it has done nothing for long enough. A running timer is the rom waiting
+    pub fn update(&mut self, machine: &dyn MachineView, count: u32, refresh: u32) -> Option<Stuck> {
+        if self.active || machine.delay_timer() > 0 || machine.sound_timer() > 0 {
+            self.active = false;
+            self.low = usize::MAX;
+            self.high = 0;
+            self.loop_low = usize::MAX;
+            self.loop_high = 0;
+            self.quiet = 0;
+            self.reported = false;
+            return None;
+        }
+        let (start, end) = (self.loop_low, self.loop_high);
+        self.loop_low = usize::MAX;
+        self.loop_high = 0;
+        self.quiet += count;
+        if self.reported || start > end || self.quiet < self.seconds * refresh {
+            return None;
+        }
+        self.reported = true;
+        Some(Stuck {
+            start,
+            end,
+            seconds: self.seconds,
+        })
+    }
+}
@@ -0,0 +1,107 @@
+use crate::display::Display;
+use crate::interpreter::FONT_DATA;
+
+// the names of the test patterns for the command line
+pub const PATTERN_NAMES: &[&str] = &["checkerboard", "densities", "moving"];
+
+// an 8x8 ball for the moving pattern
+const BALL: [u8; 8] = [0x3C, 0x7E, 0xFF, 0xFF, 0xFF, 0xFF, 0x7E, 0x3C];
+
+// the 4x4 ordered dither, each pixel is lit once the density is past its
+// threshold
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+// pictures drawn on the display like a rom would, for trying out a frontend,
+// the theme preview and the colours without running a rom. They go through
+// Display and a VideoSink the same as any rom, so the whole pipeline is used
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestPattern {
+    // every other pixel lit, shows up any pixel that is drawn the wrong size
+    // or in the wrong place
+    Checkerboard,
+    // bands from one pixel in 16 lit to all of them, with the hex digits of
+    // the font on top, to see how the colours look side by side
+    Densities,
+    // a ball bouncing around a border, to check the frames keep coming and
+    // the edges are all there
+    Moving,
+}
+
+impl TestPattern {
+    pub fn from_name(name: &str) -> Option<TestPattern> {
+        match name {
+            "checkerboard" => Some(TestPattern::Checkerboard),
+            "densities" => Some(TestPattern::Densities),
+            "moving" => Some(TestPattern::Moving),
+            _ => None,
+        }
+    }
+
+    // whether the pattern changes from frame to frame
+    pub fn is_moving(&self) -> bool {
+        *self == TestPattern::Moving
+    }
+
+    // draw the pattern as it is at a frame, counted from 0, over whatever is
+    // on the display
+    pub fn draw(&self, display: &mut Display, frame: u64) {
+        display.clear();
+        let (width, height) = (display.get_width(), display.get_height());
+        match self {
+            TestPattern::Checkerboard => {
+                fill(display, |x, y| (x + y) % 2 == 0);
+            }
+            TestPattern::Densities => {
+                let band = (width / 16).max(1);
+                let top = height / 2;
+                fill(display, |x, y| {
+                    let density = (x / band).min(15) as u8;
+                    y >= top && BAYER[y % 4][x % 4] <= density
+                });
+                for (digit, rows) in FONT_DATA.chunks(5).enumerate() {
+                    let left = 2 + digit % 8 * 5;
+                    let top = 2 + digit / 8 * 6;
+                    sprite(display, left, top, rows);
+                }
+            }
+            TestPattern::Moving => {
+                fill(display, |x, y| {
+                    x == 0 || y == 0 || x == width - 1 || y == height - 1
+                });
+                let x = 1 + bounce(frame, width - 2 - BALL.len());
+                let y = 1 + bounce(frame * 2 / 3, height - 2 - BALL.len());
+                sprite(display, x, y, &BALL);
+            }
+        }
+    }
+}
+
+// light the pixels of the display that lit says should be
+fn fill(display: &mut Display, lit: impl Fn(usize, usize) -> bool) {
+    for y in 0..display.get_height() {
+        for x in 0..display.get_width() {
+            if lit(x, y) {
+                display.set_pixel(x as u8, y as u8, 1);
+            }
+        }
+    }
+}
+
+// draw the rows of a sprite 8 pixels wide, flipping the pixels under it like
+// DXYN
+fn sprite(display: &mut Display, left: usize, top: usize, rows: &[u8]) {
+    for (y, row) in rows.iter().enumerate() {
+        for x in 0..8 {
+            if row & (0x80 >> x) != 0 {
+                display.set_pixel((left + x) as u8, (top + y) as u8, 1);
+            }
+        }
+    }
+}
+
+// a position going from 0 up to span and back down again
+fn bounce(frame: u64, span: usize) -> usize {
+    let span = span.max(1) as u64;
+    let step = frame % (span * 2);
+    (if step > span { span * 2 - step } else { step }) as usize
+}
@@ -0,0 +1,131 @@
+use std::fmt;
+
+use crate::banking;
+use crate::expr::Expr;
+use crate::expr::MachineView;
+use crate::i18n::trf;
+use crate::watch::Watchpoint;
+
+// when a tracepoint logs its message
+enum Trigger {
+    // every time the opcode at the address is about to run, in the bank if
+    // one is given
+    Address(usize, Option<usize>),
+    // each time the condition becomes true
+    Condition(Watchpoint),
+}
+
+// a part of a tracepoint message
+enum Piece {
+    Text(String),
+    // an expression shown in decimal, or in hex with :x
+    Value(Expr, bool),
+}
+
+// logs a message with values from the machine when it is hit, without
+// pausing, such as "score={V3}" at 2F0. Anything in braces is an expression
+// like those of watchpoints, {I:x} shows it in hex and {{ is a brace
+pub struct Tracepoint {
+    trigger: Trigger,
+    source: String,
+    message: Vec<Piece>,
+}
+
+impl Tracepoint {
+    // a tracepoint at an address, in one bank if one is given
+    pub fn at(addr: usize, bank: Option<usize>, message: &str) -> Result<Tracepoint, String> {
+        Tracepoint::new(Trigger::Address(addr, bank), message)
+    }
+
+    // a tracepoint on a condition such as "V3 > 10"
+    pub fn when(condition: &str, message: &str) -> Result<Tracepoint, String> {
+        Tracepoint::new(Trigger::Condition(Watchpoint::parse(condition)?), message)
+    }
+
+    fn new(trigger: Trigger, message: &str) -> Result<Tracepoint, String> {
+        Ok(Tracepoint {
+            trigger,
+            source: message.to_string(),
+            message: parse_message(message)?,
+        })
+    }
+
+    // the message if the tracepoint is hit by the opcode about to run
+    pub fn check(&mut self, machine: &dyn MachineView) -> Option<String> {
+        let hit = match self.trigger {
+            Trigger::Address(addr, bank) => {
+                machine.pc() == addr && (bank.is_none() || machine.bank() == bank)
+            }
+            Trigger::Condition(ref mut watchpoint) => watchpoint.check(machine),
+        };
+        hit.then(|| self.format(machine))
+    }
+
+    fn format(&self, machine: &dyn MachineView) -> String {
+        let mut out = String::new();
+        for piece in &self.message {
+            match piece {
+                Piece::Text(text) => out.push_str(text),
+                Piece::Value(expr, false) => out.push_str(&expr.eval(machine).to_string()),
+                Piece::Value(expr, true) => out.push_str(&format!("{:X}", expr.eval(machine))),
+            }
+        }
+        out
+    }
+}
+
+// the tracepoint as it would be typed, e.g. 2F0 "score={V3}"
+impl fmt::Display for Tracepoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.trigger {
+            Trigger::Address(addr, bank) => write!(f, "{}", banking::format_address(addr, bank))?,
+            Trigger::Condition(ref watchpoint) => write!(f, "\"{}\"", watchpoint.expr())?,
+        }
+        write!(f, " \"{}\"", self.source)
+    }
+}
+
+fn parse_message(message: &str) -> Result<Vec<Piece>, String> {
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    let mut chars = message.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut inner = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    inner.push(c);
+                }
+                let (source, hex) = match inner.strip_suffix(":x") {
+                    Some(source) => (source, true),
+                    None => (inner.as_str(), false),
+                };
+                if !closed || source.trim().is_empty() {
+                    return Err(trf("trace_invalid_message", &[&message]));
+                }
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Value(Expr::parse(source)?, hex));
+            }
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
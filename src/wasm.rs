@@ -0,0 +1,222 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::audio::BUZZER_HZ;
+use crate::backend::AudioSink;
+use crate::backend::Command;
+use crate::backend::InputSource;
+use crate::backend::VideoSink;
+use crate::clock::Instant;
+use crate::colors::Theme;
+use crate::display::Display;
+use crate::emulator::Emulator;
+use crate::interpreter::Interpreter;
+use crate::osd::Osd;
+use crate::screenshot::Pixelmap;
+
+// the browser frontend, driven by web/chip8.js. The page copies a rom into
+// the buffer from chip8_rom_buffer, starts it with chip8_start and then calls
+// chip8_frame from requestAnimationFrame, drawing chip8_pixels on a canvas
+// and playing a webaudio tone while chip8_beep is set
+
+// each chip8 pixel is this many pixels of the canvas, so a super chip high
+// resolution display is drawn one to one
+const PIXELSIZE: usize = 2;
+
+extern "C" {
+    // fill len bytes at ptr with crypto.getRandomValues
+    fn chip8_random(ptr: *mut u8, len: usize);
+}
+
+// the random numbers for CXNN come from the browser
+fn browser_random(buf: &mut [u8]) -> Result<(), getrandom::Error> {
+    unsafe { chip8_random(buf.as_mut_ptr(), buf.len()) };
+    Ok(())
+}
+
+getrandom::register_custom_getrandom!(browser_random);
+
+// what the page and the emulator pass each other
+struct Page {
+    keys: [bool; 0x10],
+    commands: Vec<Command>,
+    pixels: Pixelmap,
+    theme: Theme,
+    beep: bool,
+    pitch: f32,
+}
+
+impl Default for Page {
+    fn default() -> Page {
+        Page {
+            keys: [false; 0x10],
+            commands: Vec::new(),
+            pixels: Pixelmap::render(&Display::default(), PIXELSIZE, Theme::default()),
+            theme: Theme::default(),
+            beep: false,
+            pitch: BUZZER_HZ,
+        }
+    }
+}
+
+struct Running {
+    emulator: Emulator<'static>,
+    start_time: Instant,
+}
+
+thread_local! {
+    static PAGE: Rc<RefCell<Page>> = Rc::default();
+    static ROM: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    static RUNNING: RefCell<Option<Running>> = const { RefCell::new(None) };
+}
+
+fn with_page<T>(f: impl FnOnce(&mut Page) -> T) -> T {
+    PAGE.with(|page| f(&mut page.borrow_mut()))
+}
+
+// draws the display into the pixels the page copies to its canvas, the osd
+// isn't drawn
+struct PageVideo(Rc<RefCell<Page>>);
+
+impl VideoSink for PageVideo {
+    fn render(&mut self, display: &mut Display, osd: &mut Osd) {
+        osd.take_dirty();
+        if display.take_dirty() {
+            let mut page = self.0.borrow_mut();
+            page.pixels = Pixelmap::render(display, PIXELSIZE, page.theme);
+        }
+    }
+
+    fn pixelmap(&self, display: &Display) -> Option<Pixelmap> {
+        Some(Pixelmap::render(display, PIXELSIZE, self.0.borrow().theme))
+    }
+
+    fn set_theme(&mut self, theme: Theme) {
+        self.0.borrow_mut().theme = theme;
+    }
+}
+
+// leaves the buzzer for the page to play
+struct PageAudio(Rc<RefCell<Page>>);
+
+impl AudioSink for PageAudio {
+    fn set_beep(&mut self, on: bool) {
+        self.0.borrow_mut().beep = on;
+    }
+
+    fn set_pitch(&mut self, hz: f32) {
+        self.0.borrow_mut().pitch = hz;
+    }
+}
+
+// the keys and commands the page sends
+struct PageInput(Rc<RefCell<Page>>);
+
+impl InputSource for PageInput {
+    fn read_keys(&mut self) -> [bool; 0x10] {
+        self.0.borrow().keys
+    }
+
+    fn poll_commands(&mut self) -> Vec<Command> {
+        std::mem::take(&mut self.0.borrow_mut().commands)
+    }
+}
+
+/// A buffer of `len` bytes for the page to copy the rom into before
+/// `chip8_start`.
+#[no_mangle]
+pub extern "C" fn chip8_rom_buffer(len: usize) -> *mut u8 {
+    ROM.with(|rom| {
+        let mut rom = rom.borrow_mut();
+        *rom = vec![0; len];
+        rom.as_mut_ptr()
+    })
+}
+
+/// Start the rom in the buffer at `clockspeed` opcodes a second, in place of
+/// any rom running. Returns 0 if it doesn't fit in memory.
+#[no_mangle]
+pub extern "C" fn chip8_start(clockspeed: u32) -> u32 {
+    let mut interp = Interpreter::new();
+    if ROM.with(|rom| interp.load_rom(&rom.borrow())).is_err() {
+        return 0;
+    }
+    let page = PAGE.with(Rc::clone);
+    let start_time = Instant::now();
+    let emulator = Emulator::new(
+        interp,
+        Box::new(PageVideo(page.clone())),
+        Box::new(PageAudio(page.clone())),
+        Box::new(PageInput(page)),
+        clockspeed.max(1),
+        &start_time,
+    );
+    RUNNING.with(|running| {
+        *running.borrow_mut() = Some(Running {
+            emulator,
+            start_time,
+        })
+    });
+    1
+}
+
+/// Run everything due since the last frame, called from
+/// `requestAnimationFrame`.
+#[no_mangle]
+pub extern "C" fn chip8_frame() {
+    RUNNING.with(|running| {
+        if let Some(running) = running.borrow_mut().as_mut() {
+            running.emulator.catch_up(running.start_time.elapsed());
+        }
+    });
+}
+
+/// Press a keypad key with `down` 1, let go of it with 0.
+#[no_mangle]
+pub extern "C" fn chip8_key(key: u32, down: u32) {
+    with_page(|page| {
+        if let Some(pressed) = page.keys.get_mut(key as usize) {
+            *pressed = down != 0;
+        }
+    });
+}
+
+/// Pause the machine or carry on.
+#[no_mangle]
+pub extern "C" fn chip8_toggle_pause() {
+    with_page(|page| page.commands.push(Command::TogglePause));
+}
+
+/// Start the rom again from the beginning.
+#[no_mangle]
+pub extern "C" fn chip8_reset() {
+    with_page(|page| page.commands.push(Command::Reset));
+}
+
+/// The display as rgb pixels row by row, `chip8_width` by `chip8_height`.
+#[no_mangle]
+pub extern "C" fn chip8_pixels() -> *const u8 {
+    with_page(|page| page.pixels.data.as_ptr())
+}
+
+#[no_mangle]
+pub extern "C" fn chip8_width() -> usize {
+    with_page(|page| page.pixels.width)
+}
+
+#[no_mangle]
+pub extern "C" fn chip8_height() -> usize {
+    with_page(|page| page.pixels.height)
+}
+
+/// 1 while the buzzer sounds.
+#[no_mangle]
+pub extern "C" fn chip8_beep() -> u32 {
+    with_page(|page| page.beep as u32)
+}
+
+/// The frequency of the buzzer in hz.
+#[no_mangle]
+pub extern "C" fn chip8_pitch() -> f32 {
+    with_page(|page| page.pitch)
+}
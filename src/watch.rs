@@ -0,0 +1,35 @@
+use crate::expr::Expr;
+use crate::expr::MachineView;
+
+// a condition on the machine state that fires on the transition from false to
+// true, so a condition that stays true only fires once until it goes false
+// again
+pub struct Watchpoint {
+    expr: Expr,
+    was_true: bool,
+}
+
+impl Watchpoint {
+    pub fn new(expr: Expr) -> Watchpoint {
+        Watchpoint {
+            expr,
+            was_true: false,
+        }
+    }
+
+    pub fn parse(source: &str) -> Result<Watchpoint, String> {
+        Ok(Watchpoint::new(Expr::parse(source)?))
+    }
+
+    // evaluate the condition, returning true if it has just become true
+    pub fn check(&mut self, machine: &dyn MachineView) -> bool {
+        let is_true = self.expr.is_true(machine);
+        let fired = is_true && !self.was_true;
+        self.was_true = is_true;
+        fired
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+}
@@ -0,0 +1,139 @@
+// the browser frontend: runs chip8.wasm with the rom picked or dropped on the
+// page, draws the display on the canvas, plays the buzzer with webaudio and
+// runs the emulator from requestAnimationFrame. Build chip8.wasm with
+//
+//   cargo rustc --lib --crate-type cdylib --release \
+//       --target wasm32-unknown-unknown --no-default-features --features wasm
+//
+// and copy target/wasm32-unknown-unknown/release/chip8.wasm next to this file
+
+// the opcodes run a second
+const CLOCKSPEED = 400;
+
+// the keyboard keys in the same places as those of the hex keypad
+const KEYPAD = {
+  Digit1: 0x1, Digit2: 0x2, Digit3: 0x3, Digit4: 0xc,
+  KeyQ: 0x4, KeyW: 0x5, KeyE: 0x6, KeyR: 0xd,
+  KeyA: 0x7, KeyS: 0x8, KeyD: 0x9, KeyF: 0xe,
+  KeyZ: 0xa, KeyX: 0x0, KeyC: 0xb, KeyV: 0xf,
+};
+
+const canvas = document.getElementById("display");
+const context = canvas.getContext("2d");
+const status = document.getElementById("status");
+
+let chip8 = null;
+let running = false;
+
+// the buzzer is an oscillator that is only connected while it sounds, the
+// audio context can only start after the user did something on the page
+let audio = null;
+let oscillator = null;
+let beeping = false;
+
+function buzz(on, pitch) {
+  if (audio === null) {
+    audio = new AudioContext();
+    oscillator = audio.createOscillator();
+    oscillator.type = "square";
+    oscillator.start();
+  }
+  oscillator.frequency.value = pitch;
+  if (on !== beeping) {
+    if (on) {
+      oscillator.connect(audio.destination);
+    } else {
+      oscillator.disconnect();
+    }
+    beeping = on;
+  }
+}
+
+// copy the rgb pixels of the display to the canvas
+function draw() {
+  const width = chip8.chip8_width();
+  const height = chip8.chip8_height();
+  if (canvas.width !== width || canvas.height !== height) {
+    canvas.width = width;
+    canvas.height = height;
+  }
+  // the memory can grow while running, so the view is made again each frame
+  const rgb = new Uint8Array(chip8.memory.buffer, chip8.chip8_pixels(), width * height * 3);
+  const image = context.createImageData(width, height);
+  for (let pixel = 0; pixel < width * height; pixel++) {
+    image.data.set(rgb.subarray(pixel * 3, pixel * 3 + 3), pixel * 4);
+    image.data[pixel * 4 + 3] = 255;
+  }
+  context.putImageData(image, 0, 0);
+}
+
+function frame() {
+  chip8.chip8_frame();
+  draw();
+  buzz(chip8.chip8_beep() !== 0, chip8.chip8_pitch());
+  requestAnimationFrame(frame);
+}
+
+function start(name, rom) {
+  const buffer = chip8.chip8_rom_buffer(rom.length);
+  new Uint8Array(chip8.memory.buffer, buffer, rom.length).set(rom);
+  if (!chip8.chip8_start(CLOCKSPEED)) {
+    status.textContent = name + " is too big for the chip8 memory";
+    return;
+  }
+  status.textContent = name;
+  if (!running) {
+    running = true;
+    requestAnimationFrame(frame);
+  }
+}
+
+async function load(file) {
+  start(file.name, new Uint8Array(await file.arrayBuffer()));
+}
+
+document.getElementById("romfile").addEventListener("change", (event) => {
+  if (event.target.files.length > 0) {
+    load(event.target.files[0]);
+  }
+  // give the keys back to the page
+  event.target.blur();
+});
+document.addEventListener("dragover", (event) => event.preventDefault());
+document.addEventListener("drop", (event) => {
+  event.preventDefault();
+  if (event.dataTransfer.files.length > 0) {
+    load(event.dataTransfer.files[0]);
+  }
+});
+
+function key(event, down) {
+  if (chip8 === null || event.target.tagName === "INPUT") {
+    return;
+  }
+  if (event.code in KEYPAD) {
+    chip8.chip8_key(KEYPAD[event.code], down ? 1 : 0);
+  } else if (down && event.code === "Space") {
+    chip8.chip8_toggle_pause();
+  } else if (down && event.code === "F5") {
+    chip8.chip8_reset();
+  } else {
+    return;
+  }
+  event.preventDefault();
+}
+document.addEventListener("keydown", (event) => key(event, true));
+document.addEventListener("keyup", (event) => key(event, false));
+
+const imports = {
+  env: {
+    chip8_now: () => performance.now(),
+    chip8_random: (ptr, len) => {
+      crypto.getRandomValues(new Uint8Array(chip8.memory.buffer, ptr, len));
+    },
+  },
+};
+
+WebAssembly.instantiateStreaming(fetch("chip8.wasm"), imports).then(({ instance }) => {
+  chip8 = instance.exports;
+});